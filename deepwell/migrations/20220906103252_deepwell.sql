@@ -15,6 +15,11 @@ CREATE TYPE user_type AS ENUM (
     'bot'
 );
 
+CREATE TYPE message_privacy AS ENUM (
+    'everyone',
+    'contacts'
+);
+
 CREATE TABLE "user" (
     user_id BIGSERIAL PRIMARY KEY,
     user_type user_type NOT NULL DEFAULT 'regular',
@@ -41,6 +46,8 @@ CREATE TABLE "user" (
     location TEXT,
     biography TEXT,
     user_page TEXT,
+    message_privacy message_privacy NOT NULL DEFAULT 'everyone',
+    platform_staff BOOLEAN NOT NULL DEFAULT false,
 
     -- Name uniqueness constraints
     UNIQUE (name, deleted_at),
@@ -77,6 +84,22 @@ CREATE TABLE user_bot_owner (
 -- Site
 --
 
+-- Enum types for site scoring policy
+CREATE TYPE score_type AS ENUM (
+    'null',
+    'sum',
+    'mean',
+    'median',
+    'percent',
+    'wilson',
+    'bayesian'
+);
+
+CREATE TYPE vote_type AS ENUM (
+    'ups-downs',
+    'five-star'
+);
+
 CREATE TABLE site (
     site_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
@@ -90,8 +113,13 @@ CREATE TABLE site (
     locale TEXT NOT NULL,
     default_page TEXT NOT NULL DEFAULT 'start',
     custom_domain TEXT,  -- Dependency cycle, add foreign key constraint after
+    score_type score_type NOT NULL DEFAULT 'sum',
+    vote_type vote_type NOT NULL DEFAULT 'ups-downs',
+    default_file_licensing JSON NOT NULL DEFAULT '{"license": "CC-BY-SA-3.0"}',
+    file_quota_bytes BIGINT,  -- Overrides the configured site file quota, if set
 
-    UNIQUE (slug, deleted_at)
+    UNIQUE (slug, deleted_at),
+    CHECK (file_quota_bytes IS NULL OR file_quota_bytes > 0)
 );
 
 CREATE TABLE site_domain (
@@ -163,6 +191,13 @@ CREATE TABLE relation (
     )
 );
 
+-- For paginating extant relations in either direction
+CREATE INDEX relation_dest_idx ON relation (relation_type, dest_type, dest_id, relation_id)
+    WHERE overwritten_at IS NULL AND deleted_at IS NULL;
+
+CREATE INDEX relation_from_idx ON relation (relation_type, from_type, from_id, relation_id)
+    WHERE overwritten_at IS NULL AND deleted_at IS NULL;
+
 --
 -- Session
 --
@@ -206,6 +241,47 @@ CREATE TABLE page (
     UNIQUE (site_id, slug, deleted_at)
 );
 
+--
+-- Permissions
+--
+
+-- Enum types for site_permission
+CREATE TYPE site_role AS ENUM (
+    'guest',
+    'registered',
+    'member',
+    'moderator',
+    'admin',
+    'owner'
+);
+
+CREATE TYPE permission_type AS ENUM (
+    'view',
+    'create',
+    'edit',
+    'move',
+    'delete',
+    'vote',
+    'upload',
+    'moderate',
+    'manage'
+);
+
+-- Overrides for which role is needed to perform an action.
+-- If category_id is NULL, then the rule applies to the whole site,
+-- otherwise it only applies to pages within that category.
+CREATE TABLE site_permission (
+    site_permission_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    category_id BIGINT REFERENCES page_category(category_id),
+    permission permission_type NOT NULL,
+    role site_role NOT NULL,
+
+    UNIQUE NULLS NOT DISTINCT (site_id, category_id, permission)
+);
+
 --
 -- Page revisions and contents
 --
@@ -315,18 +391,47 @@ CREATE TABLE page_parent (
     PRIMARY KEY (parent_page_id, child_page_id)
 );
 
+-- Enum types for page_attribution
+-- Currently synced to Crom
+CREATE TYPE page_attribution_type AS ENUM (
+    'author',
+    'rewrite',
+    'translator',
+    'maintainer'
+);
+
 CREATE TABLE page_attribution (
     page_id BIGINT REFERENCES page(page_id),
     user_id BIGINT REFERENCES "user"(user_id),
-    -- Text enum describing the kind of attribution
-    -- Currently synced to Crom: 'author', 'rewrite', 'translator', 'maintainer'
-    attribution_type TEXT NOT NULL,
+    attribution_type page_attribution_type NOT NULL,
     attribution_date DATE NOT NULL,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
 
     PRIMARY KEY (page_id, user_id, attribution_type, attribution_date)
 );
 
+-- Holds the field values for pages which use data forms.
+-- These are used by ListPages to select and order pages.
+CREATE TABLE page_data_field (
+    page_id BIGINT REFERENCES page(page_id),
+    field_name TEXT,
+    field_value TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+
+    PRIMARY KEY (page_id, field_name),
+    CHECK (length(field_name) > 0)
+);
+
+-- Enum types for page_lock
+--
+-- An 'edit' lock marks that a user is currently editing a page, it does not prevent edits.
+-- A 'protect' lock prevents anyone below moderator from editing, moving, or deleting a page.
+CREATE TYPE page_lock_type AS ENUM (
+    'edit',
+    'protect'
+);
+
 CREATE TABLE page_lock (
     page_lock_id BIGSERIAL PRIMARY KEY,
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
@@ -334,16 +439,21 @@ CREATE TABLE page_lock (
     deleted_at TIMESTAMP WITH TIME ZONE,
     expires_at TIMESTAMP WITH TIME ZONE,
     from_wikidot BOOLEAN NOT NULL DEFAULT false,
-    -- Text enum describing what kind of lock (e.g. authors only, staff only)
-    -- Currently the only value is 'wikidot' (meaning mods+ only)
-    lock_type TEXT NOT NULL,
+    lock_type page_lock_type NOT NULL,
     page_id BIGINT NOT NULL REFERENCES page(page_id),
     user_id BIGINT NOT NULL REFERENCES "user"(user_id),
     reason TEXT NOT NULL,
 
-    UNIQUE (page_id, deleted_at)
+    CHECK (lock_type != 'edit' OR expires_at IS NOT NULL)  -- edit locks always expire
 );
 
+-- Only one active edit lock per user, and only one active protection per page
+CREATE UNIQUE INDEX page_lock_edit_idx ON page_lock (page_id, user_id)
+    WHERE lock_type = 'edit' AND deleted_at IS NULL;
+
+CREATE UNIQUE INDEX page_lock_protect_idx ON page_lock (page_id)
+    WHERE lock_type = 'protect' AND deleted_at IS NULL;
+
 --
 -- Page backlinks tracking
 --
@@ -499,6 +609,101 @@ CREATE TABLE file_revision (
     UNIQUE (file_id, page_id, revision_number)
 );
 
+-- Resized copies of image blobs, with their metadata stripped.
+--
+-- Each derivative is itself a content-addressed blob, fit within a square
+-- of the given dimension. Images are never enlarged, so the derivative for
+-- a large dimension may be the same size as the original.
+CREATE TABLE blob_derivative (
+    source_hash BYTEA,
+    max_dimension INTEGER,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    derivative_hash BYTEA NOT NULL,
+    mime TEXT NOT NULL,
+    size BIGINT NOT NULL,
+
+    PRIMARY KEY (source_hash, max_dimension),
+    CHECK (length(source_hash) = 64),      -- SHA-512 hash size
+    CHECK (length(derivative_hash) = 64),  -- SHA-512 hash size
+    CHECK (max_dimension > 0)
+);
+
+-- For checking whether a blob is still used as a derivative
+CREATE INDEX blob_derivative_hash_idx ON blob_derivative (derivative_hash);
+
+-- Results of scanning blobs for malware.
+--
+-- Scans are recorded by hash so that identical contents are not scanned again.
+-- Infected contents are never stored at their content address, instead they
+-- are kept under the "quarantine/" prefix for review.
+CREATE TABLE blob_scan (
+    s3_hash BYTEA PRIMARY KEY,
+    scanned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    scanner TEXT NOT NULL,
+    database_version TEXT NOT NULL,  -- Verdicts from older signatures are rescanned
+    infected BOOLEAN NOT NULL,
+    signature TEXT,
+
+    CHECK (length(s3_hash) = 64),  -- SHA-512 hash size
+    CHECK (infected = (signature IS NOT NULL))
+);
+
+-- Audit trail of hard deletions of files.
+--
+-- Since the file rows are removed, the relevant information is copied here.
+-- The blob hashes are stored as hex, along with which of them were queued for
+-- removal from S3, since blobs shared with other files or avatars are retained.
+CREATE TABLE file_hard_deletion (
+    deletion_id BIGSERIAL PRIMARY KEY,
+    deleted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    deleted_by BIGINT NOT NULL REFERENCES "user"(user_id),
+    file_id BIGINT NOT NULL,
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    name TEXT NOT NULL,
+    revision_count INTEGER NOT NULL,
+    blob_hashes TEXT[] NOT NULL,
+    deleted_blob_hashes TEXT[] NOT NULL,
+    reason TEXT NOT NULL,
+
+    CHECK (deleted_blob_hashes <@ blob_hashes)
+);
+
+-- In-progress chunked uploads of files.
+--
+-- Parts are streamed into an S3 multipart upload under a temporary key,
+-- which is moved to its content address once the upload is finished.
+-- Sessions which see no activity are aborted and removed by a job.
+CREATE TABLE file_upload (
+    upload_id TEXT PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    name TEXT NOT NULL,
+    revision_comments TEXT NOT NULL,
+    licensing JSON NOT NULL,
+    s3_key TEXT NOT NULL UNIQUE,
+    s3_upload_id TEXT,  -- Set once the first part is uploaded
+    mime TEXT,          -- Detected from the first part
+
+    CHECK (length(name) > 0 AND length(name) < 256),
+    CHECK ((s3_upload_id IS NULL) = (mime IS NULL))
+);
+
+CREATE TABLE file_upload_part (
+    upload_id TEXT REFERENCES file_upload(upload_id) ON DELETE CASCADE,
+    part_number INTEGER,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    size BIGINT NOT NULL,
+    etag TEXT NOT NULL,
+
+    PRIMARY KEY (upload_id, part_number),
+    CHECK (part_number > 0),
+    CHECK (size > 0)
+);
+
 --
 -- Direct Messages
 --
@@ -538,6 +743,7 @@ CREATE TABLE message_record (
 -- (one for each recipient, including the sender's "Sent" folder).
 CREATE TABLE message (
     internal_id BIGSERIAL PRIMARY KEY,
+    deleted_at TIMESTAMP WITH TIME ZONE,  -- If the user deleted their copy of this record
     record_id TEXT NOT NULL REFERENCES message_record(external_id),  -- The record this corresponds to
     user_id BIGINT NOT NULL REFERENCES "user"(user_id),  -- The user who owns the copy of this record
 
@@ -548,6 +754,7 @@ CREATE TABLE message (
     flag_self BOOLEAN NOT NULL,  -- Messages sent to oneself, as a kind of "notes to self" section.
     flag_trash BOOLEAN NOT NULL DEFAULT false,
     flag_star BOOLEAN NOT NULL DEFAULT false,
+    flag_archive BOOLEAN NOT NULL DEFAULT false,  -- Removed from the inbox, but not trashed.
 
     -- User-customizable tagging
     tags TEXT[] NOT NULL DEFAULT '{}',
@@ -585,18 +792,69 @@ CREATE TABLE message_draft (
     CHECK (length(external_id) = 24)  -- default length for a cuid2
 );
 
+CREATE TYPE message_report_status AS ENUM (
+    'pending',
+    'resolved',
+    'dismissed'
+);
+
 -- If a message has been reported, then a row for it is created here.
 -- Messages can be reported per-site or globally (at the platform level).
+--
+-- Reports start as pending, and are then either resolved (action was taken)
+-- or dismissed by a moderator.
 CREATE TABLE message_report (
+    message_report_id BIGSERIAL PRIMARY KEY,
     message_id BIGINT NOT NULL REFERENCES message(internal_id),
     reported_to_site_id BIGINT REFERENCES site(site_id),
     created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
     updated_at TIMESTAMP WITH TIME ZONE,
     reason TEXT NOT NULL,
+    status message_report_status NOT NULL DEFAULT 'pending',
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    resolved_by BIGINT REFERENCES "user"(user_id),
+    resolution_comments TEXT,
+
+    UNIQUE NULLS NOT DISTINCT (message_id, reported_to_site_id),
+    CHECK ((status = 'pending') = (resolved_at IS NULL)),
+    CHECK ((resolved_at IS NULL) = (resolved_by IS NULL))
+);
+
+--
+-- Notifications
+--
 
-    PRIMARY KEY (message_id, reported_to_site_id)
+CREATE TYPE notification_type AS ENUM (
+    'page_edit',
+    'page_move',
+    'page_delete',
+    'followed_page_create',
+    'message'
 );
 
+-- Notifications for a user about activity they are watching or following.
+--
+-- While a notification is unread, further events of the same kind
+-- for the same page are merged into it, incrementing event_count.
+CREATE TABLE notification (
+    notification_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP WITH TIME ZONE,
+    read_at TIMESTAMP WITH TIME ZONE,
+    user_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    notification_type notification_type NOT NULL,
+    actor_id BIGINT NOT NULL REFERENCES "user"(user_id),
+    site_id BIGINT REFERENCES site(site_id),
+    page_id BIGINT REFERENCES page(page_id),
+    message_record_id TEXT REFERENCES message_record(external_id),
+    event_count INTEGER NOT NULL DEFAULT 1 CHECK (event_count > 0),
+
+    CHECK ((notification_type = 'message') = (message_record_id IS NOT NULL)),
+    CHECK ((notification_type = 'message') = (page_id IS NULL))
+);
+
+CREATE INDEX notification_user_idx ON notification (user_id, notification_id);
+
 --
 -- Filters
 --
@@ -623,3 +881,111 @@ CREATE TABLE filter (
 
     UNIQUE (site_id, regex, deleted_at)
 );
+
+-- Enum types for filter_violation
+CREATE TYPE filter_content_type AS ENUM (
+    'page_wikitext',
+    'page_title',
+    'file_name',
+    'email',
+    'user_name'
+);
+
+-- Audit log of content which was rejected by a filter.
+--
+-- Both site_id and user_id are optional, for instance
+-- platform filters during user registration have neither.
+CREATE TABLE filter_violation (
+    filter_violation_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    filter_id BIGINT NOT NULL REFERENCES filter(filter_id),
+    site_id BIGINT REFERENCES site(site_id),
+    user_id BIGINT REFERENCES "user"(user_id),
+    content_type filter_content_type NOT NULL,
+    excerpt TEXT NOT NULL
+);
+
+--
+-- Jobs
+--
+
+-- Background jobs which are waiting to be run, or are currently running.
+--
+-- A worker claims a job by setting locked_at. If the worker dies while running it,
+-- then once the processing time has passed the job is available to other workers again.
+-- Jobs which succeed are deleted, and jobs which fail on every attempt
+-- are moved to job_dead_letter.
+CREATE TABLE job_queue (
+    job_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    job JSON NOT NULL,
+    attempts SMALLINT NOT NULL DEFAULT 0,
+    run_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    locked_at TIMESTAMP WITH TIME ZONE,
+    last_error TEXT,
+
+    CHECK (attempts >= 0)
+);
+
+CREATE INDEX job_queue_run_at_idx ON job_queue (run_at);
+
+-- Jobs which have failed on every attempt.
+-- These are kept so that they can be inspected and retried.
+CREATE TABLE job_dead_letter (
+    job_id BIGINT PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    failed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    job JSON NOT NULL,
+    attempts SMALLINT NOT NULL,
+    last_error TEXT NOT NULL
+);
+
+-- Recurring jobs, which are added to job_queue each time their interval elapses.
+CREATE TABLE job_schedule (
+    name TEXT PRIMARY KEY,
+    job JSON NOT NULL,
+    interval_secs BIGINT NOT NULL,
+    next_run_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    last_run_at TIMESTAMP WITH TIME ZONE,
+
+    CHECK (interval_secs > 0)
+);
+
+--
+-- Wikidot import
+--
+
+-- Pages which have been fully imported from Wikidot, that is,
+-- with all of their revisions, files, and votes.
+--
+-- This is used to resume an import which was interrupted.
+CREATE TABLE import_completed_page (
+    page_id BIGINT PRIMARY KEY REFERENCES page(page_id),
+    site_id BIGINT NOT NULL REFERENCES site(site_id),
+    completed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+-- Revisions of pages which have been imported from Wikidot.
+--
+-- Revisions without any changes are skipped, so revision numbers here are
+-- Wikidot's, and may not match the page_revision they were imported as.
+-- The revision ID is NULL for those which were skipped.
+CREATE TABLE import_page_revision (
+    page_id BIGINT NOT NULL REFERENCES page(page_id),
+    wikidot_revision_number INT NOT NULL,
+    revision_id BIGINT REFERENCES page_revision(revision_id),
+
+    PRIMARY KEY (page_id, wikidot_revision_number)
+);
+
+-- Revisions of files which have been imported from Wikidot.
+--
+-- Like import_page_revision, revisions without any changes are skipped,
+-- so the revision ID is NULL for those.
+CREATE TABLE import_file_revision (
+    file_id BIGINT NOT NULL REFERENCES file(file_id),
+    wikidot_revision_number INT NOT NULL,
+    revision_id BIGINT REFERENCES file_revision(revision_id),
+
+    PRIMARY KEY (file_id, wikidot_revision_number)
+);
@@ -1,4 +1,4 @@
 from .generator import generate_seed
 from .structures import *
 from .scuttle import run_scuttle_import
-from .wikicomma import run_wikicomma_import
+from .wikicomma import run_wikicomma_import, run_wikicomma_api_import
@@ -0,0 +1,211 @@
+import json
+import logging
+from datetime import datetime, timezone
+from urllib.request import Request, urlopen
+
+from .structures import *
+
+logger = logging.getLogger(__name__)
+
+
+def format_datetime(value: datetime) -> str:
+    """
+    Formats a datetime in the form DEEPWELL accepts for OffsetDateTime.
+    Naive datetimes are assumed to be in UTC.
+    """
+
+    if value.tzinfo is None:
+        value = value.replace(tzinfo=timezone.utc)
+
+    value = value.astimezone(timezone.utc)
+    return value.strftime("%Y-%m-%d %H:%M:%S.%f +00:00:00")
+
+
+class DeepwellError(RuntimeError):
+    pass
+
+
+class DeepwellClient:
+    """
+    Minimal JSON-RPC client for DEEPWELL's import methods.
+    """
+
+    __slots__ = ("url", "request_id")
+
+    def __init__(self, url: str):
+        self.url = url
+        self.request_id = 0
+
+    def call(self, method: str, params):
+        self.request_id += 1
+        body = json.dumps(
+            {
+                "jsonrpc": "2.0",
+                "id": self.request_id,
+                "method": method,
+                "params": params,
+            }
+        ).encode("utf-8")
+
+        request = Request(
+            self.url,
+            data=body,
+            headers={"Content-Type": "application/json"},
+            method="POST",
+        )
+
+        with urlopen(request) as response:
+            data = json.load(response)
+
+        if "error" in data:
+            raise DeepwellError(f"{method} failed: {data['error']}")
+
+        return data["result"]
+
+
+class DeepwellGenerator:
+    """
+    Imports data directly into DEEPWELL using its import API.
+
+    This implements the same interface as Generator, so it can be used
+    by the same importers. Unlike the SQL dump, the import is idempotent
+    and can be resumed, pages which were completed are skipped.
+    """
+
+    __slots__ = (
+        "client",
+        "completed_pages",
+    )
+
+    def __init__(self, client: DeepwellClient):
+        self.client = client
+        self.completed_pages = set()  # Set[int]
+
+    def section_sql(self, name: str):
+        logger.debug("Section: %s", name)
+
+    def section_sh(self, name: str):
+        pass
+
+    def add_user(self, user: User):
+        # Users are imported separately, since their IDs must be known
+        raise NotImplementedError
+
+    def add_site(self, site: Site) -> int:
+        site_id = self.client.call(
+            "import_site",
+            {
+                "site_id": site.wikidot_id,
+                "created_at": format_datetime(site.created_at),
+                "name": site.name,
+                "slug": site.slug,
+                "locale": "en",
+            },
+        )
+
+        progress = self.client.call("import_progress", [site_id])
+        self.completed_pages.update(progress["completed_page_ids"])
+        logger.info(
+            "Site %s has %d pages already imported",
+            site.slug,
+            len(progress["completed_page_ids"]),
+        )
+        return site_id
+
+    def add_page(self, page: Page):
+        self.client.call(
+            "import_page",
+            {
+                "page_id": page.wikidot_id,
+                "site_id": page.site_id,
+                "created_at": format_datetime(page.created_at),
+                "slug": page.slug,
+                "locked": page.locked,
+                "discussion_thread_id": page.discussion_thread_id,
+            },
+        )
+
+    def add_page_revision(self, revision: PageRevision):
+        self.client.call(
+            "import_page_revision",
+            {
+                "page_id": revision.page_id,
+                "site_id": revision.site_id,
+                "revision_number": revision.revision_number,
+                "created_at": format_datetime(revision.created_at),
+                "user_id": revision.user_id,
+                "wikitext": revision.wikitext,
+                "title": revision.title,
+                "slug": revision.slug,
+                "tags": revision.tags,
+                "comments": revision.comments,
+            },
+        )
+
+    def add_page_vote(self, vote: PageVote):
+        self.client.call(
+            "import_page_vote",
+            {
+                "page_id": vote.page_id,
+                "user_id": vote.user_id,
+                "value": vote.value,
+            },
+        )
+
+    def add_page_parent(self, parent_page_id: int, child_page_id: int):
+        self.client.call(
+            "import_page_parent",
+            {
+                "parent_page_id": parent_page_id,
+                "child_page_id": child_page_id,
+            },
+        )
+
+    def add_page_attribution(self, attribution: PageAttribution):
+        self.client.call(
+            "import_page_attribution",
+            {
+                "page_id": attribution.page_id,
+                "user_id": attribution.user_id,
+                "attribution_type": attribution.attribution_type,
+                "attribution_date": attribution.attribution_date.isoformat(),
+            },
+        )
+
+    def add_file(self, file: File, data: bytes):
+        self.client.call(
+            "import_file",
+            {
+                "file_id": file.wikidot_id,
+                "page_id": file.page_id,
+                "site_id": file.site_id,
+                "created_at": format_datetime(file.created_at),
+                "name": file.name,
+            },
+        )
+
+        # Wikidot does not keep file history, so there is only one revision
+        self.client.call(
+            "import_file_revision",
+            {
+                "file_id": file.wikidot_id,
+                "page_id": file.page_id,
+                "site_id": file.site_id,
+                "revision_number": 0,
+                "created_at": format_datetime(file.created_at),
+                "user_id": file.user_id,
+                "name": file.name,
+                "data": list(data),
+                "comments": "",
+            },
+        )
+
+    def page_completed(self, page_id: int) -> bool:
+        return page_id in self.completed_pages
+
+    def complete_page(self, site_id: int, page_id: int):
+        self.client.call(
+            "import_page_complete",
+            {"site_id": site_id, "page_id": page_id},
+        )
+        self.completed_pages.add(page_id)
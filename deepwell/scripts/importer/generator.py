@@ -112,12 +112,12 @@ class Generator:
         self.id_add(self.user_ids, user.wikidot_id)
         self.user_slugs.add(user.slug)
 
-    def add_site(self, site: Site):
+    def add_site(self, site: Site) -> Optional[int]:
         if (
             self.id_exists(self.site_ids, site.wikidot_id)
             or site.slug in self.site_slugs
         ):
-            return
+            return site.wikidot_id
 
         self.append_sql(
             "INSERT INTO site (site_id, name, slug, subtitle, description) VALUES (%s, %s, %s, %s, %s)",
@@ -126,6 +126,7 @@ class Generator:
 
         self.id_add(self.site_ids, site.wikidot_id)
         self.site_slugs.add(site.slug)
+        return site.wikidot_id
 
     def add_page(self, page: Page):
         if (
@@ -151,6 +152,9 @@ class Generator:
             ),
         )
 
+        if page.wikidot_id is not None:
+            self.add_page_lock(page.wikidot_id, page.locked)
+
         self.id_add(self.page_ids, page.wikidot_id)
         self.page_slugs.add((page.site_id, page.slug))
 
@@ -214,10 +218,11 @@ class Generator:
     def add_page_lock(self, page_id: int, locked: bool = True):
         if locked:
             self.append_sql(
-                "INSERT INTO page_lock (created_at, lock_type, page_id, user_id, reason) VALUES (%s, %s, %s, %s, %s)",
+                "INSERT INTO page_lock (created_at, from_wikidot, lock_type, page_id, user_id, reason) VALUES (%s, %s, %s, %s, %s, %s)",
                 (
                     UNKNOWN_CREATION_DATE,
-                    "wikidot",
+                    True,
+                    "protect",
                     page_id,
                     ANONYMOUS_USER_ID,
                     "Imported from Wikidot",
@@ -236,7 +241,7 @@ class Generator:
 
         return page_category_id
 
-    def add_file(self, file: File):
+    def add_file(self, file: File, data: bytes):
         if (
                 self.id_exists(self.file_ids, file.wikidot_id)
                 or (file.page_id, file.name) in self.file_names
@@ -249,6 +254,30 @@ class Generator:
         )
         self.file_names.add((file.page_id, file.name))
 
+    def add_page_parent(self, parent_page_id: int, child_page_id: int):
+        self.append_sql(
+            "INSERT INTO page_parent (parent_page_id, child_page_id) VALUES (%s, %s)",
+            (parent_page_id, child_page_id),
+        )
+
+    def add_page_attribution(self, attribution: PageAttribution):
+        self.append_sql(
+            "INSERT INTO page_attribution (page_id, user_id, attribution_type, attribution_date) VALUES (%s, %s, %s, %s)",
+            (
+                attribution.page_id,
+                attribution.user_id,
+                attribution.attribution_type,
+                attribution.attribution_date,
+            ),
+        )
+
+    def page_completed(self, page_id: int) -> bool:
+        # The SQL dump is always generated from scratch
+        return False
+
+    def complete_page(self, site_id: int, page_id: int):
+        pass
+
     # TODO add forums
 
     def add_blob(self, data: bytes) -> str:
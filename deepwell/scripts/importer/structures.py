@@ -1,5 +1,5 @@
 from dataclasses import dataclass
-from datetime import datetime
+from datetime import date, datetime
 from typing import List, Optional
 
 
@@ -32,6 +32,7 @@ class Page:
     title: str
     slug: str
     discussion_thread_id: Optional[int]
+    locked: bool = False
 
 
 @dataclass(frozen=True)
@@ -58,10 +59,20 @@ class PageVote:
     value: int
 
 
+@dataclass(frozen=True)
+class PageAttribution:
+    page_id: int
+    user_id: int
+    # One of 'author', 'rewrite', 'translator', 'maintainer'
+    attribution_type: str
+    attribution_date: date
+
+
 @dataclass(frozen=True)
 class File:
     wikidot_id: Optional[int]
     page_id: int
+    site_id: int
     name: str
     mime: str
     size: int
@@ -5,6 +5,7 @@ import re
 from datetime import datetime
 
 from .constants import UNKNOWN_CREATION_DATE
+from .deepwell import DeepwellClient, DeepwellGenerator
 from .generator import generate_seed
 from .structures import *
 
@@ -41,7 +42,7 @@ class WikicommaImporter:
 
         # Add site
         unknown_description = f"[NEEDS UPDATE] {site_slug}"
-        self.generator.add_site(
+        site_id = self.generator.add_site(
             Site(
                 wikidot_id=None,
                 created_at=UNKNOWN_CREATION_DATE,
@@ -54,10 +55,10 @@ class WikicommaImporter:
 
         # Process site internals
         site_directory = os.path.join(self.directory, site_slug)
-        self.process_site_pages(site_slug, site_directory)
+        self.process_site_pages(site_slug, site_id, site_directory)
         self.process_site_forum(site_slug, site_directory)
 
-    def process_site_pages(self, site_slug: str, site_directory: str):
+    def process_site_pages(self, site_slug: str, site_id: int, site_directory: str):
         page_mapping = self.read_json(site_directory, "meta", "page_id_map.json")
         file_mapping = self.read_json(site_directory, "meta", "file_map.json")
         logger.info("Processing %d pages", len(page_mapping))
@@ -76,16 +77,27 @@ class WikicommaImporter:
 
             return start_revision, last_revision
 
+        if site_id is None:
+            site_id = -1  # TODO unknown
+
+        page_slug_mapping = {}  # dict[str, int]
+        parents = []  # List[Tuple[str, int]]
+
         for page_id, page_slug in page_mapping.items():
-            self.generator.section_sql(f"Page: {page_slug}")
             page_id = int(page_id)
+            page_slug_mapping[page_slug] = page_id
+
+            if self.generator.page_completed(page_id):
+                logger.info("Page %s already imported, skipping", page_slug)
+                continue
+
+            self.generator.section_sql(f"Page: {page_slug}")
             metadata = self.read_page_metadata(site_directory, page_slug)
             start_revision, last_revision = get_first_last_revisions(
                 metadata["revisions"]
             )
             created_at = datetime.fromtimestamp(start_revision["stamp"])
             updated_at = datetime.fromtimestamp(last_revision["stamp"])
-            site_id = -1  # TODO unknown
 
             self.generator.add_page(
                 Page(
@@ -96,17 +108,33 @@ class WikicommaImporter:
                     title=metadata.get("title", ""),
                     slug=page_slug,
                     discussion_thread_id=None,  # TODO unknown
+                    locked=metadata.get("is_locked", False),
                 )
             )
-            self.generator.add_page_lock(page_id, metadata.get("is_locked", False))
             self.process_page_revisions(site_directory, site_id, metadata)
             self.process_page_files(
                 site_directory,
+                site_id,
                 page_id,
                 file_mapping,
                 metadata["files"],
             )
             self.process_page_votes(metadata)
+            self.generator.complete_page(site_id, page_id)
+
+            parent_slug = metadata.get("parent")
+            if parent_slug:
+                parents.append((parent_slug, page_id))
+
+        # Parents are added last, since both pages must exist
+        logger.info("Processing %d parent relationships", len(parents))
+        for parent_slug, child_page_id in parents:
+            parent_page_id = page_slug_mapping.get(parent_slug)
+            if parent_page_id is None:
+                logger.warn("Skipping parent, unknown page: %s", parent_slug)
+                continue
+
+            self.generator.add_page_parent(parent_page_id, child_page_id)
 
     def process_page_revisions(self, site_directory: str, site_id: int, metadata: dict):
         page_slug = metadata["name"]
@@ -122,8 +150,11 @@ class WikicommaImporter:
                 match = REVISION_FILENAME_REGEX.fullmatch(filename)
                 revision_number = int(match[1])
                 wikitext = data.read().decode("utf-8")
+                wikitext_mapping[revision_number] = wikitext
 
-        for revision in metadata["revisions"]:
+        # Revisions must be added in order
+        revisions = sorted(metadata["revisions"], key=lambda r: r["revision"])
+        for revision in revisions:
             revision_number = revision["revision"]
             user_spec = revision["author"]
             logger.debug("Processing revision number %d", revision_number)
@@ -160,6 +191,7 @@ class WikicommaImporter:
     def process_page_files(
         self,
         site_directory: str,
+        site_id: int,
         page_id: int,
         file_mapping: dict,
         metadata_list: list,
@@ -190,12 +222,14 @@ class WikicommaImporter:
                 File(
                     wikidot_id=metadata["file_id"],
                     page_id=page_id,
+                    site_id=site_id,
                     name=metadata["name"],
                     mime=metadata["mime"],
                     size=metadata["size_bytes"],
                     user_id=user_spec,
                     created_at=datetime.fromtimestamp(metadata["stamp"]),
-                )
+                ),
+                file_data,
             )
 
     def process_page_votes(self, metadata: dict):
@@ -285,3 +319,18 @@ def run_wikicomma_import(
         postgres_url=postgres_url,
         last_page_category_id=last_page_category_id,
     )
+
+
+def run_wikicomma_api_import(*, wikicomma_directory: str, deepwell_url: str):
+    """
+    Imports WikiComma data directly into a running DEEPWELL instance.
+
+    Unlike run_wikicomma_import(), this can be interrupted and run again,
+    pages which were already completely imported will be skipped.
+    Users referenced by the data must have already been imported.
+    """
+
+    wikicomma_directory = os.path.normpath(wikicomma_directory)
+    generator = DeepwellGenerator(DeepwellClient(deepwell_url))
+    importer = WikicommaImporter(generator, wikicomma_directory)
+    importer.process_all()
@@ -3,7 +3,7 @@
 import argparse
 import logging
 import sys
-from importer import run_wikicomma_import
+from importer import run_wikicomma_api_import, run_wikicomma_import
 
 LOG_FORMAT = "[%(levelname)s] %(asctime)s %(name)s: %(message)s"
 LOG_DATE_FORMAT = "[%Y/%m/%d %H:%M:%S]"
@@ -38,7 +38,6 @@ if __name__ == "__main__":
         "--sql",
         "--output-sql",
         dest="sql_path",
-        required=True,
         help="The location to output the SQL dump to",
     )
     argparser.add_argument(
@@ -46,7 +45,6 @@ if __name__ == "__main__":
         "--shell",
         "--output-shell",
         dest="sh_path",
-        required=True,
         help="The location to output the shell dump to",
     )
     argparser.add_argument(
@@ -54,18 +52,30 @@ if __name__ == "__main__":
         "--s3",
         "--s3-bucket",
         dest="s3_bucket",
-        required=True,
         help="The name of the S3 bucket to use (read-only)",
     )
     argparser.add_argument(
         "-u",
         "--postgres-url",
         dest="postgres_url",
-        required=True,
         help="The DEEPWELL database to connect to (read-only)",
     )
+    argparser.add_argument(
+        "-a",
+        "--deepwell-url",
+        dest="deepwell_url",
+        help="Import directly into the DEEPWELL instance at this URL, instead of generating dumps",
+    )
     args = argparser.parse_args()
 
+    if args.deepwell_url is None and None in (
+        args.sql_path,
+        args.sh_path,
+        args.s3_bucket,
+        args.postgres_url,
+    ):
+        argparser.error("--sql, --shell, --s3 and --postgres-url are required without --deepwell-url")
+
     log_fmtr = logging.Formatter(LOG_FORMAT, datefmt=LOG_DATE_FORMAT)
     log_stdout = logging.StreamHandler(sys.stdout)
     log_stdout.setFormatter(log_fmtr)
@@ -75,10 +85,16 @@ if __name__ == "__main__":
     logger.setLevel(level=log_level)
     logger.addHandler(log_stdout)
 
-    run_wikicomma_import(
-        wikicomma_directory=args.wikicomma_directory,
-        sql_path=args.sql_path,
-        sh_path=args.sh_path,
-        s3_bucket=args.s3_bucket,
-        postgres_url=args.postgres_url,
-    )
+    if args.deepwell_url is not None:
+        run_wikicomma_api_import(
+            wikicomma_directory=args.wikicomma_directory,
+            deepwell_url=args.deepwell_url,
+        )
+    else:
+        run_wikicomma_import(
+            wikicomma_directory=args.wikicomma_directory,
+            sql_path=args.sql_path,
+            sh_path=args.sh_path,
+            s3_bucket=args.s3_bucket,
+            postgres_url=args.postgres_url,
+        )
@@ -28,19 +28,20 @@
 
 use crate::config::{Config, Secrets};
 use crate::endpoints::{
-    auth::*, category::*, domain::*, email::*, file::*, file_revision::*, link::*,
-    locale::*, message::*, misc::*, page::*, page_revision::*, parent::*, site::*,
-    site_member::*, text::*, user::*, user_bot::*, view::*, vote::*,
+    auth::*, category::*, domain::*, email::*, file::*, file_revision::*, file_upload::*,
+    filter::*, import::*, job::*, link::*, locale::*, message::*, misc::*,
+    notification::*, page::*, page_attribution::*, page_lock::*, page_revision::*,
+    parent::*, permission::*, site::*, site_member::*, text::*, user::*, user_bot::*,
+    user_contact::*, view::*, vote::*,
 };
 use crate::locales::Localizations;
-use crate::services::blob::MimeAnalyzer;
+use crate::services::blob::{build_scanner, BlobScanner, MimeAnalyzer};
+use crate::services::file_upload::MAXIMUM_PART_SIZE;
 use crate::services::job::JobWorker;
-use crate::services::{into_rpc_error, ServiceContext};
-use crate::utils::debug_pointer;
+use crate::services::{into_rpc_error, JobService, ServiceContext};
 use crate::{database, redis as redis_db};
 use jsonrpsee::server::{RpcModule, Server, ServerHandle};
 use jsonrpsee::types::error::ErrorObjectOwned;
-use rsmq_async::PooledRsmq;
 use s3::bucket::Bucket;
 use sea_orm::{DatabaseConnection, TransactionTrait};
 use std::fmt::{self, Debug};
@@ -53,10 +54,10 @@ pub struct ServerStateInner {
     pub config: Config,
     pub database: DatabaseConnection,
     pub redis: redis::Client,
-    pub rsmq: PooledRsmq,
     pub localizations: Localizations,
     pub mime_analyzer: MimeAnalyzer,
     pub s3_bucket: Bucket,
+    pub blob_scanner: Box<dyn BlobScanner>,
 }
 
 impl Debug for ServerStateInner {
@@ -65,10 +66,10 @@ impl Debug for ServerStateInner {
             .field("config", &self.config)
             .field("database", &self.database)
             .field("redis", &self.redis)
-            .field("rsmq", &debug_pointer(&self.rsmq))
             .field("localizations", &self.localizations)
             .field("mime_analyzer", &self.mime_analyzer)
             .field("s3_bucket", &self.s3_bucket)
+            .field("blob_scanner", &self.blob_scanner)
             .finish()
     }
 }
@@ -82,7 +83,7 @@ pub async fn build_server_state(
     let database = database::connect(&secrets.database_url).await?;
 
     info!("Connecting to Redis");
-    let (redis, rsmq) = redis_db::connect(&secrets.redis_url).await?;
+    let redis = redis_db::connect(&secrets.redis_url).await?;
 
     // Load localization data
     info!("Loading localization data");
@@ -109,17 +110,30 @@ pub async fn build_server_state(
         bucket
     };
 
+    // Set up malware scanning for uploads
+    info!("Connecting to malware scanner");
+    let blob_scanner = build_scanner(&config).await?;
+
     // Build server state
     let state = Arc::new(ServerStateInner {
         config,
         database,
         redis,
-        rsmq,
         localizations,
         mime_analyzer,
         s3_bucket,
+        blob_scanner,
     });
 
+    // Set up recurring jobs (requires ServerState)
+    info!("Setting up recurring job schedules");
+    {
+        let txn = state.database.begin().await?;
+        let ctx = &ServiceContext::new(&state, &txn);
+        JobService::setup_schedules(ctx).await?;
+        txn.commit().await?;
+    }
+
     // Start workers listening to the job queue (requires ServerState)
     JobWorker::spawn_all(&state);
 
@@ -127,9 +141,18 @@ pub async fn build_server_state(
     Ok(state)
 }
 
+/// The largest request the server accepts.
+///
+/// Binary data is sent as hex, doubling its size, so this must fit
+/// the largest file upload part, along with the rest of the request.
+const MAXIMUM_REQUEST_BODY_SIZE: u32 = 2 * MAXIMUM_PART_SIZE as u32 + 1024 * 1024;
+
 pub async fn build_server(app_state: ServerState) -> anyhow::Result<ServerHandle> {
     let socket_address = app_state.config.address;
-    let server = Server::builder().build(socket_address).await?;
+    let server = Server::builder()
+        .max_request_body_size(MAXIMUM_REQUEST_BODY_SIZE)
+        .build(socket_address)
+        .await?;
     let module = build_module(app_state).await?;
     let handle = server.start(module);
     Ok(handle)
@@ -206,6 +229,7 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("site_create", site_create);
     register!("site_get", site_get);
     register!("site_update", site_update);
+    register!("site_file_quota_set", site_file_quota_set);
     register!("site_from_domain", site_get_from_domain);
 
     // Site custom domain
@@ -217,6 +241,17 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("member_set", membership_set);
     register!("member_get", membership_get);
     register!("member_delete", membership_delete);
+    register!("member_list", membership_list);
+    register!("ban_set", site_ban_set);
+    register!("ban_get", site_ban_get);
+    register!("ban_delete", site_ban_delete);
+    register!("ban_list", site_ban_list);
+
+    // Site roles and permissions
+    register!("permission_get", permission_get);
+    register!("permission_rule_list", permission_rule_list);
+    register!("permission_rule_set", permission_rule_set);
+    register!("site_role_set", site_role_set);
 
     // Category
     register!("category_get", category_get);
@@ -230,14 +265,32 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("page_delete", page_delete);
     register!("page_move", page_move);
     register!("page_rollback", page_rollback);
+    register!("page_undo", page_undo);
     register!("page_rerender", page_rerender);
     register!("page_restore", page_restore);
+    register!("page_query", page_query);
+    register!("page_star_list", page_star_list);
+    register!("page_watch_list", page_watch_list);
+
+    // Page attributions
+    register!("page_attribution_create", page_attribution_create);
+    register!("page_attribution_remove", page_attribution_remove);
+    register!("page_attribution_get", page_attribution_get);
+    register!("user_attribution_get", user_attribution_get);
+
+    // Page locks
+    register!("page_lock_get", page_lock_get);
+    register!("page_lock_acquire", page_lock_acquire);
+    register!("page_lock_release", page_lock_release);
+    register!("page_protect", page_protect);
+    register!("page_unprotect", page_unprotect);
 
     // Page revisions
     register!("page_revision_create", page_revision_edit);
     register!("page_revision_get", page_revision_get);
     register!("page_revision_count", page_revision_count);
     register!("page_revision_range", page_revision_range);
+    register!("page_revision_diff", page_revision_diff);
 
     // Page links
     register!("page_get_links_from", page_links_from_get);
@@ -260,6 +313,15 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("file_move", file_move);
     register!("file_restore", file_restore);
     register!("file_hard_delete", file_hard_delete);
+    register!("file_license_list", file_license_list);
+    register!("file_storage_usage", file_storage_usage);
+
+    // Chunked file uploads
+    register!("file_upload_start", file_upload_start);
+    register!("file_upload_part", file_upload_part);
+    register!("file_upload_status", file_upload_status);
+    register!("file_upload_finish", file_upload_finish);
+    register!("file_upload_cancel", file_upload_cancel);
 
     // File revisions
     register!("file_revision_get", file_revision_get);
@@ -273,11 +335,13 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
 
     // User
     register!("user_create", user_create);
-    register!("user_import", user_import);
     register!("user_get", user_get);
     register!("user_edit", user_edit);
     register!("user_delete", user_delete);
     register!("user_add_name_change", user_add_name_change);
+    register!("user_follower_list", user_follower_list);
+    register!("user_following_list", user_following_list);
+    register!("user_block_list", user_block_list);
 
     // Bot user
     register!("bot_user_create", bot_user_create);
@@ -285,11 +349,36 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("bot_user_owner_set", bot_user_owner_set);
     register!("bot_user_owner_remove", bot_user_owner_remove);
 
+    // User contacts
+    register!("user_contact_request_send", user_contact_request_send);
+    register!("user_contact_request_accept", user_contact_request_accept);
+    register!("user_contact_request_decline", user_contact_request_decline);
+    register!("user_contact_request_cancel", user_contact_request_cancel);
+    register!("user_contact_request_list", user_contact_request_list);
+    register!("user_contact_list", user_contact_list);
+    register!("user_contact_remove", user_contact_remove);
+
     // Direct messages
     register!("message_draft_create", message_draft_create);
     register!("message_draft_edit", message_draft_edit);
     register!("message_draft_delete", message_draft_delete);
     register!("message_draft_send", message_draft_send);
+    register!("message_get", message_get);
+    register!("message_list", message_list);
+    register!("message_unread_count", message_unread_count);
+    register!("message_thread", message_thread);
+    register!("message_edit_flags", message_edit_flags);
+    register!("message_delete", message_delete);
+    register!("message_report_create", message_report_create);
+    register!("message_report_list", message_report_list);
+    register!("message_report_resolve", message_report_resolve);
+    register!("message_report_dismiss", message_report_dismiss);
+
+    // Notifications
+    register!("notification_list", notification_list);
+    register!("notification_unread_count", notification_unread_count);
+    register!("notification_mark_read", notification_mark_read);
+    register!("notification_digest", notification_digest);
 
     // Email
     register!("email_validate", validate_email);
@@ -302,6 +391,35 @@ async fn build_module(app_state: ServerState) -> anyhow::Result<RpcModule<Server
     register!("vote_list", vote_list_get);
     register!("vote_list_count", vote_list_count);
 
+    // Jobs
+    register!("job_list", job_list);
+    register!("job_list_failed", job_list_failed);
+    register!("job_retry", job_retry);
+    register!("job_cancel", job_cancel);
+
+    // Filters
+    register!("filter_create", filter_create);
+    register!("filter_get", filter_get);
+    register!("filter_list", filter_list);
+    register!("filter_edit", filter_edit);
+    register!("filter_delete", filter_delete);
+    register!("filter_restore", filter_restore);
+    register!("filter_test", filter_test);
+    register!("filter_violation_list", filter_violation_list);
+
+    // Wikidot import
+    register!("import_user", import_user);
+    register!("import_site", import_site);
+    register!("import_page", import_page);
+    register!("import_page_revision", import_page_revision);
+    register!("import_page_vote", import_page_vote);
+    register!("import_page_parent", import_page_parent);
+    register!("import_page_attribution", import_page_attribution);
+    register!("import_file", import_file);
+    register!("import_file_revision", import_file_revision);
+    register!("import_page_complete", import_page_complete);
+    register!("import_progress", import_progress);
+
     // Return
     Ok(module)
 }
@@ -19,7 +19,6 @@
  */
 
 use crate::services::Result;
-use rsmq_async::Rsmq;
 
 /// Creates primary `redis::Client` instance.
 ///
@@ -28,12 +27,3 @@ pub async fn connect_redis(redis_uri: &str) -> Result<redis::Client> {
     let client = redis::Client::open(redis_uri)?;
     Ok(client)
 }
-
-/// Creates an RSMQ instance for temporary use.
-///
-/// A connection is fetched from the `Client`, which is then used to create
-/// an owned `Rsmq` available for use by the caller.
-pub async fn connect_rsmq(client: &redis::Client) -> Result<Rsmq> {
-    let connection = client.get_tokio_connection().await?;
-    Ok(Rsmq::new_with_connection(connection, true, None))
-}
@@ -53,6 +53,10 @@ pub struct ConfigFile {
     special_pages: SpecialPages,
     user: User,
     message: Message,
+    page: Page,
+    upload: Upload,
+    image: Image,
+    scanner: Scanner,
 }
 
 /// Structure containing extra fields not found in `ConfigFile`.
@@ -118,6 +122,8 @@ struct Mfa {
 struct Job {
     workers: NonZeroU16,
     max_attempts: u16,
+    retry_delay_secs: u64,
+    process_time_secs: u64,
     delay_ms: u64,
     min_delay_poll_secs: u64,
     max_delay_poll_secs: u64,
@@ -125,6 +131,8 @@ struct Job {
     prune_text_secs: u64,
     name_change_refill_secs: u64,
     lift_expired_punishments_secs: u64,
+    prune_page_locks_secs: u64,
+    prune_file_uploads_secs: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -144,6 +152,7 @@ struct Domain {
 #[serde(rename_all = "kebab-case")]
 struct Ftml {
     render_timeout_ms: u64,
+    include_max_depth: u32,
     rerender_skip: Vec<RerenderSkip>,
 }
 
@@ -181,6 +190,34 @@ struct Message {
     maximum_recipients: usize,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Page {
+    edit_lock_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Upload {
+    maximum_file_bytes: u64,
+    session_secs: u64,
+    site_quota_bytes: u64,
+    user_quota_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Image {
+    convert_path: PathBuf,
+    derivative_sizes: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct Scanner {
+    clamd_socket: Option<PathBuf>,
+}
+
 impl ConfigFile {
     pub fn load(path: PathBuf) -> Result<(Self, ExtraConfig)> {
         // Read TOML
@@ -260,6 +297,8 @@ impl ConfigFile {
                 Job {
                     workers: job_workers,
                     max_attempts: job_max_attempts,
+                    retry_delay_secs: job_retry_delay_secs,
+                    process_time_secs: job_process_time_secs,
                     delay_ms: job_work_delay_ms,
                     min_delay_poll_secs: job_min_poll_delay_secs,
                     max_delay_poll_secs: job_max_poll_delay_secs,
@@ -267,6 +306,8 @@ impl ConfigFile {
                     prune_text_secs: job_prune_text_secs,
                     name_change_refill_secs: job_name_change_refill_secs,
                     lift_expired_punishments_secs: job_lift_expired_punishments_secs,
+                    prune_page_locks_secs: job_prune_page_locks_secs,
+                    prune_file_uploads_secs: job_prune_file_uploads_secs,
                 },
             locale: Locale {
                 path: localization_path,
@@ -274,6 +315,7 @@ impl ConfigFile {
             ftml:
                 Ftml {
                     render_timeout_ms,
+                    include_max_depth,
                     rerender_skip,
                 },
             special_pages:
@@ -297,28 +339,66 @@ impl ConfigFile {
                     maximum_body_bytes: maximum_message_body_bytes,
                     maximum_recipients: maximum_message_recipients,
                 },
+            page: Page { edit_lock_secs },
+            upload:
+                Upload {
+                    maximum_file_bytes: maximum_upload_bytes,
+                    session_secs: upload_session_secs,
+                    site_quota_bytes: site_file_quota_bytes,
+                    user_quota_bytes: user_file_quota_bytes,
+                },
+            image:
+                Image {
+                    convert_path: image_convert_path,
+                    derivative_sizes: mut image_derivative_sizes,
+                },
+            scanner:
+                Scanner {
+                    clamd_socket: mut scanner_clamd_socket,
+                },
         } = self;
 
         // Assertions for bad values
-        const RSMQ_DELAY_LIMIT: u64 = 9999999;
-
         assert!(
-            job_prune_session_secs < RSMQ_DELAY_LIMIT,
-            "Session prune job period time too long",
+            job_prune_session_secs > 0,
+            "Session prune job period time cannot be zero",
+        );
+        assert!(
+            job_prune_text_secs > 0,
+            "Text prune job period time cannot be zero",
+        );
+        assert!(
+            job_name_change_refill_secs > 0,
+            "Name change refill job period time cannot be zero",
+        );
+        assert!(
+            job_lift_expired_punishments_secs > 0,
+            "Expired punishment cleanup job period time cannot be zero",
+        );
+        assert!(
+            job_prune_page_locks_secs > 0,
+            "Page lock prune job period time cannot be zero",
         );
         assert!(
-            job_prune_text_secs < RSMQ_DELAY_LIMIT,
-            "Text prune job period time too long",
+            job_prune_file_uploads_secs > 0,
+            "File upload prune job period time cannot be zero",
         );
+        assert!(edit_lock_secs > 0, "Edit lock duration cannot be zero");
         assert!(
-            job_name_change_refill_secs < RSMQ_DELAY_LIMIT,
-            "Name change refill job period time too long",
+            upload_session_secs > 0,
+            "Upload session duration cannot be zero",
         );
+        assert!(site_file_quota_bytes > 0, "Site file quota cannot be zero");
+        assert!(user_file_quota_bytes > 0, "User file quota cannot be zero");
         assert!(
-            job_lift_expired_punishments_secs < RSMQ_DELAY_LIMIT,
-            "Expired punishment cleanup job period time too long",
+            !image_derivative_sizes.contains(&0),
+            "Image derivative size cannot be zero",
         );
 
+        // Derivatives are looked up by size, so keep them in order
+        image_derivative_sizes.sort_unstable();
+        image_derivative_sizes.dedup();
+
         // Prefix domains with '.' so we can do easy subdomain checks
         // and concatenations.
         let (main_domain, main_domain_no_dot) = prefix_domain(main_domain);
@@ -332,6 +412,13 @@ impl ConfigFile {
             }
         }
 
+        // Same for scanner_clamd_socket.
+        if let Some(ref path) = scanner_clamd_socket {
+            if path.as_os_str().is_empty() {
+                scanner_clamd_socket = None;
+            }
+        }
+
         Config {
             raw_toml,
             raw_toml_path,
@@ -367,6 +454,8 @@ impl ConfigFile {
             totp_time_skew: time_skew,
             job_workers,
             job_max_attempts,
+            job_retry_delay: StdDuration::from_secs(job_retry_delay_secs),
+            job_process_time: StdDuration::from_secs(job_process_time_secs),
             job_work_delay: StdDuration::from_millis(job_work_delay_ms),
             job_min_poll_delay: StdDuration::from_secs(job_min_poll_delay_secs),
             job_max_poll_delay: StdDuration::from_secs(job_max_poll_delay_secs),
@@ -376,7 +465,10 @@ impl ConfigFile {
             job_lift_expired_punishments: StdDuration::from_secs(
                 job_lift_expired_punishments_secs,
             ),
+            job_prune_page_locks: StdDuration::from_secs(job_prune_page_locks_secs),
+            job_prune_file_uploads: StdDuration::from_secs(job_prune_file_uploads_secs),
             render_timeout: StdDuration::from_millis(render_timeout_ms),
+            include_max_depth,
             rerender_skip: rerender_skip
                 .iter()
                 .map(
@@ -414,6 +506,14 @@ impl ConfigFile {
             maximum_message_subject_bytes,
             maximum_message_body_bytes,
             maximum_message_recipients,
+            page_edit_lock_duration: time_duration!(from_secs, edit_lock_secs),
+            maximum_upload_bytes,
+            upload_session_duration: time_duration!(from_secs, upload_session_secs),
+            site_file_quota_bytes,
+            user_file_quota_bytes,
+            image_convert_path,
+            image_derivative_sizes,
+            scanner_clamd_socket,
         }
     }
 }
@@ -119,9 +119,17 @@ pub struct Config {
     /// The number of job workers to run in this process.
     pub job_workers: NonZeroU16,
 
-    /// How many times to retry a job before deleting it anyways.
+    /// How many times to retry a job before moving it to the dead-letter table.
     pub job_max_attempts: u16,
 
+    /// How long to wait before retrying a failed job.
+    /// This doubles with each failed attempt.
+    pub job_retry_delay: StdDuration,
+
+    /// How long a job may run before it is assumed that its worker died.
+    /// After this, the job becomes available to other workers.
+    pub job_process_time: StdDuration,
+
     /// How long to sleep after finishing work on a job.
     pub job_work_delay: StdDuration,
 
@@ -145,9 +153,21 @@ pub struct Config {
     /// How often to run the "lift expired punishments" recurring job.
     pub job_lift_expired_punishments: StdDuration,
 
+    /// How often to run the "prune expired page locks" recurring job.
+    pub job_prune_page_locks: StdDuration,
+
+    /// How often to run the "prune abandoned file uploads" recurring job.
+    pub job_prune_file_uploads: StdDuration,
+
     /// Maximum run time for a render request.
     pub render_timeout: StdDuration,
 
+    /// How many layers deep page includes may be nested.
+    ///
+    /// Includes deeper than this are replaced with an error message
+    /// rather than being resolved.
+    pub include_max_depth: u32,
+
     /// In what circumstances a page rerender should be skipped.
     ///
     /// A list of rerender job depths and durations. If any item in this
@@ -198,6 +218,34 @@ pub struct Config {
 
     /// Maximum number of total recipients allowed in a direct message.
     pub maximum_message_recipients: usize,
+
+    /// How long an edit lock on a page lasts before it expires.
+    pub page_edit_lock_duration: TimeDuration,
+
+    /// Maximum size of a file uploaded in chunks.
+    pub maximum_upload_bytes: u64,
+
+    /// How long an upload session may be idle before it is abandoned.
+    pub upload_session_duration: TimeDuration,
+
+    /// Maximum storage used by all files on a site.
+    pub site_file_quota_bytes: u64,
+
+    /// Maximum storage used by all files uploaded by a user.
+    pub user_file_quota_bytes: u64,
+
+    /// Path to the ImageMagick `convert` binary, used to resize images.
+    pub image_convert_path: PathBuf,
+
+    /// The sizes of derivatives to generate for uploaded images, in ascending order.
+    ///
+    /// Each is the maximum width and height, in pixels, of a resized copy.
+    pub image_derivative_sizes: Vec<u32>,
+
+    /// Path to the socket for ClamAV's `clamd`, used to scan uploads for malware.
+    ///
+    /// If `None`, then a stub scanner is used, which only detects the EICAR test file.
+    pub scanner_clamd_socket: Option<PathBuf>,
 }
 
 impl Config {
@@ -99,6 +99,9 @@ pub struct User {
     pub biography: Option<String>,
     pub user_page: Option<String>,
     pub aliases: Vec<String>,
+
+    #[serde(default)]
+    pub platform_staff: bool,
 }
 
 #[derive(Deserialize, Debug)]
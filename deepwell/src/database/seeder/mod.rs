@@ -23,13 +23,15 @@ mod data;
 use self::data::{SeedData, SitePages};
 use crate::api::ServerState;
 use crate::constants::{ADMIN_USER_ID, SYSTEM_USER_ID};
-use crate::models::sea_orm_active_enums::AliasType;
+use crate::models::sea_orm_active_enums::{AliasType, PageAttributionType};
 use crate::services::alias::{AliasService, CreateAlias};
-use crate::services::filter::{CreateFilter, FilterService};
+use crate::services::filter::{CreateFilterBody, FilterService};
 use crate::services::page::{CreatePage, PageService};
+use crate::services::page_attribution::{CreatePageAttribution, PageAttributionService};
 use crate::services::site::{CreateSite, CreateSiteOutput, SiteService};
 use crate::services::user::{CreateUser, CreateUserOutput, UpdateUserBody, UserService};
 use crate::services::ServiceContext;
+use crate::utils::now;
 use crate::web::{ProvidedValue, Reference};
 use anyhow::Result;
 use sea_orm::{
@@ -104,6 +106,10 @@ pub async fn seed(state: &ServerState) -> Result<()> {
         )
         .await?;
 
+        if user.platform_staff {
+            UserService::set_platform_staff(&ctx, user_id, true).await?;
+        }
+
         // Queue up aliases to add
         //
         // This has to be a separate list, since the alias is "added"
@@ -145,7 +151,11 @@ pub async fn seed(state: &ServerState) -> Result<()> {
     {
         info!("Creating seed site '{}' (slug {})", site.name, site.slug);
 
-        let CreateSiteOutput { site_id, .. } = SiteService::create(
+        let CreateSiteOutput {
+            site_id,
+            site_user_id,
+            ..
+        } = SiteService::create(
             &ctx,
             CreateSite {
                 slug: site.slug,
@@ -153,6 +163,7 @@ pub async fn seed(state: &ServerState) -> Result<()> {
                 tagline: site.tagline,
                 description: site.description,
                 locale: site.locale,
+                user_id: ADMIN_USER_ID,
             },
         )
         .await?;
@@ -191,8 +202,18 @@ pub async fn seed(state: &ServerState) -> Result<()> {
             )
             .await?;
 
-            // TODO add attribution with site_user as author
-            let _ = model;
+            PageAttributionService::create(
+                &ctx,
+                CreatePageAttribution {
+                    site_id,
+                    page: Reference::Id(model.page_id),
+                    user_id: site_user_id,
+                    attribution_type: PageAttributionType::Author,
+                    attribution_date: now().date(),
+                    created_by: SYSTEM_USER_ID,
+                },
+            )
+            .await?;
         }
     }
 
@@ -227,7 +248,7 @@ pub async fn seed(state: &ServerState) -> Result<()> {
         FilterService::create(
             &ctx,
             site_id,
-            CreateFilter {
+            CreateFilterBody {
                 affects_user: filter.user,
                 affects_email: filter.email,
                 affects_page: filter.page,
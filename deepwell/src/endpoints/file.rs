@@ -20,12 +20,15 @@
 
 use super::prelude::*;
 use crate::models::file::Model as FileModel;
+use crate::models::file_hard_deletion::Model as FileHardDeletionModel;
 use crate::models::file_revision::Model as FileRevisionModel;
 use crate::services::file::{
-    DeleteFile, DeleteFileOutput, EditFile, EditFileOutput, GetFileDetails,
-    GetFileOutput, MoveFile, MoveFileOutput, RestoreFile, RestoreFileOutput, UploadFile,
-    UploadFileOutput,
+    DeleteFile, DeleteFileOutput, EditFile, EditFileOutput, FileDerivative,
+    FileLicenseEntry, GetFileDetails, GetFileOutput, GetFilesByLicense, GetStorageUsage,
+    GetStorageUsageOutput, HardDeleteFile, MoveFile, MoveFileOutput, RestoreFile,
+    RestoreFileOutput, UploadFile, UploadFileOutput,
 };
+use crate::services::permission::PermissionType;
 use crate::services::Result;
 use crate::web::{Bytes, FileDetails};
 
@@ -72,6 +75,14 @@ pub async fn file_upload(
         input.site_id,
     );
 
+    PermissionService::check_page_id(
+        ctx,
+        input.page_id,
+        input.user_id,
+        PermissionType::Upload,
+    )
+    .await?;
+
     FileService::upload(ctx, input).await
 }
 
@@ -86,6 +97,14 @@ pub async fn file_edit(
         input.file_id, input.page_id, input.site_id,
     );
 
+    PermissionService::check_page_id(
+        ctx,
+        input.page_id,
+        input.user_id,
+        PermissionType::Upload,
+    )
+    .await?;
+
     FileService::edit(ctx, input).await
 }
 
@@ -100,6 +119,14 @@ pub async fn file_delete(
         input.file, input.page_id, input.site_id,
     );
 
+    PermissionService::check_page_id(
+        ctx,
+        input.page_id,
+        input.user_id,
+        PermissionType::Delete,
+    )
+    .await?;
+
     FileService::delete(ctx, input).await
 }
 
@@ -114,6 +141,14 @@ pub async fn file_restore(
         input.file_id, input.page_id, input.site_id,
     );
 
+    PermissionService::check_page_id(
+        ctx,
+        input.page_id,
+        input.user_id,
+        PermissionType::Delete,
+    )
+    .await?;
+
     FileService::restore(ctx, input).await
 }
 
@@ -128,20 +163,85 @@ pub async fn file_move(
         input.file_id, input.current_page_id, input.destination_page_id, input.site_id,
     );
 
+    try_join!(
+        PermissionService::check_page_id(
+            ctx,
+            input.current_page_id,
+            input.user_id,
+            PermissionType::Upload,
+        ),
+        PermissionService::check_page_id(
+            ctx,
+            input.destination_page_id,
+            input.user_id,
+            PermissionType::Upload,
+        ),
+    )?;
+
     FileService::r#move(ctx, input).await
 }
 
 pub async fn file_hard_delete(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
-) -> Result<()> {
-    let file_id: i64 = params.one()?;
+) -> Result<FileHardDeletionModel> {
+    let input: HardDeleteFile = params.parse()?;
+
+    // Hard deletions cannot be undone, so only platform staff may do them
+    PermissionService::check_platform_staff(ctx, input.user_id).await?;
 
     info!(
-        "Hard deleting file ID {file_id} and all duplicates, including underlying data",
+        "Hard deleting file ID {} and all its revisions, including underlying data",
+        input.file_id,
     );
 
-    FileService::hard_delete_all(ctx, file_id).await
+    FileService::hard_delete_all(ctx, input).await
+}
+
+pub async fn file_license_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<FileLicenseEntry>> {
+    let input: GetFilesByLicense = params.parse()?;
+
+    info!(
+        "Listing file licensing for site ID {} (license {:?}, non-compliant only {})",
+        input.site_id, input.license, input.non_compliant,
+    );
+
+    PermissionService::check(
+        ctx,
+        input.site_id,
+        None,
+        input.user_id,
+        PermissionType::Moderate,
+    )
+    .await?;
+
+    FileService::get_by_license(ctx, input).await
+}
+
+pub async fn file_storage_usage(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetStorageUsageOutput> {
+    let input: GetStorageUsage = params.parse()?;
+
+    info!(
+        "Getting file storage usage for site ID {} and user ID {}",
+        input.site_id, input.user_id,
+    );
+
+    PermissionService::check(
+        ctx,
+        input.site_id,
+        None,
+        input.user_id,
+        PermissionType::Upload,
+    )
+    .await?;
+
+    FileService::get_storage_usage(ctx, input).await
 }
 
 async fn build_file_response(
@@ -150,7 +250,45 @@ async fn build_file_response(
     revision: FileRevisionModel,
     details: FileDetails,
 ) -> Result<GetFileOutput> {
-    let data = BlobService::get_maybe(ctx, details.data, &revision.s3_hash).await?;
+    let derivatives = if details.derivatives || details.size.is_some() {
+        BlobService::get_derivatives(ctx, &revision.s3_hash).await?
+    } else {
+        vec![]
+    };
+
+    // Use a resized copy if one was requested and is available
+    let (data_hash, data_derivative) = match details.size {
+        Some(size) => {
+            let derivative = derivatives
+                .iter()
+                .find(|derivative| derivative.max_dimension as u32 >= size)
+                .or_else(|| derivatives.last());
+
+            match derivative {
+                Some(derivative) => (
+                    derivative.derivative_hash.as_slice(),
+                    Some(derivative.max_dimension),
+                ),
+                None => (revision.s3_hash.as_slice(), None),
+            }
+        }
+        None => (revision.s3_hash.as_slice(), None),
+    };
+
+    let data = BlobService::get_maybe(ctx, details.data, data_hash).await?;
+    let data_derivative = data.as_ref().and(data_derivative);
+    let derivatives = details.derivatives.then(|| {
+        derivatives
+            .iter()
+            .map(|derivative| FileDerivative {
+                max_dimension: derivative.max_dimension,
+                created_at: derivative.created_at,
+                mime: derivative.mime.clone(),
+                size: derivative.size,
+            })
+            .collect()
+    });
+
     Ok(GetFileOutput {
         file_id: file.file_id,
         file_created_at: file.created_at,
@@ -164,6 +302,8 @@ async fn build_file_response(
         revision_user_id: revision.user_id,
         name: file.name,
         data: data.map(Bytes::from),
+        data_derivative,
+        derivatives,
         mime: revision.mime_hint,
         size: revision.size_hint,
         licensing: revision.licensing,
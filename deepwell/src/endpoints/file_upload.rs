@@ -0,0 +1,89 @@
+/*
+ * endpoints/file_upload.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::file::UploadFileOutput;
+use crate::services::file_upload::{
+    FileUploadStatus, GetFileUpload, StartFileUpload, StartFileUploadOutput,
+    UploadFilePart,
+};
+use crate::services::permission::PermissionType;
+
+pub async fn file_upload_start(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<StartFileUploadOutput> {
+    let input: StartFileUpload = params.parse()?;
+
+    info!(
+        "Starting chunked upload of file '{}' to page ID {} in site ID {}",
+        input.name, input.page_id, input.site_id,
+    );
+
+    PermissionService::check_page_id(
+        ctx,
+        input.page_id,
+        input.user_id,
+        PermissionType::Upload,
+    )
+    .await?;
+
+    FileUploadService::start(ctx, input).await
+}
+
+pub async fn file_upload_part(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: UploadFilePart = params.parse()?;
+    FileUploadService::upload_part(ctx, input).await
+}
+
+pub async fn file_upload_status(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FileUploadStatus> {
+    let input: GetFileUpload = params.parse()?;
+    FileUploadService::get_status(ctx, input).await
+}
+
+pub async fn file_upload_finish(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<UploadFileOutput> {
+    let input: GetFileUpload = params.parse()?;
+
+    // Check that the user can still upload to this page
+    let FileUploadStatus { page_id, .. } =
+        FileUploadService::get_status(ctx, input.clone()).await?;
+
+    PermissionService::check_page_id(ctx, page_id, input.user_id, PermissionType::Upload)
+        .await?;
+
+    FileUploadService::finish(ctx, input).await
+}
+
+pub async fn file_upload_cancel(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: GetFileUpload = params.parse()?;
+    FileUploadService::cancel(ctx, input).await
+}
@@ -0,0 +1,146 @@
+/*
+ * endpoints/filter.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::filter::Model as FilterModel;
+use crate::models::filter_violation::Model as FilterViolationModel;
+use crate::services::filter::{
+    CreateFilter, FilterClass, FilterReference, FilterTestMatch, GetFilterViolations,
+    GetFilters, TestFilter, UpdateFilter,
+};
+use crate::services::permission::PermissionType;
+
+pub async fn filter_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FilterModel> {
+    let CreateFilter {
+        site_id,
+        user_id,
+        body,
+    } = params.parse()?;
+
+    check_filter_permission(ctx, site_id, user_id).await?;
+    FilterService::create(ctx, site_id, body).await
+}
+
+pub async fn filter_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<FilterModel>> {
+    let FilterReference { filter_id, user_id } = params.parse()?;
+
+    match FilterService::get_optional(ctx, filter_id).await? {
+        None => Ok(None),
+        Some(filter) => {
+            check_filter_permission(ctx, filter.site_id, user_id).await?;
+            Ok(Some(filter))
+        }
+    }
+}
+
+pub async fn filter_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<FilterModel>> {
+    let GetFilters {
+        site_id,
+        user_id,
+        filter_type,
+        deleted,
+    } = params.parse()?;
+
+    check_filter_permission(ctx, site_id, user_id).await?;
+    FilterService::get_all(ctx, FilterClass::from(site_id), filter_type, deleted).await
+}
+
+pub async fn filter_edit(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FilterModel> {
+    let input: UpdateFilter = params.parse()?;
+    let filter = FilterService::get(ctx, input.filter_id).await?;
+    check_filter_permission(ctx, filter.site_id, input.user_id).await?;
+    FilterService::update(ctx, input).await
+}
+
+pub async fn filter_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let FilterReference { filter_id, user_id } = params.parse()?;
+    let filter = FilterService::get(ctx, filter_id).await?;
+    check_filter_permission(ctx, filter.site_id, user_id).await?;
+    FilterService::delete(ctx, filter_id).await
+}
+
+pub async fn filter_restore(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<FilterModel> {
+    let FilterReference { filter_id, user_id } = params.parse()?;
+    let filter = FilterService::get(ctx, filter_id).await?;
+    check_filter_permission(ctx, filter.site_id, user_id).await?;
+    FilterService::restore(ctx, filter_id).await
+}
+
+pub async fn filter_test(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<FilterTestMatch>> {
+    let input: TestFilter = params.parse()?;
+    check_filter_permission(ctx, input.site_id, input.user_id).await?;
+    FilterService::test(ctx, input).await
+}
+
+pub async fn filter_violation_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<FilterViolationModel>> {
+    let input: GetFilterViolations = params.parse()?;
+
+    // Excerpts may include email addresses and other private details,
+    // so platform violations are only visible to platform staff.
+    check_filter_permission(ctx, input.site_id, input.user_id).await?;
+    FilterService::get_violations(ctx, input).await
+}
+
+/// Ensures the user can manage the filters for this site.
+///
+/// If `site_id` is `None`, then this refers to the platform filters.
+async fn check_filter_permission(
+    ctx: &ServiceContext<'_>,
+    site_id: Option<i64>,
+    user_id: i64,
+) -> Result<()> {
+    match site_id {
+        Some(site_id) => {
+            PermissionService::check(
+                ctx,
+                site_id,
+                None,
+                user_id,
+                PermissionType::Moderate,
+            )
+            .await
+        }
+        None => PermissionService::check_platform_staff(ctx, user_id).await,
+    }
+}
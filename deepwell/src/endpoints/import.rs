@@ -0,0 +1,114 @@
+/*
+ * endpoints/import.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::import::{
+    ImportCompletePage, ImportFile, ImportFileRevision, ImportPage,
+    ImportPageAttribution, ImportPageParent, ImportPageRevision, ImportPageVote,
+    ImportProgress, ImportSite, ImportUser,
+};
+
+pub async fn import_user(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let input: ImportUser = params.parse()?;
+    ImportService::add_user(ctx, input).await
+}
+
+pub async fn import_site(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<i64> {
+    let input: ImportSite = params.parse()?;
+    ImportService::add_site(ctx, input).await
+}
+
+pub async fn import_page(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let input: ImportPage = params.parse()?;
+    ImportService::add_page(ctx, input).await
+}
+
+pub async fn import_page_revision(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let input: ImportPageRevision = params.parse()?;
+    ImportService::add_page_revision(ctx, input).await
+}
+
+pub async fn import_page_vote(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let input: ImportPageVote = params.parse()?;
+    ImportService::add_page_vote(ctx, input).await
+}
+
+pub async fn import_page_parent(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let input: ImportPageParent = params.parse()?;
+    ImportService::add_page_parent(ctx, input).await
+}
+
+pub async fn import_page_attribution(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let input: ImportPageAttribution = params.parse()?;
+    ImportService::add_page_attribution(ctx, input).await
+}
+
+pub async fn import_file(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let input: ImportFile = params.parse()?;
+    ImportService::add_file(ctx, input).await
+}
+
+pub async fn import_file_revision(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let input: ImportFileRevision = params.parse()?;
+    ImportService::add_file_revision(ctx, input).await
+}
+
+pub async fn import_page_complete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: ImportCompletePage = params.parse()?;
+    ImportService::complete_page(ctx, input).await
+}
+
+pub async fn import_progress(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<ImportProgress> {
+    let site_id: i64 = params.one()?;
+    ImportService::get_progress(ctx, site_id).await
+}
@@ -0,0 +1,54 @@
+/*
+ * endpoints/job.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::job_dead_letter::Model as JobDeadLetterModel;
+use crate::models::job_queue::Model as JobQueueModel;
+
+pub async fn job_list(
+    ctx: &ServiceContext<'_>,
+    _params: Params<'static>,
+) -> Result<Vec<JobQueueModel>> {
+    info!("Getting all queued jobs");
+    JobService::get_queued(ctx).await
+}
+
+pub async fn job_list_failed(
+    ctx: &ServiceContext<'_>,
+    _params: Params<'static>,
+) -> Result<Vec<JobDeadLetterModel>> {
+    info!("Getting all failed jobs");
+    JobService::get_failed(ctx).await
+}
+
+pub async fn job_retry(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<JobQueueModel> {
+    let job_id: i64 = params.one()?;
+    info!("Retrying failed job ID {job_id}");
+    JobService::retry(ctx, job_id).await
+}
+
+pub async fn job_cancel(ctx: &ServiceContext<'_>, params: Params<'static>) -> Result<()> {
+    let job_id: i64 = params.one()?;
+    info!("Cancelling queued job ID {job_id}");
+    JobService::cancel(ctx, job_id).await
+}
@@ -19,10 +19,18 @@
  */
 
 use super::prelude::*;
+use crate::models::message::Model as MessageModel;
 use crate::models::message_draft::Model as MessageDraftModel;
 use crate::models::message_record::Model as MessageRecordModel;
+use crate::models::message_report::Model as MessageReportModel;
 use crate::services::message::{
-    CreateMessageDraft, DeleteMessageDraft, SendMessageDraft, UpdateMessageDraft,
+    CreateMessageDraft, DeleteMessage, DeleteMessageDraft, GetMessage, GetMessageOutput,
+    GetMessageThread, GetMessages, MessageListItem, SendMessageDraft, UpdateMessageDraft,
+    UpdateMessageFlags,
+};
+use crate::services::message_report::{
+    CreateMessageReport, DismissMessageReport, GetMessageReports, MessageReportOutput,
+    ResolveMessageReport,
 };
 
 pub async fn message_draft_create(
@@ -63,3 +71,91 @@ pub async fn message_draft_send(
     info!("Sending message draft with ID {message_draft_id}");
     MessageService::send(ctx, &message_draft_id).await
 }
+
+pub async fn message_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetMessageOutput> {
+    let input: GetMessage = params.parse()?;
+    info!(
+        "Getting message {} for user ID {}",
+        input.record_id, input.user_id,
+    );
+    MessageService::get_full(ctx, input).await
+}
+
+pub async fn message_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<MessageListItem>> {
+    let input: GetMessages = params.parse()?;
+    MessageService::get_folder(ctx, input).await
+}
+
+pub async fn message_unread_count(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<u64> {
+    let user_id: i64 = params.one()?;
+    MessageService::count_unread(ctx, user_id).await
+}
+
+pub async fn message_thread(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<MessageListItem>> {
+    let input: GetMessageThread = params.parse()?;
+    MessageService::get_thread(ctx, input).await
+}
+
+pub async fn message_edit_flags(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<MessageModel> {
+    let input: UpdateMessageFlags = params.parse()?;
+    MessageService::update_flags(ctx, input).await
+}
+
+pub async fn message_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: DeleteMessage = params.parse()?;
+    MessageService::delete(ctx, input).await
+}
+
+pub async fn message_report_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<MessageReportModel> {
+    let input: CreateMessageReport = params.parse()?;
+    MessageReportService::create(ctx, input).await
+}
+
+pub async fn message_report_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<MessageReportOutput>> {
+    let input: GetMessageReports = params.parse()?;
+    info!(
+        "Getting message reports for site ID {:?} (status {:?})",
+        input.site_id, input.status,
+    );
+    MessageReportService::get_all(ctx, input).await
+}
+
+pub async fn message_report_resolve(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<MessageReportModel> {
+    let input: ResolveMessageReport = params.parse()?;
+    MessageReportService::resolve(ctx, input).await
+}
+
+pub async fn message_report_dismiss(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<MessageReportModel> {
+    let input: DismissMessageReport = params.parse()?;
+    MessageReportService::dismiss(ctx, input).await
+}
@@ -32,11 +32,13 @@ mod prelude {
     pub use crate::api::ServerState;
     pub use crate::services::{
         AliasService, BlobService, CategoryService, DomainService, Error as ServiceError,
-        FileRevisionService, FileService, LinkService, MessageReportService,
-        MessageService, MfaService, PageRevisionService, PageService, ParentService,
-        RelationService, RenderService, Result, ScoreService, ServiceContext,
-        SessionService, SiteService, StdResult, TextService, UserService, ViewService,
-        VoteService,
+        FileRevisionService, FileService, FileUploadService, FilterService,
+        ImportService, JobService, LinkService, MessageReportService, MessageService,
+        MfaService, NotificationService, PageAttributionService, PageLockService,
+        PageQueryService, PageRevisionService, PageService, ParentService,
+        PermissionService, RelationService, RenderService, Result, ScoreService,
+        ServiceContext, SessionService, SiteService, StdResult, TextService, UserService,
+        ViewService, VoteService,
     };
     pub use jsonrpsee::types::params::Params;
     pub use std::convert::TryFrom;
@@ -48,17 +50,26 @@ pub mod domain;
 pub mod email;
 pub mod file;
 pub mod file_revision;
+pub mod file_upload;
+pub mod filter;
+pub mod import;
+pub mod job;
 pub mod link;
 pub mod locale;
 pub mod message;
 pub mod misc;
+pub mod notification;
 pub mod page;
+pub mod page_attribution;
+pub mod page_lock;
 pub mod page_revision;
 pub mod parent;
+pub mod permission;
 pub mod site;
 pub mod site_member;
 pub mod text;
 pub mod user;
 pub mod user_bot;
+pub mod user_contact;
 pub mod view;
 pub mod vote;
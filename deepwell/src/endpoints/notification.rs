@@ -0,0 +1,57 @@
+/*
+ * endpoints/notification.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::notification::Model as NotificationModel;
+use crate::services::notification::{
+    GetNotifications, MarkNotificationsRead, NotificationDigestEntry,
+};
+
+pub async fn notification_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<NotificationModel>> {
+    let input: GetNotifications = params.parse()?;
+    NotificationService::get_all(ctx, input).await
+}
+
+pub async fn notification_unread_count(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<u64> {
+    let user_id: i64 = params.one()?;
+    NotificationService::count_unread(ctx, user_id).await
+}
+
+pub async fn notification_mark_read(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<u64> {
+    let input: MarkNotificationsRead = params.parse()?;
+    NotificationService::mark_read(ctx, input).await
+}
+
+pub async fn notification_digest(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<NotificationDigestEntry>> {
+    let user_id: i64 = params.one()?;
+    NotificationService::get_digest(ctx, user_id).await
+}
@@ -23,8 +23,13 @@ use crate::models::page::Model as PageModel;
 use crate::services::page::{
     CreatePage, CreatePageOutput, DeletePage, DeletePageOutput, EditPage, EditPageOutput,
     GetPageAnyDetails, GetPageDirect, GetPageOutput, GetPageReferenceDetails, MovePage,
-    MovePageOutput, RestorePage, RestorePageOutput, RollbackPage,
+    MovePageOutput, RestorePage, RestorePageOutput, RollbackPage, UndoPage,
+    UndoPageOutput,
 };
+use crate::services::page_lock::ReleaseEditLock;
+use crate::services::page_query::{PageQuery, PageQueryOutput};
+use crate::services::permission::PermissionType;
+use crate::services::relation::{GetPageStars, GetPageWatches, RelationPage};
 use crate::services::{Result, TextService};
 use crate::web::{PageDetails, Reference};
 
@@ -34,6 +39,16 @@ pub async fn page_create(
 ) -> Result<CreatePageOutput> {
     let input: CreatePage = params.parse()?;
     info!("Creating new page in site ID {}", input.site_id);
+
+    PermissionService::check_slug(
+        ctx,
+        input.site_id,
+        &input.slug,
+        input.user_id,
+        PermissionType::Create,
+    )
+    .await?;
+
     PageService::create(ctx, input).await
 }
 
@@ -78,6 +93,36 @@ pub async fn page_edit(
 ) -> Result<Option<EditPageOutput>> {
     let input: EditPage = params.parse()?;
     info!("Editing page {:?} in site ID {}", input.page, input.site_id);
+
+    PermissionService::check_page(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+        PermissionType::Edit,
+    )
+    .await?;
+
+    PageLockService::check_protection(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+    )
+    .await?;
+
+    // Saving ends the user's edit session.
+    // If the edit fails, this is rolled back with the rest of the transaction.
+    PageLockService::release_edit_lock(
+        ctx,
+        ReleaseEditLock {
+            site_id: input.site_id,
+            page: input.page.clone(),
+            user_id: input.user_id,
+        },
+    )
+    .await?;
+
     PageService::edit(ctx, input).await
 }
 
@@ -90,6 +135,24 @@ pub async fn page_delete(
         "Deleting page {:?} in site ID {}",
         input.page, input.site_id,
     );
+
+    PermissionService::check_page(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+        PermissionType::Delete,
+    )
+    .await?;
+
+    PageLockService::check_protection(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+    )
+    .await?;
+
     PageService::delete(ctx, input).await
 }
 
@@ -102,6 +165,34 @@ pub async fn page_move(
         "Moving page {:?} in site ID {} to {}",
         input.page, input.site_id, input.new_slug,
     );
+
+    // Moving is like deleting the page at the old location and creating
+    // it at the new one, so it needs permissions for both categories.
+    try_join!(
+        PermissionService::check_page(
+            ctx,
+            input.site_id,
+            input.page.clone(),
+            input.user_id,
+            PermissionType::Move,
+        ),
+        PermissionService::check_slug(
+            ctx,
+            input.site_id,
+            &input.new_slug,
+            input.user_id,
+            PermissionType::Create,
+        ),
+    )?;
+
+    PageLockService::check_protection(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+    )
+    .await?;
+
     PageService::r#move(ctx, input).await
 }
 
@@ -123,9 +214,30 @@ pub async fn page_restore(
         "Un-deleting page ID {} in site ID {}",
         input.page_id, input.site_id,
     );
+
+    PermissionService::check_page_id(
+        ctx,
+        input.page_id,
+        input.user_id,
+        PermissionType::Delete,
+    )
+    .await?;
+
     PageService::restore(ctx, input).await
 }
 
+pub async fn page_query(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageQueryOutput> {
+    let input: PageQuery = params.parse()?;
+    info!(
+        "Running ListPages query from page ID {} in site ID {}",
+        input.current_page_id, input.current_site_id,
+    );
+    PageQueryService::execute(ctx, input).await
+}
+
 pub async fn page_rollback(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
@@ -137,9 +249,57 @@ pub async fn page_rollback(
         input.page, input.site_id, input.revision_number,
     );
 
+    PermissionService::check_page(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+        PermissionType::Edit,
+    )
+    .await?;
+
+    PageLockService::check_protection(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+    )
+    .await?;
+
     PageService::rollback(ctx, input).await
 }
 
+pub async fn page_undo(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<UndoPageOutput> {
+    let input: UndoPage = params.parse()?;
+
+    info!(
+        "Undoing revision number {} of page {:?} in site ID {}",
+        input.revision_number, input.page, input.site_id,
+    );
+
+    PermissionService::check_page(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+        PermissionType::Edit,
+    )
+    .await?;
+
+    PageLockService::check_protection(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+    )
+    .await?;
+
+    PageService::undo(ctx, input).await
+}
+
 async fn build_page_output(
     ctx: &ServiceContext<'_>,
     page: PageModel,
@@ -192,3 +352,19 @@ async fn build_page_output(
         rating,
     }))
 }
+
+pub async fn page_star_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationPage> {
+    let input: GetPageStars = params.parse()?;
+    RelationService::get_page_stars(ctx, input).await
+}
+
+pub async fn page_watch_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationPage> {
+    let input: GetPageWatches = params.parse()?;
+    RelationService::get_page_watches(ctx, input).await
+}
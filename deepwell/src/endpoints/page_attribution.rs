@@ -0,0 +1,86 @@
+/*
+ * endpoints/page_attribution.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_attribution::Model as PageAttributionModel;
+use crate::services::page_attribution::{
+    CreatePageAttribution, GetPageAttributions, GetUserAttributions,
+    RemovePageAttribution,
+};
+use crate::services::permission::PermissionType;
+
+pub async fn page_attribution_create(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageAttributionModel> {
+    let input: CreatePageAttribution = params.parse()?;
+
+    PermissionService::check_page(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.created_by,
+        PermissionType::Moderate,
+    )
+    .await?;
+
+    PageAttributionService::create(ctx, input).await
+}
+
+pub async fn page_attribution_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageAttributionModel> {
+    let input: RemovePageAttribution = params.parse()?;
+
+    PermissionService::check_page(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.removed_by,
+        PermissionType::Moderate,
+    )
+    .await?;
+
+    PageAttributionService::remove(ctx, input).await
+}
+
+pub async fn page_attribution_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageAttributionModel>> {
+    let GetPageAttributions {
+        site_id,
+        page: reference,
+    } = params.parse()?;
+
+    info!("Getting attributions for page {reference:?} in site ID {site_id}");
+    let page = PageService::get(ctx, site_id, reference).await?;
+    PageAttributionService::get_all_for_page(ctx, page.page_id).await
+}
+
+pub async fn user_attribution_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<PageAttributionModel>> {
+    let input: GetUserAttributions = params.parse()?;
+    info!("Getting page attributions for user ID {}", input.user_id);
+    PageAttributionService::get_all_for_user(ctx, input).await
+}
@@ -0,0 +1,117 @@
+/*
+ * endpoints/page_lock.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_lock::Model as PageLockModel;
+use crate::services::page_lock::{
+    AcquireEditLock, AcquireEditLockOutput, GetPageLocks, GetPageLocksOutput,
+    ProtectPage, ReleaseEditLock, UnprotectPage,
+};
+use crate::services::permission::PermissionType;
+
+pub async fn page_lock_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<GetPageLocksOutput> {
+    let input: GetPageLocks = params.parse()?;
+    info!(
+        "Getting locks for page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+    PageLockService::get_all(ctx, input).await
+}
+
+pub async fn page_lock_acquire(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<AcquireEditLockOutput> {
+    let input: AcquireEditLock = params.parse()?;
+    info!(
+        "Acquiring edit lock for page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+
+    PermissionService::check_page(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+        PermissionType::Edit,
+    )
+    .await?;
+
+    PageLockService::acquire_edit_lock(ctx, input).await
+}
+
+pub async fn page_lock_release(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<bool> {
+    let input: ReleaseEditLock = params.parse()?;
+    info!(
+        "Releasing edit lock for page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+    PageLockService::release_edit_lock(ctx, input).await
+}
+
+pub async fn page_protect(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageLockModel> {
+    let input: ProtectPage = params.parse()?;
+    info!(
+        "Protecting page {:?} in site ID {}",
+        input.page, input.site_id
+    );
+
+    PermissionService::check_page(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+        PermissionType::Moderate,
+    )
+    .await?;
+
+    PageLockService::protect(ctx, input).await
+}
+
+pub async fn page_unprotect(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: UnprotectPage = params.parse()?;
+    info!(
+        "Unprotecting page {:?} in site ID {}",
+        input.page, input.site_id,
+    );
+
+    PermissionService::check_page(
+        ctx,
+        input.site_id,
+        input.page.clone(),
+        input.user_id,
+        PermissionType::Moderate,
+    )
+    .await?;
+
+    PageLockService::unprotect(ctx, input).await
+}
@@ -22,8 +22,9 @@ use super::prelude::*;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::services::page::GetPageReferenceDetails;
 use crate::services::page_revision::{
-    GetPageRevision, GetPageRevisionDetails, GetPageRevisionRangeDetails,
-    PageRevisionCountOutput, PageRevisionModelFiltered, UpdatePageRevisionDetails,
+    diff_revisions, GetPageRevision, GetPageRevisionDetails, GetPageRevisionDiff,
+    GetPageRevisionRangeDetails, PageRevisionCountOutput, PageRevisionDiffOutput,
+    PageRevisionModelFiltered, UpdatePageRevisionDetails,
 };
 use crate::services::{Result, TextService};
 use crate::web::PageDetails;
@@ -108,6 +109,40 @@ pub async fn page_revision_range(
     filter_and_populate_revisions(ctx, revisions, details).await
 }
 
+pub async fn page_revision_diff(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<PageRevisionDiffOutput> {
+    let GetPageRevisionDiff {
+        site_id,
+        page_id,
+        from_revision_number,
+        to_revision_number,
+    } = params.parse()?;
+
+    info!(
+        "Comparing revisions {from_revision_number} and {to_revision_number} for page ID {page_id} in site ID {site_id}",
+    );
+
+    let (from, to) = try_join!(
+        PageRevisionService::get(ctx, site_id, page_id, from_revision_number),
+        PageRevisionService::get(ctx, site_id, page_id, to_revision_number),
+    )?;
+
+    // Only compare what page_revision_get would show
+    let details = PageDetails {
+        wikitext: true,
+        compiled_html: false,
+    };
+
+    let (from, to) = try_join!(
+        filter_and_populate_revision(ctx, from, details),
+        filter_and_populate_revision(ctx, to, details),
+    )?;
+
+    Ok(diff_revisions(&from, &to))
+}
+
 // Helper functions
 
 async fn filter_and_populate_revision(
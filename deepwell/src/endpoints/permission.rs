@@ -0,0 +1,64 @@
+/*
+ * endpoints/permission.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::site_permission::Model as SitePermissionModel;
+use crate::services::permission::{
+    GetUserPermissions, SetPermissionRule, SetSiteRole, UserPermissions,
+};
+
+pub async fn permission_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<UserPermissions> {
+    let input: GetUserPermissions = params.parse()?;
+
+    info!(
+        "Getting permissions for user ID {:?} in site ID {}",
+        input.user_id, input.site_id,
+    );
+
+    PermissionService::get_user_permissions(ctx, input).await
+}
+
+pub async fn permission_rule_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Vec<SitePermissionModel>> {
+    let site_id: i64 = params.one()?;
+    info!("Getting permission rules for site ID {site_id}");
+    PermissionService::get_rules(ctx, site_id).await
+}
+
+pub async fn permission_rule_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: SetPermissionRule = params.parse()?;
+    PermissionService::set_rule(ctx, input).await
+}
+
+pub async fn site_role_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: SetSiteRole = params.parse()?;
+    PermissionService::set_role(ctx, input).await
+}
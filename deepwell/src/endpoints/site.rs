@@ -22,7 +22,7 @@ use super::prelude::*;
 use crate::models::sea_orm_active_enums::AliasType;
 use crate::models::site::Model as SiteModel;
 use crate::services::site::{
-    CreateSite, CreateSiteOutput, GetSite, GetSiteOutput, UpdateSite,
+    CreateSite, CreateSiteOutput, GetSite, GetSiteOutput, SetSiteFileQuota, UpdateSite,
 };
 
 pub async fn site_create(
@@ -69,3 +69,17 @@ pub async fn site_update(
     info!("Updating site {:?}", site);
     SiteService::update(ctx, site, body, user_id).await
 }
+
+pub async fn site_file_quota_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<SiteModel> {
+    let SetSiteFileQuota {
+        site_id,
+        user_id,
+        quota_bytes,
+    } = params.parse()?;
+
+    PermissionService::check_platform_staff(ctx, user_id).await?;
+    SiteService::set_file_quota(ctx, site_id, quota_bytes).await
+}
@@ -20,7 +20,11 @@
 
 use super::prelude::*;
 use crate::models::relation::Model as RelationModel;
-use crate::services::relation::{CreateSiteMember, GetSiteMember, RemoveSiteMember};
+use crate::services::permission::{GetUserPermissions, PermissionType};
+use crate::services::relation::{
+    CreateSiteBan, CreateSiteMember, GetSiteBan, GetSiteBans, GetSiteMember,
+    GetSiteMembers, RelationPage, RemoveSiteBan, RemoveSiteMember,
+};
 
 pub async fn membership_get(
     ctx: &ServiceContext<'_>,
@@ -45,3 +49,96 @@ pub async fn membership_delete(
     let input: RemoveSiteMember = params.parse()?;
     RelationService::remove_site_member(ctx, input).await
 }
+
+pub async fn membership_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationPage> {
+    let input: GetSiteMembers = params.parse()?;
+    RelationService::get_site_members(ctx, input).await
+}
+
+pub async fn site_ban_get(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<Option<RelationModel>> {
+    let input: GetSiteBan = params.parse()?;
+    RelationService::get_optional_site_ban(ctx, input).await
+}
+
+pub async fn site_ban_set(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: CreateSiteBan = params.parse()?;
+    check_ban_permission(ctx, input.site_id, input.user_id, input.created_by).await?;
+    RelationService::create_site_ban(ctx, input).await
+}
+
+pub async fn site_ban_delete(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationModel> {
+    let input: RemoveSiteBan = params.parse()?;
+    check_ban_permission(ctx, input.site_id, input.user_id, input.removed_by).await?;
+    RelationService::remove_site_ban(ctx, input).await
+}
+
+pub async fn site_ban_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationPage> {
+    let input: GetSiteBans = params.parse()?;
+
+    PermissionService::check(
+        ctx,
+        input.site_id,
+        None,
+        input.user_id,
+        PermissionType::Moderate,
+    )
+    .await?;
+
+    RelationService::get_site_bans(ctx, input).await
+}
+
+/// Ensures the acting user can ban or unban the given user.
+///
+/// They must be a moderator, and cannot ban staff with a higher role than their own.
+async fn check_ban_permission(
+    ctx: &ServiceContext<'_>,
+    site_id: i64,
+    user_id: i64,
+    acting_user_id: i64,
+) -> Result<()> {
+    PermissionService::check(
+        ctx,
+        site_id,
+        None,
+        acting_user_id,
+        PermissionType::Moderate,
+    )
+    .await?;
+
+    let get_permissions = |user_id| {
+        PermissionService::get_user_permissions(
+            ctx,
+            GetUserPermissions {
+                site_id,
+                user_id: Some(user_id),
+            },
+        )
+    };
+
+    let acting = get_permissions(acting_user_id).await?;
+    let target = get_permissions(user_id).await?;
+    if !acting.satisfies(target.role) {
+        warn!(
+            "User ID {acting_user_id} ({:?}) cannot ban user ID {user_id} ({:?})",
+            acting.role, target.role,
+        );
+        return Err(ServiceError::PermissionDenied);
+    }
+
+    Ok(())
+}
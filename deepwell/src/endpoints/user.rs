@@ -21,6 +21,7 @@
 use super::prelude::*;
 use crate::models::sea_orm_active_enums::AliasType;
 use crate::models::user::Model as UserModel;
+use crate::services::relation::{GetUserBlocks, GetUserFollows, RelationPage};
 use crate::services::user::{
     CreateUser, CreateUserOutput, GetUser, GetUserOutput, UpdateUser,
 };
@@ -34,14 +35,6 @@ pub async fn user_create(
     UserService::create(ctx, input).await
 }
 
-pub async fn user_import(
-    _ctx: &ServiceContext<'_>,
-    _params: Params<'static>,
-) -> Result<CreateUserOutput> {
-    // TODO implement importing user from Wikidot
-    todo!()
-}
-
 pub async fn user_get(
     ctx: &ServiceContext<'_>,
     params: Params<'static>,
@@ -91,3 +84,27 @@ pub async fn user_add_name_change(
     let user = UserService::get(ctx, reference).await?;
     UserService::add_name_change_token(ctx, &user).await
 }
+
+pub async fn user_follower_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationPage> {
+    let input: GetUserFollows = params.parse()?;
+    RelationService::get_user_followers(ctx, input).await
+}
+
+pub async fn user_following_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationPage> {
+    let input: GetUserFollows = params.parse()?;
+    RelationService::get_user_following(ctx, input).await
+}
+
+pub async fn user_block_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationPage> {
+    let input: GetUserBlocks = params.parse()?;
+    RelationService::get_user_blocks(ctx, input).await
+}
@@ -0,0 +1,81 @@
+/*
+ * endpoints/user_contact.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::relation::{
+    CreateUserContactRequest, GetUserContactRequest, GetUserContactRequests,
+    GetUserContacts, RelationPage, RemoveUserContact,
+};
+
+pub async fn user_contact_request_send(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: CreateUserContactRequest = params.parse()?;
+    RelationService::create_user_contact_request(ctx, input).await
+}
+
+pub async fn user_contact_request_accept(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: GetUserContactRequest = params.parse()?;
+    RelationService::accept_user_contact_request(ctx, input).await
+}
+
+pub async fn user_contact_request_decline(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: GetUserContactRequest = params.parse()?;
+    RelationService::decline_user_contact_request(ctx, input).await
+}
+
+pub async fn user_contact_request_cancel(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: GetUserContactRequest = params.parse()?;
+    RelationService::cancel_user_contact_request(ctx, input).await
+}
+
+pub async fn user_contact_request_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationPage> {
+    let input: GetUserContactRequests = params.parse()?;
+    RelationService::get_user_contact_requests(ctx, input).await
+}
+
+pub async fn user_contact_list(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<RelationPage> {
+    let input: GetUserContacts = params.parse()?;
+    RelationService::get_user_contacts(ctx, input).await
+}
+
+pub async fn user_contact_remove(
+    ctx: &ServiceContext<'_>,
+    params: Params<'static>,
+) -> Result<()> {
+    let input: RemoveUserContact = params.parse()?;
+    RelationService::remove_user_contact_pair(ctx, input).await
+}
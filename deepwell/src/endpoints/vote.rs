@@ -20,6 +20,7 @@
 
 use super::prelude::*;
 use crate::models::page_vote::Model as PageVoteModel;
+use crate::services::permission::PermissionType;
 use crate::services::vote::{
     CountVoteHistory, CreateVote, GetVote, GetVoteHistory, VoteAction,
 };
@@ -49,6 +50,14 @@ pub async fn vote_set(
         input.user_id, input.page_id,
     );
 
+    PermissionService::check_page_id(
+        ctx,
+        input.page_id,
+        input.user_id,
+        PermissionType::Vote,
+    )
+    .await?;
+
     VoteService::add(ctx, input).await
 }
 
@@ -63,6 +72,14 @@ pub async fn vote_remove(
         input.user_id, input.page_id,
     );
 
+    PermissionService::check_page_id(
+        ctx,
+        input.page_id,
+        input.user_id,
+        PermissionType::Vote,
+    )
+    .await?;
+
     VoteService::remove(ctx, input).await
 }
 
@@ -77,6 +94,14 @@ pub async fn vote_action(
         acting_user_id,
     } = params.parse()?;
 
+    PermissionService::check_page_id(
+        ctx,
+        page_id,
+        acting_user_id,
+        PermissionType::Moderate,
+    )
+    .await?;
+
     let key = GetVote { page_id, user_id };
     VoteService::action(ctx, key, enable, acting_user_id).await
 }
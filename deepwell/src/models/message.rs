@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key)]
     pub internal_id: i64,
+    pub deleted_at: Option<TimeDateTimeWithTimeZone>,
     #[sea_orm(column_type = "Text")]
     pub record_id: String,
     pub user_id: i64,
@@ -17,6 +18,7 @@ pub struct Model {
     pub flag_self: bool,
     pub flag_trash: bool,
     pub flag_star: bool,
+    pub flag_archive: bool,
     pub tags: Vec<String>,
 }
 
@@ -1,19 +1,25 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+use super::sea_orm_active_enums::MessageReportStatus;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "message_report")]
 pub struct Model {
-    #[sea_orm(primary_key, auto_increment = false)]
+    #[sea_orm(primary_key)]
+    pub message_report_id: i64,
     pub message_id: i64,
-    #[sea_orm(primary_key, auto_increment = false)]
-    pub reported_to_site_id: i64,
+    pub reported_to_site_id: Option<i64>,
     pub created_at: TimeDateTimeWithTimeZone,
     pub updated_at: Option<TimeDateTimeWithTimeZone>,
     #[sea_orm(column_type = "Text")]
     pub reason: String,
+    pub status: MessageReportStatus,
+    pub resolved_at: Option<TimeDateTimeWithTimeZone>,
+    pub resolved_by: Option<i64>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub resolution_comments: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -34,6 +40,14 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Site,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::ResolvedBy",
+        to = "super::user::Column::UserId",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    User,
 }
 
 impl Related<super::message::Entity> for Entity {
@@ -48,4 +62,10 @@ impl Related<super::site::Entity> for Entity {
     }
 }
 
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
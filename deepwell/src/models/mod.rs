@@ -3,19 +3,33 @@
 pub mod prelude;
 
 pub mod alias;
+pub mod blob_derivative;
+pub mod blob_scan;
 pub mod file;
+pub mod file_hard_deletion;
 pub mod file_revision;
+pub mod file_upload;
+pub mod file_upload_part;
 pub mod filter;
+pub mod filter_violation;
+pub mod import_completed_page;
+pub mod import_file_revision;
+pub mod import_page_revision;
+pub mod job_dead_letter;
+pub mod job_queue;
+pub mod job_schedule;
 pub mod message;
 pub mod message_draft;
 pub mod message_recipient;
 pub mod message_record;
 pub mod message_report;
+pub mod notification;
 pub mod page;
 pub mod page_attribution;
 pub mod page_category;
 pub mod page_connection;
 pub mod page_connection_missing;
+pub mod page_data_field;
 pub mod page_link;
 pub mod page_lock;
 pub mod page_parent;
@@ -26,6 +40,7 @@ pub mod sea_orm_active_enums;
 pub mod session;
 pub mod site;
 pub mod site_domain;
+pub mod site_permission;
 pub mod text;
 pub mod user;
 pub mod user_bot_owner;
@@ -36,6 +36,8 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     PageCategory,
+    #[sea_orm(has_many = "super::page_data_field::Entity")]
+    PageDataField,
     #[sea_orm(has_many = "super::page_link::Entity")]
     PageLink,
     #[sea_orm(has_many = "super::page_lock::Entity")]
@@ -84,6 +86,12 @@ impl Related<super::page_category::Entity> for Entity {
     }
 }
 
+impl Related<super::page_data_field::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PageDataField.def()
+    }
+}
+
 impl Related<super::page_link::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageLink.def()
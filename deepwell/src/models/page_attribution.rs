@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+use super::sea_orm_active_enums::PageAttributionType;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -10,8 +11,8 @@ pub struct Model {
     pub page_id: i64,
     #[sea_orm(primary_key, auto_increment = false)]
     pub user_id: i64,
-    #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
-    pub attribution_type: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub attribution_type: PageAttributionType,
     #[sea_orm(primary_key, auto_increment = false)]
     pub attribution_date: TimeDate,
     pub created_at: TimeDateTimeWithTimeZone,
@@ -19,6 +19,8 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::page::Entity")]
     Page,
+    #[sea_orm(has_many = "super::site_permission::Entity")]
+    SitePermission,
     #[sea_orm(
         belongs_to = "super::site::Entity",
         from = "Column::SiteId",
@@ -35,6 +37,12 @@ impl Related<super::page::Entity> for Entity {
     }
 }
 
+impl Related<super::site_permission::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SitePermission.def()
+    }
+}
+
 impl Related<super::site::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Site.def()
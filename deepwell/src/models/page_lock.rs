@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+use super::sea_orm_active_enums::PageLockType;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -13,8 +14,7 @@ pub struct Model {
     pub deleted_at: Option<TimeDateTimeWithTimeZone>,
     pub expires_at: Option<TimeDateTimeWithTimeZone>,
     pub from_wikidot: bool,
-    #[sea_orm(column_type = "Text")]
-    pub lock_type: String,
+    pub lock_type: PageLockType,
     pub page_id: i64,
     pub user_id: i64,
     #[sea_orm(column_type = "Text")]
@@ -3,19 +3,33 @@
 #![allow(unused_imports)]
 
 pub use super::alias::Entity as Alias;
+pub use super::blob_derivative::Entity as BlobDerivative;
+pub use super::blob_scan::Entity as BlobScan;
 pub use super::file::Entity as File;
+pub use super::file_hard_deletion::Entity as FileHardDeletion;
 pub use super::file_revision::Entity as FileRevision;
+pub use super::file_upload::Entity as FileUpload;
+pub use super::file_upload_part::Entity as FileUploadPart;
 pub use super::filter::Entity as Filter;
+pub use super::filter_violation::Entity as FilterViolation;
+pub use super::import_completed_page::Entity as ImportCompletedPage;
+pub use super::import_file_revision::Entity as ImportFileRevision;
+pub use super::import_page_revision::Entity as ImportPageRevision;
+pub use super::job_dead_letter::Entity as JobDeadLetter;
+pub use super::job_queue::Entity as JobQueue;
+pub use super::job_schedule::Entity as JobSchedule;
 pub use super::message::Entity as Message;
 pub use super::message_draft::Entity as MessageDraft;
 pub use super::message_recipient::Entity as MessageRecipient;
 pub use super::message_record::Entity as MessageRecord;
 pub use super::message_report::Entity as MessageReport;
+pub use super::notification::Entity as Notification;
 pub use super::page::Entity as Page;
 pub use super::page_attribution::Entity as PageAttribution;
 pub use super::page_category::Entity as PageCategory;
 pub use super::page_connection::Entity as PageConnection;
 pub use super::page_connection_missing::Entity as PageConnectionMissing;
+pub use super::page_data_field::Entity as PageDataField;
 pub use super::page_link::Entity as PageLink;
 pub use super::page_lock::Entity as PageLock;
 pub use super::page_parent::Entity as PageParent;
@@ -25,6 +39,7 @@ pub use super::relation::Entity as Relation;
 pub use super::session::Entity as Session;
 pub use super::site::Entity as Site;
 pub use super::site_domain::Entity as SiteDomain;
+pub use super::site_permission::Entity as SitePermission;
 pub use super::text::Entity as Text;
 pub use super::user::Entity as User;
 pub use super::user_bot_owner::Entity as UserBotOwner;
@@ -32,6 +32,38 @@ pub enum FileRevisionType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "filter_content_type"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum FilterContentType {
+    #[sea_orm(string_value = "email")]
+    Email,
+    #[sea_orm(string_value = "file_name")]
+    FileName,
+    #[sea_orm(string_value = "page_title")]
+    PageTitle,
+    #[sea_orm(string_value = "page_wikitext")]
+    PageWikitext,
+    #[sea_orm(string_value = "user_name")]
+    UserName,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "message_privacy")]
+#[serde(rename_all = "kebab-case")]
+pub enum MessagePrivacy {
+    #[sea_orm(string_value = "contacts")]
+    Contacts,
+    #[sea_orm(string_value = "everyone")]
+    Everyone,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(
     rs_type = "String",
     db_type = "Enum",
@@ -49,6 +81,70 @@ pub enum MessageRecipientType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "message_report_status"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum MessageReportStatus {
+    #[sea_orm(string_value = "dismissed")]
+    Dismissed,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "resolved")]
+    Resolved,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "notification_type")]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationType {
+    #[sea_orm(string_value = "followed_page_create")]
+    FollowedPageCreate,
+    #[sea_orm(string_value = "message")]
+    Message,
+    #[sea_orm(string_value = "page_delete")]
+    PageDelete,
+    #[sea_orm(string_value = "page_edit")]
+    PageEdit,
+    #[sea_orm(string_value = "page_move")]
+    PageMove,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "page_attribution_type"
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum PageAttributionType {
+    #[sea_orm(string_value = "author")]
+    Author,
+    #[sea_orm(string_value = "maintainer")]
+    Maintainer,
+    #[sea_orm(string_value = "rewrite")]
+    Rewrite,
+    #[sea_orm(string_value = "translator")]
+    Translator,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "page_lock_type")]
+#[serde(rename_all = "kebab-case")]
+pub enum PageLockType {
+    #[sea_orm(string_value = "edit")]
+    Edit,
+    #[sea_orm(string_value = "protect")]
+    Protect,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "page_revision_type")]
 #[serde(rename_all = "kebab-case")]
 pub enum PageRevisionType {
@@ -66,6 +162,31 @@ pub enum PageRevisionType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "permission_type")]
+#[serde(rename_all = "kebab-case")]
+pub enum PermissionType {
+    #[sea_orm(string_value = "create")]
+    Create,
+    #[sea_orm(string_value = "delete")]
+    Delete,
+    #[sea_orm(string_value = "edit")]
+    Edit,
+    #[sea_orm(string_value = "manage")]
+    Manage,
+    #[sea_orm(string_value = "moderate")]
+    Moderate,
+    #[sea_orm(string_value = "move")]
+    Move,
+    #[sea_orm(string_value = "upload")]
+    Upload,
+    #[sea_orm(string_value = "view")]
+    View,
+    #[sea_orm(string_value = "vote")]
+    Vote,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(
     rs_type = "String",
     db_type = "Enum",
@@ -85,6 +206,46 @@ pub enum RelationObjectType {
 #[derive(
     Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
 )]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "score_type")]
+#[serde(rename_all = "kebab-case")]
+pub enum ScoreType {
+    #[sea_orm(string_value = "bayesian")]
+    Bayesian,
+    #[sea_orm(string_value = "mean")]
+    Mean,
+    #[sea_orm(string_value = "median")]
+    Median,
+    #[sea_orm(string_value = "null")]
+    Null,
+    #[sea_orm(string_value = "percent")]
+    Percent,
+    #[sea_orm(string_value = "sum")]
+    Sum,
+    #[sea_orm(string_value = "wilson")]
+    Wilson,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "site_role")]
+#[serde(rename_all = "kebab-case")]
+pub enum SiteRole {
+    #[sea_orm(string_value = "admin")]
+    Admin,
+    #[sea_orm(string_value = "guest")]
+    Guest,
+    #[sea_orm(string_value = "member")]
+    Member,
+    #[sea_orm(string_value = "moderator")]
+    Moderator,
+    #[sea_orm(string_value = "owner")]
+    Owner,
+    #[sea_orm(string_value = "registered")]
+    Registered,
+}
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "user_type")]
 #[serde(rename_all = "kebab-case")]
 pub enum UserType {
@@ -97,3 +258,14 @@ pub enum UserType {
     #[sea_orm(string_value = "system")]
     System,
 }
+#[derive(
+    Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "vote_type")]
+#[serde(rename_all = "kebab-case")]
+pub enum VoteType {
+    #[sea_orm(string_value = "five-star")]
+    FiveStar,
+    #[sea_orm(string_value = "ups-downs")]
+    UpsDowns,
+}
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+use super::sea_orm_active_enums::{ScoreType, VoteType};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -26,6 +27,10 @@ pub struct Model {
     pub default_page: String,
     #[sea_orm(column_type = "Text", nullable)]
     pub custom_domain: Option<String>,
+    pub score_type: ScoreType,
+    pub vote_type: VoteType,
+    pub default_file_licensing: Json,
+    pub file_quota_bytes: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -44,6 +49,8 @@ pub enum Relation {
     PageCategory,
     #[sea_orm(has_many = "super::page_revision::Entity")]
     PageRevision,
+    #[sea_orm(has_many = "super::site_permission::Entity")]
+    SitePermission,
     #[sea_orm(
         belongs_to = "super::site_domain::Entity",
         from = "Column::CustomDomain",
@@ -96,6 +103,12 @@ impl Related<super::page_revision::Entity> for Entity {
     }
 }
 
+impl Related<super::site_permission::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SitePermission.def()
+    }
+}
+
 impl Related<super::site_domain::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::SiteDomain.def()
@@ -1,6 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
-use super::sea_orm_active_enums::UserType;
+use super::sea_orm_active_enums::{MessagePrivacy, UserType};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -44,6 +44,8 @@ pub struct Model {
     pub biography: Option<String>,
     #[sea_orm(column_type = "Text", nullable)]
     pub user_page: Option<String>,
+    pub message_privacy: MessagePrivacy,
+    pub platform_staff: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -60,6 +62,8 @@ pub enum Relation {
     MessageRecipient,
     #[sea_orm(has_many = "super::message_record::Entity")]
     MessageRecord,
+    #[sea_orm(has_many = "super::message_report::Entity")]
+    MessageReport,
     #[sea_orm(has_many = "super::page_attribution::Entity")]
     PageAttribution,
     #[sea_orm(has_many = "super::page_lock::Entity")]
@@ -106,6 +110,12 @@ impl Related<super::message_record::Entity> for Entity {
     }
 }
 
+impl Related<super::message_report::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageReport.def()
+    }
+}
+
 impl Related<super::page_attribution::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PageAttribution.def()
@@ -20,10 +20,10 @@
 
 use super::prelude::*;
 use crate::models::alias::{self, Entity as Alias, Model as AliasModel};
-use crate::models::sea_orm_active_enums::AliasType;
+use crate::models::sea_orm_active_enums::{AliasType, FilterContentType};
 use crate::models::site::{self, Entity as Site};
 use crate::models::user::{self, Entity as User};
-use crate::services::filter::{FilterClass, FilterType};
+use crate::services::filter::{FilterCheck, FilterClass, FilterType};
 use crate::services::{FilterService, SiteService, UserService};
 use crate::utils::get_regular_slug;
 use crate::web::Reference;
@@ -65,7 +65,7 @@ impl AliasService {
 
         // Perform filter validation
         if !bypass_filter {
-            Self::run_filter(ctx, alias_type, &slug).await?;
+            Self::run_filter(ctx, alias_type, created_by, &slug).await?;
         }
 
         // Check for existence and conflicts
@@ -348,6 +348,7 @@ impl AliasService {
     async fn run_filter(
         ctx: &ServiceContext<'_>,
         alias_type: AliasType,
+        created_by: i64,
         slug: &str,
     ) -> Result<()> {
         info!("Checking user alias data against filters...");
@@ -364,7 +365,13 @@ impl AliasService {
         let filter_matcher =
             FilterService::get_matcher(ctx, FilterClass::Platform, filter_type).await?;
 
-        filter_matcher.verify(ctx, slug).await?;
+        let check = FilterCheck {
+            site_id: None,
+            user_id: Some(created_by),
+            content_type: FilterContentType::UserName,
+        };
+
+        filter_matcher.verify(ctx, check, slug).await?;
         Ok(())
     }
 }
@@ -0,0 +1,230 @@
+/*
+ * services/blob/image.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Produces resized copies of images using ImageMagick.
+//!
+//! Rather than decoding images in-process, the `convert` binary is run
+//! with the image on standard input, and the result read from standard output.
+//! Resized images are always stripped of their metadata, such as EXIF
+//! data with camera details or the GPS location the photo was taken at.
+//!
+//! Since uploaded images are untrusted, the decoder is chosen from the
+//! detected MIME type rather than letting ImageMagick guess from the
+//! contents, and its resource usage and running time are limited.
+
+use super::prelude::*;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+/// The image formats which derivatives are generated for,
+/// along with the ImageMagick coder used to read and write each.
+///
+/// This excludes vector formats such as SVG, which scale on their own.
+const RESIZABLE_MIME_TYPES: &[(&str, &str)] = &[
+    ("image/avif", "avif"),
+    ("image/bmp", "bmp"),
+    ("image/gif", "gif"),
+    ("image/jpeg", "jpeg"),
+    ("image/png", "png"),
+    ("image/tiff", "tiff"),
+    ("image/webp", "webp"),
+];
+
+/// Resource limits passed to ImageMagick, as pairs of resource and limit.
+///
+/// These prevent decompression bombs from exhausting the host. If an
+/// image needs more than this, then ImageMagick fails instead.
+const RESOURCE_LIMITS: &[(&str, &str)] = &[
+    ("memory", "256MiB"),
+    ("map", "512MiB"),
+    ("disk", "1GiB"),
+    ("area", "128MP"),
+    ("width", "16KP"),
+    ("height", "16KP"),
+];
+
+/// How long ImageMagick may run before it is killed.
+const RESIZE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Gets the ImageMagick coder for a blob of this MIME type, if it is resizable.
+///
+/// The MIME type may have parameters, as produced by libmagic.
+fn image_coder(mime: &str) -> Option<&'static str> {
+    let essence = match mime.split_once(';') {
+        Some((essence, _)) => essence,
+        None => mime,
+    };
+
+    RESIZABLE_MIME_TYPES
+        .iter()
+        .find(|(mime, _)| *mime == essence.trim())
+        .map(|(_, coder)| *coder)
+}
+
+/// Determines if derivatives should be generated for a blob of this MIME type.
+#[inline]
+pub fn is_resizable_image(mime: &str) -> bool {
+    image_coder(mime).is_some()
+}
+
+/// Builds the arguments for `convert` to resize an image.
+fn resize_args(coder: &str, max_dimension: u32) -> Vec<String> {
+    let mut args = Vec::new();
+
+    for (resource, limit) in RESOURCE_LIMITS {
+        args.extend([str!("-limit"), str!(resource), str!(limit)]);
+    }
+
+    // Pin the coder for both input and output, so the
+    // contents cannot select some other, riskier decoder.
+    args.extend([
+        format!("{coder}:-"),
+        str!("-auto-orient"),
+        str!("-thumbnail"),
+        format!("{max_dimension}x{max_dimension}>"),
+        str!("-strip"),
+        format!("{coder}:-"),
+    ]);
+
+    args
+}
+
+/// Resizes an image to fit within a square of the given dimension.
+///
+/// The output is in the same format as the input, is rotated according to its
+/// orientation metadata, and then has all metadata removed. Images smaller
+/// than the dimension are not enlarged.
+pub async fn resize_image(
+    convert_path: &Path,
+    data: &[u8],
+    mime: &str,
+    max_dimension: u32,
+) -> Result<Vec<u8>> {
+    debug!(
+        "Resizing image (length {}, type {mime}) to fit within {max_dimension}px",
+        data.len(),
+    );
+
+    let coder = match image_coder(mime) {
+        Some(coder) => coder,
+        None => {
+            error!("Cannot resize image of type {mime}");
+            return Err(Error::ImageProcessing(format!("unsupported type {mime}")));
+        }
+    };
+
+    let mut child = Command::new(convert_path)
+        .args(resize_args(coder, max_dimension))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|error| Error::ImageProcessing(error.to_string()))?;
+
+    // Write the image while the output is read, so neither pipe fills up
+    let mut stdin = child.stdin.take().expect("Child has no stdin");
+    let input = data.to_vec();
+    let writer = tokio::spawn(async move {
+        let result = stdin.write_all(&input).await;
+        drop(stdin);
+        result
+    });
+
+    // If this times out, the child is killed when it is dropped
+    let output = match timeout(RESIZE_TIMEOUT, child.wait_with_output()).await {
+        Ok(output) => {
+            output.map_err(|error| Error::ImageProcessing(error.to_string()))?
+        }
+        Err(_) => {
+            error!("ImageMagick did not finish within {RESIZE_TIMEOUT:?}");
+            return Err(Error::ImageProcessing(str!("timed out")));
+        }
+    };
+
+    // Check the exit status first, since if it fails early
+    // then writing to it also fails, with a less useful error.
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        error!("ImageMagick failed ({}): {message}", output.status);
+        return Err(Error::ImageProcessing(message.into_owned()));
+    }
+
+    writer
+        .await
+        .expect("Image writer task panicked")
+        .map_err(|error| Error::ImageProcessing(error.to_string()))?;
+
+    Ok(output.stdout)
+}
+
+#[test]
+fn resizable_images() {
+    macro_rules! check {
+        ($mime:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                is_resizable_image($mime),
+                $expected,
+                "Resizable image check for {} doesn't match expected",
+                $mime,
+            );
+        };
+    }
+
+    check!("image/png", true);
+    check!("image/png; charset=binary", true);
+    check!("image/jpeg; charset=binary", true);
+    check!("image/gif", true);
+    check!("image/svg+xml; charset=us-ascii", false);
+    check!("text/plain; charset=us-ascii", false);
+    check!("application/pdf", false);
+    check!("inode/x-empty; charset=binary", false);
+    check!("", false);
+}
+
+#[test]
+fn resize_arguments() {
+    let args = resize_args("png", 640);
+    let position = |arg: &str| args.iter().position(|a| a == arg);
+
+    assert_eq!(image_coder("image/png; charset=binary"), Some("png"));
+    assert_eq!(image_coder("image/jpeg"), Some("jpeg"));
+    assert_eq!(image_coder("image/svg+xml"), None);
+
+    // Input and output coders are pinned
+    assert_eq!(args.iter().filter(|arg| *arg == "png:-").count(), 2);
+    assert_eq!(args.last().map(String::as_str), Some("png:-"));
+    assert!(!args.iter().any(|arg| arg == "-"));
+
+    // Limits come before the input is read
+    for (resource, _) in RESOURCE_LIMITS {
+        let index = args
+            .iter()
+            .position(|arg| arg == resource)
+            .expect("Resource limit missing");
+        assert!(index < position("png:-").unwrap());
+    }
+
+    assert!(position("640x640>").is_some());
+}
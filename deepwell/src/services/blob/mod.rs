@@ -31,10 +31,15 @@ mod prelude {
     pub use crate::hash::{blob_hash_to_hex, sha512_hash, BlobHash};
 }
 
+mod image;
 mod mime;
+mod scanner;
 mod service;
 mod structs;
 
 pub use self::mime::MimeAnalyzer;
-pub use self::service::BlobService;
+pub use self::scanner::{
+    build_scanner, BlobScanner, ClamdScanner, ScanSession, ScanVerdict, StubScanner,
+};
+pub use self::service::{BlobService, EMPTY_BLOB_HASH};
 pub use self::structs::*;
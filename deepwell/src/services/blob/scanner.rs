@@ -0,0 +1,404 @@
+/*
+ * services/blob/scanner.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Scans blob contents for malware before they are stored.
+//!
+//! Contents are passed to a scanner in pieces, so that large files
+//! uploaded in chunks never need to be held in memory at once.
+//!
+//! In production this is [ClamAV](https://www.clamav.net/), via the
+//! `clamd` daemon's socket. For local development and tests, a stub
+//! scanner is used, which only detects the EICAR antivirus test file.
+
+use super::prelude::*;
+use async_trait::async_trait;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+/// The largest chunk sent to `clamd` in one message.
+const CLAMD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The EICAR antivirus test file, which all scanners must detect.
+///
+/// See <https://www.eicar.org/download-anti-malware-testfile/>.
+const EICAR_TEST_FILE: &[u8] =
+    b"X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+/// The signature name reported by the stub scanner, matching ClamAV's.
+const EICAR_SIGNATURE: &str = "Eicar-Test-Signature";
+
+/// The outcome of scanning a blob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+
+    /// The contents are malicious, with the name of the signature they matched.
+    Infected(String),
+}
+
+#[async_trait]
+pub trait BlobScanner: Debug + Send + Sync {
+    /// The name of this scanner, recorded alongside its results.
+    fn name(&self) -> &'static str;
+
+    /// The version of the signature database currently in use.
+    ///
+    /// Results from other versions are expired, and blobs are scanned again.
+    async fn version(&self) -> Result<String>;
+
+    /// Begins scanning a new blob.
+    async fn start(&self) -> Result<Box<dyn ScanSession>>;
+}
+
+#[async_trait]
+pub trait ScanSession: Send {
+    /// Passes the next piece of the blob's contents to the scanner.
+    async fn write(&mut self, data: &[u8]) -> Result<()>;
+
+    /// Finishes the scan once all the contents have been written.
+    async fn finish(self: Box<Self>) -> Result<ScanVerdict>;
+}
+
+/// Builds the scanner selected in the configuration.
+///
+/// If clamd is configured, it must be reachable now, so that a missing
+/// daemon fails startup instead of leaving uploads unscanned.
+pub async fn build_scanner(config: &Config) -> Result<Box<dyn BlobScanner>> {
+    match config.scanner_clamd_socket {
+        Some(ref socket_path) => {
+            let scanner = ClamdScanner::new(socket_path.clone());
+            let version = scanner.version().await?;
+            info!(
+                "Connected to clamd at {}, signature database version {version}",
+                socket_path.display(),
+            );
+            Ok(Box::new(scanner))
+        }
+        None => {
+            warn!("No clamd socket configured, uploads will not be scanned for malware");
+            Ok(Box::new(StubScanner))
+        }
+    }
+}
+
+// ClamAV
+
+/// Scanner which sends contents to ClamAV's `clamd` over its Unix socket.
+#[derive(Debug)]
+pub struct ClamdScanner {
+    socket_path: PathBuf,
+}
+
+impl ClamdScanner {
+    #[inline]
+    pub fn new(socket_path: PathBuf) -> Self {
+        ClamdScanner { socket_path }
+    }
+}
+
+#[async_trait]
+impl BlobScanner for ClamdScanner {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "clamd"
+    }
+
+    async fn version(&self) -> Result<String> {
+        let mut stream = self.connect().await?;
+        let mut response = Vec::new();
+
+        stream
+            .write_all(b"zVERSION\0")
+            .await
+            .map_err(|error| Error::BlobScan(error.to_string()))?;
+
+        stream
+            .read_to_end(&mut response)
+            .await
+            .map_err(|error| Error::BlobScan(error.to_string()))?;
+
+        parse_clamd_version(&response)
+    }
+
+    async fn start(&self) -> Result<Box<dyn ScanSession>> {
+        let mut stream = self.connect().await?;
+
+        stream
+            .write_all(b"zINSTREAM\0")
+            .await
+            .map_err(|error| Error::BlobScan(error.to_string()))?;
+
+        Ok(Box::new(ClamdSession { stream }))
+    }
+}
+
+impl ClamdScanner {
+    async fn connect(&self) -> Result<UnixStream> {
+        debug!("Connecting to clamd at {}", self.socket_path.display());
+
+        UnixStream::connect(&self.socket_path)
+            .await
+            .map_err(|error| Error::BlobScan(error.to_string()))
+    }
+}
+
+/// An `INSTREAM` command in progress.
+///
+/// The contents are sent as a series of chunks, each prefixed with its
+/// length as a big-endian 32-bit integer, and terminated by an empty chunk.
+#[derive(Debug)]
+struct ClamdSession {
+    stream: UnixStream,
+}
+
+#[async_trait]
+impl ScanSession for ClamdSession {
+    async fn write(&mut self, data: &[u8]) -> Result<()> {
+        for chunk in data.chunks(CLAMD_CHUNK_SIZE) {
+            let length = chunk.len() as u32;
+
+            // If clamd stops the scan early (e.g. the stream is too long),
+            // it closes the connection, so these writes fail.
+            self.stream
+                .write_all(&length.to_be_bytes())
+                .await
+                .map_err(|error| Error::BlobScan(error.to_string()))?;
+
+            self.stream
+                .write_all(chunk)
+                .await
+                .map_err(|error| Error::BlobScan(error.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    async fn finish(mut self: Box<Self>) -> Result<ScanVerdict> {
+        let mut response = Vec::new();
+
+        self.stream
+            .write_all(&0u32.to_be_bytes())
+            .await
+            .map_err(|error| Error::BlobScan(error.to_string()))?;
+
+        self.stream
+            .read_to_end(&mut response)
+            .await
+            .map_err(|error| Error::BlobScan(error.to_string()))?;
+
+        parse_clamd_response(&response)
+    }
+}
+
+/// Interprets the reply to an `INSTREAM` command.
+///
+/// This is one of `stream: OK`, `stream: <signature> FOUND`,
+/// or `<message> ERROR`, terminated by a null byte.
+fn parse_clamd_response(response: &[u8]) -> Result<ScanVerdict> {
+    let response = String::from_utf8_lossy(response);
+    let response = response.trim_end_matches(['\0', '\n']);
+    let result = response.strip_prefix("stream: ").unwrap_or(response);
+
+    if result == "OK" {
+        return Ok(ScanVerdict::Clean);
+    }
+
+    if let Some(signature) = result.strip_suffix(" FOUND") {
+        return Ok(ScanVerdict::Infected(str!(signature)));
+    }
+
+    error!("Unexpected response from clamd: {response}");
+    Err(Error::BlobScan(str!(response)))
+}
+
+/// Interprets the reply to a `VERSION` command, returning the signature database version.
+///
+/// This is formatted as `ClamAV <engine>/<database>/<database date>`,
+/// terminated by a null byte.
+fn parse_clamd_version(response: &[u8]) -> Result<String> {
+    let response = String::from_utf8_lossy(response);
+    let response = response.trim_end_matches(['\0', '\n']);
+
+    match response.split('/').nth(1) {
+        Some(version) if !version.is_empty() => Ok(str!(version)),
+        _ => {
+            error!("Unexpected version response from clamd: {response}");
+            Err(Error::BlobScan(str!(response)))
+        }
+    }
+}
+
+// Stub
+
+/// Scanner which only detects the EICAR test file.
+///
+/// This is used when no real scanner is configured, so that local
+/// deployments and tests can still exercise the rejection of infected files.
+#[derive(Debug)]
+pub struct StubScanner;
+
+#[async_trait]
+impl BlobScanner for StubScanner {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "stub"
+    }
+
+    async fn version(&self) -> Result<String> {
+        // Only ever detects the one file
+        Ok(str!("1"))
+    }
+
+    async fn start(&self) -> Result<Box<dyn ScanSession>> {
+        Ok(Box::<EicarMatcher>::default())
+    }
+}
+
+/// Searches for the EICAR test file, even when split across writes.
+#[derive(Debug, Default)]
+struct EicarMatcher {
+    /// The end of the contents so far, which may be the start of a match.
+    tail: Vec<u8>,
+    found: bool,
+}
+
+impl EicarMatcher {
+    fn update(&mut self, data: &[u8]) {
+        if self.found {
+            return;
+        }
+
+        self.tail.extend_from_slice(data);
+        self.found = self
+            .tail
+            .windows(EICAR_TEST_FILE.len())
+            .any(|window| window == EICAR_TEST_FILE);
+
+        let keep = EICAR_TEST_FILE.len() - 1;
+        if self.tail.len() > keep {
+            self.tail.drain(..self.tail.len() - keep);
+        }
+    }
+
+    fn verdict(&self) -> ScanVerdict {
+        if self.found {
+            ScanVerdict::Infected(str!(EICAR_SIGNATURE))
+        } else {
+            ScanVerdict::Clean
+        }
+    }
+}
+
+#[async_trait]
+impl ScanSession for EicarMatcher {
+    async fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.update(data);
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> Result<ScanVerdict> {
+        Ok(self.verdict())
+    }
+}
+
+#[test]
+fn clamd_responses() {
+    macro_rules! check {
+        ($response:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                parse_clamd_response($response).ok(),
+                $expected,
+                "Parsed clamd response doesn't match expected",
+            );
+        };
+    }
+
+    check!(b"stream: OK\0", Some(ScanVerdict::Clean));
+    check!(b"stream: OK\n", Some(ScanVerdict::Clean));
+    check!(
+        b"stream: Eicar-Test-Signature FOUND\0",
+        Some(ScanVerdict::Infected(str!("Eicar-Test-Signature"))),
+    );
+    check!(
+        b"stream: Win.Trojan.Agent-123 FOUND\0",
+        Some(ScanVerdict::Infected(str!("Win.Trojan.Agent-123"))),
+    );
+    check!(b"INSTREAM size limit exceeded. ERROR\0", None);
+    check!(b"", None);
+}
+
+#[test]
+fn clamd_versions() {
+    macro_rules! check {
+        ($response:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                parse_clamd_version($response).ok().as_deref(),
+                $expected,
+                "Parsed clamd version doesn't match expected",
+            );
+        };
+    }
+
+    check!(
+        b"ClamAV 1.0.1/26853/Sun Apr  2 07:25:44 2023\0",
+        Some("26853")
+    );
+    check!(
+        b"ClamAV 0.103.8/26827/Mon Mar  6 08:23:21 2023\n",
+        Some("26827")
+    );
+    check!(b"ClamAV 1.0.1\0", None);
+    check!(b"", None);
+}
+
+#[test]
+fn eicar_matcher() {
+    macro_rules! check {
+        ($chunks:expr, $expected:expr $(,)?) => {{
+            let mut matcher = EicarMatcher::default();
+            for chunk in $chunks {
+                matcher.update(chunk);
+            }
+
+            assert_eq!(
+                matcher.verdict(),
+                $expected,
+                "Stub scanner verdict doesn't match expected",
+            );
+        }};
+    }
+
+    let infected = ScanVerdict::Infected(str!(EICAR_SIGNATURE));
+    let (first, second) = EICAR_TEST_FILE.split_at(20);
+
+    check!([b"" as &[u8]], ScanVerdict::Clean);
+    check!([b"hello world" as &[u8]], ScanVerdict::Clean);
+    check!([EICAR_TEST_FILE], infected);
+    check!([b"prefix " as &[u8], EICAR_TEST_FILE, b" suffix"], infected);
+    check!([first, second], infected);
+    check!([first, b"x", second], ScanVerdict::Clean);
+    check!([first, b"", b"", second], infected);
+    check!(
+        [&EICAR_TEST_FILE[..EICAR_TEST_FILE.len() - 1]],
+        ScanVerdict::Clean
+    );
+}
@@ -21,11 +21,25 @@
 // TEMP, until https://scuttle.atlassian.net/browse/WJ-1032
 #![allow(dead_code)]
 
+use super::image::{is_resizable_image, resize_image};
 use super::prelude::*;
-use s3::request_trait::ResponseData;
-use s3::serde_types::HeadObjectResult;
+use super::scanner::ScanVerdict;
+use crate::models::blob_derivative::{
+    self, Entity as BlobDerivative, Model as BlobDerivativeModel,
+};
+use crate::models::blob_scan::{self, Entity as BlobScan};
+use crate::services::JobService;
+use s3::command::{Command, Multipart};
+use s3::error::S3Error;
+use s3::request::Reqwest as S3Request;
+use s3::request_trait::{Request, ResponseData};
+use s3::serde_types::{
+    CompleteMultipartUploadData, HeadObjectResult, InitiateMultipartUploadResponse, Part,
+};
+use sea_orm::sea_query::OnConflict;
+use sha2::{Digest, Sha512};
 use std::str;
-use time::format_description::well_known::Rfc2822;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
 use time::OffsetDateTime;
 
 /// Hash for empty blobs.
@@ -48,6 +62,12 @@ pub const EMPTY_BLOB_MIME: &str = "inode/x-empty; charset=binary";
 /// Timestamp is 2019/01/18 at midnight, the date of the first Wikijump commit.
 pub const EMPTY_BLOB_TIMESTAMP: i64 = 1547769600;
 
+/// How much of a staged upload is read from S3 at once while hashing or scanning it.
+const STAGED_READ_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Prefix for S3 keys of infected contents, which are kept aside for review.
+const QUARANTINE_PREFIX: &str = "quarantine/";
+
 #[derive(Debug)]
 pub struct BlobService;
 
@@ -76,6 +96,11 @@ impl BlobService {
         let hash = sha512_hash(data);
         let hex_hash = blob_hash_to_hex(&hash);
 
+        // Check for malware before storing
+        if let ScanVerdict::Infected(signature) = Self::scan(ctx, &hash, data).await? {
+            return Err(Error::BlobInfected(signature));
+        }
+
         // Convert size to correct integer type
         let size: i64 = data.len().try_into().expect("Buffer size exceeds i64");
 
@@ -240,6 +265,515 @@ impl BlobService {
         }
     }
 
+    /// Begins a multipart upload to a temporary key.
+    ///
+    /// The MIME type must be known up front, since it is stored
+    /// with the object and preserved when it is moved to its hash.
+    ///
+    /// # Returns
+    /// The S3 upload ID, which is needed for all subsequent calls.
+    pub async fn start_upload(
+        ctx: &ServiceContext<'_>,
+        key: &str,
+        mime: &str,
+    ) -> Result<String> {
+        info!("Starting multipart upload for S3 key {key}");
+
+        let bucket = ctx.s3_bucket();
+        let command = Command::InitiateMultipartUpload { content_type: mime };
+        let response = S3Request::new(bucket, key, command)
+            .response_data(false)
+            .await?;
+
+        match response.status_code() {
+            200 => {
+                let body =
+                    str::from_utf8(response.bytes()).map_err(|_| Error::S3Response)?;
+
+                let output: InitiateMultipartUploadResponse =
+                    serde_xml_rs::from_str(body).map_err(|_| Error::S3Response)?;
+
+                Ok(output.upload_id)
+            }
+            _ => s3_error(&response, "starting S3 multipart upload"),
+        }
+    }
+
+    /// Uploads one part of a multipart upload.
+    ///
+    /// Uploading a part number again replaces the data previously sent for it.
+    ///
+    /// # Returns
+    /// The ETag of the part, which is needed to finish the upload.
+    pub async fn upload_part(
+        ctx: &ServiceContext<'_>,
+        key: &str,
+        upload_id: &str,
+        mime: &str,
+        part_number: u32,
+        data: &[u8],
+    ) -> Result<String> {
+        debug!(
+            "Uploading part {part_number} (length {}) for S3 key {key}",
+            data.len(),
+        );
+
+        let bucket = ctx.s3_bucket();
+        let command = Command::PutObject {
+            content: data,
+            content_type: mime,
+            multipart: Some(Multipart::new(part_number, upload_id)),
+        };
+
+        // With the flag set, the response body is the ETag header
+        let response = S3Request::new(bucket, key, command)
+            .response_data(true)
+            .await?;
+        match response.status_code() {
+            200 => {
+                let etag =
+                    str::from_utf8(response.bytes()).map_err(|_| Error::S3Response)?;
+
+                Ok(str!(etag))
+            }
+            _ => s3_error(&response, "uploading S3 multipart part"),
+        }
+    }
+
+    /// Finishes a multipart upload, assembling the parts into one object.
+    ///
+    /// The parts are given as `(part_number, etag)` pairs, in order.
+    pub async fn finish_upload(
+        ctx: &ServiceContext<'_>,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<(u32, String)>,
+    ) -> Result<()> {
+        info!("Finishing multipart upload for S3 key {key}");
+
+        let bucket = ctx.s3_bucket();
+        let data = CompleteMultipartUploadData {
+            parts: parts
+                .into_iter()
+                .map(|(part_number, etag)| Part { part_number, etag })
+                .collect(),
+        };
+        let command = Command::CompleteMultipartUpload { upload_id, data };
+
+        let response = S3Request::new(bucket, key, command)
+            .response_data(false)
+            .await?;
+        match response.status_code() {
+            200 => Ok(()),
+            _ => s3_error(&response, "finishing S3 multipart upload"),
+        }
+    }
+
+    /// Aborts a multipart upload, discarding any parts which were uploaded.
+    pub async fn abort_upload(
+        ctx: &ServiceContext<'_>,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<()> {
+        info!("Aborting multipart upload for S3 key {key}");
+
+        let bucket = ctx.s3_bucket();
+        match bucket.abort_upload(key, upload_id).await {
+            Ok(()) => Ok(()),
+            Err(S3Error::Http(404, _)) => {
+                // Already completed, e.g. if finishing failed because it was infected
+                debug!("Multipart upload for S3 key {key} no longer exists");
+                Ok(())
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Lists the multipart uploads in progress for keys with this prefix.
+    ///
+    /// # Returns
+    /// The key, S3 upload ID, and time it was started, for each upload.
+    pub async fn list_uploads(
+        ctx: &ServiceContext<'_>,
+        prefix: &str,
+    ) -> Result<Vec<(String, String, OffsetDateTime)>> {
+        let bucket = ctx.s3_bucket();
+        let mut uploads = Vec::new();
+
+        for page in bucket.list_multiparts_uploads(Some(prefix), None).await? {
+            for upload in page.uploads {
+                let initiated = OffsetDateTime::parse(&upload.initiated, &Rfc3339)
+                    .map_err(|_| Error::S3Response)?;
+
+                uploads.push((upload.key, upload.id, initiated));
+            }
+        }
+
+        Ok(uploads)
+    }
+
+    /// Creates a blob from a finished upload, stored at a temporary key.
+    ///
+    /// The object is read back in pieces to hash and scan it, so that large
+    /// files are never held in memory. Then it is moved to its content address,
+    /// or discarded if a blob with the same hash already exists.
+    pub async fn create_from_upload(
+        ctx: &ServiceContext<'_>,
+        key: &str,
+    ) -> Result<CreateBlobOutput> {
+        info!("Creating blob from uploaded S3 key {key}");
+
+        let bucket = ctx.s3_bucket();
+        let staged = Self::head(ctx, key).await?.ok_or(Error::S3Response)?;
+        let size = staged.content_length.ok_or(Error::S3Response)?;
+        let mime = staged.content_type.ok_or(Error::S3Response)?;
+
+        // Hash the object in pieces
+        let length = u64::try_from(size).map_err(|_| Error::S3Response)?;
+        let mut hasher = Sha512::new();
+        let mut start = 0;
+
+        while start < length {
+            let (data, next) = Self::read_staged(ctx, key, start, length).await?;
+            hasher.update(data);
+            start = next;
+        }
+
+        let mut hash = [0; 64];
+        hash.copy_from_slice(&hasher.finalize());
+        let hex_hash = blob_hash_to_hex(&hash);
+
+        // Check for malware, discarding the upload if found
+        if let ScanVerdict::Infected(signature) =
+            Self::scan_staged(ctx, &hash, key, length).await?
+        {
+            let response = bucket.delete_object(key).await?;
+            return match response.status_code() {
+                204 => Err(Error::BlobInfected(signature)),
+                _ => s3_error(&response, "deleting infected S3 upload"),
+            };
+        }
+
+        // Move to the content address, unless it's a duplicate
+        let created = if Self::exists(ctx, &hash).await? {
+            debug!("Blob with hash {hex_hash} already exists, discarding upload");
+            false
+        } else {
+            debug!("Moving upload to blob with hash {hex_hash}");
+
+            let status = bucket.copy_object_internal(key, &hex_hash).await?;
+            if status != 200 {
+                return s3_error(
+                    &ResponseData::new(vec![], status),
+                    "copying staged S3 upload",
+                );
+            }
+
+            true
+        };
+
+        let response = bucket.delete_object(key).await?;
+        match response.status_code() {
+            204 => Ok(CreateBlobOutput {
+                hash,
+                mime,
+                size,
+                created,
+            }),
+            _ => s3_error(&response, "deleting staged S3 upload"),
+        }
+    }
+
+    /// Reads one piece of a staged upload, starting at the given offset.
+    ///
+    /// Returns the data, and the offset of the next piece.
+    async fn read_staged(
+        ctx: &ServiceContext<'_>,
+        key: &str,
+        start: u64,
+        length: u64,
+    ) -> Result<(Vec<u8>, u64)> {
+        let bucket = ctx.s3_bucket();
+        let end = (start + STAGED_READ_SIZE).min(length) - 1;
+        let response = bucket
+            .get_object_range(key, start, (end > start).then_some(end))
+            .await?;
+
+        match response.status_code() {
+            200 | 206 => Ok((response.bytes().to_vec(), end + 1)),
+            _ => s3_error(&response, "reading staged S3 upload"),
+        }
+    }
+
+    /// Scans new contents for malware, unless they have been scanned before.
+    ///
+    /// If infected, the contents are stored under the quarantine prefix
+    /// rather than at their content address.
+    async fn scan(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        data: &[u8],
+    ) -> Result<ScanVerdict> {
+        let version = ctx.blob_scanner().version().await?;
+        if let Some(verdict) = Self::get_scan_verdict(ctx, hash, &version).await? {
+            return Ok(verdict);
+        }
+
+        let mut session = ctx.blob_scanner().start().await?;
+        session.write(data).await?;
+        let verdict = session.finish().await?;
+
+        if let ScanVerdict::Infected(_) = verdict {
+            let key = quarantine_key(hash);
+            let response = ctx.s3_bucket().put_object(&key, data).await?;
+            if response.status_code() != 200 {
+                return s3_error(&response, "quarantining infected blob");
+            }
+        }
+
+        Self::record_scan(ctx, hash, &version, &verdict).await?;
+        Ok(verdict)
+    }
+
+    /// Scans a staged upload for malware, unless it has been scanned before.
+    ///
+    /// Like `scan()`, but reads the contents from S3 in pieces.
+    async fn scan_staged(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        key: &str,
+        length: u64,
+    ) -> Result<ScanVerdict> {
+        let version = ctx.blob_scanner().version().await?;
+        if let Some(verdict) = Self::get_scan_verdict(ctx, hash, &version).await? {
+            return Ok(verdict);
+        }
+
+        let mut session = ctx.blob_scanner().start().await?;
+        let mut start = 0;
+
+        while start < length {
+            let (data, next) = Self::read_staged(ctx, key, start, length).await?;
+            session.write(&data).await?;
+            start = next;
+        }
+
+        let verdict = session.finish().await?;
+
+        if let ScanVerdict::Infected(_) = verdict {
+            let status = ctx
+                .s3_bucket()
+                .copy_object_internal(key, quarantine_key(hash))
+                .await?;
+
+            if status != 200 {
+                return s3_error(
+                    &ResponseData::new(vec![], status),
+                    "quarantining infected S3 upload",
+                );
+            }
+        }
+
+        Self::record_scan(ctx, hash, &version, &verdict).await?;
+        Ok(verdict)
+    }
+
+    /// Gets the result of a previous scan of this blob, if any.
+    ///
+    /// Only scans by the current scanner, using its current signature
+    /// database, are used. Otherwise the verdict is considered expired,
+    /// since newer signatures may detect malware that older ones did not.
+    async fn get_scan_verdict(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        version: &str,
+    ) -> Result<Option<ScanVerdict>> {
+        let scan = BlobScan::find()
+            .filter(
+                Condition::all()
+                    .add(blob_scan::Column::S3Hash.eq(hash))
+                    .add(blob_scan::Column::Scanner.eq(ctx.blob_scanner().name()))
+                    .add(blob_scan::Column::DatabaseVersion.eq(version)),
+            )
+            .one(ctx.database())
+            .await?;
+
+        let verdict = scan.map(|scan| match scan.signature {
+            Some(signature) => ScanVerdict::Infected(signature),
+            None => ScanVerdict::Clean,
+        });
+
+        Ok(verdict)
+    }
+
+    /// Records the result of scanning this blob, replacing any expired result.
+    ///
+    /// This is done outside of the current transaction, so that infected
+    /// contents are still recorded when the request fails because of them.
+    async fn record_scan(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        version: &str,
+        verdict: &ScanVerdict,
+    ) -> Result<()> {
+        let hex_hash = blob_hash_to_hex(hash);
+        let signature = match verdict {
+            ScanVerdict::Clean => {
+                debug!("Blob with hash {hex_hash} is clean");
+                None
+            }
+            ScanVerdict::Infected(signature) => {
+                warn!("Blob with hash {hex_hash} is infected ({signature}), quarantined");
+                Some(signature.clone())
+            }
+        };
+
+        let model = blob_scan::ActiveModel {
+            s3_hash: Set(hash.to_vec()),
+            scanned_at: Set(now()),
+            scanner: Set(str!(ctx.blob_scanner().name())),
+            database_version: Set(str!(version)),
+            infected: Set(signature.is_some()),
+            signature: Set(signature),
+        };
+
+        BlobScan::insert(model)
+            .on_conflict(
+                OnConflict::column(blob_scan::Column::S3Hash)
+                    .update_columns([
+                        blob_scan::Column::ScannedAt,
+                        blob_scan::Column::Scanner,
+                        blob_scan::Column::DatabaseVersion,
+                        blob_scan::Column::Infected,
+                        blob_scan::Column::Signature,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(ctx.database())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Queues generation of derivatives for this blob, if it is an image.
+    pub async fn queue_derivatives(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+        mime: &str,
+    ) -> Result<()> {
+        if !is_resizable_image(mime) || ctx.config().image_derivative_sizes.is_empty() {
+            return Ok(());
+        }
+
+        JobService::queue_generate_derivatives(ctx, hash.to_vec()).await
+    }
+
+    /// Generates resized copies of an image blob, at each configured size.
+    ///
+    /// Each derivative is stored as its own blob, and linked to the
+    /// original in the `blob_derivative` table. Sizes which already
+    /// have a derivative are skipped.
+    ///
+    /// # Returns
+    /// The number of derivatives which were generated.
+    pub async fn generate_derivatives(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+    ) -> Result<u64> {
+        let txn = ctx.transaction();
+        let hex_hash = blob_hash_to_hex(hash);
+        info!("Generating derivatives for blob {hex_hash}");
+
+        let existing = Self::get_derivatives(ctx, hash).await?;
+        let sizes: Vec<u32> = ctx
+            .config()
+            .image_derivative_sizes
+            .iter()
+            .copied()
+            .filter(|&size| {
+                !existing
+                    .iter()
+                    .any(|derivative| derivative.max_dimension as u32 == size)
+            })
+            .collect();
+
+        if sizes.is_empty() {
+            debug!("All derivatives for blob {hex_hash} already exist");
+            return Ok(0);
+        }
+
+        let BlobMetadata { mime, .. } = Self::get_metadata(ctx, hash).await?;
+        if !is_resizable_image(&mime) {
+            debug!("Blob {hex_hash} is not a resizable image ({mime}), skipping");
+            return Ok(0);
+        }
+
+        let data = Self::get(ctx, hash).await?;
+        let convert_path = &ctx.config().image_convert_path;
+        let mut generated = 0;
+
+        for size in sizes {
+            let resized = resize_image(convert_path, &data, &mime, size).await?;
+            let CreateBlobOutput {
+                hash: derivative_hash,
+                mime,
+                size: derivative_size,
+                created: _,
+            } = Self::create(ctx, &resized).await?;
+
+            let model = blob_derivative::ActiveModel {
+                source_hash: Set(hash.to_vec()),
+                max_dimension: Set(size as i32),
+                derivative_hash: Set(derivative_hash.to_vec()),
+                mime: Set(mime),
+                size: Set(derivative_size),
+                ..Default::default()
+            };
+            model.insert(txn).await?;
+            generated += 1;
+        }
+
+        debug!("Generated {generated} derivatives for blob {hex_hash}");
+        Ok(generated)
+    }
+
+    /// Gets all the derivatives of a blob, smallest first.
+    pub async fn get_derivatives(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+    ) -> Result<Vec<BlobDerivativeModel>> {
+        let txn = ctx.transaction();
+        let derivatives = BlobDerivative::find()
+            .filter(blob_derivative::Column::SourceHash.eq(hash.to_vec()))
+            .order_by_asc(blob_derivative::Column::MaxDimension)
+            .all(txn)
+            .await?;
+
+        Ok(derivatives)
+    }
+
+    /// Removes the derivative links for a blob.
+    ///
+    /// The derivative blobs themselves are not removed, since they may be
+    /// in use elsewhere. The caller is responsible for deleting them.
+    ///
+    /// # Returns
+    /// The derivatives which were unlinked.
+    pub async fn delete_derivatives(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+    ) -> Result<Vec<BlobDerivativeModel>> {
+        let txn = ctx.transaction();
+        let derivatives = Self::get_derivatives(ctx, hash).await?;
+
+        BlobDerivative::delete_many()
+            .filter(blob_derivative::Column::SourceHash.eq(hash.to_vec()))
+            .exec(txn)
+            .await?;
+
+        Ok(derivatives)
+    }
+
     pub async fn hard_delete(ctx: &ServiceContext<'_>, hash: &[u8]) -> Result<()> {
         // Special handling for empty blobs
         //
@@ -262,6 +796,11 @@ impl BlobService {
     }
 }
 
+/// Gets the S3 key where infected contents with this hash are kept.
+fn quarantine_key(hash: &[u8]) -> String {
+    format!("{QUARANTINE_PREFIX}{}", blob_hash_to_hex(hash))
+}
+
 /// Helper method to parse out an S3 error response and print the message (if any).
 fn s3_error<T>(response: &ResponseData, action: &str) -> Result<T> {
     let error_message = match str::from_utf8(response.bytes()) {
@@ -21,12 +21,11 @@
 use crate::api::ServerState;
 use crate::config::Config;
 use crate::locales::Localizations;
-use crate::services::blob::MimeAnalyzer;
+use crate::services::blob::{BlobScanner, MimeAnalyzer};
 use crate::services::error::Result;
 use redis::aio::MultiplexedConnection as RedisMultiplexedConnection;
-use rsmq_async::PooledRsmq;
 use s3::bucket::Bucket;
-use sea_orm::DatabaseTransaction;
+use sea_orm::{DatabaseConnection, DatabaseTransaction};
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -67,11 +66,6 @@ impl<'txn> ServiceContext<'txn> {
         Ok(conn)
     }
 
-    #[inline]
-    pub fn rsmq(&self) -> PooledRsmq {
-        PooledRsmq::clone(&self.state.rsmq)
-    }
-
     #[inline]
     pub fn localization(&self) -> &Localizations {
         &self.state.localizations
@@ -87,8 +81,22 @@ impl<'txn> ServiceContext<'txn> {
         &self.state.s3_bucket
     }
 
+    #[inline]
+    pub fn blob_scanner(&self) -> &dyn BlobScanner {
+        self.state.blob_scanner.as_ref()
+    }
+
     #[inline]
     pub fn transaction(&self) -> &'txn DatabaseTransaction {
         self.transaction
     }
+
+    /// Gets the database connection, outside of the current transaction.
+    ///
+    /// Only for data which must be persisted even if the transaction is rolled back,
+    /// everything else should use `transaction()`.
+    #[inline]
+    pub fn database(&self) -> &DatabaseConnection {
+        &self.state.database
+    }
 }
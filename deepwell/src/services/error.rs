@@ -18,6 +18,8 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::services::file::LicensingProblem;
 use filemagic::FileMagicError;
 use jsonrpsee::types::error::ErrorObjectOwned;
 use reqwest::Error as ReqwestError;
@@ -47,9 +49,6 @@ pub enum Error {
     #[error("Redis error: {0}")]
     Redis(#[from] redis::RedisError),
 
-    #[error("Redis Simple Message Queue (RSMQ) error: {0}")]
-    Rsmq(#[from] rsmq_async::RsmqError),
-
     #[error("Invalid locale: {0}")]
     LocaleInvalid(#[from] LanguageIdentifierError),
 
@@ -83,6 +82,12 @@ pub enum Error {
     #[error("S3 service failed to respond properly")]
     S3Response,
 
+    #[error("Image processing failed: {0}")]
+    ImageProcessing(String),
+
+    #[error("Error scanning blob for malware: {0}")]
+    BlobScan(String),
+
     #[error("Email verification error: {}", .0.as_ref().unwrap_or(&str!("<unspecified>")))]
     EmailVerification(Option<String>),
 
@@ -113,6 +118,9 @@ pub enum Error {
         session_user_id: i64,
     },
 
+    #[error("User does not have permission to perform this action")]
+    PermissionDenied,
+
     #[error("A password is required")]
     EmptyPassword,
 
@@ -185,6 +193,54 @@ pub enum Error {
     #[error("Message has too many recipients")]
     MessageTooManyRecipients,
 
+    #[error("Vote value is not allowed for this site's vote type")]
+    VoteValueInvalid,
+
+    #[error("Score type cannot be used with this vote type")]
+    ScoreTypeIncompatible,
+
+    #[error("Changing the vote type requires resetting the site's votes")]
+    VoteTypeChangeNeedsReset,
+
+    #[error("Only staff roles can be assigned to site members")]
+    SiteRoleInvalid,
+
+    #[error("Message report has already been resolved or dismissed")]
+    MessageReportClosed,
+
+    #[error("Only messages which were received can be reported")]
+    MessageNotReceived,
+
+    #[error("The first revision of a page cannot be undone")]
+    CannotUndoFirstRevision,
+
+    #[error("This page is protected and cannot be modified")]
+    PageProtected,
+
+    #[error("The page was edited since this edit was started, and the changes overlap")]
+    PageEditConflict(Vec<PageRevisionModel>),
+
+    #[error("No regular expression or filters were given to test")]
+    FilterTestEmpty,
+
+    #[error("The uploaded file is larger than the maximum allowed size")]
+    FileUploadTooLarge,
+
+    #[error("The upload part is too small or too large")]
+    FileUploadPartSize,
+
+    #[error("The upload part number is invalid, or earlier parts are missing")]
+    FileUploadPartOrder,
+
+    #[error("The file's licensing information is invalid: {0}")]
+    FileLicensingInvalid(LicensingProblem),
+
+    #[error("Storing this file would exceed the site or user storage quota")]
+    FileQuotaExceeded,
+
+    #[error("The file was detected as malware and has been quarantined")]
+    BlobInfected(String),
+
     #[error("Unspecified entity not found")]
     GeneralNotFound,
 
@@ -239,6 +295,21 @@ pub enum Error {
     #[error("Text item does not exist")]
     TextNotFound,
 
+    #[error("Job does not exist")]
+    JobNotFound,
+
+    #[error("Message report does not exist")]
+    MessageReportNotFound,
+
+    #[error("Page lock does not exist")]
+    PageLockNotFound,
+
+    #[error("Page attribution does not exist")]
+    PageAttributionNotFound,
+
+    #[error("File upload session does not exist")]
+    FileUploadNotFound,
+
     #[error("Cannot perform, user already exists")]
     UserExists,
 
@@ -266,12 +337,24 @@ pub enum Error {
     #[error("Cannot perform, custom domain already exists")]
     CustomDomainExists,
 
+    #[error("Cannot perform, message has already been reported")]
+    MessageReportExists,
+
+    #[error("Cannot perform, page attribution already exists")]
+    PageAttributionExists,
+
     #[error("Cannot perform this action because you are blocked by the user")]
     UserBlockedUser,
 
     #[error("Cannot perform this action because you are blocked by the site")]
     SiteBlockedUser,
 
+    #[error("Cannot perform, these users are already contacts")]
+    UserContactExists,
+
+    #[error("This user only accepts direct messages from their contacts")]
+    UserContactsOnly,
+
     #[error("The rate limit for an external API has been reached")]
     RateLimited,
 }
@@ -315,6 +398,11 @@ impl Error {
             Error::MessageDraftNotFound => 2015,
             Error::BlobNotFound => 2016,
             Error::TextNotFound => 2017,
+            Error::JobNotFound => 2018,
+            Error::MessageReportNotFound => 2019,
+            Error::PageLockNotFound => 2020,
+            Error::PageAttributionNotFound => 2021,
+            Error::FileUploadNotFound => 2022,
 
             // 2100 -- Existing data
             Error::UserExists => 2100,
@@ -326,6 +414,8 @@ impl Error {
             Error::FileExists => 2106,
             Error::FilterExists => 2107,
             Error::CustomDomainExists => 2108,
+            Error::MessageReportExists => 2109,
+            Error::PageAttributionExists => 2110,
 
             // 3000 - Server errors, unexpected
             Error::RateLimited => 3000,
@@ -345,7 +435,8 @@ impl Error {
             Error::Magic(_) => 3204,
             Error::Otp(_) => 3205,
             Error::Redis(_) => 3206,
-            Error::Rsmq(_) => 3207,
+            Error::ImageProcessing(_) => 3207,
+            Error::BlobScan(_) => 3208,
 
             // 4000 - Client, request errors
             //        BadRequest is pretty general, avoid it except for rare weird cases
@@ -372,6 +463,22 @@ impl Error {
             Error::MessageBodyTooLong => 4019,
             Error::MessageNoRecipients => 4020,
             Error::MessageTooManyRecipients => 4021,
+            Error::VoteValueInvalid => 4023,
+            Error::ScoreTypeIncompatible => 4024,
+            Error::SiteRoleInvalid => 4025,
+            Error::MessageReportClosed => 4026,
+            Error::MessageNotReceived => 4027,
+            Error::CannotUndoFirstRevision => 4028,
+            Error::PageProtected => 4029,
+            Error::PageEditConflict(_) => 4030,
+            Error::FilterTestEmpty => 4031,
+            Error::FileUploadTooLarge => 4032,
+            Error::FileUploadPartSize => 4033,
+            Error::FileUploadPartOrder => 4034,
+            Error::FileLicensingInvalid(_) => 4035,
+            Error::FileQuotaExceeded => 4036,
+            Error::BlobInfected(_) => 4037,
+            Error::VoteTypeChangeNeedsReset => 4038,
 
             // 4100 -- Localization
             Error::LocaleInvalid(_) => 4100,
@@ -389,12 +496,15 @@ impl Error {
             // 4300 -- Relationship conflicts
             Error::SiteBlockedUser => 4300,
             Error::UserBlockedUser => 4301,
+            Error::UserContactExists => 4302,
+            Error::UserContactsOnly => 4303,
 
             // 5000 - Authentication, permission, or role errors
             Error::InvalidAuthentication => 5000,
             Error::InvalidSessionToken => 5001,
             Error::SessionUserId { .. } => 5002,
-            // TODO: permission errors (e.g. locked page, cannot apply bans)
+            Error::PermissionDenied => 5003,
+            // TODO: other permission errors (e.g. locked page, cannot apply bans)
         }
     }
 
@@ -424,6 +534,11 @@ impl Error {
 
             // Emit as-is
             Error::EmailVerification(value) => json!(value),
+            Error::ImageProcessing(value) => json!(value),
+            Error::FileLicensingInvalid(value) => json!(value),
+            Error::BlobScan(value) => json!(value),
+            Error::BlobInfected(signature) => json!(signature),
+            Error::PageEditConflict(revisions) => json!(revisions),
 
             // Emit as a Debug string
             Error::Cryptography(value) => json!(format!("{value:?}")),
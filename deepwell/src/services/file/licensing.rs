@@ -0,0 +1,340 @@
+/*
+ * services/file/licensing.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Structured licensing information for files.
+//!
+//! Every file revision records the license its contents are under, as a
+//! [SPDX identifier](https://spdx.org/licenses/), along with who made it,
+//! where it came from, and what works it was derived from.
+//!
+//! Licenses not in the SPDX list can be given using the SPDX convention for
+//! custom identifiers, `LicenseRef-` followed by a name, for instance
+//! `LicenseRef-Fair-Use`.
+
+use std::fmt::{self, Display};
+
+/// The SPDX identifiers accepted as licenses.
+///
+/// This covers the Creative Commons licenses, and common licenses
+/// used for fonts, code, and other assets that may be uploaded.
+pub const KNOWN_LICENSES: &[&str] = &[
+    "CC0-1.0",
+    "CC-PDDC",
+    "CC-BY-2.0",
+    "CC-BY-2.5",
+    "CC-BY-3.0",
+    "CC-BY-4.0",
+    "CC-BY-SA-2.0",
+    "CC-BY-SA-2.5",
+    "CC-BY-SA-3.0",
+    "CC-BY-SA-4.0",
+    "CC-BY-NC-2.0",
+    "CC-BY-NC-2.5",
+    "CC-BY-NC-3.0",
+    "CC-BY-NC-4.0",
+    "CC-BY-NC-SA-2.0",
+    "CC-BY-NC-SA-2.5",
+    "CC-BY-NC-SA-3.0",
+    "CC-BY-NC-SA-4.0",
+    "CC-BY-ND-2.0",
+    "CC-BY-ND-2.5",
+    "CC-BY-ND-3.0",
+    "CC-BY-ND-4.0",
+    "CC-BY-NC-ND-2.0",
+    "CC-BY-NC-ND-2.5",
+    "CC-BY-NC-ND-3.0",
+    "CC-BY-NC-ND-4.0",
+    "Apache-2.0",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "GPL-2.0-only",
+    "GPL-2.0-or-later",
+    "GPL-3.0-only",
+    "GPL-3.0-or-later",
+    "LGPL-3.0-only",
+    "LGPL-3.0-or-later",
+    "MIT",
+    "OFL-1.1",
+    "Unlicense",
+];
+
+/// Prefix for custom license identifiers, per the SPDX specification.
+pub const CUSTOM_LICENSE_PREFIX: &str = "LicenseRef-";
+
+const MAXIMUM_AUTHOR_LENGTH: usize = 256;
+const MAXIMUM_URL_LENGTH: usize = 2048;
+const MAXIMUM_DERIVATIVE_SOURCES: usize = 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileLicensing {
+    /// The SPDX identifier of the license.
+    pub license: String,
+
+    /// Who created the work, for attribution.
+    ///
+    /// If not set, then the user who uploaded the file is the author.
+    #[serde(default)]
+    pub author: Option<String>,
+
+    /// Where the work was originally published, if elsewhere.
+    #[serde(default)]
+    pub source_url: Option<String>,
+
+    /// Which works this one is an adaptation of.
+    #[serde(default)]
+    pub derivative_of: Vec<LicensedWork>,
+}
+
+/// A work which a file is derived from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum LicensedWork {
+    /// Another file hosted here.
+    File { file_id: i64 },
+
+    /// A work hosted elsewhere.
+    External {
+        url: String,
+
+        #[serde(default)]
+        title: Option<String>,
+    },
+}
+
+/// Why licensing information is not valid.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", tag = "problem", content = "value")]
+pub enum LicensingProblem {
+    /// The stored licensing is not in the expected structure.
+    Malformed,
+    UnknownLicense(String),
+    AuthorEmpty,
+    AuthorTooLong,
+    UrlInvalid(String),
+    TooManyDerivativeSources,
+    DerivativeFileMissing(i64),
+}
+
+impl Display for LicensingProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LicensingProblem::Malformed => write!(f, "licensing is malformed"),
+            LicensingProblem::UnknownLicense(license) => {
+                write!(f, "unknown license identifier '{license}'")
+            }
+            LicensingProblem::AuthorEmpty => write!(f, "author is empty"),
+            LicensingProblem::AuthorTooLong => write!(f, "author is too long"),
+            LicensingProblem::UrlInvalid(url) => write!(f, "invalid URL '{url}'"),
+            LicensingProblem::TooManyDerivativeSources => {
+                write!(f, "too many works derived from")
+            }
+            LicensingProblem::DerivativeFileMissing(file_id) => {
+                write!(f, "derived from file ID {file_id}, which does not exist")
+            }
+        }
+    }
+}
+
+impl FileLicensing {
+    /// Checks the licensing information, other than references to other files.
+    ///
+    /// Since that requires the database, it is done by `FileService`.
+    pub fn validate(&self) -> Result<(), LicensingProblem> {
+        if !is_valid_license(&self.license) {
+            return Err(LicensingProblem::UnknownLicense(self.license.clone()));
+        }
+
+        if let Some(ref author) = self.author {
+            if author.trim().is_empty() {
+                return Err(LicensingProblem::AuthorEmpty);
+            }
+
+            if author.len() > MAXIMUM_AUTHOR_LENGTH {
+                return Err(LicensingProblem::AuthorTooLong);
+            }
+        }
+
+        if let Some(ref url) = self.source_url {
+            check_url(url)?;
+        }
+
+        if self.derivative_of.len() > MAXIMUM_DERIVATIVE_SOURCES {
+            return Err(LicensingProblem::TooManyDerivativeSources);
+        }
+
+        for work in &self.derivative_of {
+            if let LicensedWork::External { url, .. } = work {
+                check_url(url)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the IDs of all hosted files this work is derived from.
+    pub fn derivative_file_ids(&self) -> impl Iterator<Item = i64> + '_ {
+        self.derivative_of.iter().filter_map(|work| match work {
+            LicensedWork::File { file_id } => Some(*file_id),
+            LicensedWork::External { .. } => None,
+        })
+    }
+}
+
+/// Determines if this is an accepted SPDX license identifier.
+pub fn is_valid_license(license: &str) -> bool {
+    if KNOWN_LICENSES.contains(&license) {
+        return true;
+    }
+
+    match license.strip_prefix(CUSTOM_LICENSE_PREFIX) {
+        Some(name) => {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        }
+        None => false,
+    }
+}
+
+fn check_url(url: &str) -> Result<(), LicensingProblem> {
+    let valid = (url.starts_with("https://") || url.starts_with("http://"))
+        && url.len() <= MAXIMUM_URL_LENGTH
+        && !url.contains(char::is_whitespace);
+
+    if valid {
+        Ok(())
+    } else {
+        Err(LicensingProblem::UrlInvalid(str!(url)))
+    }
+}
+
+#[test]
+fn license_identifiers() {
+    macro_rules! check {
+        ($license:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                is_valid_license($license),
+                $expected,
+                "License validity for '{}' doesn't match expected",
+                $license,
+            );
+        };
+    }
+
+    check!("CC-BY-SA-3.0", true);
+    check!("CC0-1.0", true);
+    check!("MIT", true);
+    check!("LicenseRef-Fair-Use", true);
+    check!("LicenseRef-All-Rights-Reserved", true);
+    check!("LicenseRef-v1.2", true);
+    check!("cc-by-sa-3.0", false);
+    check!("CC-BY-SA", false);
+    check!("LicenseRef-", false);
+    check!("LicenseRef-Fair Use", false);
+    check!("", false);
+}
+
+#[test]
+fn validate_licensing() {
+    macro_rules! check {
+        ($licensing:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                $licensing.validate(),
+                $expected,
+                "Licensing validation doesn't match expected",
+            );
+        };
+    }
+
+    let base = FileLicensing {
+        license: str!("CC-BY-SA-4.0"),
+        author: None,
+        source_url: None,
+        derivative_of: vec![],
+    };
+
+    check!(base, Ok(()));
+    check!(
+        FileLicensing {
+            license: str!("Public Domain"),
+            ..base.clone()
+        },
+        Err(LicensingProblem::UnknownLicense(str!("Public Domain"))),
+    );
+    check!(
+        FileLicensing {
+            author: Some(str!("  ")),
+            ..base.clone()
+        },
+        Err(LicensingProblem::AuthorEmpty),
+    );
+    check!(
+        FileLicensing {
+            author: Some("x".repeat(300)),
+            ..base.clone()
+        },
+        Err(LicensingProblem::AuthorTooLong),
+    );
+    check!(
+        FileLicensing {
+            source_url: Some(str!("https://example.com/art.png")),
+            ..base.clone()
+        },
+        Ok(()),
+    );
+    check!(
+        FileLicensing {
+            source_url: Some(str!("javascript:alert(1)")),
+            ..base.clone()
+        },
+        Err(LicensingProblem::UrlInvalid(str!("javascript:alert(1)"))),
+    );
+    check!(
+        FileLicensing {
+            derivative_of: vec![
+                LicensedWork::File { file_id: 4 },
+                LicensedWork::External {
+                    url: str!("https://example.com/original"),
+                    title: Some(str!("Original")),
+                },
+            ],
+            ..base.clone()
+        },
+        Ok(()),
+    );
+    check!(
+        FileLicensing {
+            derivative_of: vec![LicensedWork::External {
+                url: str!("example.com"),
+                title: None,
+            }],
+            ..base.clone()
+        },
+        Err(LicensingProblem::UrlInvalid(str!("example.com"))),
+    );
+    check!(
+        FileLicensing {
+            derivative_of: vec![LicensedWork::File { file_id: 1 }; 40],
+            ..base.clone()
+        },
+        Err(LicensingProblem::TooManyDerivativeSources),
+    );
+}
@@ -24,8 +24,10 @@ mod prelude {
     pub use super::structs::*;
 }
 
+mod licensing;
 mod service;
 mod structs;
 
+pub use self::licensing::{FileLicensing, LicensedWork, LicensingProblem};
 pub use self::service::FileService;
 pub use self::structs::*;
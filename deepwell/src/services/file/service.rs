@@ -18,15 +18,27 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::licensing::{FileLicensing, LicensingProblem};
 use super::prelude::*;
+use crate::hash::{blob_hash_to_hex, sha512_hash};
+use crate::models::blob_derivative::{self, Entity as BlobDerivative};
 use crate::models::file::{self, Entity as File, Model as FileModel};
-use crate::services::blob::CreateBlobOutput;
+use crate::models::file_hard_deletion::{self, Model as FileHardDeletionModel};
+use crate::models::file_revision::{self, Entity as FileRevision};
+use crate::models::sea_orm_active_enums::FilterContentType;
+use crate::models::user::{self, Entity as User};
+use crate::services::blob::{CreateBlobOutput, EMPTY_BLOB_HASH};
 use crate::services::file_revision::{
     CreateFileRevision, CreateFileRevisionBody, CreateFirstFileRevision,
     CreateResurrectionFileRevision, CreateTombstoneFileRevision, FileBlob,
 };
-use crate::services::filter::{FilterClass, FilterType};
-use crate::services::{BlobService, FileRevisionService, FilterService};
+use crate::services::filter::{FilterCheck, FilterClass, FilterType};
+use crate::services::{
+    BlobService, FileRevisionService, FilterService, JobService, SiteService,
+};
+use sea_orm::{DatabaseBackend, Statement};
+use sea_query::{func::Func, Alias, Expr, Query, SimpleExpr};
+use serde_json::Value as JsonValue;
 
 #[derive(Debug)]
 pub struct FileService;
@@ -49,8 +61,6 @@ impl FileService {
             bypass_filter,
         }: UploadFile,
     ) -> Result<UploadFileOutput> {
-        let txn = ctx.transaction();
-
         info!(
             "Creating file with name '{}', content length {}",
             name,
@@ -62,16 +72,61 @@ impl FileService {
 
         // Perform filter validation
         if !bypass_filter {
-            Self::run_filter(ctx, site_id, Some(&name)).await?;
+            Self::run_filter(ctx, site_id, user_id, Some(&name)).await?;
         }
 
+        let licensing = Self::resolve_licensing(ctx, site_id, licensing).await?;
+        // Reject early if over quota, before uploading anything
+        let hash = sha512_hash(data.as_ref());
+        Self::check_quota(ctx, site_id, user_id, data.len() as u64, Some(&hash)).await?;
+
         // Upload to S3, get derived metadata
-        let CreateBlobOutput {
+        let blob = BlobService::create(ctx, &data).await?;
+
+        // Add file entity
+        Self::create_from_blob(
+            ctx,
+            CreateFile {
+                site_id,
+                page_id,
+                name,
+                revision_comments,
+                user_id,
+                licensing,
+            },
+            blob,
+        )
+        .await
+    }
+
+    /// Creates a file entity for a blob which has already been stored.
+    ///
+    /// Callers are responsible for checking conflicts and running filters.
+    pub async fn create_from_blob(
+        ctx: &ServiceContext<'_>,
+        CreateFile {
+            site_id,
+            page_id,
+            name,
+            revision_comments,
+            user_id,
+            licensing,
+        }: CreateFile,
+        CreateBlobOutput {
             hash,
             mime,
             size,
             created: _,
-        } = BlobService::create(ctx, &data).await?;
+        }: CreateBlobOutput,
+    ) -> Result<UploadFileOutput> {
+        let txn = ctx.transaction();
+
+        // Other files may have been added since the contents were uploaded
+        Self::lock_quotas(ctx, site_id, user_id).await?;
+        Self::check_quota(ctx, site_id, user_id, size as u64, Some(&hash)).await?;
+
+        // Generate resized copies, if it's an image
+        BlobService::queue_derivatives(ctx, &hash, &mime).await?;
 
         // Add new file
         let model = file::ActiveModel {
@@ -136,14 +191,28 @@ impl FileService {
             Self::check_conflicts(ctx, page_id, name, "update").await?;
 
             if !bypass_filter {
-                Self::run_filter(ctx, site_id, Some(name)).await?;
+                Self::run_filter(ctx, site_id, user_id, Some(name)).await?;
             }
         }
 
+        // Verify licensing change
+        let licensing = match licensing {
+            ProvidedValue::Unset => ProvidedValue::Unset,
+            ProvidedValue::Set(licensing) => {
+                Self::check_licensing(ctx, site_id, &licensing).await?;
+                ProvidedValue::Set(serde_json::to_value(licensing)?)
+            }
+        };
+
         // Upload to S3, get derived metadata
         let blob = match data {
             ProvidedValue::Unset => ProvidedValue::Unset,
             ProvidedValue::Set(bytes) => {
+                // Reject early if over quota, before uploading anything
+                let hash = sha512_hash(bytes.as_ref());
+                Self::check_quota(ctx, site_id, user_id, bytes.len() as u64, Some(&hash))
+                    .await?;
+
                 let CreateBlobOutput {
                     hash,
                     mime,
@@ -151,6 +220,8 @@ impl FileService {
                     created: _,
                 } = BlobService::create(ctx, &bytes).await?;
 
+                BlobService::queue_derivatives(ctx, &hash, &mime).await?;
+
                 ProvidedValue::Set(FileBlob {
                     s3_hash: hash,
                     size_hint: size,
@@ -161,6 +232,18 @@ impl FileService {
 
         // Make database changes
 
+        // Other files may have been added since the contents were uploaded
+        if let ProvidedValue::Set(FileBlob {
+            ref s3_hash,
+            size_hint,
+            ..
+        }) = blob
+        {
+            Self::lock_quotas(ctx, site_id, user_id).await?;
+            Self::check_quota(ctx, site_id, user_id, size_hint as u64, Some(s3_hash))
+                .await?;
+        }
+
         // Update file metadata
         let model = file::ActiveModel {
             file_id: Set(file_id),
@@ -487,30 +570,161 @@ impl FileService {
         find_or_error!(Self::get_direct_optional(ctx, file_id, allow_deleted), File)
     }
 
-    /// Hard deletes this file and all duplicates.
+    /// Hard deletes this file and all of its revisions.
     ///
     /// This is a very powerful method and needs to be used carefully.
     /// It should only be accessible to platform staff.
     ///
     /// As opposed to normal soft deletions, this method will completely
     /// remove a file from Wikijump. The file rows will be deleted themselves,
-    /// and will cascade to any places where file IDs are used.
+    /// along with the underlying blobs, unless they are still used elsewhere
+    /// (by another file or as a user avatar). The deletion is recorded in the
+    /// `file_hard_deletion` table.
+    ///
+    /// The blobs are removed by a job once this transaction commits, since
+    /// removing them from S3 cannot be rolled back.
     ///
     /// This method should only be used very rarely to clear content such
     /// as severe copyright violations, abuse content, or comply with court orders.
-    pub async fn hard_delete_all(_ctx: &ServiceContext<'_>, _file_id: i64) -> Result<()> {
-        // TODO find hash. update all files with the same hash
-        // TODO if hash == 00000 then error
-        // TODO add to audit log
-        // TODO hard delete BlobService
+    pub async fn hard_delete_all(
+        ctx: &ServiceContext<'_>,
+        HardDeleteFile {
+            file_id,
+            user_id,
+            reason,
+        }: HardDeleteFile,
+    ) -> Result<FileHardDeletionModel> {
+        let txn = ctx.transaction();
+
+        warn!("Hard deleting file ID {file_id} and all its revisions (by user ID {user_id})");
+
+        // Get file and all its revisions
+        let file = Self::get_direct(ctx, file_id, true).await?;
+        let revisions = FileRevision::find()
+            .filter(file_revision::Column::FileId.eq(file_id))
+            .all(txn)
+            .await?;
 
-        todo!()
+        let revision_count = revisions.len();
+        let mut hashes: Vec<Vec<u8>> = revisions
+            .into_iter()
+            .map(|revision| revision.s3_hash)
+            .collect();
+
+        hashes.sort();
+        hashes.dedup();
+
+        // Delete the rows, revisions first since they reference the file
+        FileRevision::delete_many()
+            .filter(file_revision::Column::FileId.eq(file_id))
+            .exec(txn)
+            .await?;
+
+        File::delete_by_id(file_id).exec(txn).await?;
+
+        // Determine which blobs are no longer used anywhere
+        let mut unused_hashes = Vec::new();
+        for hash in &hashes {
+            if hash.as_slice() == EMPTY_BLOB_HASH {
+                continue;
+            }
+
+            if Self::blob_in_use(ctx, hash).await? {
+                info!(
+                    "Blob {} is still in use, not deleting",
+                    blob_hash_to_hex(hash),
+                );
+            } else {
+                unused_hashes.push(hash);
+            }
+        }
+
+        // Record in audit log
+        let to_hex = |hash: &Vec<u8>| str!(blob_hash_to_hex(hash));
+        let model = file_hard_deletion::ActiveModel {
+            deleted_by: Set(user_id),
+            file_id: Set(file_id),
+            site_id: Set(file.site_id),
+            page_id: Set(file.page_id),
+            name: Set(file.name),
+            revision_count: Set(revision_count.try_into().unwrap_or(i32::MAX)),
+            blob_hashes: Set(hashes.iter().map(to_hex).collect()),
+            deleted_blob_hashes: Set(unused_hashes
+                .iter()
+                .map(|hash| to_hex(hash))
+                .collect()),
+            reason: Set(reason),
+            ..Default::default()
+        };
+        let deletion = model.insert(txn).await?;
+
+        // Queue removal of the blobs, which checks again that they're unused
+        for hash in unused_hashes {
+            JobService::queue_delete_unused_blob(ctx, hash.clone()).await?;
+        }
+
+        Ok(deletion)
+    }
+
+    /// Removes a blob from S3, along with its derivatives, if nothing uses it.
+    ///
+    /// This is run as a job after a hard deletion has committed. Since the blob
+    /// may have been reused by a new upload since then, it is checked again.
+    pub async fn delete_blob_if_unused(
+        ctx: &ServiceContext<'_>,
+        hash: &[u8],
+    ) -> Result<()> {
+        let hex_hash = blob_hash_to_hex(hash);
+
+        if hash == EMPTY_BLOB_HASH || Self::blob_in_use(ctx, hash).await? {
+            info!("Blob {hex_hash} is in use, not deleting");
+            return Ok(());
+        }
+
+        // Unlink any derivatives of the blob,
+        // and find which of those are no longer used either.
+        let mut unused_derivative_hashes = Vec::new();
+        for derivative in BlobService::delete_derivatives(ctx, hash).await? {
+            if !Self::blob_in_use(ctx, &derivative.derivative_hash).await? {
+                unused_derivative_hashes.push(derivative.derivative_hash);
+            }
+        }
+
+        info!("Deleting unused blob {hex_hash}");
+        BlobService::hard_delete(ctx, hash).await?;
+
+        for hash in unused_derivative_hashes {
+            info!("Deleting derivative blob {}", blob_hash_to_hex(&hash));
+            BlobService::hard_delete(ctx, &hash).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Determines if any file revision, user avatar, or derivative still uses this blob.
+    async fn blob_in_use(ctx: &ServiceContext<'_>, hash: &[u8]) -> Result<bool> {
+        let txn = ctx.transaction();
+        let hash = hash.to_vec();
+
+        let (revisions, avatars, derivatives) = try_join!(
+            FileRevision::find()
+                .filter(file_revision::Column::S3Hash.eq(hash.clone()))
+                .count(txn),
+            User::find()
+                .filter(user::Column::AvatarS3Hash.eq(hash.clone()))
+                .count(txn),
+            BlobDerivative::find()
+                .filter(blob_derivative::Column::DerivativeHash.eq(hash))
+                .count(txn),
+        )?;
+
+        Ok(revisions > 0 || avatars > 0 || derivatives > 0)
     }
 
     /// Checks to see if a file already exists at the name specified.
     ///
     /// If so, this method fails with `Error::FileExists`. Otherwise it returns nothing.
-    async fn check_conflicts(
+    pub async fn check_conflicts(
         ctx: &ServiceContext<'_>,
         page_id: i64,
         name: &str,
@@ -541,13 +755,317 @@ impl FileService {
         }
     }
 
+    /// Gets how much storage a site and user are using, and their quotas.
+    ///
+    /// The site's quota is the configured default, unless it has been overridden.
+    pub async fn get_storage_usage(
+        ctx: &ServiceContext<'_>,
+        GetStorageUsage { site_id, user_id }: GetStorageUsage,
+    ) -> Result<GetStorageUsageOutput> {
+        let config = ctx.config();
+        let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+        let site_usage =
+            Self::get_usage(ctx, file_revision::Column::SiteId.eq(site_id)).await?;
+        let user_usage =
+            Self::get_usage(ctx, file_revision::Column::UserId.eq(user_id)).await?;
+
+        let site_quota = site
+            .file_quota_bytes
+            .and_then(|quota| u64::try_from(quota).ok())
+            .unwrap_or(config.site_file_quota_bytes);
+
+        Ok(GetStorageUsageOutput {
+            site: StorageUsage {
+                used_bytes: site_usage,
+                quota_bytes: site_quota,
+            },
+            user: StorageUsage {
+                used_bytes: user_usage,
+                quota_bytes: config.user_file_quota_bytes,
+            },
+        })
+    }
+
+    /// Checks that adding a file of the given size stays within storage quotas.
+    ///
+    /// Since each blob is only counted once, contents which the site or user
+    /// already has do not count towards their quota again. If the hash of the
+    /// contents is not known yet, as with chunked uploads before they are
+    /// assembled, then the size is always counted.
+    ///
+    /// This takes no locks, so it can be used to reject uploads early. It must
+    /// be checked again after `lock_quotas()`, right before the file revision
+    /// is added, so that concurrent uploads cannot together exceed it.
+    ///
+    /// If either the site or user quota would be exceeded, this method fails
+    /// with `Error::FileQuotaExceeded`.
+    pub async fn check_quota(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: i64,
+        size: u64,
+        hash: Option<&[u8]>,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let GetStorageUsageOutput { site, user } =
+            Self::get_storage_usage(ctx, GetStorageUsage { site_id, user_id }).await?;
+
+        // Determine how much this adds for each, if the contents are already stored
+        let added_size = |condition: SimpleExpr| async move {
+            let stored = match hash {
+                None => false,
+                Some(hash) => {
+                    FileRevision::find()
+                        .filter(condition)
+                        .filter(file_revision::Column::S3Hash.eq(hash))
+                        .count(txn)
+                        .await?
+                        > 0
+                }
+            };
+
+            Ok::<_, Error>(if stored { 0 } else { size })
+        };
+
+        let (site_size, user_size) = try_join!(
+            added_size(file_revision::Column::SiteId.eq(site_id)),
+            added_size(file_revision::Column::UserId.eq(user_id)),
+        )?;
+
+        if site.used_bytes.saturating_add(site_size) > site.quota_bytes {
+            error!(
+                "Site ID {site_id} is using {} of {} bytes, cannot add file of {size} bytes",
+                site.used_bytes, site.quota_bytes,
+            );
+            return Err(Error::FileQuotaExceeded);
+        }
+
+        if user.used_bytes.saturating_add(user_size) > user.quota_bytes {
+            error!(
+                "User ID {user_id} is using {} of {} bytes, cannot add file of {size} bytes",
+                user.used_bytes, user.quota_bytes,
+            );
+            return Err(Error::FileQuotaExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// Locks the storage quotas of the site and user until the transaction ends.
+    ///
+    /// Advisory locks are used instead of locking the site and user rows,
+    /// since row locks would block everything else which references them.
+    async fn lock_quotas(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        user_id: i64,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+
+        // Always locked in this order, to avoid deadlocks
+        for key in [
+            format!("file-quota:site:{site_id}"),
+            format!("file-quota:user:{user_id}"),
+        ] {
+            txn.execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "SELECT pg_advisory_xact_lock(hashtextextended($1, 0))",
+                [key.into()],
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sums the sizes of all file revisions matching the condition.
+    ///
+    /// Each blob is only counted once, since identical contents are
+    /// only stored once. Deleted files are included, as their contents
+    /// are retained until they are hard deleted.
+    async fn get_usage(ctx: &ServiceContext<'_>, condition: SimpleExpr) -> Result<u64> {
+        let txn = ctx.transaction();
+        let blobs = Query::select()
+            .distinct()
+            .columns([
+                file_revision::Column::S3Hash,
+                file_revision::Column::SizeHint,
+            ])
+            .from(FileRevision)
+            .and_where(condition)
+            .to_owned();
+
+        // SUM() of a BIGINT is a NUMERIC, so cast it back
+        let query = Query::select()
+            .expr_as(
+                Func::cast_as(
+                    Func::coalesce([
+                        Expr::col(file_revision::Column::SizeHint).sum(),
+                        Expr::val(0).into(),
+                    ]),
+                    Alias::new("BIGINT"),
+                ),
+                Alias::new("usage"),
+            )
+            .from_subquery(blobs, Alias::new("blobs"))
+            .to_owned();
+
+        let usage = match txn
+            .query_one(txn.get_database_backend().build(&query))
+            .await?
+        {
+            Some(row) => row.try_get::<i64>("", "usage")?,
+            None => 0,
+        };
+
+        Ok(u64::try_from(usage).unwrap_or(0))
+    }
+
+    /// Determines the licensing to store for a new file.
+    ///
+    /// If none was provided, then the site's default licensing is used.
+    pub async fn resolve_licensing(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        licensing: Option<FileLicensing>,
+    ) -> Result<JsonValue> {
+        match licensing {
+            Some(licensing) => {
+                Self::check_licensing(ctx, site_id, &licensing).await?;
+                Ok(serde_json::to_value(licensing)?)
+            }
+            None => {
+                let site = SiteService::get(ctx, Reference::Id(site_id)).await?;
+                Ok(site.default_file_licensing)
+            }
+        }
+    }
+
+    /// Validates licensing information for a file.
+    ///
+    /// In addition to the checks in `FileLicensing::validate()`, this ensures
+    /// that any files it is derived from exist on the same site.
+    pub async fn check_licensing(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        licensing: &FileLicensing,
+    ) -> Result<()> {
+        licensing.validate().map_err(Error::FileLicensingInvalid)?;
+
+        let txn = ctx.transaction();
+        for file_id in licensing.derivative_file_ids() {
+            let count = File::find()
+                .filter(
+                    Condition::all()
+                        .add(file::Column::FileId.eq(file_id))
+                        .add(file::Column::SiteId.eq(site_id))
+                        .add(file::Column::DeletedAt.is_null()),
+                )
+                .count(txn)
+                .await?;
+
+            if count == 0 {
+                error!("Licensing refers to file ID {file_id}, which does not exist");
+                return Err(Error::FileLicensingInvalid(
+                    LicensingProblem::DerivativeFileMissing(file_id),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lists the licensing of all current files on a site.
+    ///
+    /// This is intended for auditing, so that files under a particular
+    /// license, or with missing or invalid licensing, can be found.
+    /// Only the checks in `FileLicensing::validate()` are performed,
+    /// references to other files are not verified.
+    pub async fn get_by_license(
+        ctx: &ServiceContext<'_>,
+        GetFilesByLicense {
+            site_id,
+            user_id: _,
+            license,
+            non_compliant,
+        }: GetFilesByLicense,
+    ) -> Result<Vec<FileLicenseEntry>> {
+        info!("Getting files by license for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let mut condition = Condition::all()
+            .add(file_revision::Column::SiteId.eq(site_id))
+            .add(
+                file_revision::Column::RevisionId.in_subquery(
+                    Query::select()
+                        .expr(Expr::col(file_revision::Column::RevisionId).max())
+                        .from(FileRevision)
+                        .and_where(file_revision::Column::SiteId.eq(site_id))
+                        .group_by_col(file_revision::Column::FileId)
+                        .to_owned(),
+                ),
+            )
+            .add(
+                file_revision::Column::FileId.in_subquery(
+                    Query::select()
+                        .column(file::Column::FileId)
+                        .from(File)
+                        .and_where(file::Column::SiteId.eq(site_id))
+                        .and_where(file::Column::DeletedAt.is_null())
+                        .to_owned(),
+                ),
+            );
+
+        if let Some(license) = license {
+            condition = condition.add(Expr::cust_with_values(
+                "licensing->>'license' = $1",
+                [license],
+            ));
+        }
+
+        let revisions = FileRevision::find()
+            .filter(condition)
+            .order_by_asc(file_revision::Column::FileId)
+            .all(txn)
+            .await?;
+
+        let entries = revisions
+            .into_iter()
+            .filter_map(|revision| {
+                let problem = match serde_json::from_value::<FileLicensing>(
+                    revision.licensing.clone(),
+                ) {
+                    Ok(licensing) => licensing.validate().err(),
+                    Err(_) => Some(LicensingProblem::Malformed),
+                };
+
+                if non_compliant && problem.is_none() {
+                    return None;
+                }
+
+                Some(FileLicenseEntry {
+                    file_id: revision.file_id,
+                    page_id: revision.page_id,
+                    name: revision.name,
+                    revision_id: revision.revision_id,
+                    revision_user_id: revision.user_id,
+                    licensing: revision.licensing,
+                    problem,
+                })
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
     /// This runs the regular expression-based text filters against a file's name.
     ///
     /// It does not check the file's contents, as that is a binary blob.
     /// Such a hash filter would need to be implemented through a separate system.
-    async fn run_filter(
+    pub async fn run_filter(
         ctx: &ServiceContext<'_>,
         site_id: i64,
+        user_id: i64,
         name: Option<&str>,
     ) -> Result<()> {
         info!("Checking file data against filters...");
@@ -560,7 +1078,13 @@ impl FileService {
         .await?;
 
         if let Some(name) = name {
-            filter_matcher.verify(ctx, name).await?;
+            let check = FilterCheck {
+                site_id: Some(site_id),
+                user_id: Some(user_id),
+                content_type: FilterContentType::FileName,
+            };
+
+            filter_matcher.verify(ctx, check, name).await?;
         }
 
         Ok(())
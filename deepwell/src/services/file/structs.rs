@@ -18,6 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::licensing::{FileLicensing, LicensingProblem};
 use crate::models::sea_orm_active_enums::FileRevisionType;
 use crate::services::file_revision::{
     CreateFileRevisionOutput, CreateFirstFileRevisionOutput,
@@ -34,7 +35,10 @@ pub struct UploadFile {
     pub revision_comments: String,
     pub user_id: i64,
     pub data: Bytes<'static>,
-    pub licensing: JsonValue, // TODO
+
+    /// If not set, the site's default licensing is used.
+    #[serde(default)]
+    pub licensing: Option<FileLicensing>,
 
     #[serde(default)]
     pub bypass_filter: bool,
@@ -42,6 +46,26 @@ pub struct UploadFile {
 
 pub type UploadFileOutput = CreateFirstFileRevisionOutput;
 
+/// A resized copy of an image file.
+#[derive(Serialize, Debug, Clone)]
+pub struct FileDerivative {
+    pub max_dimension: i32,
+    pub created_at: OffsetDateTime,
+    pub mime: String,
+    pub size: i64,
+}
+
+/// The metadata for a new file, separate from its contents.
+#[derive(Debug, Clone)]
+pub struct CreateFile {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub name: String,
+    pub revision_comments: String,
+    pub user_id: i64,
+    pub licensing: JsonValue,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetFile<'a> {
     pub site_id: i64,
@@ -72,6 +96,8 @@ pub struct GetFileOutput {
     pub revision_user_id: i64,
     pub name: String,
     pub data: Option<Bytes<'static>>,
+    pub data_derivative: Option<i32>,
+    pub derivatives: Option<Vec<FileDerivative>>,
     pub mime: String,
     pub size: i64,
     pub licensing: JsonValue,
@@ -99,7 +125,7 @@ pub struct EditFile {
 pub struct EditFileBody {
     pub name: ProvidedValue<String>,
     pub data: ProvidedValue<Bytes<'static>>,
-    pub licensing: ProvidedValue<serde_json::Value>,
+    pub licensing: ProvidedValue<FileLicensing>,
 }
 
 pub type EditFileOutput = CreateFileRevisionOutput;
@@ -152,3 +178,53 @@ pub struct RestoreFileOutput {
     pub file_revision_id: i64,
     pub file_revision_number: i32,
 }
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HardDeleteFile {
+    pub file_id: i64,
+    pub user_id: i64,
+    pub reason: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetFilesByLicense {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    /// Only return files under this license.
+    #[serde(default)]
+    pub license: Option<String>,
+
+    /// Only return files whose licensing does not pass validation.
+    #[serde(default)]
+    pub non_compliant: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FileLicenseEntry {
+    pub file_id: i64,
+    pub page_id: i64,
+    pub name: String,
+    pub revision_id: i64,
+    pub revision_user_id: i64,
+    pub licensing: JsonValue,
+    pub problem: Option<LicensingProblem>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetStorageUsage {
+    pub site_id: i64,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetStorageUsageOutput {
+    pub site: StorageUsage,
+    pub user: StorageUsage,
+}
+
+#[derive(Serialize, Debug, Copy, Clone)]
+pub struct StorageUsage {
+    pub used_bytes: u64,
+    pub quota_bytes: u64,
+}
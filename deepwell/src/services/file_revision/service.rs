@@ -137,7 +137,7 @@ impl FileRevisionService {
             return Err(Error::FileMimeEmpty);
         }
 
-        // TODO validate licensing field
+        // Licensing is validated by FileService before getting here
 
         // Run outdater
         let page_slug = Self::get_page_slug(ctx, site_id, page_id).await?;
@@ -0,0 +1,43 @@
+/*
+ * services/file_upload/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service for uploading large files in chunks.
+//!
+//! An upload session is started with the file's metadata, then its contents
+//! are sent as numbered parts, each in its own request. These are streamed
+//! into an S3 multipart upload, so the server never holds the whole file.
+//! Parts can be re-sent, and the session's status lists which parts were
+//! received, so that an interrupted upload can be resumed.
+//!
+//! Once finished, the upload is hashed and moved to its content address,
+//! and the file entity is created as with a regular upload.
+//! Sessions which are left idle are aborted by a recurring job.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::{FileUploadService, MAXIMUM_PART_SIZE};
+pub use self::structs::*;
@@ -0,0 +1,523 @@
+/*
+ * services/file_upload/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::file_upload::{self, Entity as FileUpload, Model as FileUploadModel};
+use crate::models::file_upload_part::{
+    self, Entity as FileUploadPart, Model as FileUploadPartModel,
+};
+use crate::services::file::{CreateFile, UploadFileOutput};
+use crate::services::{BlobService, FileService};
+use cuid2::cuid;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::DeleteResult;
+use time::OffsetDateTime;
+
+/// The smallest part S3 will accept, other than the final part.
+const MINIMUM_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// The largest part we will accept.
+///
+/// S3 allows much larger parts, but each part is sent as one request,
+/// so this is kept low enough to be held in memory. The server's
+/// maximum request size is derived from this.
+pub const MAXIMUM_PART_SIZE: usize = 16 * 1024 * 1024;
+
+/// Prefix for the S3 keys that uploads are staged at.
+const UPLOAD_KEY_PREFIX: &str = "upload/";
+
+/// The highest part number S3 will accept.
+const MAXIMUM_PART_NUMBER: u32 = 10000;
+
+#[derive(Debug)]
+pub struct FileUploadService;
+
+impl FileUploadService {
+    /// Starts an upload session for a new file.
+    ///
+    /// The file's name is checked here, so that the user finds out
+    /// about any problems before sending the contents.
+    pub async fn start(
+        ctx: &ServiceContext<'_>,
+        StartFileUpload {
+            site_id,
+            page_id,
+            name,
+            revision_comments,
+            user_id,
+            licensing,
+            bypass_filter,
+        }: StartFileUpload,
+    ) -> Result<StartFileUploadOutput> {
+        info!("Starting upload session for file '{name}' on page ID {page_id}");
+
+        let txn = ctx.transaction();
+        FileService::check_conflicts(ctx, page_id, &name, "upload").await?;
+
+        if !bypass_filter {
+            FileService::run_filter(ctx, site_id, user_id, Some(&name)).await?;
+        }
+
+        let licensing = FileService::resolve_licensing(ctx, site_id, licensing).await?;
+        let upload_id = cuid();
+        let model = file_upload::ActiveModel {
+            upload_id: Set(upload_id.clone()),
+            user_id: Set(user_id),
+            site_id: Set(site_id),
+            page_id: Set(page_id),
+            name: Set(name),
+            revision_comments: Set(revision_comments),
+            licensing: Set(licensing),
+            s3_key: Set(format!("{UPLOAD_KEY_PREFIX}{upload_id}")),
+            ..Default::default()
+        };
+        let upload = model.insert(txn).await?;
+
+        Ok(StartFileUploadOutput {
+            upload_id,
+            expires_at: Self::expires_at(ctx, &upload),
+        })
+    }
+
+    /// Uploads one part of the file's contents.
+    ///
+    /// Parts are numbered from 1, and the first part must be sent before
+    /// any others, since it is used to determine the file's MIME type.
+    /// Sending a part number again replaces it.
+    pub async fn upload_part(
+        ctx: &ServiceContext<'_>,
+        UploadFilePart {
+            upload_id,
+            user_id,
+            part_number,
+            data,
+        }: UploadFilePart,
+    ) -> Result<()> {
+        let data = data.as_ref();
+        info!(
+            "Uploading part {part_number} (length {}) for upload ID {upload_id}",
+            data.len(),
+        );
+
+        let upload = Self::get(ctx, &upload_id, user_id).await?;
+
+        if part_number == 0 || part_number > MAXIMUM_PART_NUMBER {
+            error!("Part number {part_number} is out of range");
+            return Err(Error::FileUploadPartOrder);
+        }
+
+        if data.is_empty() || data.len() > MAXIMUM_PART_SIZE {
+            error!("Part size {} is out of range", data.len());
+            return Err(Error::FileUploadPartSize);
+        }
+
+        // Check the total size, replacing this part if it was already sent
+        let size = i64::try_from(data.len()).expect("Buffer size exceeds i64");
+        let other_parts_size: i64 = Self::get_parts(ctx, &upload_id)
+            .await?
+            .iter()
+            .filter(|part| part.part_number != part_number as i32)
+            .map(|part| part.size)
+            .sum();
+
+        let total_size = u64::try_from(other_parts_size + size).unwrap_or(u64::MAX);
+        if total_size > ctx.config().maximum_upload_bytes {
+            error!("Upload would be {total_size} bytes, which exceeds the maximum");
+            return Err(Error::FileUploadTooLarge);
+        }
+
+        FileService::check_quota(ctx, upload.site_id, upload.user_id, total_size, None)
+            .await?;
+
+        // Start the S3 upload, if this is the first part
+        //
+        // The session row is locked, so concurrent requests for
+        // the first part cannot each start their own S3 upload.
+        let (s3_upload_id, mime, started) =
+            match (upload.s3_upload_id.clone(), upload.mime.clone()) {
+                (Some(s3_upload_id), Some(mime)) => (s3_upload_id, mime, false),
+                _ if part_number != 1 => {
+                    error!("Cannot upload part {part_number} before the first part");
+                    return Err(Error::FileUploadPartOrder);
+                }
+                _ => {
+                    let mime = ctx.mime().get_mime_type(data.to_vec()).await?;
+                    let s3_upload_id =
+                        BlobService::start_upload(ctx, &upload.s3_key, &mime).await?;
+
+                    (s3_upload_id, mime, true)
+                }
+            };
+
+        let result =
+            Self::add_part(ctx, &upload, &s3_upload_id, &mime, part_number, data).await;
+
+        // Don't leave behind an S3 upload which the session doesn't refer to.
+        //
+        // If the transaction fails to commit after this, then the S3 upload
+        // is instead aborted when abandoned uploads are pruned.
+        if result.is_err() && started {
+            if let Err(error) =
+                BlobService::abort_upload(ctx, &upload.s3_key, &s3_upload_id).await
+            {
+                warn!("Unable to abort S3 upload for upload ID {upload_id}: {error}");
+            }
+        }
+
+        result
+    }
+
+    /// Sends a part to S3, and records it in the session.
+    async fn add_part(
+        ctx: &ServiceContext<'_>,
+        upload: &FileUploadModel,
+        s3_upload_id: &str,
+        mime: &str,
+        part_number: u32,
+        data: &[u8],
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let upload_id = &upload.upload_id;
+        let size = i64::try_from(data.len()).expect("Buffer size exceeds i64");
+        let etag = BlobService::upload_part(
+            ctx,
+            &upload.s3_key,
+            s3_upload_id,
+            mime,
+            part_number,
+            data,
+        )
+        .await?;
+
+        // Record part
+        let model = file_upload_part::ActiveModel {
+            upload_id: Set(upload_id.clone()),
+            part_number: Set(part_number as i32),
+            created_at: Set(now()),
+            size: Set(size),
+            etag: Set(etag),
+        };
+
+        FileUploadPart::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    file_upload_part::Column::UploadId,
+                    file_upload_part::Column::PartNumber,
+                ])
+                .update_columns([
+                    file_upload_part::Column::CreatedAt,
+                    file_upload_part::Column::Size,
+                    file_upload_part::Column::Etag,
+                ])
+                .to_owned(),
+            )
+            .exec(txn)
+            .await?;
+
+        // Keep the session alive
+        let model = file_upload::ActiveModel {
+            upload_id: Set(upload_id.clone()),
+            updated_at: Set(now()),
+            s3_upload_id: Set(Some(str!(s3_upload_id))),
+            mime: Set(Some(str!(mime))),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Ok(())
+    }
+
+    /// Finishes an upload session, creating the file.
+    ///
+    /// The parts must be numbered contiguously from 1, and every part
+    /// except the last must be at least 5 MiB, as required by S3.
+    pub async fn finish(
+        ctx: &ServiceContext<'_>,
+        GetFileUpload { upload_id, user_id }: GetFileUpload,
+    ) -> Result<UploadFileOutput> {
+        info!("Finishing upload ID {upload_id}");
+
+        let txn = ctx.transaction();
+        let upload = Self::get(ctx, &upload_id, user_id).await?;
+        let parts = Self::get_parts(ctx, &upload_id).await?;
+
+        let s3_upload_id = match upload.s3_upload_id {
+            Some(ref s3_upload_id) if !parts.is_empty() => s3_upload_id,
+            _ => {
+                error!("Cannot finish upload ID {upload_id}, no parts were uploaded");
+                return Err(Error::FileUploadPartOrder);
+            }
+        };
+
+        for (index, part) in parts.iter().enumerate() {
+            if part.part_number as usize != index + 1 {
+                error!("Part {} is missing from upload ID {upload_id}", index + 1);
+                return Err(Error::FileUploadPartOrder);
+            }
+
+            let is_last = index + 1 == parts.len();
+            if !is_last && (part.size as usize) < MINIMUM_PART_SIZE {
+                error!("Part {} is smaller than the minimum size", part.part_number);
+                return Err(Error::FileUploadPartSize);
+            }
+        }
+
+        // The name may have been taken while the upload was in progress
+        FileService::check_conflicts(ctx, upload.page_id, &upload.name, "create").await?;
+
+        // Other files may have been added while the upload was in progress,
+        // so reject early before assembling. The contents aren't hashed yet,
+        // so their size is always counted. This is checked again once the file
+        // is created, after the quotas are locked.
+        let total_size = parts.iter().map(|part| part.size).sum::<i64>();
+        FileService::check_quota(
+            ctx,
+            upload.site_id,
+            upload.user_id,
+            u64::try_from(total_size).unwrap_or(u64::MAX),
+            None,
+        )
+        .await?;
+
+        // Assemble the parts and move the result to its content address
+        let s3_parts = parts
+            .into_iter()
+            .map(|part| (part.part_number as u32, part.etag))
+            .collect();
+
+        BlobService::finish_upload(ctx, &upload.s3_key, s3_upload_id, s3_parts).await?;
+        // If the file is infected, the upload is discarded and this fails.
+        // The session can't be retried, and is removed by prune() once it expires.
+        let blob = BlobService::create_from_upload(ctx, &upload.s3_key).await?;
+
+        // Remove the session, its parts are deleted by cascade
+        FileUpload::delete_by_id(upload_id).exec(txn).await?;
+
+        FileService::create_from_blob(
+            ctx,
+            CreateFile {
+                site_id: upload.site_id,
+                page_id: upload.page_id,
+                name: upload.name,
+                revision_comments: upload.revision_comments,
+                user_id: upload.user_id,
+                licensing: upload.licensing,
+            },
+            blob,
+        )
+        .await
+    }
+
+    /// Cancels an upload session, discarding any parts which were uploaded.
+    pub async fn cancel(
+        ctx: &ServiceContext<'_>,
+        GetFileUpload { upload_id, user_id }: GetFileUpload,
+    ) -> Result<()> {
+        info!("Cancelling upload ID {upload_id}");
+
+        let upload = Self::get(ctx, &upload_id, user_id).await?;
+        Self::abort(ctx, upload).await
+    }
+
+    /// Gets the status of an upload session, including which parts were received.
+    ///
+    /// This is used to resume an interrupted upload.
+    pub async fn get_status(
+        ctx: &ServiceContext<'_>,
+        GetFileUpload { upload_id, user_id }: GetFileUpload,
+    ) -> Result<FileUploadStatus> {
+        info!("Getting status of upload ID {upload_id}");
+
+        let upload = Self::get(ctx, &upload_id, user_id).await?;
+        let parts = Self::get_parts(ctx, &upload_id).await?;
+        let expires_at = Self::expires_at(ctx, &upload);
+
+        Ok(FileUploadStatus {
+            upload_id: upload.upload_id,
+            site_id: upload.site_id,
+            page_id: upload.page_id,
+            name: upload.name,
+            created_at: upload.created_at,
+            expires_at,
+            parts,
+        })
+    }
+
+    /// Aborts and removes all upload sessions which have been idle too long.
+    ///
+    /// # Returns
+    /// The number of upload sessions which were removed.
+    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<u64> {
+        info!("Pruning abandoned upload sessions");
+
+        let txn = ctx.transaction();
+        let cutoff = now() - ctx.config().upload_session_duration;
+        let s3_uploads = BlobService::list_uploads(ctx, UPLOAD_KEY_PREFIX).await?;
+        let uploads = FileUpload::find()
+            .filter(file_upload::Column::UpdatedAt.lt(cutoff))
+            .all(txn)
+            .await?;
+
+        let mut pruned = 0;
+        for upload in uploads {
+            // If the S3 upload was already completed, such as when finishing
+            // failed because the file was infected, there is nothing to abort.
+            if s3_upload_open(&upload, &s3_uploads) {
+                Self::abort(ctx, upload).await?;
+            } else {
+                FileUpload::delete_by_id(upload.upload_id).exec(txn).await?;
+            }
+
+            pruned += 1;
+        }
+
+        // Abort S3 uploads which no session refers to, which can happen
+        // if the transaction that started one failed to commit.
+        for (key, s3_upload_id, initiated) in s3_uploads {
+            if initiated >= cutoff {
+                continue;
+            }
+
+            let sessions = FileUpload::find()
+                .filter(file_upload::Column::S3UploadId.eq(s3_upload_id.as_str()))
+                .count(txn)
+                .await?;
+
+            if sessions == 0 {
+                BlobService::abort_upload(ctx, &key, &s3_upload_id).await?;
+                pruned += 1;
+            }
+        }
+
+        debug!("Pruned {pruned} abandoned upload sessions");
+        Ok(pruned)
+    }
+
+    /// Aborts the S3 upload, if one was started, and removes the session.
+    async fn abort(ctx: &ServiceContext<'_>, upload: FileUploadModel) -> Result<()> {
+        let txn = ctx.transaction();
+
+        if let Some(ref s3_upload_id) = upload.s3_upload_id {
+            BlobService::abort_upload(ctx, &upload.s3_key, s3_upload_id).await?;
+        }
+
+        let DeleteResult { rows_affected } =
+            FileUpload::delete_by_id(upload.upload_id).exec(txn).await?;
+
+        debug_assert_eq!(rows_affected, 1, "Upload session was not deleted");
+        Ok(())
+    }
+
+    /// Gets an upload session belonging to this user.
+    ///
+    /// Sessions which have been idle too long are treated as missing,
+    /// even if they have not been pruned yet. The row is locked until the
+    /// transaction ends, so that requests for the same session are serialized.
+    async fn get(
+        ctx: &ServiceContext<'_>,
+        upload_id: &str,
+        user_id: i64,
+    ) -> Result<FileUploadModel> {
+        let txn = ctx.transaction();
+        let cutoff = now() - ctx.config().upload_session_duration;
+        let upload = FileUpload::find()
+            .filter(
+                Condition::all()
+                    .add(file_upload::Column::UploadId.eq(upload_id))
+                    .add(file_upload::Column::UserId.eq(user_id))
+                    .add(file_upload::Column::UpdatedAt.gte(cutoff)),
+            )
+            .lock_exclusive()
+            .one(txn)
+            .await?;
+
+        upload.ok_or(Error::FileUploadNotFound)
+    }
+
+    async fn get_parts(
+        ctx: &ServiceContext<'_>,
+        upload_id: &str,
+    ) -> Result<Vec<FileUploadPartModel>> {
+        let txn = ctx.transaction();
+        let parts = FileUploadPart::find()
+            .filter(file_upload_part::Column::UploadId.eq(upload_id))
+            .order_by_asc(file_upload_part::Column::PartNumber)
+            .all(txn)
+            .await?;
+
+        Ok(parts)
+    }
+
+    #[inline]
+    fn expires_at(ctx: &ServiceContext<'_>, upload: &FileUploadModel) -> OffsetDateTime {
+        upload.updated_at + ctx.config().upload_session_duration
+    }
+}
+
+/// Determines if the session's S3 upload is still in progress.
+///
+/// # Arguments
+/// * `s3_uploads` &mdash; The S3 uploads in progress, from `BlobService::list_uploads()`.
+fn s3_upload_open(
+    upload: &FileUploadModel,
+    s3_uploads: &[(String, String, OffsetDateTime)],
+) -> bool {
+    match upload.s3_upload_id {
+        None => false,
+        Some(ref s3_upload_id) => s3_uploads
+            .iter()
+            .any(|(key, id, _)| key == &upload.s3_key && id == s3_upload_id),
+    }
+}
+
+#[test]
+fn prune_after_infected_finish() {
+    let upload = FileUploadModel {
+        upload_id: str!("upload"),
+        created_at: now(),
+        updated_at: now(),
+        user_id: 1,
+        site_id: 1,
+        page_id: 1,
+        name: str!("file.png"),
+        revision_comments: String::new(),
+        licensing: serde_json::Value::Null,
+        s3_key: str!("upload/key"),
+        s3_upload_id: Some(str!("s3-upload")),
+        mime: Some(str!("image/png")),
+    };
+
+    let in_progress = vec![(str!("upload/key"), str!("s3-upload"), now())];
+    let other = vec![(str!("upload/other"), str!("s3-other"), now())];
+
+    // Still being uploaded, so it must be aborted
+    assert!(s3_upload_open(&upload, &in_progress));
+
+    // Completed by finish(), then discarded as infected
+    assert!(!s3_upload_open(&upload, &[]));
+    assert!(!s3_upload_open(&upload, &other));
+
+    // No parts were ever uploaded
+    let upload = FileUploadModel {
+        s3_upload_id: None,
+        ..upload
+    };
+    assert!(!s3_upload_open(&upload, &in_progress));
+}
@@ -0,0 +1,73 @@
+/*
+ * services/file_upload/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::file_upload_part::Model as FileUploadPartModel;
+use crate::services::file::FileLicensing;
+use crate::web::Bytes;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StartFileUpload {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub name: String,
+    pub revision_comments: String,
+    pub user_id: i64,
+
+    /// If not set, the site's default licensing is used.
+    #[serde(default)]
+    pub licensing: Option<FileLicensing>,
+
+    #[serde(default)]
+    pub bypass_filter: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StartFileUploadOutput {
+    pub upload_id: String,
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UploadFilePart {
+    pub upload_id: String,
+    pub user_id: i64,
+    pub part_number: u32,
+    pub data: Bytes<'static>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetFileUpload {
+    pub upload_id: String,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FileUploadStatus {
+    pub upload_id: String,
+    pub site_id: i64,
+    pub page_id: i64,
+    pub name: String,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+
+    /// The parts which have been received so far, in order.
+    pub parts: Vec<FileUploadPartModel>,
+}
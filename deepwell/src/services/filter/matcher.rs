@@ -19,7 +19,12 @@
  */
 
 use super::prelude::*;
-use regex::RegexSet;
+use crate::models::sea_orm_active_enums::FilterContentType;
+use crate::services::FilterService;
+use regex::{Regex, RegexSet};
+
+/// How many characters of context to include on either side of a match.
+const EXCERPT_CONTEXT_CHARS: usize = 40;
 
 /// Describes one filter which a `FilterMatcher` can verify against.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -49,8 +54,14 @@ impl FilterMatcher {
 
     /// Verifies that the given string does not trip any filters of this type.
     ///
-    /// For any filter violations, they are logged and an error is returned.
-    pub async fn verify(&self, ctx: &ServiceContext<'_>, text: &str) -> Result<()> {
+    /// For any filter violations, they are logged, recorded in the
+    /// audit log along with what was being checked, and an error is returned.
+    pub async fn verify(
+        &self,
+        ctx: &ServiceContext<'_>,
+        check: FilterCheck,
+        text: &str,
+    ) -> Result<()> {
         let matches = self.regex_set.matches(text);
         if !matches.matched_any() {
             info!("String passed all filters, is clear");
@@ -60,15 +71,253 @@ impl FilterMatcher {
         for index in matches {
             let description = &self.filter_data[index];
             error!(
-                "String failed filter ID {}: {}",
-                description.filter_id, description.description,
+                "String failed filter ID {} ({:?}): {}",
+                description.filter_id, check.content_type, description.description,
             );
 
-            // TODO audit log, with contextual data (what it's checking)
-            //      (will need to add extra args)
-            let _ = ctx;
+            // Find where the filter matched, to record the offending text.
+            //
+            // This pattern already compiled as part of the set, so it is valid.
+            let pattern = &self.regex_set.patterns()[index];
+            let excerpt =
+                match Regex::new(pattern).ok().and_then(|regex| regex.find(text)) {
+                    Some(mat) => excerpt(text, mat.start(), mat.end()),
+                    None => excerpt(text, 0, 0),
+                };
+
+            FilterService::add_violation(ctx, description.filter_id, check, excerpt)
+                .await?;
         }
 
         Err(Error::FilterViolation)
     }
 }
+
+/// Checks content against filters, without enforcing them.
+///
+/// Used to see which existing content a filter would match
+/// before it is enabled. Stops once the limit of matches is reached,
+/// or once the maximum number of objects (pages, files, users) have been checked.
+#[derive(Debug)]
+pub struct FilterTester {
+    regexes: Vec<(Option<i64>, Regex)>,
+    limit: usize,
+    maximum_objects: usize,
+    objects: usize,
+    matches: Vec<FilterTestMatch>,
+}
+
+impl FilterTester {
+    #[inline]
+    pub fn new(
+        regexes: Vec<(Option<i64>, Regex)>,
+        limit: usize,
+        maximum_objects: usize,
+    ) -> Self {
+        FilterTester {
+            regexes,
+            limit,
+            maximum_objects,
+            objects: 0,
+            matches: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.matches.len() >= self.limit
+    }
+
+    /// Counts another object to be checked.
+    ///
+    /// Returns `false` if testing should stop instead.
+    pub fn next_object(&mut self) -> bool {
+        if self.is_full() || self.objects >= self.maximum_objects {
+            return false;
+        }
+
+        self.objects += 1;
+        true
+    }
+
+    pub fn check(
+        &mut self,
+        content_type: FilterContentType,
+        site_id: Option<i64>,
+        object_id: i64,
+        text: &str,
+    ) {
+        self.add_matches(content_type, site_id, object_id, text, false);
+    }
+
+    /// Checks an email address, masking it in any excerpts.
+    pub fn check_email(&mut self, object_id: i64, email: &str) {
+        self.add_matches(FilterContentType::Email, None, object_id, email, true);
+    }
+
+    fn add_matches(
+        &mut self,
+        content_type: FilterContentType,
+        site_id: Option<i64>,
+        object_id: i64,
+        text: &str,
+        mask: bool,
+    ) {
+        for (filter_id, regex) in &self.regexes {
+            if self.matches.len() >= self.limit {
+                break;
+            }
+
+            if let Some(mat) = regex.find(text) {
+                let excerpt = if mask {
+                    mask_email(text)
+                } else {
+                    excerpt(text, mat.start(), mat.end())
+                };
+
+                self.matches.push(FilterTestMatch {
+                    filter_id: *filter_id,
+                    content_type,
+                    site_id,
+                    object_id,
+                    excerpt,
+                });
+            }
+        }
+    }
+
+    #[inline]
+    pub fn into_matches(self) -> Vec<FilterTestMatch> {
+        self.matches
+    }
+}
+
+/// Extracts the matched portion of the text, with some surrounding context.
+///
+/// Ellipses are added on either side if the text was truncated there.
+fn excerpt(text: &str, start: usize, end: usize) -> String {
+    let before = text[..start]
+        .char_indices()
+        .rev()
+        .nth(EXCERPT_CONTEXT_CHARS - 1)
+        .map(|(index, _)| index);
+
+    let after = text[end..]
+        .char_indices()
+        .nth(EXCERPT_CONTEXT_CHARS)
+        .map(|(index, _)| end + index);
+
+    let mut excerpt = String::new();
+    if before.is_some_and(|index| index > 0) {
+        excerpt.push('…');
+    }
+
+    excerpt.push_str(&text[before.unwrap_or(0)..after.unwrap_or(text.len())]);
+
+    if after.is_some() {
+        excerpt.push('…');
+    }
+
+    excerpt
+}
+
+/// Hides the local part of an email address, other than its first character.
+///
+/// The domain is kept, since filters on email addresses are usually for domains.
+fn mask_email(email: &str) -> String {
+    match email.split_once('@') {
+        Some((local, domain)) => match local.chars().next() {
+            Some(first) => format!("{first}***@{domain}"),
+            None => format!("***@{domain}"),
+        },
+        None => str!("***"),
+    }
+}
+
+#[test]
+fn excerpts() {
+    macro_rules! check {
+        ($text:expr, $start:expr, $end:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                excerpt($text, $start, $end),
+                $expected,
+                "Actual excerpt doesn't match expected",
+            );
+        };
+    }
+
+    let long = "a".repeat(50);
+    let long_text = format!("{long}BAD{long}");
+    let context = "a".repeat(EXCERPT_CONTEXT_CHARS);
+
+    check!("", 0, 0, "");
+    check!("bad", 0, 3, "bad");
+    check!("this is bad text", 8, 11, "this is bad text");
+    check!(&long_text, 50, 53, format!("…{context}BAD{context}…"));
+    check!("ééé bad ééé", 7, 10, "ééé bad ééé");
+}
+
+#[test]
+fn filter_tester() {
+    let regexes = vec![
+        (None, Regex::new("(?i)bad").unwrap()),
+        (Some(1), Regex::new("worse").unwrap()),
+    ];
+    let mut tester = FilterTester::new(regexes, 3, 10);
+
+    tester.check(FilterContentType::PageTitle, Some(1), 10, "fine title");
+    tester.check(
+        FilterContentType::PageWikitext,
+        Some(1),
+        10,
+        "BAD and worse",
+    );
+    assert!(!tester.is_full());
+
+    tester.check(FilterContentType::UserName, None, 20, "bad and worse");
+    assert!(tester.is_full());
+
+    let matches = tester.into_matches();
+    assert_eq!(matches.len(), 3, "Limit not respected");
+    assert_eq!(matches[0].filter_id, None);
+    assert_eq!(matches[0].object_id, 10);
+    assert_eq!(matches[0].excerpt, "BAD and worse");
+    assert_eq!(matches[1].filter_id, Some(1));
+    assert_eq!(matches[2].object_id, 20);
+}
+
+#[test]
+fn filter_tester_objects() {
+    let regexes = vec![(None, Regex::new("spam").unwrap())];
+    let mut tester = FilterTester::new(regexes, 10, 2);
+
+    assert!(tester.next_object());
+    tester.check_email(1, "spammer@example.com");
+    assert!(tester.next_object());
+    tester.check_email(2, "someone@spam.example");
+    assert!(!tester.next_object(), "Object maximum not respected");
+
+    let matches = tester.into_matches();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].excerpt, "s***@example.com");
+    assert_eq!(matches[1].excerpt, "s***@spam.example");
+}
+
+#[test]
+fn masked_emails() {
+    macro_rules! check {
+        ($email:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                mask_email($email),
+                $expected,
+                "Masked email doesn't match expected",
+            );
+        };
+    }
+
+    check!("user@example.com", "u***@example.com");
+    check!("é@example.com", "é***@example.com");
+    check!("@example.com", "***@example.com");
+    check!("not an email", "***");
+    check!("", "***");
+}
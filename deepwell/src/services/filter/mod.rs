@@ -30,7 +30,7 @@
 #[allow(unused_imports)]
 mod prelude {
     pub use super::super::prelude::*;
-    pub use super::matcher::{FilterMatcher, FilterSummary};
+    pub use super::matcher::{FilterMatcher, FilterSummary, FilterTester};
     pub use super::structs::*;
 }
 
@@ -38,6 +38,6 @@ mod matcher;
 mod service;
 mod structs;
 
-pub use self::matcher::{FilterMatcher, FilterSummary};
+pub use self::matcher::{FilterMatcher, FilterSummary, FilterTester};
 pub use self::service::FilterService;
 pub use self::structs::*;
@@ -19,9 +19,34 @@
  */
 
 use super::prelude::*;
+use crate::models::file::{self, Entity as File};
 use crate::models::filter::{self, Entity as Filter, Model as FilterModel};
+use crate::models::filter_violation::{
+    self, Entity as FilterViolation, Model as FilterViolationModel,
+};
+use crate::models::page::{self, Entity as Page};
+use crate::models::relation::{self, Entity as Relation};
+use crate::models::sea_orm_active_enums::FilterContentType;
+use crate::models::user::{self, Entity as User};
+use crate::services::relation::{
+    current_entries_condition, RelationDirection, RelationObject, RelationType,
+};
+use crate::services::{PageRevisionService, TextService};
 use crate::utils::trim_start_matches_in_place;
 use regex::{Regex, RegexSet};
+use sea_orm::sea_query::Query;
+
+/// The most violations which can be fetched at once.
+const MAXIMUM_VIOLATIONS_LIMIT: u64 = 100;
+
+/// The most matches a filter test can return.
+const MAXIMUM_TEST_MATCHES: usize = 500;
+
+/// The most pages, files, and users a filter test checks.
+///
+/// This bounds the work done for a test across a large site or the platform,
+/// even if few matches are found.
+const MAXIMUM_TEST_OBJECTS: usize = 5000;
 
 #[derive(Debug)]
 pub struct FilterService;
@@ -30,7 +55,7 @@ impl FilterService {
     pub async fn create(
         ctx: &ServiceContext<'_>,
         site_id: Option<i64>,
-        CreateFilter {
+        CreateFilterBody {
             affects_user,
             affects_email,
             affects_page,
@@ -39,7 +64,7 @@ impl FilterService {
             case_sensitive,
             mut regex,
             description,
-        }: CreateFilter,
+        }: CreateFilterBody,
     ) -> Result<FilterModel> {
         let txn = ctx.transaction();
 
@@ -75,11 +100,11 @@ impl FilterService {
         Ok(filter)
     }
 
-    #[allow(dead_code)] // TEMP
     pub async fn update(
         ctx: &ServiceContext<'_>,
         UpdateFilter {
             filter_id,
+            user_id: _,
             affects_user,
             affects_email,
             affects_page,
@@ -94,6 +119,20 @@ impl FilterService {
 
         info!("Updating filter with ID {filter_id}");
 
+        let filter = Self::get(ctx, filter_id).await?;
+        if filter.deleted_at.is_some() {
+            error!("Attempting to update deleted filter");
+            return Err(Error::FilterNotFound);
+        }
+
+        // Ensure the new regular expression is valid
+        if let ProvidedValue::Set(ref regex) = regex {
+            if let Err(error) = Regex::new(regex) {
+                error!("Passed regular expression '{regex}' pattern is invalid: {error}",);
+                return Err(Error::FilterRegexInvalid(error));
+            }
+        }
+
         let mut model = filter::ActiveModel {
             filter_id: Set(filter_id),
             updated_at: Set(Some(now())),
@@ -114,7 +153,7 @@ impl FilterService {
                 // If the regex is not being changed, remove (and conditionally readd) the
                 // case-insensitivity flag from the database's regex.
                 ProvidedValue::Unset => {
-                    let mut model_regex = str!(model.get(filter::Column::Regex).as_ref());
+                    let mut model_regex = filter.regex;
                     trim_start_matches_in_place(&mut model_regex, "(?i)");
 
                     if !case_sensitive {
@@ -160,7 +199,6 @@ impl FilterService {
         Ok(filter)
     }
 
-    #[allow(dead_code)] // TEMP
     pub async fn delete(ctx: &ServiceContext<'_>, filter_id: i64) -> Result<()> {
         info!("Deleting filter with ID {filter_id}");
         let txn = ctx.transaction();
@@ -183,7 +221,6 @@ impl FilterService {
     }
 
     /// Restores a filter, causing it to be undeleted.
-    #[allow(dead_code)] // TEMP
     pub async fn restore(
         ctx: &ServiceContext<'_>,
         filter_id: i64,
@@ -315,6 +352,312 @@ impl FilterService {
         Ok(FilterMatcher::new(regex_set, filter_data))
     }
 
+    /// Records that some content violated a filter.
+    ///
+    /// Because a filter violation fails the request, this is written outside
+    /// of the current transaction, so that it is kept when it is rolled back.
+    pub async fn add_violation(
+        ctx: &ServiceContext<'_>,
+        filter_id: i64,
+        FilterCheck {
+            site_id,
+            user_id,
+            content_type,
+        }: FilterCheck,
+        excerpt: String,
+    ) -> Result<()> {
+        info!("Recording {content_type:?} violation of filter ID {filter_id}");
+
+        let model = filter_violation::ActiveModel {
+            filter_id: Set(filter_id),
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            content_type: Set(content_type),
+            excerpt: Set(excerpt),
+            ..Default::default()
+        };
+        FilterViolation::insert(model).exec(ctx.database()).await?;
+        Ok(())
+    }
+
+    /// Gets recorded filter violations, newest first.
+    pub async fn get_violations(
+        ctx: &ServiceContext<'_>,
+        GetFilterViolations {
+            site_id,
+            user_id: _,
+            filter_id,
+            violator_id,
+            content_type,
+            before_id,
+            limit,
+        }: GetFilterViolations,
+    ) -> Result<Vec<FilterViolationModel>> {
+        let txn = ctx.transaction();
+
+        info!("Getting filter violations for site ID {site_id:?}");
+
+        let site_condition = match site_id {
+            Some(site_id) => filter_violation::Column::SiteId.eq(site_id),
+            None => filter_violation::Column::SiteId.is_null(),
+        };
+
+        let violations = FilterViolation::find()
+            .filter(
+                Condition::all()
+                    .add(site_condition)
+                    .add_option(
+                        filter_id.map(|id| filter_violation::Column::FilterId.eq(id)),
+                    )
+                    .add_option(
+                        violator_id.map(|id| filter_violation::Column::UserId.eq(id)),
+                    )
+                    .add_option(
+                        content_type
+                            .map(|value| filter_violation::Column::ContentType.eq(value)),
+                    )
+                    .add_option(
+                        before_id
+                            .map(|id| filter_violation::Column::FilterViolationId.lt(id)),
+                    ),
+            )
+            .order_by_desc(filter_violation::Column::FilterViolationId)
+            .limit(limit.min(MAXIMUM_VIOLATIONS_LIMIT))
+            .all(txn)
+            .await?;
+
+        Ok(violations)
+    }
+
+    /// Checks which existing content would match the given filters.
+    ///
+    /// This is a dry run, nothing is enforced or recorded in the audit log.
+    pub async fn test(
+        ctx: &ServiceContext<'_>,
+        TestFilter {
+            site_id,
+            user_id: _,
+            regex,
+            case_sensitive,
+            filter_ids,
+            filter_types,
+            limit,
+        }: TestFilter,
+    ) -> Result<Vec<FilterTestMatch>> {
+        info!("Testing filters against existing content for site ID {site_id:?}");
+
+        let compile = |regex: &str| {
+            Regex::new(regex).map_err(|error| {
+                error!("Passed regular expression '{regex}' pattern is invalid: {error}",);
+                Error::FilterRegexInvalid(error)
+            })
+        };
+
+        let mut regexes = Vec::new();
+
+        // Add the candidate regular expression, if any
+        if let Some(mut regex) = regex {
+            if !case_sensitive {
+                trim_start_matches_in_place(&mut regex, "(?i)");
+                regex.insert_str(0, "(?i)");
+            }
+
+            regexes.push((None, compile(&regex)?));
+        }
+
+        // Add existing filters, if any
+        for filter_id in filter_ids {
+            let filter = Self::get(ctx, filter_id).await?;
+            if filter.site_id != site_id {
+                error!("Filter ID {filter_id} is not in site ID {site_id:?}");
+                return Err(Error::FilterNotFound);
+            }
+
+            regexes.push((Some(filter_id), compile(&filter.regex)?));
+        }
+
+        if regexes.is_empty() {
+            error!("No regular expression or filter IDs passed to test");
+            return Err(Error::FilterTestEmpty);
+        }
+
+        let limit = limit.min(MAXIMUM_TEST_MATCHES);
+        let mut tester = FilterTester::new(regexes, limit, MAXIMUM_TEST_OBJECTS);
+        for filter_type in filter_types {
+            match filter_type {
+                FilterType::Page => Self::test_pages(ctx, site_id, &mut tester).await?,
+                FilterType::File => Self::test_files(ctx, site_id, &mut tester).await?,
+                FilterType::User => {
+                    Self::test_users(ctx, site_id, false, &mut tester).await?
+                }
+
+                // Don't expose email addresses to site moderators,
+                // and mask them for platform staff.
+                FilterType::Email if site_id.is_none() => {
+                    Self::test_users(ctx, site_id, true, &mut tester).await?
+                }
+                FilterType::Email => (),
+
+                // There is no forum content yet
+                FilterType::Forum => (),
+            }
+        }
+
+        Ok(tester.into_matches())
+    }
+
+    async fn test_pages(
+        ctx: &ServiceContext<'_>,
+        site_id: Option<i64>,
+        tester: &mut FilterTester,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let mut page_chunks = Page::find()
+            .filter(
+                Condition::all()
+                    .add_option(site_id.map(|id| page::Column::SiteId.eq(id)))
+                    .add(page::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(page::Column::PageId)
+            .paginate(txn, 100);
+
+        while let Some(pages) = page_chunks.fetch_and_next().await? {
+            for page in pages {
+                if !tester.next_object() {
+                    return Ok(());
+                }
+
+                let revision_id = match page.latest_revision_id {
+                    Some(revision_id) => revision_id,
+                    None => continue,
+                };
+
+                let revision = PageRevisionService::get_direct(ctx, revision_id).await?;
+                let wikitext = TextService::get(ctx, &revision.wikitext_hash).await?;
+                let site_id = Some(page.site_id);
+
+                tester.check(
+                    FilterContentType::PageTitle,
+                    site_id,
+                    page.page_id,
+                    &revision.title,
+                );
+
+                if let Some(ref alt_title) = revision.alt_title {
+                    tester.check(
+                        FilterContentType::PageTitle,
+                        site_id,
+                        page.page_id,
+                        alt_title,
+                    );
+                }
+
+                tester.check(
+                    FilterContentType::PageWikitext,
+                    site_id,
+                    page.page_id,
+                    &wikitext,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn test_files(
+        ctx: &ServiceContext<'_>,
+        site_id: Option<i64>,
+        tester: &mut FilterTester,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let mut file_chunks = File::find()
+            .filter(
+                Condition::all()
+                    .add_option(site_id.map(|id| file::Column::SiteId.eq(id)))
+                    .add(file::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(file::Column::FileId)
+            .paginate(txn, 100);
+
+        while let Some(files) = file_chunks.fetch_and_next().await? {
+            for file in files {
+                if !tester.next_object() {
+                    return Ok(());
+                }
+
+                tester.check(
+                    FilterContentType::FileName,
+                    Some(file.site_id),
+                    file.file_id,
+                    &file.name,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks user names, or email addresses if `email` is set.
+    ///
+    /// For a site, only the members of that site are checked.
+    async fn test_users(
+        ctx: &ServiceContext<'_>,
+        site_id: Option<i64>,
+        email: bool,
+        tester: &mut FilterTester,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let member_condition = site_id.map(|site_id| {
+            let members = Query::select()
+                .column(relation::Column::FromId)
+                .from(Relation)
+                .cond_where(current_entries_condition(
+                    RelationType::SiteMember,
+                    RelationObject::Site(site_id),
+                    RelationDirection::Dest,
+                ))
+                .to_owned();
+
+            user::Column::UserId.in_subquery(members)
+        });
+
+        let mut user_chunks = User::find()
+            .filter(
+                Condition::all()
+                    .add_option(member_condition)
+                    .add(user::Column::DeletedAt.is_null()),
+            )
+            .order_by_asc(user::Column::UserId)
+            .paginate(txn, 100);
+
+        while let Some(users) = user_chunks.fetch_and_next().await? {
+            for user in users {
+                if !tester.next_object() {
+                    return Ok(());
+                }
+
+                if email {
+                    tester.check_email(user.user_id, &user.email);
+                } else {
+                    tester.check(
+                        FilterContentType::UserName,
+                        site_id,
+                        user.user_id,
+                        &user.name,
+                    );
+                    tester.check(
+                        FilterContentType::UserName,
+                        site_id,
+                        user.user_id,
+                        &user.slug,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Checks if creating / reinstating this filter would cause constraint violations.
     async fn check_conflicts(
         ctx: &ServiceContext<'_>,
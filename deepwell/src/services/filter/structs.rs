@@ -19,6 +19,7 @@
  */
 
 use crate::models::filter;
+use crate::models::sea_orm_active_enums::FilterContentType;
 use crate::web::ProvidedValue;
 use sea_orm::{ColumnTrait, Condition};
 
@@ -148,8 +149,82 @@ impl From<FilterType> for filter::Column {
     }
 }
 
+/// Describes what is being checked against filters.
+///
+/// This is recorded in the audit log if a filter is violated.
+#[derive(Debug, Copy, Clone)]
+pub struct FilterCheck {
+    /// The site this content is in, if any.
+    pub site_id: Option<i64>,
+
+    /// The user responsible for this content, if known.
+    pub user_id: Option<i64>,
+
+    pub content_type: FilterContentType,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetFilterViolations {
+    /// Which site to get violations for.
+    ///
+    /// If `None`, then violations which did not occur in a site are returned.
+    pub site_id: Option<i64>,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub filter_id: Option<i64>,
+
+    #[serde(default)]
+    pub violator_id: Option<i64>,
+
+    #[serde(default)]
+    pub content_type: Option<FilterContentType>,
+
+    /// Only return violations older than this violation ID.
+    ///
+    /// Used to get the next page of results.
+    #[serde(default)]
+    pub before_id: Option<i64>,
+
+    /// The most violations to return, up to a maximum of 100.
+    pub limit: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetFilters {
+    /// Which site to get filters for.
+    ///
+    /// If `None`, then platform filters are returned.
+    pub site_id: Option<i64>,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub filter_type: Option<FilterType>,
+
+    #[serde(default)]
+    pub deleted: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FilterReference {
+    pub filter_id: i64,
+    pub user_id: i64,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct CreateFilter {
+    /// Which site to create this filter in.
+    ///
+    /// If `None`, then this creates a platform filter.
+    pub site_id: Option<i64>,
+    pub user_id: i64,
+
+    #[serde(flatten)]
+    pub body: CreateFilterBody,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateFilterBody {
     pub affects_user: bool,
     pub affects_email: bool,
     pub affects_page: bool,
@@ -163,12 +238,75 @@ pub struct CreateFilter {
 #[derive(Deserialize, Debug, Clone)]
 pub struct UpdateFilter {
     pub filter_id: i64,
+    pub user_id: i64,
+
+    #[serde(default)]
     pub affects_user: ProvidedValue<bool>,
+
+    #[serde(default)]
     pub affects_email: ProvidedValue<bool>,
+
+    #[serde(default)]
     pub affects_page: ProvidedValue<bool>,
+
+    #[serde(default)]
     pub affects_file: ProvidedValue<bool>,
+
+    #[serde(default)]
     pub affects_forum: ProvidedValue<bool>,
+
+    #[serde(default)]
     pub case_sensitive: ProvidedValue<bool>,
+
+    #[serde(default)]
     pub regex: ProvidedValue<String>,
+
+    #[serde(default)]
     pub description: ProvidedValue<String>,
 }
+
+/// Checks which existing content would trip the given filters.
+///
+/// Either a candidate regular expression, existing filters, or both
+/// may be tested. Nothing is recorded in the filter audit log.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TestFilter {
+    /// Which site's content to check.
+    ///
+    /// If `None`, then content across the platform is checked.
+    pub site_id: Option<i64>,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub regex: Option<String>,
+
+    #[serde(default)]
+    pub case_sensitive: bool,
+
+    /// Existing filters to test, which must belong to the same site.
+    #[serde(default)]
+    pub filter_ids: Vec<i64>,
+
+    /// What kinds of content to check.
+    ///
+    /// Forum content is not checked. Email addresses are only
+    /// checked when testing across the platform, and are masked in the results.
+    pub filter_types: Vec<FilterType>,
+
+    /// The most matches to return, up to a maximum of 500.
+    ///
+    /// At most 5000 objects are checked, regardless of how many match.
+    pub limit: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FilterTestMatch {
+    /// Which filter matched, or `None` if it was the candidate regular expression.
+    pub filter_id: Option<i64>,
+    pub content_type: FilterContentType,
+    pub site_id: Option<i64>,
+
+    /// The ID of the page, user, or file which matched.
+    pub object_id: i64,
+    pub excerpt: String,
+}
@@ -24,19 +24,73 @@
 //! uniqueness (this will get blocked by the database probably),
 //! inconsistency, or perform filter validation.
 //!
+//! All of the methods here are idempotent, data which has already been
+//! imported is skipped, and methods return whether anything was added.
+//! This way an import which was interrupted can simply be run again.
+//! Once everything for a page has been imported, it should be marked
+//! completed, so that importers can skip it entirely when resuming.
+//!
 //! It is for limited use during initial setup only.
 
-// TODO implement and use this service
-#![allow(dead_code)]
-
 use super::prelude::*;
+use crate::constants::SYSTEM_USER_ID;
+use crate::hash::sha512_hash;
+use crate::models::file::{self, Entity as File};
+use crate::models::file_revision::{self, Entity as FileRevision};
+use crate::models::import_completed_page::{self, Entity as ImportCompletedPage};
+use crate::models::import_file_revision::{self, Entity as ImportFileRevisionEntity};
+use crate::models::import_page_revision::{self, Entity as ImportPageRevisionEntity};
 use crate::models::page::{self, Entity as Page};
+use crate::models::page_attribution::{self, Entity as PageAttribution};
 use crate::models::page_category::Model as PageCategoryModel;
-use crate::models::sea_orm_active_enums::UserType;
+use crate::models::page_lock::{self, Entity as PageLock};
+use crate::models::page_parent::{self, Entity as PageParent};
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::page_vote::{self, Entity as PageVote};
+use crate::models::sea_orm_active_enums::{
+    FileRevisionType, PageLockType, PageRevisionType, UserType,
+};
 use crate::models::site::{self, Entity as Site};
 use crate::models::user::{self, Entity as User};
-use crate::services::{BlobService, CategoryService};
+use crate::services::{
+    BlobService, CategoryService, FileService, JobService, TextService,
+};
 use crate::utils::get_category_name;
+use once_cell::sync::Lazy;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::NotSet;
+
+/// The value of `compiled_generator` for imported page revisions.
+///
+/// Imported revisions are not rendered, instead the latest
+/// revision is rerendered once the page has been completed.
+const IMPORT_COMPILED_GENERATOR: &str = "Imported from Wikidot";
+
+/// The changes for the first page revision.
+///
+/// See `services/page_revision/service.rs`.
+static ALL_PAGE_CHANGES: Lazy<Vec<String>> = Lazy::new(|| {
+    vec![
+        str!("wikitext"),
+        str!("title"),
+        str!("alt_title"),
+        str!("slug"),
+        str!("tags"),
+    ]
+});
+
+/// The changes for the first file revision.
+///
+/// See `services/file_revision/service.rs`.
+static ALL_FILE_CHANGES: Lazy<Vec<String>> = Lazy::new(|| {
+    vec![
+        str!("page"),
+        str!("name"),
+        str!("blob"),
+        str!("mime"),
+        str!("licensing"),
+    ]
+});
 
 #[derive(Debug)]
 pub struct ImportService;
@@ -59,11 +113,17 @@ impl ImportService {
             biography,
             user_page,
         }: ImportUser,
-    ) -> Result<()> {
-        info!("Importing user (name '{}', slug '{}')", name, slug);
-
+    ) -> Result<bool> {
         let txn = ctx.transaction();
 
+        // Checked first to avoid uploading the avatar again
+        if User::find_by_id(user_id).one(txn).await?.is_some() {
+            debug!("User ID {user_id} already imported, skipping");
+            return Ok(false);
+        }
+
+        info!("Importing user (name '{}', slug '{}')", name, slug);
+
         // Upload avatar to S3
         let avatar_s3_hash = match avatar {
             None => None,
@@ -94,9 +154,12 @@ impl ImportService {
         };
 
         User::insert(user).exec(txn).await?;
-        Ok(())
+        Ok(true)
     }
 
+    /// Imports a site, returning its site ID.
+    ///
+    /// If the site already exists, its ID is returned without changes.
     pub async fn add_site(
         ctx: &ServiceContext<'_>,
         ImportSite {
@@ -106,15 +169,30 @@ impl ImportService {
             slug,
             locale,
         }: ImportSite,
-    ) -> Result<()> {
+    ) -> Result<i64> {
+        let txn = ctx.transaction();
+
+        // Check if already present
+        let condition = match site_id {
+            Some(site_id) => site::Column::SiteId.eq(site_id),
+            None => site::Column::Slug.eq(slug.as_str()),
+        };
+
+        if let Some(site) = Site::find().filter(condition).one(txn).await? {
+            debug!("Site '{}' already imported, skipping", site.slug);
+            return Ok(site.site_id);
+        }
+
         info!(
             "Importing site (name '{}', slug '{}', locale '{}')",
             name, slug, locale,
         );
 
-        let txn = ctx.transaction();
         let site = site::ActiveModel {
-            site_id: Set(site_id),
+            site_id: match site_id {
+                Some(site_id) => Set(site_id),
+                None => NotSet,
+            },
             created_at: Set(created_at),
             from_wikidot: Set(true),
             name: Set(name),
@@ -123,8 +201,8 @@ impl ImportService {
             ..Default::default()
         };
 
-        Site::insert(site).exec(txn).await?;
-        Ok(())
+        let site::Model { site_id, .. } = site.insert(txn).await?;
+        Ok(site_id)
     }
 
     pub async fn add_page(
@@ -137,8 +215,8 @@ impl ImportService {
             locked,
             discussion_thread_id,
         }: ImportPage,
-    ) -> Result<()> {
-        info!("Creating page '{}' in site ID {}", slug, site_id);
+    ) -> Result<bool> {
+        info!("Importing page '{}' in site ID {}", slug, site_id);
 
         let txn = ctx.transaction();
 
@@ -159,18 +237,633 @@ impl ImportService {
             ..Default::default()
         };
 
+        let inserted = Page::insert(page)
+            .on_conflict(
+                OnConflict::column(page::Column::PageId)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+
+        if inserted == 0 {
+            debug!("Page ID {page_id} already imported, skipping");
+            return Ok(false);
+        }
+
         // If locked, add that too
+        //
+        // Wikidot only has one kind of lock, which restricts
+        // editing to moderators and above.
         if locked {
-            // TODO
+            let lock = page_lock::ActiveModel {
+                from_wikidot: Set(true),
+                lock_type: Set(PageLockType::Protect),
+                page_id: Set(page_id),
+                user_id: Set(SYSTEM_USER_ID),
+                reason: Set(str!("Imported from Wikidot")),
+                ..Default::default()
+            };
+
+            PageLock::insert(lock).exec(txn).await?;
+        }
+
+        Ok(true)
+    }
+
+    /// Imports a page revision.
+    ///
+    /// The changes for the revision are determined by comparing it
+    /// to the previous revision, so revisions should be imported in order.
+    /// If nothing visible changed (e.g. a revision which only uploaded a file),
+    /// then the revision is skipped.
+    pub async fn add_page_revision(
+        ctx: &ServiceContext<'_>,
+        ImportPageRevision {
+            page_id,
+            site_id,
+            revision_number,
+            created_at,
+            user_id,
+            wikitext,
+            title,
+            slug,
+            mut tags,
+            comments,
+        }: ImportPageRevision,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+
+        // Check if already present
+        let existing = ImportPageRevisionEntity::find_by_id((page_id, revision_number))
+            .one(txn)
+            .await?;
+
+        if existing.is_some() {
+            debug!(
+                "Revision {revision_number} for page ID {page_id} already imported, skipping",
+            );
+            return Ok(false);
+        }
+
+        info!("Importing revision {revision_number} for page ID {page_id}");
+
+        tags.sort();
+        tags.dedup();
+
+        let wikitext_hash = TextService::create(ctx, wikitext).await?.to_vec();
+        let compiled_hash = TextService::create(ctx, String::new()).await?.to_vec();
+
+        // Determine changes from the previous revision
+        //
+        // Revisions must be imported in order. Since revisions without
+        // changes are skipped, the new revision is numbered after the
+        // last one imported, rather than using Wikidot's numbering.
+        let previous = PageRevision::find()
+            .filter(
+                Condition::all()
+                    .add(page_revision::Column::PageId.eq(page_id))
+                    .add(page_revision::Column::SiteId.eq(site_id)),
+            )
+            .order_by_desc(page_revision::Column::RevisionNumber)
+            .one(txn)
+            .await?;
+
+        let new_revision_number = match previous {
+            Some(ref previous) => previous.revision_number + 1,
+            None => 0,
+        };
+
+        let (revision_type, changes) = match previous {
+            None => (PageRevisionType::Create, ALL_PAGE_CHANGES.clone()),
+            Some(previous) => {
+                let mut changes = Vec::new();
+
+                if previous.wikitext_hash != wikitext_hash {
+                    changes.push(str!("wikitext"));
+                }
+
+                if previous.title != title {
+                    changes.push(str!("title"));
+                }
+
+                if previous.slug != slug {
+                    changes.push(str!("slug"));
+                }
+
+                if previous.tags != tags {
+                    changes.push(str!("tags"));
+                }
+
+                if changes.is_empty() {
+                    info!(
+                        "Revision {revision_number} for page ID {page_id} has no changes, skipping",
+                    );
+                    Self::record_page_revision(ctx, page_id, revision_number, None)
+                        .await?;
+                    return Ok(false);
+                }
+
+                // If the slug changes it's "move", otherwise "regular".
+                let revision_type = if previous.slug != slug {
+                    PageRevisionType::Move
+                } else {
+                    PageRevisionType::Regular
+                };
+
+                (revision_type, changes)
+            }
+        };
+
+        let model = page_revision::ActiveModel {
+            revision_type: Set(revision_type),
+            created_at: Set(created_at),
+            revision_number: Set(new_revision_number),
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            from_wikidot: Set(true),
+            changes: Set(changes),
+            wikitext_hash: Set(wikitext_hash),
+            compiled_hash: Set(compiled_hash),
+            compiled_at: Set(created_at),
+            compiled_generator: Set(str!(IMPORT_COMPILED_GENERATOR)),
+            comments: Set(comments),
+            hidden: Set(vec![]),
+            title: Set(title),
+            alt_title: Set(None),
+            slug: Set(slug),
+            tags: Set(tags),
+            ..Default::default()
+        };
+
+        let revision = model.insert(txn).await?;
+        Self::record_page_revision(
+            ctx,
+            page_id,
+            revision_number,
+            Some(revision.revision_id),
+        )
+        .await?;
+
+        Ok(true)
+    }
+
+    /// Records that a Wikidot revision has been imported, or skipped.
+    async fn record_page_revision(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        wikidot_revision_number: i32,
+        revision_id: Option<i64>,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let model = import_page_revision::ActiveModel {
+            page_id: Set(page_id),
+            wikidot_revision_number: Set(wikidot_revision_number),
+            revision_id: Set(revision_id),
+        };
+
+        model.insert(txn).await?;
+        Ok(())
+    }
+
+    pub async fn add_page_vote(
+        ctx: &ServiceContext<'_>,
+        ImportPageVote {
+            page_id,
+            user_id,
+            value,
+        }: ImportPageVote,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+
+        // Check if already present
+        //
+        // Cannot use ON CONFLICT here, since deleted_at is NULL.
+        let existing = PageVote::find()
+            .filter(
+                Condition::all()
+                    .add(page_vote::Column::PageId.eq(page_id))
+                    .add(page_vote::Column::UserId.eq(user_id))
+                    .add(page_vote::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        if existing.is_some() {
+            debug!("Vote by user ID {user_id} on page ID {page_id} already imported, skipping");
+            return Ok(false);
+        }
+
+        info!("Importing vote by user ID {user_id} on page ID {page_id}");
+
+        let model = page_vote::ActiveModel {
+            from_wikidot: Set(true),
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            value: Set(value),
+            ..Default::default()
+        };
+
+        PageVote::insert(model).exec(txn).await?;
+        Ok(true)
+    }
+
+    /// Imports a parent relationship between two pages.
+    ///
+    /// Both pages must have already been imported.
+    pub async fn add_page_parent(
+        ctx: &ServiceContext<'_>,
+        ImportPageParent {
+            parent_page_id,
+            child_page_id,
+        }: ImportPageParent,
+    ) -> Result<bool> {
+        info!("Importing parent relationship: {parent_page_id} -> {child_page_id}");
+
+        let txn = ctx.transaction();
+        let model = page_parent::ActiveModel {
+            parent_page_id: Set(parent_page_id),
+            child_page_id: Set(child_page_id),
+            ..Default::default()
+        };
+
+        let inserted = PageParent::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    page_parent::Column::ParentPageId,
+                    page_parent::Column::ChildPageId,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+
+        Ok(inserted > 0)
+    }
+
+    /// Imports an attribution of a page to a user.
+    ///
+    /// Wikidot has no concept of attributions, so these
+    /// come from external sources, such as Crom.
+    pub async fn add_page_attribution(
+        ctx: &ServiceContext<'_>,
+        ImportPageAttribution {
+            page_id,
+            user_id,
+            attribution_type,
+            attribution_date,
+        }: ImportPageAttribution,
+    ) -> Result<bool> {
+        info!("Importing {attribution_type:?} attribution for user ID {user_id} on page ID {page_id}");
+
+        let txn = ctx.transaction();
+        let model = page_attribution::ActiveModel {
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            attribution_type: Set(attribution_type),
+            attribution_date: Set(attribution_date),
+            ..Default::default()
+        };
+
+        let inserted = PageAttribution::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    page_attribution::Column::PageId,
+                    page_attribution::Column::UserId,
+                    page_attribution::Column::AttributionType,
+                    page_attribution::Column::AttributionDate,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+
+        Ok(inserted > 0)
+    }
+
+    pub async fn add_file(
+        ctx: &ServiceContext<'_>,
+        ImportFile {
+            file_id,
+            page_id,
+            site_id,
+            created_at,
+            name,
+        }: ImportFile,
+    ) -> Result<bool> {
+        info!("Importing file '{name}' on page ID {page_id}");
+
+        let txn = ctx.transaction();
+        let model = file::ActiveModel {
+            file_id: Set(file_id),
+            created_at: Set(created_at),
+            from_wikidot: Set(true),
+            name: Set(name),
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+            ..Default::default()
+        };
+
+        let inserted = File::insert(model)
+            .on_conflict(
+                OnConflict::column(file::Column::FileId)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+
+        Ok(inserted > 0)
+    }
+
+    /// Imports a file revision, uploading its contents.
+    ///
+    /// Like page revisions, the changes are determined by comparing
+    /// it to the previous revision, so they should be imported in order.
+    /// Revisions without changes are skipped, and their contents are
+    /// not uploaded.
+    pub async fn add_file_revision(
+        ctx: &ServiceContext<'_>,
+        ImportFileRevision {
+            file_id,
+            page_id,
+            site_id,
+            revision_number,
+            created_at,
+            user_id,
+            name,
+            data,
+            comments,
+        }: ImportFileRevision,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+
+        // Check if already present
+        //
+        // Checked first to avoid uploading the blob again
+        let existing = ImportFileRevisionEntity::find_by_id((file_id, revision_number))
+            .one(txn)
+            .await?;
+
+        if existing.is_some() {
+            debug!(
+                "Revision {revision_number} for file ID {file_id} already imported, skipping",
+            );
+            return Ok(false);
+        }
+
+        info!("Importing revision {revision_number} for file ID {file_id}");
+
+        // Determine changes from the previous revision
+        //
+        // As with pages, revisions without changes are skipped, so the new
+        // revision is numbered after the last one imported, rather than using
+        // Wikidot's numbering.
+        let previous = FileRevision::find()
+            .filter(file_revision::Column::FileId.eq(file_id))
+            .order_by_desc(file_revision::Column::RevisionNumber)
+            .one(txn)
+            .await?;
+
+        let new_revision_number = match previous {
+            Some(ref previous) => previous.revision_number + 1,
+            None => 0,
+        };
+
+        // Compare contents by hash, so unchanged contents aren't uploaded
+        let s3_hash = sha512_hash(&data).to_vec();
+        let (revision_type, mut changes) = match previous {
+            None => (FileRevisionType::Create, ALL_FILE_CHANGES.clone()),
+            Some(ref previous) => {
+                let mut changes = Vec::new();
+
+                if previous.name != name {
+                    changes.push(str!("name"));
+                }
+
+                if previous.s3_hash != s3_hash {
+                    changes.push(str!("blob"));
+                }
+
+                if changes.is_empty() {
+                    info!(
+                        "Revision {revision_number} for file ID {file_id} has no changes, skipping",
+                    );
+                    Self::record_file_revision(ctx, file_id, revision_number, None)
+                        .await?;
+                    return Ok(false);
+                }
+
+                (FileRevisionType::Update, changes)
+            }
+        };
+
+        // Upload to S3
+        let blob = BlobService::create(ctx, &data).await?;
+        if let Some(ref previous) = previous {
+            if previous.mime_hint != blob.mime {
+                changes.push(str!("mime"));
+            }
         }
 
-        Page::insert(page).exec(txn).await?;
+        // Keep the file's name up to date
+        if changes.iter().any(|change| change == "name") {
+            let model = file::ActiveModel {
+                file_id: Set(file_id),
+                updated_at: Set(Some(created_at)),
+                name: Set(name.clone()),
+                ..Default::default()
+            };
+
+            model.update(txn).await?;
+        }
+
+        // Wikidot has no per-file licensing, so use the site's default
+        let licensing = FileService::resolve_licensing(ctx, site_id, None).await?;
+
+        let model = file_revision::ActiveModel {
+            revision_type: Set(revision_type),
+            created_at: Set(created_at),
+            revision_number: Set(new_revision_number),
+            file_id: Set(file_id),
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+            user_id: Set(user_id),
+            name: Set(name),
+            s3_hash: Set(s3_hash),
+            mime_hint: Set(blob.mime),
+            size_hint: Set(blob.size),
+            licensing: Set(licensing),
+            changes: Set(changes),
+            comments: Set(comments),
+            hidden: Set(vec![]),
+            ..Default::default()
+        };
+
+        let revision = model.insert(txn).await?;
+        Self::record_file_revision(
+            ctx,
+            file_id,
+            revision_number,
+            Some(revision.revision_id),
+        )
+        .await?;
+
+        Ok(true)
+    }
+
+    /// Records that a Wikidot file revision has been imported, or skipped.
+    async fn record_file_revision(
+        ctx: &ServiceContext<'_>,
+        file_id: i64,
+        wikidot_revision_number: i32,
+        revision_id: Option<i64>,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let model = import_file_revision::ActiveModel {
+            file_id: Set(file_id),
+            wikidot_revision_number: Set(wikidot_revision_number),
+            revision_id: Set(revision_id),
+        };
+
+        model.insert(txn).await?;
+        Ok(())
+    }
+
+    /// Marks a page as completely imported.
+    ///
+    /// This points the page at its latest revision, queues that revision
+    /// to be rendered, and records the page so later runs can skip it.
+    pub async fn complete_page(
+        ctx: &ServiceContext<'_>,
+        ImportCompletePage { site_id, page_id }: ImportCompletePage,
+    ) -> Result<()> {
+        info!("Marking page ID {page_id} in site ID {site_id} as imported");
+
+        let txn = ctx.transaction();
+        let revision = PageRevision::find()
+            .filter(
+                Condition::all()
+                    .add(page_revision::Column::PageId.eq(page_id))
+                    .add(page_revision::Column::SiteId.eq(site_id)),
+            )
+            .order_by_desc(page_revision::Column::RevisionNumber)
+            .one(txn)
+            .await?
+            .ok_or(Error::PageRevisionNotFound)?;
+
+        // Update page to point to the latest revision
+        let updated_at = if revision.revision_number > 0 {
+            Some(revision.created_at)
+        } else {
+            None
+        };
+
+        let model = page::ActiveModel {
+            page_id: Set(page_id),
+            updated_at: Set(updated_at),
+            latest_revision_id: Set(Some(revision.revision_id)),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        // Imported revisions are not rendered, so render the latest one
+        JobService::queue_rerender_page(ctx, site_id, page_id, 0).await?;
+
+        // Record completion
+        let model = import_completed_page::ActiveModel {
+            page_id: Set(page_id),
+            site_id: Set(site_id),
+            ..Default::default()
+        };
+
+        ImportCompletedPage::insert(model)
+            .on_conflict(
+                OnConflict::column(import_completed_page::Column::PageId)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+
         Ok(())
     }
 
-    // TODO page_revision
-    // TODO page_vote
+    /// Reports how much has been imported for the given site.
+    pub async fn get_progress(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<ImportProgress> {
+        info!("Getting import progress for site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let (
+            pages,
+            page_revisions,
+            page_votes,
+            files,
+            file_revisions,
+            completed_page_ids,
+        ) = try_join!(
+            Page::find()
+                .filter(
+                    Condition::all()
+                        .add(page::Column::SiteId.eq(site_id))
+                        .add(page::Column::FromWikidot.eq(true)),
+                )
+                .count(txn),
+            PageRevision::find()
+                .filter(
+                    Condition::all()
+                        .add(page_revision::Column::SiteId.eq(site_id))
+                        .add(page_revision::Column::FromWikidot.eq(true)),
+                )
+                .count(txn),
+            PageVote::find()
+                .join(JoinType::InnerJoin, page_vote::Relation::Page.def())
+                .filter(
+                    Condition::all()
+                        .add(page::Column::SiteId.eq(site_id))
+                        .add(page_vote::Column::FromWikidot.eq(true)),
+                )
+                .count(txn),
+            File::find()
+                .filter(
+                    Condition::all()
+                        .add(file::Column::SiteId.eq(site_id))
+                        .add(file::Column::FromWikidot.eq(true)),
+                )
+                .count(txn),
+            FileRevision::find()
+                .join(JoinType::InnerJoin, file_revision::Relation::File.def())
+                .filter(
+                    Condition::all()
+                        .add(file_revision::Column::SiteId.eq(site_id))
+                        .add(file::Column::FromWikidot.eq(true)),
+                )
+                .count(txn),
+            ImportCompletedPage::find()
+                .select_only()
+                .column(import_completed_page::Column::PageId)
+                .filter(import_completed_page::Column::SiteId.eq(site_id))
+                .order_by_asc(import_completed_page::Column::PageId)
+                .into_tuple()
+                .all(txn),
+        )?;
+
+        Ok(ImportProgress {
+            site_id,
+            pages,
+            page_revisions,
+            page_votes,
+            files,
+            file_revisions,
+            completed_page_ids,
+        })
+    }
 
-    // TODO file
     // TODO forum
 }
@@ -17,6 +17,7 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
+use crate::models::sea_orm_active_enums::PageAttributionType;
 
 use time::{Date, OffsetDateTime};
 
@@ -39,7 +40,11 @@ pub struct ImportUser {
 
 #[derive(Deserialize, Debug)]
 pub struct ImportSite {
-    pub site_id: i64,
+    /// The site ID from Wikidot, if known.
+    ///
+    /// If this is `None`, then the site is looked up by its slug,
+    /// and if not present, is inserted with a new ID.
+    pub site_id: Option<i64>,
     pub created_at: OffsetDateTime,
     pub name: String,
     pub slug: String,
@@ -55,3 +60,87 @@ pub struct ImportPage {
     pub locked: bool,
     pub discussion_thread_id: Option<i64>,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct ImportPageRevision {
+    pub page_id: i64,
+    pub site_id: i64,
+
+    /// The revision number on Wikidot.
+    ///
+    /// Revisions without changes are skipped, so this may differ
+    /// from the number of the revision which is created.
+    pub revision_number: i32,
+    pub created_at: OffsetDateTime,
+    pub user_id: i64,
+    pub wikitext: String,
+    pub title: String,
+    pub slug: String,
+    pub tags: Vec<String>,
+    pub comments: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportPageVote {
+    pub page_id: i64,
+    pub user_id: i64,
+    pub value: i16,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportPageParent {
+    pub parent_page_id: i64,
+    pub child_page_id: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportPageAttribution {
+    pub page_id: i64,
+    pub user_id: i64,
+    pub attribution_type: PageAttributionType,
+    pub attribution_date: Date,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportFile {
+    pub file_id: i64,
+    pub page_id: i64,
+    pub site_id: i64,
+    pub created_at: OffsetDateTime,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportFileRevision {
+    pub file_id: i64,
+    pub page_id: i64,
+    pub site_id: i64,
+
+    /// The revision number on Wikidot.
+    ///
+    /// Revisions without changes are skipped, so this may differ
+    /// from the number of the revision which is created.
+    pub revision_number: i32,
+    pub created_at: OffsetDateTime,
+    pub user_id: i64,
+    pub name: String,
+    pub data: Vec<u8>,
+    pub comments: String,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct ImportCompletePage {
+    pub site_id: i64,
+    pub page_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportProgress {
+    pub site_id: i64,
+    pub pages: u64,
+    pub page_revisions: u64,
+    pub page_votes: u64,
+    pub files: u64,
+    pub file_revisions: u64,
+    pub completed_page_ids: Vec<i64>,
+}
@@ -18,18 +18,20 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-//! This service runs asynchronous jobs in the background using an in-memory queue.
+//! This service runs asynchronous jobs in the background using a queue stored in the database.
 //!
-//! At present we do not use a separate service which stores jobs durably. This
-//! can mean that if this DEEPWELL node fails, the queued jobs will not be run.
+//! Because jobs are stored durably in the `job_queue` table, they are not lost if this
+//! DEEPWELL node fails. Any job which was running on a failed node is picked up by
+//! another worker once its process time has elapsed.
+//!
+//! Jobs which fail are retried with exponential backoff, and once they have run out
+//! of attempts they are moved to the `job_dead_letter` table, where they can be
+//! inspected and retried. Recurring jobs are queued from the `job_schedule` table.
 
 #[allow(unused_imports)]
 mod prelude {
     pub use super::super::prelude::*;
-    pub use super::service::{
-        JobService, JOB_QUEUE_DELAY, JOB_QUEUE_MAXIMUM_SIZE, JOB_QUEUE_NAME,
-        JOB_QUEUE_PROCESS_TIME,
-    };
+    pub use super::service::JobService;
     pub use super::structs::*;
 }
 
@@ -19,35 +19,22 @@
  */
 
 use super::prelude::*;
-use rsmq_async::RsmqConnection;
+use crate::hash::blob_hash_to_hex;
+use crate::models::job_dead_letter::{
+    self, Entity as JobDeadLetter, Model as JobDeadLetterModel,
+};
+use crate::models::job_queue::{self, Entity as JobQueue, Model as JobQueueModel};
+use crate::models::job_schedule::{self, Entity as JobSchedule};
+use crate::services::notification::NotificationEvent;
+use sea_orm::sea_query::{Expr, LockBehavior, LockType, OnConflict};
 use std::time::Duration;
+use time::OffsetDateTime;
 
-pub const JOB_QUEUE_NAME: &str = "job";
-
-/// How long messages, after being delivered, cannot be delivered to another consumer.
-///
-/// This feature is a part of job queues to prevent a job from being run twice by
-/// two different consumers. Because a job which fails won't report back to the queue,
-/// so you cannot tell the difference between a job which is in progress vs a job that failed.
+/// The largest power of two used when calculating retry backoff.
 ///
-/// The way we resolve this is setting a time limit for "process time", which is the designated
-/// period a job is allowed to run. If a job takes longer than that, then we assume it failed or
-/// died. This risks a false positive of still-running jobs, but as long as this time is well
-/// above what a job should take to run this risk is minimal.
-pub const JOB_QUEUE_PROCESS_TIME: Option<Duration> = Some(Duration::from_secs(30));
-
-/// How long to wait before messages are delivered to consumers.
-pub const JOB_QUEUE_DELAY: Option<Duration> = None;
-
-/// The maximum size, in bytes, that a job payload is allowed to be
-///
-/// Presently, our jobs are mostly unit types, and the biggest variant
-/// is composed of three integers, so this is more than large enough.
-/// If larger jobs become a thing in the future, this may need to be updated.
-///
-/// (But as a general code principle there shouldn't be huge jobs, they should
-/// just use IDs and references to items in the database.)
-pub const JOB_QUEUE_MAXIMUM_SIZE: Option<i32> = Some(1024);
+/// This prevents the delay from overflowing when a job has been
+/// configured to be retried a very large number of times.
+const MAXIMUM_BACKOFF_EXPONENT: u32 = 16;
 
 #[derive(Debug)]
 pub struct JobService;
@@ -59,11 +46,18 @@ impl JobService {
         delay: Option<Duration>,
     ) -> Result<()> {
         info!("Queuing job {job:?} (delay {delay:?})");
-        let payload = serde_json::to_vec(job)?;
-        ctx.rsmq()
-            .send_message(JOB_QUEUE_NAME, payload, delay)
-            .await?;
+        let txn = ctx.transaction();
+        let run_at = match delay {
+            Some(delay) => now() + delay,
+            None => now(),
+        };
 
+        let model = job_queue::ActiveModel {
+            job: Set(serde_json::to_value(job)?),
+            run_at: Set(run_at),
+            ..Default::default()
+        };
+        model.insert(txn).await?;
         Ok(())
     }
 
@@ -94,4 +88,381 @@ impl JobService {
         )
         .await
     }
+
+    /// Queues a batch of pages in a site for being rerendered.
+    ///
+    /// See `OutdateService::outdate_site_batch()`.
+    pub async fn queue_outdate_site(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        after_page_id: Option<i64>,
+    ) -> Result<()> {
+        debug!("Queuing outdate of site ID {site_id} after page ID {after_page_id:?}");
+        Self::queue_job(
+            ctx,
+            &Job::OutdateSite {
+                site_id,
+                after_page_id,
+            },
+            None,
+        )
+        .await
+    }
+
+    /// Queues generation of resized copies of an image blob.
+    pub async fn queue_generate_derivatives(
+        ctx: &ServiceContext<'_>,
+        hash: Vec<u8>,
+    ) -> Result<()> {
+        debug!(
+            "Queuing derivative generation for blob {}",
+            blob_hash_to_hex(&hash)
+        );
+        Self::queue_job(ctx, &Job::GenerateBlobDerivatives { hash }, None).await
+    }
+
+    /// Queues removal of a blob from S3, if it is no longer used.
+    ///
+    /// Since this job is only visible once the current transaction commits,
+    /// the blob is never removed while rows which use it could be rolled back.
+    pub async fn queue_delete_unused_blob(
+        ctx: &ServiceContext<'_>,
+        hash: Vec<u8>,
+    ) -> Result<()> {
+        debug!("Queuing deletion of blob {}", blob_hash_to_hex(&hash));
+        Self::queue_job(ctx, &Job::DeleteUnusedBlob { hash }, None).await
+    }
+
+    /// Queues notifications to be sent for an event.
+    ///
+    /// Fanning out to watchers and followers can touch many users,
+    /// so it is done in the background rather than during the request.
+    pub async fn queue_send_notifications(
+        ctx: &ServiceContext<'_>,
+        event: NotificationEvent,
+    ) -> Result<()> {
+        debug!("Queuing notifications for event {event:?}");
+        Self::queue_job(ctx, &Job::SendNotifications { event }, None).await
+    }
+
+    /// Claims the next available job, if any.
+    ///
+    /// A job is available if its run time has arrived, and it is either not
+    /// being run or the worker running it has taken longer than the configured
+    /// process time (in which case it is assumed to have died).
+    ///
+    /// Rows are selected using `FOR UPDATE SKIP LOCKED`, so concurrent workers
+    /// will never claim the same job. The transaction should be committed
+    /// immediately after this call so the claim is visible to other workers.
+    pub async fn claim_job(ctx: &ServiceContext<'_>) -> Result<Option<JobQueueModel>> {
+        let txn = ctx.transaction();
+        let now = now();
+        let lock_expiry = now - ctx.config().job_process_time;
+
+        let entry = JobQueue::find()
+            .filter(
+                Condition::all().add(job_queue::Column::RunAt.lte(now)).add(
+                    Condition::any()
+                        .add(job_queue::Column::LockedAt.is_null())
+                        .add(job_queue::Column::LockedAt.lt(lock_expiry)),
+                ),
+            )
+            .order_by_asc(job_queue::Column::RunAt)
+            .order_by_asc(job_queue::Column::JobId)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .one(txn)
+            .await?;
+
+        let entry = match entry {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        debug!(
+            "Claiming job ID {} (previous attempts {})",
+            entry.job_id, entry.attempts,
+        );
+
+        let attempts = entry.attempts + 1;
+        let mut model = entry.into_active_model();
+        model.locked_at = Set(Some(now));
+        model.attempts = Set(attempts);
+        let entry = model.update(txn).await?;
+        Ok(Some(entry))
+    }
+
+    /// Removes a job from the queue after it has been run successfully.
+    pub async fn complete_job(ctx: &ServiceContext<'_>, job_id: i64) -> Result<()> {
+        debug!("Marking job ID {job_id} as completed");
+        let txn = ctx.transaction();
+
+        // If the job was cancelled while running, then there will be nothing to delete.
+        JobQueue::delete_by_id(job_id).exec(txn).await?;
+        Ok(())
+    }
+
+    /// Records the failure of a job.
+    ///
+    /// If the job has attempts remaining, it is scheduled to be retried, with
+    /// the delay doubling after each failed attempt. Otherwise it is moved
+    /// to the dead-letter table.
+    pub async fn fail_job(
+        ctx: &ServiceContext<'_>,
+        JobQueueModel {
+            job_id,
+            created_at,
+            job,
+            attempts,
+            ..
+        }: JobQueueModel,
+        error: String,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let config = ctx.config();
+
+        if attempts > i16::try_from(config.job_max_attempts).unwrap_or(i16::MAX) {
+            warn!("Job ID {job_id} failed on its final attempt, moving to dead letters");
+
+            let DeleteResult { rows_affected } =
+                JobQueue::delete_by_id(job_id).exec(txn).await?;
+
+            // The job was cancelled while running, nothing to record.
+            if rows_affected == 0 {
+                return Ok(());
+            }
+
+            let model = job_dead_letter::ActiveModel {
+                job_id: Set(job_id),
+                created_at: Set(created_at),
+                job: Set(job),
+                attempts: Set(attempts),
+                last_error: Set(error),
+                ..Default::default()
+            };
+            model.insert(txn).await?;
+        } else {
+            let delay = Self::retry_delay(config.job_retry_delay, attempts);
+            warn!("Job ID {job_id} failed, retrying in {delay:?}");
+
+            JobQueue::update_many()
+                .col_expr(
+                    job_queue::Column::LockedAt,
+                    Expr::value(None::<OffsetDateTime>),
+                )
+                .col_expr(job_queue::Column::RunAt, Expr::value(now() + delay))
+                .col_expr(job_queue::Column::LastError, Expr::value(error))
+                .filter(job_queue::Column::JobId.eq(job_id))
+                .exec(txn)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Determines how long to wait before retrying a job, using exponential backoff.
+    fn retry_delay(base: Duration, attempts: i16) -> Duration {
+        let exponent = u32::try_from(attempts - 1)
+            .unwrap_or(0)
+            .min(MAXIMUM_BACKOFF_EXPONENT);
+
+        base * 2_u32.pow(exponent)
+    }
+
+    /// Lists all jobs which are queued or currently running.
+    pub async fn get_queued(ctx: &ServiceContext<'_>) -> Result<Vec<JobQueueModel>> {
+        let txn = ctx.transaction();
+        let jobs = JobQueue::find()
+            .order_by_asc(job_queue::Column::RunAt)
+            .order_by_asc(job_queue::Column::JobId)
+            .all(txn)
+            .await?;
+
+        Ok(jobs)
+    }
+
+    /// Lists all jobs which failed on every attempt.
+    pub async fn get_failed(ctx: &ServiceContext<'_>) -> Result<Vec<JobDeadLetterModel>> {
+        let txn = ctx.transaction();
+        let jobs = JobDeadLetter::find()
+            .order_by_desc(job_dead_letter::Column::FailedAt)
+            .order_by_desc(job_dead_letter::Column::JobId)
+            .all(txn)
+            .await?;
+
+        Ok(jobs)
+    }
+
+    /// Moves a failed job from the dead-letter table back into the queue.
+    ///
+    /// Its attempt count is reset, and it will be run as soon as a worker is available.
+    pub async fn retry(ctx: &ServiceContext<'_>, job_id: i64) -> Result<JobQueueModel> {
+        info!("Retrying failed job ID {job_id}");
+        let txn = ctx.transaction();
+
+        let JobDeadLetterModel {
+            job_id,
+            created_at,
+            job,
+            last_error,
+            ..
+        } = find_or_error!(JobDeadLetter::find_by_id(job_id).one(txn), Job)?;
+
+        JobDeadLetter::delete_by_id(job_id).exec(txn).await?;
+
+        let model = job_queue::ActiveModel {
+            job_id: Set(job_id),
+            created_at: Set(created_at),
+            job: Set(job),
+            attempts: Set(0),
+            run_at: Set(now()),
+            locked_at: Set(None),
+            last_error: Set(Some(last_error)),
+        };
+        let entry = model.insert(txn).await?;
+        Ok(entry)
+    }
+
+    /// Removes a job from the queue so that it will not be run.
+    ///
+    /// If the job is currently being run, this does not interrupt it,
+    /// but it will not be retried if it fails.
+    pub async fn cancel(ctx: &ServiceContext<'_>, job_id: i64) -> Result<()> {
+        info!("Cancelling job ID {job_id}");
+        let txn = ctx.transaction();
+        let DeleteResult { rows_affected } =
+            JobQueue::delete_by_id(job_id).exec(txn).await?;
+
+        if rows_affected == 0 {
+            return Err(Error::JobNotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Updates the schedule of recurring jobs from the configuration.
+    ///
+    /// Schedules which do not exist yet are created to run immediately.
+    /// Existing schedules keep their next run time, but have their
+    /// interval updated to match the configuration.
+    pub async fn setup_schedules(ctx: &ServiceContext<'_>) -> Result<()> {
+        info!("Setting up recurring job schedules");
+        let txn = ctx.transaction();
+        let config = ctx.config();
+
+        let schedules = [
+            (
+                "prune_sessions",
+                Job::PruneSessions,
+                config.job_prune_session,
+            ),
+            ("prune_text", Job::PruneText, config.job_prune_text),
+            (
+                "name_change_refill",
+                Job::NameChangeRefill,
+                config.job_name_change_refill,
+            ),
+            (
+                "lift_expired_punishments",
+                Job::LiftExpiredPunishments,
+                config.job_lift_expired_punishments,
+            ),
+            (
+                "prune_page_locks",
+                Job::PrunePageLocks,
+                config.job_prune_page_locks,
+            ),
+            (
+                "prune_file_uploads",
+                Job::PruneFileUploads,
+                config.job_prune_file_uploads,
+            ),
+        ];
+
+        for (name, job, interval) in schedules {
+            debug!("Scheduling recurring job '{name}' every {interval:?}");
+
+            let interval_secs = i64::try_from(interval.as_secs())
+                .expect("Recurring job interval too large");
+
+            let model = job_schedule::ActiveModel {
+                name: Set(str!(name)),
+                job: Set(serde_json::to_value(&job)?),
+                interval_secs: Set(interval_secs),
+                ..Default::default()
+            };
+
+            JobSchedule::insert(model)
+                .on_conflict(
+                    OnConflict::column(job_schedule::Column::Name)
+                        .update_columns([
+                            job_schedule::Column::Job,
+                            job_schedule::Column::IntervalSecs,
+                        ])
+                        .to_owned(),
+                )
+                .exec(txn)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Queues any recurring jobs which are due to be run.
+    ///
+    /// As with claiming jobs, rows are locked with `SKIP LOCKED`,
+    /// so each scheduled run is only queued once across all workers.
+    ///
+    /// # Returns
+    /// The number of jobs which were queued.
+    pub async fn queue_scheduled(ctx: &ServiceContext<'_>) -> Result<u64> {
+        let txn = ctx.transaction();
+        let now = now();
+
+        let schedules = JobSchedule::find()
+            .filter(job_schedule::Column::NextRunAt.lte(now))
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .all(txn)
+            .await?;
+
+        let mut queued = 0;
+        for schedule in schedules {
+            debug!("Queuing scheduled job '{}'", schedule.name);
+
+            let job = job_queue::ActiveModel {
+                job: Set(schedule.job.clone()),
+                run_at: Set(now),
+                ..Default::default()
+            };
+            job.insert(txn).await?;
+
+            let next_run_at = now + Duration::from_secs(schedule.interval_secs as u64);
+            let mut model = schedule.into_active_model();
+            model.next_run_at = Set(next_run_at);
+            model.last_run_at = Set(Some(now));
+            model.update(txn).await?;
+
+            queued += 1;
+        }
+
+        Ok(queued)
+    }
+}
+
+#[test]
+fn retry_delays() {
+    macro_rules! check {
+        ($attempts:expr, $expected_secs:expr $(,)?) => {
+            assert_eq!(
+                JobService::retry_delay(Duration::from_secs(30), $attempts),
+                Duration::from_secs($expected_secs),
+                "Actual retry delay doesn't match expected",
+            );
+        };
+    }
+
+    check!(1, 30);
+    check!(2, 60);
+    check!(3, 120);
+    check!(5, 480);
+    check!(100, 30 * 65536);
 }
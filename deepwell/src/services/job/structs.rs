@@ -18,6 +18,8 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::services::notification::NotificationEvent;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case", tag = "job", content = "data")]
 pub enum Job {
@@ -26,8 +28,25 @@ pub enum Job {
         page_id: i64,
         depth: u32,
     },
+    OutdateSite {
+        site_id: i64,
+        after_page_id: Option<i64>,
+    },
     PruneSessions,
     PruneText,
     NameChangeRefill,
     LiftExpiredPunishments,
+    PrunePageLocks,
+    PruneFileUploads,
+    GenerateBlobDerivatives {
+        #[serde(with = "hex")]
+        hash: Vec<u8>,
+    },
+    DeleteUnusedBlob {
+        #[serde(with = "hex")]
+        hash: Vec<u8>,
+    },
+    SendNotifications {
+        event: NotificationEvent,
+    },
 }
@@ -22,14 +22,15 @@
 
 use super::prelude::*;
 use crate::api::ServerState;
-use crate::services::{PageRevisionService, SessionService, TextService, UserService};
-use crate::utils::debug_pointer;
-use rsmq_async::{PooledRsmq, RsmqConnection, RsmqMessage};
+use crate::models::job_queue::Model as JobQueueModel;
+use crate::services::{
+    BlobService, FileService, FileUploadService, NotificationService, OutdateService,
+    PageLockService, PageRevisionService, RelationService, SessionService, TextService,
+    UserService,
+};
 use sea_orm::TransactionTrait;
 use std::convert::Infallible;
-use std::fmt::{self, Debug};
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::time;
 
 /// Tells the main loop of the worker whether the queue had an item or not.
@@ -39,17 +40,9 @@ enum JobProcessStatus {
     NoJob,
 }
 
-/// Used to queue a follow-up job, if needed.
-#[derive(Debug)]
-enum NextJob {
-    Next { job: Job, delay: Option<Duration> },
-    Done,
-}
-
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct JobWorker {
     state: ServerState,
-    rsmq: PooledRsmq,
     id: u16,
 }
 
@@ -72,44 +65,30 @@ impl JobWorker {
     fn spawn_one(state: &ServerState, id: u16) {
         info!("Spawning job worker ID {id}");
         let state = Arc::clone(state);
-        let rsmq = PooledRsmq::clone(&state.rsmq);
-        let worker = JobWorker { state, rsmq, id };
+        let worker = JobWorker { state, id };
         tokio::spawn(worker.main_loop());
     }
 
     /// The main execution loop for a job worker.
     ///
-    /// This will listen to the queue, picking up new items as they arrive,
-    /// and sleeping for a random duration when there are no jobs available.
-    /// (This way we do not hammer the queue when all the workers wake up
-    /// at once.)
-    ///
-    /// # Clearing job items
-    /// RSMQ (and other queue systems) attempt to maintain job durability,
-    /// such that if a worker takes a job and then crashes, the job is later
-    /// retried. This is behavior we wish to have as well, but it means that
-    /// our job queue flow is a bit different than what may seem obvious.
-    ///
-    /// The `rsmq_async` crate provides two sets of methods for retrieving jobs:
-    /// 1. `pop_message()`
-    ///
-    /// This removes a job from the queue and yields it to a worker. If the worker
-    /// then dies, the job is no longer on the queue and thus is effectively lost.
-    ///
-    /// 2. `receive_message()` followed by `delete_message()`
+    /// This will poll the queue, picking up new items as they become available,
+    /// and sleeping for an increasing duration when there are no jobs available.
+    /// (This way we do not hammer the database when the queue is empty.)
     ///
-    /// This receives a job from the queue, during which time the job can no longer
-    /// be picked up by any other consumers. This prevents work being done twice.
+    /// # Job durability
+    /// Each step of processing a job is performed in its own transaction:
     ///
-    /// After the job execution period, if no further updates are received, then the
-    /// queue assumes the worker died or failed, and then the job is later available
-    /// on the queue for workers to retry.
+    /// 1. Any recurring jobs which are due are added to the queue.
+    /// 2. A job is claimed, marking it as locked and counting the attempt.
+    /// 3. The job is run, and if it succeeds it is deleted from the queue
+    ///    as part of the same transaction.
+    /// 4. If it fails, the failure is recorded, either scheduling a retry
+    ///    or moving the job to the dead-letter table.
     ///
-    /// This means that, after a `receive_message()`, if and only if the job was
-    /// successfully run (aside from any cases where we specifically decide we do
-    /// not want this job to re-run), we will then run `delete_message()` so that
-    /// it is no longer enqueued.
-    async fn main_loop(mut self) -> Infallible {
+    /// If this worker dies at any point after claiming a job, then the job
+    /// remains in the queue, and is claimed by another worker once the
+    /// configured process time has elapsed.
+    async fn main_loop(self) -> Infallible {
         trace!("Beginning main execution of worker ID {}", self.id);
 
         macro_rules! config {
@@ -148,33 +127,85 @@ impl JobWorker {
         }
     }
 
-    async fn process_job(&mut self) -> Result<JobProcessStatus> {
-        let data: RsmqMessage<Vec<u8>> =
-            match self.rsmq.receive_message(JOB_QUEUE_NAME, None).await? {
+    async fn process_job(&self) -> Result<JobProcessStatus> {
+        // Add any recurring jobs which are due
+        {
+            let txn = self.state.database.begin().await?;
+            let ctx = &ServiceContext::new(&self.state, &txn);
+            let queued = JobService::queue_scheduled(ctx).await?;
+            txn.commit().await?;
+
+            if queued > 0 {
+                debug!("Queued {queued} scheduled jobs (worker {})", self.id);
+            }
+        }
+
+        // Claim a job from the queue
+        let entry = {
+            let txn = self.state.database.begin().await?;
+            let ctx = &ServiceContext::new(&self.state, &txn);
+            let entry = JobService::claim_job(ctx).await?;
+            txn.commit().await?;
+
+            match entry {
+                Some(entry) => entry,
                 None => return Ok(JobProcessStatus::NoJob),
-                Some(data) => data,
-            };
+            }
+        };
 
-        debug!("Received raw data from queue (worker {})", self.id);
-        debug!("* Message ID:          {}", data.id);
-        debug!("* Previously received: {}", data.rc);
-        debug!("* Created:             {}", data.sent);
-        debug!("* Received:            {}", data.fr);
-        let job = serde_json::from_slice(&data.message)?;
+        debug!("Claimed job from queue (worker {})", self.id);
+        debug!("* Job ID:   {}", entry.job_id);
+        debug!("* Attempt:  {}", entry.attempts);
+        debug!("* Created:  {}", entry.created_at);
+        debug!("* Run at:   {}", entry.run_at);
+
+        // Run the job
+        //
+        // If it fails, then all of its changes are rolled back,
+        // and the failure is recorded in a separate transaction.
+        let result = {
+            let txn = self.state.database.begin().await?;
+            let ctx = &ServiceContext::new(&self.state, &txn);
 
-        let no_more_retries = data.rc >= u64::from(self.state.config.job_max_attempts);
-        if no_more_retries {
-            debug!("Last attempt for this message, it will not be retried if it fails");
-            self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
+            match self.run_job(ctx, &entry).await {
+                Ok(()) => {
+                    trace!("Job execution finished, removing from queue");
+                    JobService::complete_job(ctx, entry.job_id).await?;
+
+                    trace!("Committing transaction, returning success");
+                    txn.commit().await?;
+                    Ok(())
+                }
+                Err(error) => {
+                    trace!("Job execution failed, rolling back transaction");
+                    txn.rollback().await?;
+                    Err(error)
+                }
+            }
+        };
+
+        if let Err(error) = result {
+            error!("Job ID {} failed: {error}", entry.job_id);
+
+            let txn = self.state.database.begin().await?;
+            let ctx = &ServiceContext::new(&self.state, &txn);
+            JobService::fail_job(ctx, entry, error.to_string()).await?;
+            txn.commit().await?;
         }
 
+        Ok(JobProcessStatus::ReceivedJob)
+    }
+
+    async fn run_job(
+        &self,
+        ctx: &ServiceContext<'_>,
+        entry: &JobQueueModel,
+    ) -> Result<()> {
+        let job = serde_json::from_value(entry.job.clone())?;
         debug!("Received job from queue: {job:?}");
-        trace!("Setting up ServiceContext for job processing");
-        let txn = self.state.database.begin().await?;
-        let ctx = &ServiceContext::new(&self.state, &txn);
 
         trace!("Beginning job processing");
-        let next = match job {
+        match job {
             Job::RerenderPage {
                 site_id,
                 page_id,
@@ -184,80 +215,59 @@ impl JobWorker {
                     "Rerendering page ID {page_id} in site ID {site_id} (depth {depth})",
                 );
                 PageRevisionService::rerender(ctx, site_id, page_id, depth).await?;
-                NextJob::Done
+            }
+            Job::OutdateSite {
+                site_id,
+                after_page_id,
+            } => {
+                debug!("Outdating pages in site ID {site_id} after page ID {after_page_id:?}");
+                OutdateService::outdate_site_batch(ctx, site_id, after_page_id).await?;
             }
             Job::PruneSessions => {
                 debug!("Pruning all expired sesions from database");
                 SessionService::prune(ctx).await?;
-                NextJob::Next {
-                    job: Job::PruneSessions,
-                    delay: Some(self.state.config.job_prune_session),
-                }
             }
             Job::PruneText => {
                 debug!("Pruning all unused text items from database");
                 TextService::prune(ctx).await?;
-                NextJob::Next {
-                    job: Job::PruneText,
-                    delay: Some(self.state.config.job_prune_text),
-                }
             }
             Job::NameChangeRefill => {
                 debug!("Checking users for those who can get a name change token refill");
                 UserService::refresh_name_change_tokens(ctx).await?;
-                NextJob::Next {
-                    job: Job::NameChangeRefill,
-                    delay: Some(self.state.config.job_name_change_refill),
-                }
             }
             Job::LiftExpiredPunishments => {
                 debug!("Checking if any outstanding punishments have expired");
-                // TODO implement tempban removal
-                //
-                //      We aren't going to be able to create jobs that have a wait time of say,
-                //      2 years, so instead we will just have this job run daily and check
-                //      to see if any bans have expired
+
+                // We aren't going to be able to create jobs that have a wait time of say,
+                // 2 years, so instead we just have this job run daily and check
+                // to see if any bans have expired.
                 //
-                //      currently only bans are the temporary, but others can be added here
-                NextJob::Next {
-                    job: Job::LiftExpiredPunishments,
-                    delay: Some(self.state.config.job_lift_expired_punishments),
-                }
+                // Currently only bans are temporary, but others can be added here.
+                let lifted = RelationService::lift_expired_site_bans(ctx).await?;
+                debug!("Lifted {lifted} expired site bans");
             }
-        };
-
-        // Don't delete more than once
-        //
-        // NOTE: We're only at this point if the job succeeded.
-        if !no_more_retries {
-            trace!("Job execution finished, deleting message");
-            self.rsmq.delete_message(JOB_QUEUE_NAME, &data.id).await?;
-        }
-
-        // Add follow-up job to queue, if required.
-        match next {
-            NextJob::Done => debug!("Job execution finished, no follow-up job to add"),
-            NextJob::Next { job, delay } => {
-                debug!("Job execution finished, follow-up job has been produced");
-                trace!("* Job:   {job:?}");
-                trace!("* Delay: {delay:?}");
-
-                JobService::queue_job(ctx, &job, delay).await?;
+            Job::PrunePageLocks => {
+                debug!("Pruning all expired page locks from database");
+                PageLockService::prune(ctx).await?;
+            }
+            Job::GenerateBlobDerivatives { hash } => {
+                debug!("Generating derivatives for blob");
+                BlobService::generate_derivatives(ctx, &hash).await?;
+            }
+            Job::DeleteUnusedBlob { hash } => {
+                debug!("Deleting blob if unused");
+                FileService::delete_blob_if_unused(ctx, &hash).await?;
+            }
+            Job::PruneFileUploads => {
+                debug!("Aborting all abandoned file uploads");
+                FileUploadService::prune(ctx).await?;
+            }
+            Job::SendNotifications { event } => {
+                let notified = NotificationService::send(ctx, event).await?;
+                debug!("Notified {notified} users");
             }
         }
 
-        trace!("Committing transaction, returning success");
-        txn.commit().await?;
-        Ok(JobProcessStatus::ReceivedJob)
-    }
-}
-
-impl Debug for JobWorker {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("JobWorker")
-            .field("state", &self.state)
-            .field("rsmq", &debug_pointer(&self.rsmq))
-            .field("id", &self.id)
-            .finish()
+        Ok(())
     }
 }
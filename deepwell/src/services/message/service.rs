@@ -27,15 +27,22 @@ use crate::models::message_recipient::{self, Entity as MessageRecipient};
 use crate::models::message_record::{
     self, Entity as MessageRecord, Model as MessageRecordModel,
 };
-use crate::models::sea_orm_active_enums::{MessageRecipientType, UserType};
+use crate::models::sea_orm_active_enums::{
+    MessagePrivacy, MessageRecipientType, UserType,
+};
+use crate::services::notification::NotificationEvent;
+use crate::services::relation::GetUserContact;
 use crate::services::render::{RenderOutput, RenderService};
-use crate::services::{RelationService, TextService, UserService};
+use crate::services::{JobService, RelationService, TextService, UserService};
 use crate::utils::validate_locale;
 use cuid2::cuid;
 use ftml::data::{PageInfo, ScoreValue};
 use ftml::settings::{WikitextMode, WikitextSettings};
 use sea_orm::DatabaseTransaction;
 
+/// The largest number of messages returned in one folder page.
+const MAXIMUM_MESSAGE_PAGE_SIZE: u64 = 100;
+
 #[derive(Debug)]
 pub struct MessageService;
 
@@ -275,8 +282,25 @@ impl MessageService {
             )
             .await?;
 
-            // If recipient is a site user, then forward to corresponding site staff.
+            // Ensure the recipient accepts messages from the sender
             let user = UserService::get(ctx, Reference::Id(recipient_user_id)).await?;
+            if user.message_privacy == MessagePrivacy::Contacts
+                && recipient_user_id != draft.user_id
+            {
+                let contact = GetUserContact {
+                    contact_user: draft.user_id,
+                    owner_user: recipient_user_id,
+                };
+
+                if !RelationService::user_contact_exists(ctx, contact).await? {
+                    error!(
+                        "User ID {recipient_user_id} only accepts direct messages from contacts",
+                    );
+                    return Err(Error::UserContactsOnly);
+                }
+            }
+
+            // If recipient is a site user, then forward to corresponding site staff.
             if user.user_type == UserType::Site {
                 // TODO what to do if user is banned from site? needs to be possible to block
                 //      permabanned bad actors, but also allow normal banned users to message
@@ -372,6 +396,19 @@ impl MessageService {
             added_user_ids.push(user_id);
         }
 
+        // Notify recipients
+        if !added_user_ids.is_empty() {
+            JobService::queue_send_notifications(
+                ctx,
+                NotificationEvent::Message {
+                    record_id: record_id.clone(),
+                    sender_id,
+                    recipients: added_user_ids,
+                },
+            )
+            .await?;
+        }
+
         // Add outbox message.
         let (flag_outbox, flag_self) = if has_self {
             // For self-messages, we have two kinds of behavior.
@@ -399,27 +436,195 @@ impl MessageService {
         Ok(record_model)
     }
 
-    #[allow(dead_code)] // TEMP
-    pub async fn mark_read(
+    /// Updates the flags on a user's copy of a message.
+    ///
+    /// This is used to mark messages as read, or to star, archive, or trash them.
+    pub async fn update_flags(
         ctx: &ServiceContext<'_>,
-        record_id: &str,
-        user_id: i64,
-        value: bool,
+        UpdateMessageFlags {
+            record_id,
+            user_id,
+            read,
+            star,
+            archive,
+            trash,
+        }: UpdateMessageFlags,
+    ) -> Result<MessageModel> {
+        info!("Updating message flags for {record_id} / {user_id}");
+
+        let txn = ctx.transaction();
+        let message = Self::get_message(ctx, &record_id, user_id).await?;
+        let model = message::ActiveModel {
+            internal_id: Set(message.internal_id),
+            flag_read: read.into_active_value(),
+            flag_star: star.into_active_value(),
+            flag_archive: archive.into_active_value(),
+            flag_trash: trash.into_active_value(),
+            ..Default::default()
+        };
+        let message = model.update(txn).await?;
+        Ok(message)
+    }
+
+    /// Deletes a user's copy of a message.
+    ///
+    /// The message record, and other users' copies of it, are unaffected.
+    pub async fn delete(
+        ctx: &ServiceContext<'_>,
+        DeleteMessage { record_id, user_id }: DeleteMessage,
     ) -> Result<()> {
-        info!("Setting message read status for {record_id} / {user_id}: {value}",);
+        info!("Deleting message {record_id} / {user_id}");
 
         let txn = ctx.transaction();
-        let message = Self::get_message(ctx, record_id, user_id).await?;
+        let message = Self::get_message(ctx, &record_id, user_id).await?;
         let model = message::ActiveModel {
             internal_id: Set(message.internal_id),
-            flag_read: Set(value),
+            deleted_at: Set(Some(now())),
             ..Default::default()
         };
         model.update(txn).await?;
-
         Ok(())
     }
 
+    // Listings
+
+    /// Gets a page of messages in one of the user's folders, newest first.
+    pub async fn get_folder(
+        ctx: &ServiceContext<'_>,
+        GetMessages {
+            user_id,
+            folder,
+            before_id,
+            limit,
+        }: GetMessages,
+    ) -> Result<Vec<MessageListItem>> {
+        info!("Getting messages in {folder:?} for user ID {user_id}");
+        let limit = limit.clamp(1, MAXIMUM_MESSAGE_PAGE_SIZE);
+
+        let txn = ctx.transaction();
+        let folder_condition = match folder {
+            MessageFolder::Inbox => Condition::all()
+                .add(message::Column::FlagInbox.eq(true))
+                .add(message::Column::FlagArchive.eq(false))
+                .add(message::Column::FlagTrash.eq(false)),
+            MessageFolder::Outbox => Condition::all()
+                .add(message::Column::FlagOutbox.eq(true))
+                .add(message::Column::FlagTrash.eq(false)),
+            MessageFolder::ToSelf => Condition::all()
+                .add(message::Column::FlagSelf.eq(true))
+                .add(message::Column::FlagTrash.eq(false)),
+            MessageFolder::Starred => Condition::all()
+                .add(message::Column::FlagStar.eq(true))
+                .add(message::Column::FlagTrash.eq(false)),
+            MessageFolder::Archived => Condition::all()
+                .add(message::Column::FlagArchive.eq(true))
+                .add(message::Column::FlagTrash.eq(false)),
+            MessageFolder::Trash => {
+                Condition::all().add(message::Column::FlagTrash.eq(true))
+            }
+        };
+
+        let messages = Message::find()
+            .find_also_related(MessageRecord)
+            .filter(
+                Condition::all()
+                    .add(message::Column::UserId.eq(user_id))
+                    .add(message::Column::DeletedAt.is_null())
+                    .add(folder_condition)
+                    .add_option(before_id.map(|id| message::Column::InternalId.lt(id))),
+            )
+            .order_by_desc(message::Column::InternalId)
+            .limit(limit)
+            .all(txn)
+            .await?;
+
+        Ok(Self::build_list(messages))
+    }
+
+    /// Counts the number of unread messages in the user's inbox.
+    pub async fn count_unread(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        info!("Counting unread messages for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let count = Message::find()
+            .filter(
+                Condition::all()
+                    .add(message::Column::UserId.eq(user_id))
+                    .add(message::Column::DeletedAt.is_null())
+                    .add(message::Column::FlagInbox.eq(true))
+                    .add(message::Column::FlagArchive.eq(false))
+                    .add(message::Column::FlagTrash.eq(false))
+                    .add(message::Column::FlagRead.eq(false)),
+            )
+            .count(txn)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Gets all the messages in the same reply thread as the given one.
+    ///
+    /// Only messages which the user has a copy of are returned,
+    /// ordered from oldest to newest.
+    pub async fn get_thread(
+        ctx: &ServiceContext<'_>,
+        GetMessageThread { record_id, user_id }: GetMessageThread,
+    ) -> Result<Vec<MessageListItem>> {
+        info!("Getting message thread for {record_id} / {user_id}");
+
+        // Ensure the user can see this message
+        Self::get_message(ctx, &record_id, user_id).await?;
+
+        let txn = ctx.transaction();
+
+        // Find the start of the thread
+        let mut root = Self::get_record(ctx, &record_id).await?;
+        let mut visited = vec![root.external_id.clone()];
+        while let Some(ref parent_id) = root.reply_to {
+            if visited.contains(parent_id) {
+                warn!("Reply loop found in message record {parent_id}");
+                break;
+            }
+
+            root = Self::get_record(ctx, parent_id).await?;
+            visited.push(root.external_id.clone());
+        }
+
+        // Gather all replies from there
+        let mut record_ids = vec![root.external_id];
+        let mut frontier = record_ids.clone();
+        while !frontier.is_empty() {
+            let replies: Vec<String> = MessageRecord::find()
+                .select_only()
+                .column(message_record::Column::ExternalId)
+                .filter(message_record::Column::ReplyTo.is_in(frontier))
+                .into_tuple()
+                .all(txn)
+                .await?;
+
+            frontier = replies
+                .into_iter()
+                .filter(|id| !record_ids.contains(id))
+                .collect();
+
+            record_ids.extend(frontier.iter().cloned());
+        }
+
+        let messages = Message::find()
+            .find_also_related(MessageRecord)
+            .filter(
+                Condition::all()
+                    .add(message::Column::UserId.eq(user_id))
+                    .add(message::Column::DeletedAt.is_null())
+                    .add(message::Column::RecordId.is_in(record_ids)),
+            )
+            .order_by_asc(message_record::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(Self::build_list(messages))
+    }
+
     // Getters
 
     pub async fn get_message_optional(
@@ -432,7 +637,8 @@ impl MessageService {
             .filter(
                 Condition::all()
                     .add(message::Column::RecordId.eq(record_id))
-                    .add(message::Column::UserId.eq(user_id)),
+                    .add(message::Column::UserId.eq(user_id))
+                    .add(message::Column::DeletedAt.is_null()),
             )
             .one(txn)
             .await?;
@@ -461,6 +667,53 @@ impl MessageService {
         Ok(record)
     }
 
+    #[inline]
+    pub async fn get_record(
+        ctx: &ServiceContext<'_>,
+        record_id: &str,
+    ) -> Result<MessageRecordModel> {
+        find_or_error!(Self::get_record_optional(ctx, record_id), Message)
+    }
+
+    /// Gets a user's copy of a message, along with its contents.
+    ///
+    /// Blind carbon copy recipients are only visible to the sender.
+    pub async fn get_full(
+        ctx: &ServiceContext<'_>,
+        GetMessage { record_id, user_id }: GetMessage,
+    ) -> Result<GetMessageOutput> {
+        let txn = ctx.transaction();
+        let message = Self::get_message(ctx, &record_id, user_id).await?;
+        let record = Self::get_record(ctx, &record_id).await?;
+
+        let mut recipient_condition = Condition::all()
+            .add(message_recipient::Column::RecordId.eq(record_id.as_str()));
+
+        if record.sender_id != user_id {
+            recipient_condition = recipient_condition.add(
+                message_recipient::Column::RecipientType.ne(MessageRecipientType::Bcc),
+            );
+        }
+
+        let recipients = MessageRecipient::find()
+            .filter(recipient_condition)
+            .all(txn)
+            .await?;
+
+        let (wikitext, compiled_html) = try_join!(
+            TextService::get(ctx, &record.wikitext_hash),
+            TextService::get(ctx, &record.compiled_hash),
+        )?;
+
+        Ok(GetMessageOutput {
+            message,
+            record,
+            recipients,
+            wikitext,
+            compiled_html,
+        })
+    }
+
     pub async fn get_draft_optional(
         ctx: &ServiceContext<'_>,
         draft_id: &str,
@@ -483,6 +736,17 @@ impl MessageService {
 
     // Helper methods
 
+    fn build_list(
+        messages: Vec<(MessageModel, Option<MessageRecordModel>)>,
+    ) -> Vec<MessageListItem> {
+        messages
+            .into_iter()
+            .filter_map(|(message, record)| {
+                record.map(|record| MessageListItem { message, record })
+            })
+            .collect()
+    }
+
     /// Helper method to insert a group of `message_recipient` rows.
     async fn add_recipients(
         txn: &DatabaseTransaction,
@@ -18,6 +18,11 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use crate::models::message::Model as MessageModel;
+use crate::models::message_recipient::Model as MessageRecipientModel;
+use crate::models::message_record::Model as MessageRecordModel;
+use crate::web::ProvidedValue;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreateMessageDraft {
     pub user_id: i64,
@@ -61,6 +66,87 @@ pub struct DraftRecipients {
     pub blind_carbon_copy: Vec<i64>,
 }
 
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MessageFolder {
+    /// Messages received by the user, which are not archived or trashed.
+    Inbox,
+
+    /// Messages sent by the user to others.
+    Outbox,
+
+    /// Messages sent by the user to themselves.
+    #[serde(rename = "self")]
+    ToSelf,
+
+    /// Starred messages, regardless of folder.
+    Starred,
+
+    /// Messages received by the user which were archived.
+    Archived,
+
+    /// Messages which were moved to the trash.
+    Trash,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetMessages {
+    pub user_id: i64,
+    pub folder: MessageFolder,
+
+    /// Only return messages older than this message ID.
+    ///
+    /// Used to get the next page of results.
+    pub before_id: Option<i64>,
+
+    /// How many messages to return.
+    ///
+    /// This is clamped to between 1 and `MAXIMUM_MESSAGE_PAGE_SIZE`.
+    pub limit: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetMessage {
+    pub record_id: String,
+    pub user_id: i64,
+}
+
+pub type GetMessageThread = GetMessage;
+pub type DeleteMessage = GetMessage;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateMessageFlags {
+    pub record_id: String,
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub read: ProvidedValue<bool>,
+
+    #[serde(default)]
+    pub star: ProvidedValue<bool>,
+
+    #[serde(default)]
+    pub archive: ProvidedValue<bool>,
+
+    #[serde(default)]
+    pub trash: ProvidedValue<bool>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MessageListItem {
+    pub message: MessageModel,
+    pub record: MessageRecordModel,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetMessageOutput {
+    pub message: MessageModel,
+    pub record: MessageRecordModel,
+    pub recipients: Vec<MessageRecipientModel>,
+    pub wikitext: String,
+    pub compiled_html: String,
+}
+
 impl DraftRecipients {
     pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
         let i1 = self.regular.iter().copied();
@@ -18,10 +18,333 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-#[allow(dead_code)] // TODO
+use super::prelude::*;
+use crate::models::message::Entity as Message;
+use crate::models::message_report::{
+    self, Entity as MessageReport, Model as MessageReportModel,
+};
+use crate::models::sea_orm_active_enums::{MessageReportStatus, PermissionType};
+use crate::services::relation::{
+    CreateSiteBan, CreateUserBlock, GetSiteBan, GetUserBlock, SiteBanData, UserBlockData,
+};
+use crate::services::{MessageService, PermissionService, RelationService, TextService};
+
 #[derive(Debug)]
 pub struct MessageReportService;
 
 impl MessageReportService {
-    // TODO
+    /// Reports a message which the user received.
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreateMessageReport {
+            record_id,
+            user_id,
+            site_id,
+            reason,
+        }: CreateMessageReport,
+    ) -> Result<MessageReportModel> {
+        info!("Creating message report for record ID {record_id} by user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let message = MessageService::get_message(ctx, &record_id, user_id).await?;
+        if !message.flag_inbox {
+            error!("Cannot report message which was not received by user ID {user_id}");
+            return Err(Error::MessageNotReceived);
+        }
+
+        // Check for an existing report
+        let site_condition = match site_id {
+            Some(site_id) => message_report::Column::ReportedToSiteId.eq(site_id),
+            None => message_report::Column::ReportedToSiteId.is_null(),
+        };
+
+        let existing = MessageReport::find()
+            .filter(
+                Condition::all()
+                    .add(message_report::Column::MessageId.eq(message.internal_id))
+                    .add(site_condition),
+            )
+            .one(txn)
+            .await?;
+
+        if existing.is_some() {
+            error!(
+                "Message ID {} has already been reported",
+                message.internal_id
+            );
+            return Err(Error::MessageReportExists);
+        }
+
+        let model = message_report::ActiveModel {
+            message_id: Set(message.internal_id),
+            reported_to_site_id: Set(site_id),
+            reason: Set(reason),
+            ..Default::default()
+        };
+        let report = model.insert(txn).await?;
+        Ok(report)
+    }
+
+    pub async fn get_optional(
+        ctx: &ServiceContext<'_>,
+        message_report_id: i64,
+    ) -> Result<Option<MessageReportModel>> {
+        let txn = ctx.transaction();
+        let report = MessageReport::find_by_id(message_report_id)
+            .one(txn)
+            .await?;
+        Ok(report)
+    }
+
+    #[inline]
+    pub async fn get(
+        ctx: &ServiceContext<'_>,
+        message_report_id: i64,
+    ) -> Result<MessageReportModel> {
+        find_or_error!(Self::get_optional(ctx, message_report_id), MessageReport)
+    }
+
+    /// Gets the queue of message reports for a site, or the platform.
+    ///
+    /// The user must be able to moderate wherever the reports were sent to.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetMessageReports {
+            site_id,
+            user_id,
+            status,
+        }: GetMessageReports,
+    ) -> Result<Vec<MessageReportOutput>> {
+        Self::check_moderator(ctx, site_id, user_id).await?;
+
+        let txn = ctx.transaction();
+        let site_condition = match site_id {
+            Some(site_id) => message_report::Column::ReportedToSiteId.eq(site_id),
+            None => message_report::Column::ReportedToSiteId.is_null(),
+        };
+        let status_condition =
+            status.map(|status| message_report::Column::Status.eq(status));
+
+        let reports = MessageReport::find()
+            .filter(
+                Condition::all()
+                    .add(site_condition)
+                    .add_option(status_condition),
+            )
+            .order_by_asc(message_report::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        let mut output = Vec::with_capacity(reports.len());
+        for report in reports {
+            output.push(Self::build_output(ctx, report).await?);
+        }
+
+        Ok(output)
+    }
+
+    /// Resolves a message report, optionally taking action against the sender.
+    pub async fn resolve(
+        ctx: &ServiceContext<'_>,
+        ResolveMessageReport {
+            message_report_id,
+            user_id,
+            comments,
+            action,
+        }: ResolveMessageReport,
+    ) -> Result<MessageReportModel> {
+        info!("Resolving message report ID {message_report_id} by user ID {user_id}");
+
+        let report = Self::get_pending(ctx, message_report_id, user_id).await?;
+        let MessageReportOutput {
+            reporter_id,
+            sender_id,
+            ..
+        } = Self::build_output(ctx, report.clone()).await?;
+
+        match action {
+            None => debug!("No action taken against sender"),
+            Some(MessageReportAction::BlockSender) => {
+                debug!("Blocking sender user ID {sender_id} for user ID {reporter_id}");
+
+                let get_block = GetUserBlock {
+                    blocked_user: sender_id,
+                    blocking_user: reporter_id,
+                };
+
+                if !RelationService::user_block_exists(ctx, get_block).await? {
+                    RelationService::create_user_block(
+                        ctx,
+                        CreateUserBlock {
+                            blocked_user: sender_id,
+                            blocking_user: reporter_id,
+                            created_by: user_id,
+                            metadata: UserBlockData {
+                                reason: report.reason.clone(),
+                            },
+                        },
+                    )
+                    .await?;
+                }
+            }
+            Some(MessageReportAction::BanSender { banned_until }) => {
+                let site_id = match report.reported_to_site_id {
+                    Some(site_id) => site_id,
+                    None => {
+                        error!(
+                            "Cannot ban sender for a message reported to the platform"
+                        );
+                        return Err(Error::BadRequest);
+                    }
+                };
+
+                debug!("Banning sender user ID {sender_id} from site ID {site_id}");
+
+                let get_ban = GetSiteBan {
+                    site_id,
+                    user_id: sender_id,
+                };
+
+                if !RelationService::is_site_banned(ctx, get_ban).await? {
+                    RelationService::create_site_ban(
+                        ctx,
+                        CreateSiteBan {
+                            site_id,
+                            user_id: sender_id,
+                            created_by: user_id,
+                            metadata: SiteBanData {
+                                banned_until,
+                                reason: report.reason.clone(),
+                            },
+                        },
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        Self::close(
+            ctx,
+            message_report_id,
+            MessageReportStatus::Resolved,
+            user_id,
+            comments,
+        )
+        .await
+    }
+
+    /// Dismisses a message report, taking no action.
+    pub async fn dismiss(
+        ctx: &ServiceContext<'_>,
+        DismissMessageReport {
+            message_report_id,
+            user_id,
+            comments,
+        }: DismissMessageReport,
+    ) -> Result<MessageReportModel> {
+        info!("Dismissing message report ID {message_report_id} by user ID {user_id}");
+
+        Self::get_pending(ctx, message_report_id, user_id).await?;
+        Self::close(
+            ctx,
+            message_report_id,
+            MessageReportStatus::Dismissed,
+            user_id,
+            comments,
+        )
+        .await
+    }
+
+    /// Gets a report which the user can moderate, and which is still pending.
+    async fn get_pending(
+        ctx: &ServiceContext<'_>,
+        message_report_id: i64,
+        user_id: i64,
+    ) -> Result<MessageReportModel> {
+        let report = Self::get(ctx, message_report_id).await?;
+        Self::check_moderator(ctx, report.reported_to_site_id, user_id).await?;
+
+        if report.status != MessageReportStatus::Pending {
+            error!(
+                "Message report ID {message_report_id} is already closed ({:?})",
+                report.status,
+            );
+            return Err(Error::MessageReportClosed);
+        }
+
+        Ok(report)
+    }
+
+    async fn close(
+        ctx: &ServiceContext<'_>,
+        message_report_id: i64,
+        status: MessageReportStatus,
+        user_id: i64,
+        comments: Option<String>,
+    ) -> Result<MessageReportModel> {
+        let txn = ctx.transaction();
+        let now = now();
+        let model = message_report::ActiveModel {
+            message_report_id: Set(message_report_id),
+            updated_at: Set(Some(now)),
+            status: Set(status),
+            resolved_at: Set(Some(now)),
+            resolved_by: Set(Some(user_id)),
+            resolution_comments: Set(comments),
+            ..Default::default()
+        };
+
+        let report = model.update(txn).await?;
+        Ok(report)
+    }
+
+    async fn build_output(
+        ctx: &ServiceContext<'_>,
+        report: MessageReportModel,
+    ) -> Result<MessageReportOutput> {
+        let txn = ctx.transaction();
+        let message = Message::find_by_id(report.message_id)
+            .one(txn)
+            .await?
+            .ok_or(Error::MessageNotFound)?;
+
+        let record = MessageService::get_record_optional(ctx, &message.record_id)
+            .await?
+            .ok_or(Error::MessageNotFound)?;
+
+        let wikitext = TextService::get(ctx, &record.wikitext_hash).await?;
+
+        Ok(MessageReportOutput {
+            report,
+            reporter_id: message.user_id,
+            sender_id: record.sender_id,
+            subject: record.subject,
+            wikitext,
+        })
+    }
+
+    /// Checks that the user can handle reports for the given site.
+    ///
+    /// If `site_id` is `None`, then the user must be platform staff.
+    async fn check_moderator(
+        ctx: &ServiceContext<'_>,
+        site_id: Option<i64>,
+        user_id: i64,
+    ) -> Result<()> {
+        match site_id {
+            Some(site_id) => {
+                PermissionService::check(
+                    ctx,
+                    site_id,
+                    None,
+                    user_id,
+                    PermissionType::Moderate,
+                )
+                .await
+            }
+
+            // Reports sent to the platform are handled by its staff
+            None => PermissionService::check_platform_staff(ctx, user_id).await,
+        }
+    }
 }
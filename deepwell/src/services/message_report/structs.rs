@@ -18,4 +18,66 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-// TODO
+use crate::models::message_report::Model as MessageReportModel;
+use crate::models::sea_orm_active_enums::MessageReportStatus;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateMessageReport {
+    pub record_id: String,
+    pub user_id: i64,
+
+    /// The site to report this message to.
+    ///
+    /// If `None`, then it is reported to the platform.
+    pub site_id: Option<i64>,
+    pub reason: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetMessageReports {
+    pub site_id: Option<i64>,
+    pub user_id: i64,
+
+    /// Only return reports with this status.
+    ///
+    /// If `None`, then reports of any status are returned.
+    pub status: Option<MessageReportStatus>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ResolveMessageReport {
+    pub message_report_id: i64,
+    pub user_id: i64,
+    pub comments: Option<String>,
+    pub action: Option<MessageReportAction>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DismissMessageReport {
+    pub message_report_id: i64,
+    pub user_id: i64,
+    pub comments: Option<String>,
+}
+
+/// What action to take against the sender of a reported message.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum MessageReportAction {
+    /// Block the sender on behalf of the user who reported the message.
+    BlockSender,
+
+    /// Ban the sender from the site the message was reported to.
+    BanSender {
+        banned_until: Option<OffsetDateTime>,
+    },
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MessageReportOutput {
+    pub report: MessageReportModel,
+    pub reporter_id: i64,
+    pub sender_id: i64,
+    pub subject: String,
+    pub wikitext: String,
+}
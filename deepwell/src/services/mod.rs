@@ -64,6 +64,7 @@ pub mod domain;
 pub mod email;
 pub mod file;
 pub mod file_revision;
+pub mod file_upload;
 pub mod filter;
 pub mod import;
 pub mod job;
@@ -71,12 +72,16 @@ pub mod link;
 pub mod message;
 pub mod message_report;
 pub mod mfa;
+pub mod notification;
 pub mod outdate;
 pub mod page;
+pub mod page_attribution;
+pub mod page_lock;
 pub mod page_query;
 pub mod page_revision;
 pub mod parent;
 pub mod password;
+pub mod permission;
 pub mod relation;
 pub mod render;
 pub mod score;
@@ -98,19 +103,24 @@ pub use self::domain::DomainService;
 pub use self::error::*;
 pub use self::file::FileService;
 pub use self::file_revision::FileRevisionService;
+pub use self::file_upload::FileUploadService;
 pub use self::filter::FilterService;
+pub use self::import::ImportService;
 pub use self::job::JobService;
 pub use self::link::LinkService;
 pub use self::message::MessageService;
 pub use self::message_report::MessageReportService;
 pub use self::mfa::MfaService;
+pub use self::notification::NotificationService;
 pub use self::outdate::OutdateService;
 pub use self::page::PageService;
-// TODO convert page attribution to a type of relation
+pub use self::page_attribution::PageAttributionService;
+pub use self::page_lock::PageLockService;
 pub use self::page_query::PageQueryService;
 pub use self::page_revision::PageRevisionService;
 pub use self::parent::ParentService;
 pub use self::password::PasswordService;
+pub use self::permission::PermissionService;
 pub use self::relation::RelationService;
 pub use self::render::RenderService;
 pub use self::score::ScoreService;
@@ -0,0 +1,381 @@
+/*
+ * services/notification/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::notification::{
+    self, Entity as Notification, Model as NotificationModel,
+};
+use crate::models::sea_orm_active_enums::NotificationType;
+use crate::services::relation::{
+    RelationDirection, RelationObject, RelationPagination, RelationType,
+};
+use crate::services::RelationService;
+use sea_orm::sea_query::Expr;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+/// How many watchers or followers to fetch at a time when fanning out.
+const RECIPIENT_BATCH_SIZE: u64 = 100;
+
+#[derive(Debug)]
+pub struct NotificationService;
+
+impl NotificationService {
+    /// Creates notifications for everyone interested in this event.
+    ///
+    /// The user who caused the event is never notified about it.
+    ///
+    /// # Returns
+    /// The number of users who were notified.
+    pub async fn send(ctx: &ServiceContext<'_>, event: NotificationEvent) -> Result<u64> {
+        info!("Sending notifications for event {event:?}");
+
+        let (notification_type, actor_id, site_id, page_id, record_id, recipients) =
+            match event {
+                NotificationEvent::PageEdit {
+                    site_id,
+                    page_id,
+                    user_id,
+                } => (
+                    NotificationType::PageEdit,
+                    user_id,
+                    Some(site_id),
+                    Some(page_id),
+                    None,
+                    Self::get_page_watchers(ctx, page_id).await?,
+                ),
+                NotificationEvent::PageMove {
+                    site_id,
+                    page_id,
+                    user_id,
+                } => (
+                    NotificationType::PageMove,
+                    user_id,
+                    Some(site_id),
+                    Some(page_id),
+                    None,
+                    Self::get_page_watchers(ctx, page_id).await?,
+                ),
+                NotificationEvent::PageDelete {
+                    site_id,
+                    page_id,
+                    user_id,
+                } => (
+                    NotificationType::PageDelete,
+                    user_id,
+                    Some(site_id),
+                    Some(page_id),
+                    None,
+                    Self::get_page_watchers(ctx, page_id).await?,
+                ),
+                NotificationEvent::PageCreate {
+                    site_id,
+                    page_id,
+                    user_id,
+                } => (
+                    NotificationType::FollowedPageCreate,
+                    user_id,
+                    Some(site_id),
+                    Some(page_id),
+                    None,
+                    Self::get_user_followers(ctx, user_id).await?,
+                ),
+                NotificationEvent::Message {
+                    record_id,
+                    sender_id,
+                    recipients,
+                } => (
+                    NotificationType::Message,
+                    sender_id,
+                    None,
+                    None,
+                    Some(record_id),
+                    recipients.into_iter().collect(),
+                ),
+            };
+
+        let mut notified = 0;
+        for user_id in recipients {
+            if user_id == actor_id {
+                continue;
+            }
+
+            Self::add(
+                ctx,
+                user_id,
+                notification_type,
+                actor_id,
+                site_id,
+                page_id,
+                record_id.clone(),
+            )
+            .await?;
+
+            notified += 1;
+        }
+
+        debug!("Notified {notified} users about {notification_type:?}");
+        Ok(notified)
+    }
+
+    /// Adds a notification for a single user.
+    ///
+    /// If the user already has an unread notification of this type for
+    /// this page, then it is updated rather than a new one being created.
+    /// This way a page being edited many times produces one notification.
+    async fn add(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        notification_type: NotificationType,
+        actor_id: i64,
+        site_id: Option<i64>,
+        page_id: Option<i64>,
+        message_record_id: Option<String>,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+
+        if let Some(page_id) = page_id {
+            let existing = Notification::find()
+                .filter(
+                    Condition::all()
+                        .add(notification::Column::UserId.eq(user_id))
+                        .add(notification::Column::NotificationType.eq(notification_type))
+                        .add(notification::Column::PageId.eq(page_id))
+                        .add(notification::Column::ReadAt.is_null()),
+                )
+                .one(txn)
+                .await?;
+
+            if let Some(existing) = existing {
+                debug!(
+                    "Merging into unread notification ID {}",
+                    existing.notification_id,
+                );
+
+                let event_count = existing.event_count + 1;
+                let model = notification::ActiveModel {
+                    notification_id: Set(existing.notification_id),
+                    updated_at: Set(Some(now())),
+                    actor_id: Set(actor_id),
+                    event_count: Set(event_count),
+                    ..Default::default()
+                };
+                model.update(txn).await?;
+                return Ok(());
+            }
+        }
+
+        let model = notification::ActiveModel {
+            user_id: Set(user_id),
+            notification_type: Set(notification_type),
+            actor_id: Set(actor_id),
+            site_id: Set(site_id),
+            page_id: Set(page_id),
+            message_record_id: Set(message_record_id),
+            ..Default::default()
+        };
+        model.insert(txn).await?;
+        Ok(())
+    }
+
+    /// Gets a user's notifications, newest first.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetNotifications {
+            user_id,
+            unread_only,
+            before_id,
+            limit,
+        }: GetNotifications,
+    ) -> Result<Vec<NotificationModel>> {
+        info!("Getting notifications for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let mut condition =
+            Condition::all().add(notification::Column::UserId.eq(user_id));
+
+        if unread_only {
+            condition = condition.add(notification::Column::ReadAt.is_null());
+        }
+
+        if let Some(before_id) = before_id {
+            condition = condition.add(notification::Column::NotificationId.lt(before_id));
+        }
+
+        let notifications = Notification::find()
+            .filter(condition)
+            .order_by_desc(notification::Column::NotificationId)
+            .limit(limit)
+            .all(txn)
+            .await?;
+
+        Ok(notifications)
+    }
+
+    pub async fn count_unread(ctx: &ServiceContext<'_>, user_id: i64) -> Result<u64> {
+        info!("Counting unread notifications for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let count = Notification::find()
+            .filter(
+                Condition::all()
+                    .add(notification::Column::UserId.eq(user_id))
+                    .add(notification::Column::ReadAt.is_null()),
+            )
+            .count(txn)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Marks some or all of a user's notifications as read.
+    ///
+    /// # Returns
+    /// The number of notifications which were marked as read.
+    pub async fn mark_read(
+        ctx: &ServiceContext<'_>,
+        MarkNotificationsRead {
+            user_id,
+            notification_ids,
+        }: MarkNotificationsRead,
+    ) -> Result<u64> {
+        info!("Marking notifications as read for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let mut condition = Condition::all()
+            .add(notification::Column::UserId.eq(user_id))
+            .add(notification::Column::ReadAt.is_null());
+
+        if let Some(notification_ids) = notification_ids {
+            condition = condition
+                .add(notification::Column::NotificationId.is_in(notification_ids));
+        }
+
+        let result = Notification::update_many()
+            .col_expr(notification::Column::ReadAt, Expr::value(now()))
+            .filter(condition)
+            .exec(txn)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// Summarizes a user's unread notifications, grouped by type and site.
+    pub async fn get_digest(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<Vec<NotificationDigestEntry>> {
+        info!("Getting notification digest for user ID {user_id}");
+
+        let txn = ctx.transaction();
+        let notifications = Notification::find()
+            .filter(
+                Condition::all()
+                    .add(notification::Column::UserId.eq(user_id))
+                    .add(notification::Column::ReadAt.is_null()),
+            )
+            .all(txn)
+            .await?;
+
+        let mut entries: Vec<NotificationDigestEntry> = Vec::new();
+        for model in notifications {
+            let latest_at = model.updated_at.unwrap_or(model.created_at);
+            let event_count = u64::try_from(model.event_count).unwrap_or(0);
+            let existing = entries.iter_mut().find(|entry| {
+                entry.notification_type == model.notification_type
+                    && entry.site_id == model.site_id
+            });
+
+            match existing {
+                Some(entry) => {
+                    entry.notification_count += 1;
+                    entry.event_count += event_count;
+                    entry.latest_at = entry.latest_at.max(latest_at);
+                }
+                None => entries.push(NotificationDigestEntry {
+                    notification_type: model.notification_type,
+                    site_id: model.site_id,
+                    notification_count: 1,
+                    event_count,
+                    latest_at,
+                }),
+            }
+        }
+
+        // Most recent activity first
+        entries.sort_by_key(|entry| Reverse(entry.latest_at));
+        Ok(entries)
+    }
+
+    async fn get_page_watchers(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<BTreeSet<i64>> {
+        Self::get_relation_sources(
+            ctx,
+            RelationType::PageWatch,
+            RelationObject::Page(page_id),
+        )
+        .await
+    }
+
+    async fn get_user_followers(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<BTreeSet<i64>> {
+        Self::get_relation_sources(
+            ctx,
+            RelationType::UserFollow,
+            RelationObject::User(user_id),
+        )
+        .await
+    }
+
+    /// Gets the IDs of every user with an active relation of this type to the object.
+    async fn get_relation_sources(
+        ctx: &ServiceContext<'_>,
+        relation_type: RelationType,
+        object: RelationObject,
+    ) -> Result<BTreeSet<i64>> {
+        let mut user_ids = BTreeSet::new();
+        let mut before_id = None;
+
+        loop {
+            let page = RelationService::get_current_entries(
+                ctx,
+                relation_type,
+                object,
+                RelationDirection::Dest,
+                RelationPagination {
+                    before_id,
+                    limit: RECIPIENT_BATCH_SIZE,
+                },
+            )
+            .await?;
+
+            user_ids.extend(page.relations.iter().map(|relation| relation.from_id));
+
+            match page.next_before_id {
+                Some(next) => before_id = Some(next),
+                None => return Ok(user_ids),
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+/*
+ * services/notification/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::models::sea_orm_active_enums::NotificationType;
+use time::OffsetDateTime;
+
+/// Something which happened, which users may be notified about.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "event")]
+pub enum NotificationEvent {
+    /// A page was edited. Notifies the page's watchers.
+    PageEdit {
+        site_id: i64,
+        page_id: i64,
+        user_id: i64,
+    },
+
+    /// A page was moved. Notifies the page's watchers.
+    PageMove {
+        site_id: i64,
+        page_id: i64,
+        user_id: i64,
+    },
+
+    /// A page was deleted. Notifies the page's watchers.
+    PageDelete {
+        site_id: i64,
+        page_id: i64,
+        user_id: i64,
+    },
+
+    /// A page was created. Notifies the followers of its creator.
+    PageCreate {
+        site_id: i64,
+        page_id: i64,
+        user_id: i64,
+    },
+
+    /// A direct message was sent. Notifies its recipients.
+    Message {
+        record_id: String,
+        sender_id: i64,
+        recipients: Vec<i64>,
+    },
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetNotifications {
+    pub user_id: i64,
+
+    #[serde(default)]
+    pub unread_only: bool,
+
+    /// Only return notifications older than this notification ID.
+    ///
+    /// Used to get the next page of results.
+    #[serde(default)]
+    pub before_id: Option<i64>,
+    pub limit: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MarkNotificationsRead {
+    pub user_id: i64,
+
+    /// Which notifications to mark as read.
+    ///
+    /// If `None`, then all of the user's notifications are marked as read.
+    #[serde(default)]
+    pub notification_ids: Option<Vec<i64>>,
+}
+
+/// A summary of a user's unread notifications of one type in one site.
+///
+/// Used to send digests, rather than each notification individually.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NotificationDigestEntry {
+    pub notification_type: NotificationType,
+    pub site_id: Option<i64>,
+
+    /// How many notifications were merged into this entry.
+    pub notification_count: u64,
+
+    /// How many events these notifications describe.
+    pub event_count: u64,
+    pub latest_at: OffsetDateTime,
+}
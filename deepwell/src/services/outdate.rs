@@ -19,11 +19,14 @@
  */
 
 use super::prelude::*;
-use crate::models::page::Model as PageModel;
+use crate::models::page::{self, Entity as Page, Model as PageModel};
 use crate::services::{JobService, LinkService, PageService};
 use crate::utils::split_category_name;
 use crate::web::{ConnectionType, PageOrder};
 
+/// How many pages are queued for re-rendering by each site outdate job.
+const OUTDATE_SITE_BATCH_SIZE: u64 = 500;
+
 #[derive(Debug)]
 pub struct OutdateService;
 
@@ -177,4 +180,57 @@ impl OutdateService {
 
         Ok(())
     }
+
+    /// Queues every page in the site for re-rendering.
+    ///
+    /// This is needed when a site-wide setting which affects
+    /// the output of all pages, such as the score type, changes.
+    ///
+    /// Sites may have many pages, so this only queues a job which
+    /// goes through them in batches, see `outdate_site_batch()`.
+    pub async fn outdate_site(ctx: &ServiceContext<'_>, site_id: i64) -> Result<()> {
+        JobService::queue_outdate_site(ctx, site_id, None).await
+    }
+
+    /// Queues the next batch of pages in the site for re-rendering.
+    ///
+    /// Pages are gone through in order of ID, starting after `after_page_id`.
+    /// If there may be more pages, another job is queued for the next batch.
+    pub async fn outdate_site_batch(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        after_page_id: Option<i64>,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+        let page_ids: Vec<i64> = Page::find()
+            .select_only()
+            .column(page::Column::PageId)
+            .filter(
+                Condition::all()
+                    .add(page::Column::SiteId.eq(site_id))
+                    .add(page::Column::DeletedAt.is_null())
+                    .add_option(after_page_id.map(|id| page::Column::PageId.gt(id))),
+            )
+            .order_by_asc(page::Column::PageId)
+            .limit(OUTDATE_SITE_BATCH_SIZE)
+            .into_tuple()
+            .all(txn)
+            .await?;
+
+        debug!(
+            "Outdating {} pages in site ID {site_id} after page ID {after_page_id:?}",
+            page_ids.len(),
+        );
+
+        for &page_id in &page_ids {
+            JobService::queue_rerender_page(ctx, site_id, page_id, 0).await?;
+        }
+
+        if page_ids.len() as u64 == OUTDATE_SITE_BATCH_SIZE {
+            let last_page_id = page_ids.last().copied();
+            JobService::queue_outdate_site(ctx, site_id, last_page_id).await?;
+        }
+
+        Ok(())
+    }
 }
@@ -21,16 +21,23 @@
 use super::prelude::*;
 use crate::models::page::{self, Entity as Page, Model as PageModel};
 use crate::models::page_category::Model as PageCategoryModel;
-use crate::services::filter::{FilterClass, FilterType};
+use crate::models::page_data_field::{self, Entity as PageDataField};
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::sea_orm_active_enums::FilterContentType;
+use crate::services::filter::{FilterCheck, FilterClass, FilterType};
+use crate::services::notification::NotificationEvent;
 use crate::services::page_revision::{
     CreateFirstPageRevision, CreateFirstPageRevisionOutput, CreatePageRevision,
     CreatePageRevisionBody, CreatePageRevisionOutput, CreateResurrectionPageRevision,
-    CreateTombstonePageRevision,
+    CreateTombstonePageRevision, GetPageRevisionRange,
 };
-use crate::services::{CategoryService, FilterService, PageRevisionService, TextService};
-use crate::utils::{get_category_name, trim_default};
-use crate::web::PageOrder;
+use crate::services::{
+    CategoryService, FilterService, JobService, PageRevisionService, TextService,
+};
+use crate::utils::{get_category_name, merge3, trim_default, MergeHunk};
+use crate::web::{FetchDirection, PageOrder};
 use sea_orm::ActiveValue;
+use std::collections::BTreeMap;
 use wikidot_normalize::normalize;
 
 #[derive(Debug)]
@@ -61,6 +68,7 @@ impl PageService {
             Self::run_filter(
                 ctx,
                 site_id,
+                user_id,
                 Some(&wikitext),
                 Some(&title),
                 alt_title.as_ref(),
@@ -107,6 +115,17 @@ impl PageService {
         let page = model.update(txn).await?;
         check_latest_revision(&page);
 
+        // Notify the creator's followers
+        JobService::queue_send_notifications(
+            ctx,
+            NotificationEvent::PageCreate {
+                site_id,
+                page_id,
+                user_id,
+            },
+        )
+        .await?;
+
         // Build and return
         Ok(CreatePageOutput {
             page_id,
@@ -123,13 +142,9 @@ impl PageService {
             page: reference,
             revision_comments: comments,
             user_id,
-            body:
-                EditPageBody {
-                    wikitext,
-                    title,
-                    alt_title,
-                    tags,
-                },
+            base_revision_id,
+            data_fields,
+            body,
         }: EditPage<'_>,
     ) -> Result<Option<EditPageOutput>> {
         let txn = ctx.transaction();
@@ -139,10 +154,11 @@ impl PageService {
         Self::run_filter(
             ctx,
             site_id,
-            wikitext.to_option(),
-            title.to_option(),
+            user_id,
+            body.wikitext.to_option(),
+            body.title.to_option(),
             // Flatten what is essentially Option<Option<_>>
-            match alt_title {
+            match body.alt_title {
                 ProvidedValue::Set(Some(ref alt_title)) => Some(alt_title),
                 _ => None,
             },
@@ -153,6 +169,20 @@ impl PageService {
         let last_revision =
             PageRevisionService::get_latest(ctx, site_id, page_id).await?;
 
+        // If the page was edited after this edit was started,
+        // then combine these changes with the ones made since.
+        let EditPageBody {
+            wikitext,
+            title,
+            alt_title,
+            tags,
+        } = match base_revision_id {
+            Some(base_revision_id) if base_revision_id != last_revision.revision_id => {
+                Self::merge_edit(ctx, base_revision_id, &last_revision, body).await?
+            }
+            _ => body,
+        };
+
         // Create new revision
         //
         // A response of None means no revision was created
@@ -179,24 +209,37 @@ impl PageService {
         )
         .await?;
 
-        let latest_revision_id = match revision_output {
-            Some(ref output) => ActiveValue::Set(Some(output.revision_id)),
-            None => ActiveValue::NotSet,
-        };
-
         // Set page updated_at and latest_revision_id columns.
         //
         // Previously this was conditional on whether a revision was actually created.
         // But since this rerenders regardless, we need to update the page row.
         let model = page::ActiveModel {
             page_id: Set(page_id),
-            latest_revision_id,
+            latest_revision_id: latest_revision_id(&revision_output),
             updated_at: Set(Some(now())),
             ..Default::default()
         };
         let page = model.update(txn).await?;
         check_latest_revision(&page);
 
+        // Replace data form fields, if provided
+        if let Some(data_fields) = data_fields {
+            Self::set_data_fields(ctx, page_id, data_fields).await?;
+        }
+
+        // Notify page watchers, if anything changed
+        if revision_output.is_some() {
+            JobService::queue_send_notifications(
+                ctx,
+                NotificationEvent::PageEdit {
+                    site_id,
+                    page_id,
+                    user_id,
+                },
+            )
+            .await?;
+        }
+
         // Build and return
         Ok(revision_output)
     }
@@ -279,6 +322,17 @@ impl PageService {
         let page = model.update(txn).await?;
         check_latest_revision(&page);
 
+        // Notify page watchers
+        JobService::queue_send_notifications(
+            ctx,
+            NotificationEvent::PageMove {
+                site_id,
+                page_id,
+                user_id,
+            },
+        )
+        .await?;
+
         // Build and return
 
         match revision_output {
@@ -340,6 +394,17 @@ impl PageService {
         let page = model.update(txn).await?;
         check_latest_revision(&page);
 
+        // Notify page watchers
+        JobService::queue_send_notifications(
+            ctx,
+            NotificationEvent::PageDelete {
+                site_id,
+                page_id,
+                user_id,
+            },
+        )
+        .await?;
+
         Ok((output, page_id).into())
     }
 
@@ -469,14 +534,15 @@ impl PageService {
         )
         .await?;
 
-        // Set page updated_at column.
+        // Set page updated_at and latest_revision_id columns.
         let model = page::ActiveModel {
             page_id: Set(page_id),
+            latest_revision_id: latest_revision_id(&revision_output),
             updated_at: Set(Some(now())),
             ..Default::default()
         };
-
-        model.update(txn).await?;
+        let page = model.update(txn).await?;
+        check_latest_revision(&page);
 
         // Build and return
         Ok(revision_output)
@@ -492,14 +558,213 @@ impl PageService {
     /// the reversed changes interfere with other changes made since.
     ///
     /// This is equivalent to git's concept of a "revert".
-    #[allow(dead_code)]
     pub async fn undo(
-        _ctx: &ServiceContext<'_>,
-        _site_id: i64,
-        _page_id: i64,
-        _revision_number: i32,
-    ) -> Result<EditPageOutput> {
-        todo!()
+        ctx: &ServiceContext<'_>,
+        UndoPage {
+            site_id,
+            page: reference,
+            revision_number,
+            revision_comments: comments,
+            user_id,
+        }: UndoPage<'_>,
+    ) -> Result<UndoPageOutput> {
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = Self::get(ctx, site_id, reference).await?;
+
+        // There is nothing before the first revision to go back to
+        if revision_number == 0 {
+            return Err(Error::CannotUndoFirstRevision);
+        }
+
+        // Get the revision being undone, the one before it, and the latest.
+        //
+        // These are the base, "theirs", and "ours" for a three-way merge.
+        let (target_revision, previous_revision, last_revision) = try_join!(
+            PageRevisionService::get(ctx, site_id, page_id, revision_number),
+            PageRevisionService::get(ctx, site_id, page_id, revision_number - 1),
+            PageRevisionService::get_latest(ctx, site_id, page_id),
+        )?;
+
+        let (target_wikitext, previous_wikitext, last_wikitext) = try_join!(
+            TextService::get(ctx, &target_revision.wikitext_hash),
+            TextService::get(ctx, &previous_revision.wikitext_hash),
+            TextService::get(ctx, &last_revision.wikitext_hash),
+        )?;
+
+        // Merge each field
+        let body = match merge_undo(
+            RevisionContents::new(target_wikitext, &target_revision),
+            RevisionContents::new(previous_wikitext, &previous_revision),
+            RevisionContents::new(last_wikitext, &last_revision),
+        ) {
+            Ok(body) => body,
+            Err(conflict) => {
+                debug!("Conflicts found undoing revision {revision_number} of page ID {page_id}");
+                return Ok(*conflict);
+            }
+        };
+
+        // Perform filter validation
+        Self::run_filter(
+            ctx,
+            site_id,
+            user_id,
+            body.wikitext.to_option(),
+            body.title.to_option(),
+            match body.alt_title {
+                ProvidedValue::Set(Some(ref alt_title)) => Some(alt_title),
+                _ => None,
+            },
+        )
+        .await?;
+
+        // Create new revision
+        //
+        // Apply the merged body on top of the latest revision
+
+        let revision_input = CreatePageRevision {
+            user_id,
+            comments,
+            body,
+        };
+
+        let revision_output = PageRevisionService::create(
+            ctx,
+            site_id,
+            page_id,
+            revision_input,
+            last_revision,
+        )
+        .await?;
+
+        // Set page updated_at and latest_revision_id columns.
+        let model = page::ActiveModel {
+            page_id: Set(page_id),
+            latest_revision_id: latest_revision_id(&revision_output),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+        let page = model.update(txn).await?;
+        check_latest_revision(&page);
+
+        // Build and return
+        Ok(UndoPageOutput::Merged {
+            revision: revision_output,
+        })
+    }
+
+    /// Merges an edit based on an older revision with the changes made since.
+    ///
+    /// Only the fields set in the edit are merged, the others remain unset.
+    /// If the changes overlap, then `Error::PageEditConflict` is returned,
+    /// with all the revisions made after the base revision.
+    async fn merge_edit(
+        ctx: &ServiceContext<'_>,
+        base_revision_id: i64,
+        last_revision: &PageRevisionModel,
+        EditPageBody {
+            wikitext,
+            title,
+            alt_title,
+            tags,
+        }: EditPageBody,
+    ) -> Result<EditPageBody> {
+        let base_revision =
+            PageRevisionService::get_direct(ctx, base_revision_id).await?;
+        if base_revision.page_id != last_revision.page_id {
+            error!(
+                "Base revision ID {base_revision_id} is for page ID {}, not page ID {}",
+                base_revision.page_id, last_revision.page_id,
+            );
+            return Err(Error::PageRevisionNotFound);
+        }
+
+        info!(
+            "Merging edit on page ID {} from revision {} to {}",
+            last_revision.page_id,
+            base_revision.revision_number,
+            last_revision.revision_number,
+        );
+
+        let mut conflict = false;
+        let wikitext = match wikitext {
+            ProvidedValue::Set(wikitext) => {
+                let (base_wikitext, last_wikitext) = try_join!(
+                    TextService::get(ctx, &base_revision.wikitext_hash),
+                    TextService::get(ctx, &last_revision.wikitext_hash),
+                )?;
+
+                match merge_wikitext(&base_wikitext, &last_wikitext, &wikitext) {
+                    Ok(merged) => ProvidedValue::Set(merged),
+                    Err(_) => {
+                        conflict = true;
+                        ProvidedValue::Unset
+                    }
+                }
+            }
+            ProvidedValue::Unset => ProvidedValue::Unset,
+        };
+
+        macro_rules! merge {
+            ($field:ident $(,)?) => {
+                match $field {
+                    ProvidedValue::Set($field) => match merge_field(
+                        base_revision.$field.clone(),
+                        last_revision.$field.clone(),
+                        $field,
+                    ) {
+                        Ok(merged) => ProvidedValue::Set(merged),
+                        Err(_) => {
+                            conflict = true;
+                            ProvidedValue::Unset
+                        }
+                    },
+                    ProvidedValue::Unset => ProvidedValue::Unset,
+                }
+            };
+        }
+
+        let title = merge!(title);
+        let alt_title = merge!(alt_title);
+        let tags = match tags {
+            ProvidedValue::Set(tags) => ProvidedValue::Set(merge_tags(
+                &base_revision.tags,
+                &last_revision.tags,
+                &tags,
+            )),
+            ProvidedValue::Unset => ProvidedValue::Unset,
+        };
+
+        if conflict {
+            warn!(
+                "Edit on page ID {} conflicts with changes made since revision {}",
+                last_revision.page_id, base_revision.revision_number,
+            );
+
+            let revisions = PageRevisionService::get_range(
+                ctx,
+                GetPageRevisionRange {
+                    site_id: last_revision.site_id,
+                    page_id: last_revision.page_id,
+                    revision_number: base_revision.revision_number + 1,
+                    revision_direction: FetchDirection::After,
+                    limit: u64::from(
+                        (last_revision.revision_number - base_revision.revision_number)
+                            .unsigned_abs(),
+                    ),
+                },
+            )
+            .await?;
+
+            return Err(Error::PageEditConflict(revisions));
+        }
+
+        Ok(EditPageBody {
+            wikitext,
+            title,
+            alt_title,
+            tags,
+        })
     }
 
     #[inline]
@@ -685,6 +950,43 @@ impl PageService {
         Ok(pages)
     }
 
+    /// Replaces all of a page's data form fields.
+    async fn set_data_fields(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        data_fields: BTreeMap<String, String>,
+    ) -> Result<()> {
+        let txn = ctx.transaction();
+
+        debug!(
+            "Setting {} data form fields for page ID {page_id}",
+            data_fields.len(),
+        );
+
+        PageDataField::delete_many()
+            .filter(page_data_field::Column::PageId.eq(page_id))
+            .exec(txn)
+            .await?;
+
+        for (field_name, field_value) in data_fields {
+            if field_name.is_empty() {
+                error!("Data form field name is empty");
+                return Err(Error::BadRequest);
+            }
+
+            let model = page_data_field::ActiveModel {
+                page_id: Set(page_id),
+                field_name: Set(field_name),
+                field_value: Set(field_value),
+                created_at: Set(now()),
+                updated_at: Set(None),
+            };
+            model.insert(txn).await?;
+        }
+
+        Ok(())
+    }
+
     /// Checks to see if a page already exists at the slug specified.
     ///
     /// If so, this method fails with `Error::PageExists`. Otherwise it returns nothing.
@@ -727,6 +1029,7 @@ impl PageService {
     async fn run_filter<S: AsRef<str>>(
         ctx: &ServiceContext<'_>,
         site_id: i64,
+        user_id: i64,
         wikitext: Option<S>,
         title: Option<S>,
         alt_title: Option<S>,
@@ -741,10 +1044,18 @@ impl PageService {
         .await?;
 
         macro_rules! verify_optional {
-            ($option:expr) => {
+            ($option:expr, $content_type:ident $(,)?) => {
                 async {
                     match $option {
-                        Some(value) => filter_matcher.verify(ctx, value.as_ref()).await,
+                        Some(value) => {
+                            let check = FilterCheck {
+                                site_id: Some(site_id),
+                                user_id: Some(user_id),
+                                content_type: FilterContentType::$content_type,
+                            };
+
+                            filter_matcher.verify(ctx, check, value.as_ref()).await
+                        }
                         None => Ok(()),
                     }
                 }
@@ -752,9 +1063,9 @@ impl PageService {
         }
 
         try_join!(
-            verify_optional!(title),
-            verify_optional!(alt_title),
-            verify_optional!(wikitext),
+            verify_optional!(title, PageTitle),
+            verify_optional!(alt_title, PageTitle),
+            verify_optional!(wikitext, PageWikitext),
         )?;
 
         Ok(())
@@ -776,3 +1087,200 @@ fn check_latest_revision(page: &PageModel) {
         page.site_id,
     );
 }
+
+/// Gets the value to set `page.latest_revision_id` to after a revision is made.
+///
+/// If no revision was created, then the column is left unchanged.
+fn latest_revision_id(
+    revision_output: &Option<CreatePageRevisionOutput>,
+) -> ActiveValue<Option<i64>> {
+    match revision_output {
+        Some(output) => ActiveValue::Set(Some(output.revision_id)),
+        None => ActiveValue::NotSet,
+    }
+}
+
+/// The fields of a page revision which are merged when undoing.
+#[derive(Debug, Clone)]
+struct RevisionContents {
+    wikitext: String,
+    title: String,
+    alt_title: Option<String>,
+    tags: Vec<String>,
+}
+
+impl RevisionContents {
+    fn new(wikitext: String, revision: &PageRevisionModel) -> Self {
+        RevisionContents {
+            wikitext,
+            title: revision.title.clone(),
+            alt_title: revision.alt_title.clone(),
+            tags: revision.tags.clone(),
+        }
+    }
+}
+
+/// Merges the inverse of the changes made in `target` on top of `last`.
+///
+/// The `previous` revision is the one immediately before `target`.
+/// If the changes overlap with those made since, then the conflicts are returned.
+fn merge_undo(
+    target: RevisionContents,
+    previous: RevisionContents,
+    last: RevisionContents,
+) -> StdResult<CreatePageRevisionBody, Box<UndoPageOutput>> {
+    let wikitext = merge_wikitext(&target.wikitext, &last.wikitext, &previous.wikitext);
+    let title = merge_field(target.title, last.title, previous.title);
+    let alt_title = merge_field(target.alt_title, last.alt_title, previous.alt_title);
+    let tags = merge_tags(&target.tags, &last.tags, &previous.tags);
+
+    match (wikitext, title, alt_title) {
+        (Ok(wikitext), Ok(title), Ok(alt_title)) => Ok(CreatePageRevisionBody {
+            wikitext: ProvidedValue::Set(wikitext),
+            title: ProvidedValue::Set(title),
+            alt_title: ProvidedValue::Set(alt_title),
+            tags: ProvidedValue::Set(tags),
+            slug: ProvidedValue::Unset, // undos should never move a page
+        }),
+        (wikitext, title, alt_title) => Err(Box::new(UndoPageOutput::Conflict {
+            wikitext: wikitext.err().unwrap_or_default(),
+            title: title.err(),
+            alt_title: alt_title.err(),
+        })),
+    }
+}
+
+/// Three-way merges wikitext, line by line.
+///
+/// The changes from `base` to `target` are applied on top of `current`.
+/// When undoing a revision, `base` is the wikitext after the revision being
+/// undone, and `target` is the wikitext before it.
+fn merge_wikitext(
+    base: &str,
+    current: &str,
+    target: &str,
+) -> StdResult<String, Vec<WikitextConflict>> {
+    let base_lines = base.split('\n').collect::<Vec<_>>();
+    let current_lines = current.split('\n').collect::<Vec<_>>();
+    let target_lines = target.split('\n').collect::<Vec<_>>();
+    let hunks = merge3(&base_lines, &current_lines, &target_lines);
+    let mut lines = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for hunk in hunks {
+        match hunk {
+            MergeHunk::Resolved(resolved) => lines.extend(resolved),
+            MergeHunk::Conflict { base, ours, theirs } => {
+                conflicts.push(WikitextConflict {
+                    current: ours.into_iter().map(String::from).collect(),
+                    revision: base.into_iter().map(String::from).collect(),
+                    previous: theirs.into_iter().map(String::from).collect(),
+                });
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        Ok(lines.join("\n"))
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// Three-way merges a scalar field.
+///
+/// The change from `base` to `target` is applied on top of `current`.
+fn merge_field<T: PartialEq>(
+    base: T,
+    current: T,
+    target: T,
+) -> StdResult<T, FieldConflict<T>> {
+    if current == base || current == target {
+        Ok(target)
+    } else if target == base {
+        Ok(current)
+    } else {
+        Err(FieldConflict {
+            current,
+            revision: base,
+            previous: target,
+        })
+    }
+}
+
+/// Three-way merges tags, as set operations.
+///
+/// Tags removed from `base` in `target` are removed from `current`, and
+/// tags added in `target` are added to it. Any other tags are preserved.
+fn merge_tags(base: &[String], current: &[String], target: &[String]) -> Vec<String> {
+    let mut tags = current
+        .iter()
+        .filter(|tag| !base.contains(tag) || target.contains(tag))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for tag in target {
+        if !base.contains(tag) && !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+
+    tags
+}
+
+#[test]
+fn latest_revision_after_undo() {
+    let output = Some(CreatePageRevisionOutput {
+        revision_id: 42,
+        revision_number: 3,
+        parser_errors: None,
+    });
+
+    assert_eq!(latest_revision_id(&output), ActiveValue::Set(Some(42)));
+    assert_eq!(latest_revision_id(&None), ActiveValue::NotSet);
+}
+
+#[test]
+fn undo_merge() {
+    macro_rules! contents {
+        ($wikitext:expr, $title:expr, $tags:expr $(,)?) => {
+            RevisionContents {
+                wikitext: str!($wikitext),
+                title: str!($title),
+                alt_title: None,
+                tags: $tags.iter().map(|tag: &&str| str!(tag)).collect(),
+            }
+        };
+    }
+
+    // Undoing a revision which changed the second line,
+    // while keeping a later change to the fourth line.
+    let previous = contents!("A\nB\nC\nD", "Title", ["fruit"]);
+    let target = contents!("A\nX\nC\nD", "New Title", ["fruit", "apple"]);
+    let last = contents!("A\nX\nC\nY", "New Title", ["fruit", "apple", "red"]);
+
+    let body = merge_undo(target, previous, last).expect("Undo conflicted");
+    assert_eq!(body.wikitext, ProvidedValue::Set(str!("A\nB\nC\nY")));
+    assert_eq!(body.title, ProvidedValue::Set(str!("Title")));
+    assert_eq!(body.alt_title, ProvidedValue::Set(None));
+    assert_eq!(
+        body.tags,
+        ProvidedValue::Set(vec![str!("fruit"), str!("red")]),
+    );
+    assert_eq!(body.slug, ProvidedValue::Unset);
+
+    // Undoing a revision whose line was changed again since conflicts.
+    let previous = contents!("A\nB", "Title", [""; 0]);
+    let target = contents!("A\nX", "Title", [""; 0]);
+    let last = contents!("A\nZ", "Title", [""; 0]);
+
+    match merge_undo(target, previous, last).map_err(|conflict| *conflict) {
+        Err(UndoPageOutput::Conflict {
+            wikitext, title, ..
+        }) => {
+            assert_eq!(wikitext.len(), 1);
+            assert!(title.is_none());
+        }
+        _ => panic!("Undo did not conflict"),
+    }
+}
@@ -24,6 +24,7 @@ use crate::services::page_revision::CreatePageRevisionOutput;
 use crate::services::score::ScoreValue;
 use crate::web::PageDetails;
 use ftml::parsing::ParseError;
+use std::collections::BTreeMap;
 use time::OffsetDateTime;
 
 #[derive(Deserialize, Debug, Clone)]
@@ -117,6 +118,20 @@ pub struct EditPage<'a> {
     pub revision_comments: String,
     pub user_id: i64,
 
+    /// The ID of the revision this edit was started from.
+    ///
+    /// If the page has been edited since, then the changes are merged with
+    /// those made in the meantime, or if they overlap, the edit is rejected.
+    /// If `None`, then the edit is applied to the latest revision as-is.
+    #[serde(default)]
+    pub base_revision_id: Option<i64>,
+
+    /// The values of the page's data form fields, used by ListPages.
+    ///
+    /// If provided, these replace all of the page's existing fields.
+    #[serde(default)]
+    pub data_fields: Option<BTreeMap<String, String>>,
+
     #[serde(flatten)]
     pub body: EditPageBody,
 }
@@ -190,6 +205,48 @@ pub struct RollbackPage<'a> {
     pub user_id: i64,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct UndoPage<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub revision_number: i32,
+    pub revision_comments: String,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "result", rename_all = "kebab-case")]
+pub enum UndoPageOutput {
+    Merged {
+        revision: Option<EditPageOutput>,
+    },
+    Conflict {
+        wikitext: Vec<WikitextConflict>,
+        title: Option<FieldConflict<String>>,
+        alt_title: Option<FieldConflict<Option<String>>>,
+    },
+}
+
+/// A region of wikitext which could not be undone automatically.
+///
+/// * `current` is the lines as they are in the latest revision.
+/// * `revision` is the lines as they were after the revision being undone.
+/// * `previous` is the lines as they were before the revision being undone.
+#[derive(Serialize, Debug, Clone)]
+pub struct WikitextConflict {
+    pub current: Vec<String>,
+    pub revision: Vec<String>,
+    pub previous: Vec<String>,
+}
+
+/// A field which was changed both by the revision being undone and since.
+#[derive(Serialize, Debug, Clone)]
+pub struct FieldConflict<T> {
+    pub current: T,
+    pub revision: T,
+    pub previous: T,
+}
+
 pub type EditPageOutput = CreatePageRevisionOutput;
 
 impl From<(CreatePageRevisionOutput, i64)> for DeletePageOutput {
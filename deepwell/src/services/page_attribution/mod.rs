@@ -1,5 +1,5 @@
 /*
- * utils/debug.rs
+ * services/page_attribution/mod.rs
  *
  * DEEPWELL - Wikijump API provider and database manager
  * Copyright (C) 2019-2023 Wikijump Team
@@ -18,12 +18,14 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-//! Utilities to help with `Debug` formatting on type definitions.
-
-/// Returns the raw pointer to the given item.
-///
-/// Used in `Debug` to emit a pointer address for the given item.
-#[inline]
-pub fn debug_pointer<T>(item: &T) -> *const () {
-    item as *const T as *const ()
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
 }
+
+mod service;
+mod structs;
+
+pub use self::service::PageAttributionService;
+pub use self::structs::*;
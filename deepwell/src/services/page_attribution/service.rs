@@ -0,0 +1,154 @@
+/*
+ * services/page_attribution/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service for page attributions, which record who is responsible for a page.
+//!
+//! A page's authorship is not necessarily the same as who created it,
+//! for instance a page may be rewritten or translated by someone else, or
+//! have a maintainer. Attributions are set manually by moderators.
+
+use super::prelude::*;
+use crate::models::page::{self, Model as PageModel};
+use crate::models::page_attribution::{
+    self, Entity as PageAttribution, Model as PageAttributionModel,
+};
+use crate::services::PageService;
+
+#[derive(Debug)]
+pub struct PageAttributionService;
+
+impl PageAttributionService {
+    pub async fn create(
+        ctx: &ServiceContext<'_>,
+        CreatePageAttribution {
+            site_id,
+            page: reference,
+            user_id,
+            attribution_type,
+            attribution_date,
+            created_by,
+        }: CreatePageAttribution<'_>,
+    ) -> Result<PageAttributionModel> {
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+
+        info!(
+            "Adding {attribution_type:?} attribution for user ID {user_id} on page ID {page_id} by user ID {created_by}",
+        );
+
+        let existing = PageAttribution::find_by_id((
+            page_id,
+            user_id,
+            attribution_type,
+            attribution_date,
+        ))
+        .one(txn)
+        .await?;
+
+        if existing.is_some() {
+            error!("Page attribution already exists, cannot create");
+            return Err(Error::PageAttributionExists);
+        }
+
+        let model = page_attribution::ActiveModel {
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            attribution_type: Set(attribution_type),
+            attribution_date: Set(attribution_date),
+            ..Default::default()
+        };
+        let attribution = model.insert(txn).await?;
+        Ok(attribution)
+    }
+
+    pub async fn remove(
+        ctx: &ServiceContext<'_>,
+        RemovePageAttribution {
+            site_id,
+            page: reference,
+            user_id,
+            attribution_type,
+            attribution_date,
+            removed_by,
+        }: RemovePageAttribution<'_>,
+    ) -> Result<PageAttributionModel> {
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+
+        info!(
+            "Removing {attribution_type:?} attribution for user ID {user_id} on page ID {page_id} by user ID {removed_by}",
+        );
+
+        let attribution = find_or_error!(
+            PageAttribution::find_by_id((
+                page_id,
+                user_id,
+                attribution_type,
+                attribution_date,
+            ))
+            .one(txn),
+            PageAttribution,
+        )?;
+
+        attribution.clone().delete(txn).await?;
+        Ok(attribution)
+    }
+
+    /// Gets all attributions for a page, oldest first.
+    pub async fn get_all_for_page(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Vec<PageAttributionModel>> {
+        let txn = ctx.transaction();
+        let attributions = PageAttribution::find()
+            .filter(page_attribution::Column::PageId.eq(page_id))
+            .order_by_asc(page_attribution::Column::AttributionDate)
+            .order_by_asc(page_attribution::Column::AttributionType)
+            .order_by_asc(page_attribution::Column::UserId)
+            .all(txn)
+            .await?;
+
+        Ok(attributions)
+    }
+
+    /// Gets all attributions for a user, newest first.
+    ///
+    /// Attributions on deleted pages are not included.
+    pub async fn get_all_for_user(
+        ctx: &ServiceContext<'_>,
+        GetUserAttributions { user_id, site_id }: GetUserAttributions,
+    ) -> Result<Vec<PageAttributionModel>> {
+        let txn = ctx.transaction();
+        let attributions = PageAttribution::find()
+            .join(JoinType::InnerJoin, page_attribution::Relation::Page.def())
+            .filter(
+                Condition::all()
+                    .add(page_attribution::Column::UserId.eq(user_id))
+                    .add(page::Column::DeletedAt.is_null())
+                    .add_option(site_id.map(|site_id| page::Column::SiteId.eq(site_id))),
+            )
+            .order_by_desc(page_attribution::Column::AttributionDate)
+            .order_by_asc(page_attribution::Column::PageId)
+            .all(txn)
+            .await?;
+
+        Ok(attributions)
+    }
+}
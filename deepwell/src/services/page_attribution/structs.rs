@@ -0,0 +1,60 @@
+/*
+ * services/page_attribution/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::PageAttributionType;
+use time::Date;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreatePageAttribution<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub user_id: i64,
+    pub attribution_type: PageAttributionType,
+    pub attribution_date: Date,
+    pub created_by: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RemovePageAttribution<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub user_id: i64,
+    pub attribution_type: PageAttributionType,
+    pub attribution_date: Date,
+    pub removed_by: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageAttributions<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetUserAttributions {
+    pub user_id: i64,
+
+    /// Only return attributions for pages in this site.
+    ///
+    /// If `None`, then attributions across all sites are returned.
+    #[serde(default)]
+    pub site_id: Option<i64>,
+}
@@ -0,0 +1,367 @@
+/*
+ * services/page_lock/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Service for managing locks on pages.
+//!
+//! There are two kinds of locks:
+//! * Edit locks, which record that a user currently has the editor open for a page.
+//!   These are short-lived and refreshed by the client, and do not prevent any actions,
+//!   but are used to warn users about concurrent edits.
+//! * Protection locks, which are placed by moderators and prevent anyone without
+//!   moderator permissions from editing, moving, or deleting the page until lifted.
+//!
+//! Either kind may have an expiry, past which it is ignored. Edit locks always have one.
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_lock::{self, Entity as PageLock, Model as PageLockModel};
+use crate::models::sea_orm_active_enums::{PageLockType, PermissionType};
+use crate::services::permission::GetUserPermissions;
+use crate::services::{PageService, PermissionService};
+use sea_orm::sea_query::Expr;
+
+#[derive(Debug)]
+pub struct PageLockService;
+
+impl PageLockService {
+    /// Gets the active protection and edit locks for a page.
+    pub async fn get_all(
+        ctx: &ServiceContext<'_>,
+        GetPageLocks {
+            site_id,
+            page: reference,
+        }: GetPageLocks<'_>,
+    ) -> Result<GetPageLocksOutput> {
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+        let (protection, edit_locks) = try_join!(
+            Self::get_protection(ctx, page_id),
+            Self::get_edit_locks(ctx, page_id),
+        )?;
+
+        Ok(GetPageLocksOutput {
+            protection,
+            edit_locks,
+        })
+    }
+
+    /// Starts or refreshes an edit lock for this user on the page.
+    ///
+    /// Edit locks do not prevent other users from editing, but the
+    /// edit locks held by others are returned so they can be warned.
+    pub async fn acquire_edit_lock(
+        ctx: &ServiceContext<'_>,
+        AcquireEditLock {
+            site_id,
+            page: reference,
+            user_id,
+        }: AcquireEditLock<'_>,
+    ) -> Result<AcquireEditLockOutput> {
+        let txn = ctx.transaction();
+        let page = PageService::get(ctx, site_id, reference).await?;
+        let page_id = page.page_id;
+
+        // No point starting an edit which cannot be saved
+        Self::check_page_protection(ctx, &page, user_id).await?;
+
+        info!("Acquiring edit lock on page ID {page_id} for user ID {user_id}");
+
+        let now = now();
+        let expires_at = now + ctx.config().page_edit_lock_duration;
+
+        // Refresh the existing lock, if any.
+        //
+        // This includes locks which have expired but not yet been pruned,
+        // since there can only be one undeleted edit lock per user.
+        let existing = PageLock::find()
+            .filter(
+                Condition::all()
+                    .add(page_lock::Column::PageId.eq(page_id))
+                    .add(page_lock::Column::UserId.eq(user_id))
+                    .add(page_lock::Column::LockType.eq(PageLockType::Edit))
+                    .add(page_lock::Column::DeletedAt.is_null()),
+            )
+            .one(txn)
+            .await?;
+
+        let lock = match existing {
+            Some(lock) => {
+                let mut model = lock.into_active_model();
+                model.expires_at = Set(Some(expires_at));
+                model.updated_at = Set(Some(now));
+                model.update(txn).await?
+            }
+            None => {
+                let model = page_lock::ActiveModel {
+                    expires_at: Set(Some(expires_at)),
+                    lock_type: Set(PageLockType::Edit),
+                    page_id: Set(page_id),
+                    user_id: Set(user_id),
+                    reason: Set(String::new()),
+                    ..Default::default()
+                };
+                model.insert(txn).await?
+            }
+        };
+
+        let other_locks = Self::get_edit_locks(ctx, page_id)
+            .await?
+            .into_iter()
+            .filter(|lock| lock.user_id != user_id)
+            .collect();
+
+        Ok(AcquireEditLockOutput { lock, other_locks })
+    }
+
+    /// Releases this user's edit lock on the page, if they have one.
+    ///
+    /// Edit locks are only meaningful while they are held,
+    /// so they are deleted outright rather than being soft-deleted.
+    ///
+    /// # Returns
+    /// Whether an edit lock was released.
+    pub async fn release_edit_lock(
+        ctx: &ServiceContext<'_>,
+        ReleaseEditLock {
+            site_id,
+            page: reference,
+            user_id,
+        }: ReleaseEditLock<'_>,
+    ) -> Result<bool> {
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+
+        debug!("Releasing edit lock on page ID {page_id} for user ID {user_id}");
+
+        let DeleteResult { rows_affected } = PageLock::delete_many()
+            .filter(
+                Condition::all()
+                    .add(page_lock::Column::PageId.eq(page_id))
+                    .add(page_lock::Column::UserId.eq(user_id))
+                    .add(page_lock::Column::LockType.eq(PageLockType::Edit)),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(rows_affected > 0)
+    }
+
+    /// Gets all unexpired edit locks on the page.
+    pub async fn get_edit_locks(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Vec<PageLockModel>> {
+        let txn = ctx.transaction();
+        let locks = PageLock::find()
+            .filter(
+                Condition::all()
+                    .add(page_lock::Column::PageId.eq(page_id))
+                    .add(page_lock::Column::LockType.eq(PageLockType::Edit))
+                    .add(page_lock::Column::DeletedAt.is_null())
+                    .add(page_lock::Column::ExpiresAt.gt(now())),
+            )
+            .order_by_asc(page_lock::Column::CreatedAt)
+            .all(txn)
+            .await?;
+
+        Ok(locks)
+    }
+
+    /// Protects a page, so that only moderators may edit, move, or delete it.
+    ///
+    /// If the page is already protected, then that protection is replaced.
+    pub async fn protect(
+        ctx: &ServiceContext<'_>,
+        ProtectPage {
+            site_id,
+            page: reference,
+            user_id,
+            reason,
+            expires_at,
+        }: ProtectPage<'_>,
+    ) -> Result<PageLockModel> {
+        let txn = ctx.transaction();
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+
+        info!(
+            "Protecting page ID {page_id} by user ID {user_id} (expires {expires_at:?})"
+        );
+
+        Self::remove_protection(ctx, page_id).await?;
+
+        let model = page_lock::ActiveModel {
+            expires_at: Set(expires_at),
+            lock_type: Set(PageLockType::Protect),
+            page_id: Set(page_id),
+            user_id: Set(user_id),
+            reason: Set(reason),
+            ..Default::default()
+        };
+        let lock = model.insert(txn).await?;
+        Ok(lock)
+    }
+
+    /// Lifts the protection on a page.
+    pub async fn unprotect(
+        ctx: &ServiceContext<'_>,
+        UnprotectPage {
+            site_id,
+            page: reference,
+            user_id,
+        }: UnprotectPage<'_>,
+    ) -> Result<()> {
+        let PageModel { page_id, .. } = PageService::get(ctx, site_id, reference).await?;
+
+        info!("Unprotecting page ID {page_id} by user ID {user_id}");
+
+        if Self::get_protection(ctx, page_id).await?.is_none() {
+            return Err(Error::PageLockNotFound);
+        }
+
+        Self::remove_protection(ctx, page_id).await
+    }
+
+    /// Soft-deletes any protection on the page, including expired ones.
+    async fn remove_protection(ctx: &ServiceContext<'_>, page_id: i64) -> Result<()> {
+        let txn = ctx.transaction();
+        PageLock::update_many()
+            .col_expr(page_lock::Column::DeletedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(page_lock::Column::PageId.eq(page_id))
+                    .add(page_lock::Column::LockType.eq(PageLockType::Protect))
+                    .add(page_lock::Column::DeletedAt.is_null()),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets the unexpired protection on the page, if any.
+    pub async fn get_protection(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<Option<PageLockModel>> {
+        let txn = ctx.transaction();
+        let lock = PageLock::find()
+            .filter(
+                Condition::all()
+                    .add(page_lock::Column::PageId.eq(page_id))
+                    .add(page_lock::Column::LockType.eq(PageLockType::Protect))
+                    .add(page_lock::Column::DeletedAt.is_null())
+                    .add(
+                        Condition::any()
+                            .add(page_lock::Column::ExpiresAt.is_null())
+                            .add(page_lock::Column::ExpiresAt.gt(now())),
+                    ),
+            )
+            .one(txn)
+            .await?;
+
+        Ok(lock)
+    }
+
+    /// Ensures the page is not protected against this user, returning an error if so.
+    ///
+    /// Users with moderator permissions for the page are not affected by protection.
+    pub async fn check_protection(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+        user_id: i64,
+    ) -> Result<()> {
+        let page = PageService::get(ctx, site_id, reference).await?;
+        Self::check_page_protection(ctx, &page, user_id).await
+    }
+
+    async fn check_page_protection(
+        ctx: &ServiceContext<'_>,
+        page: &PageModel,
+        user_id: i64,
+    ) -> Result<()> {
+        if Self::get_protection(ctx, page.page_id).await?.is_none() {
+            return Ok(());
+        }
+
+        let user_permissions = PermissionService::get_user_permissions(
+            ctx,
+            GetUserPermissions {
+                site_id: page.site_id,
+                user_id: Some(user_id),
+            },
+        )
+        .await?;
+
+        let moderator = PermissionService::has_permission(
+            ctx,
+            page.site_id,
+            Some(page.page_category_id),
+            user_permissions,
+            PermissionType::Moderate,
+        )
+        .await?;
+
+        if moderator {
+            Ok(())
+        } else {
+            warn!(
+                "User ID {user_id} cannot modify page ID {}, it is protected",
+                page.page_id,
+            );
+
+            Err(Error::PageProtected)
+        }
+    }
+
+    /// Removes all expired page locks.
+    ///
+    /// Expired edit locks are deleted, while expired protections
+    /// are soft-deleted so there is a record of them.
+    pub async fn prune(ctx: &ServiceContext<'_>) -> Result<()> {
+        let txn = ctx.transaction();
+        let now = now();
+
+        let DeleteResult {
+            rows_affected: edit_locks,
+        } = PageLock::delete_many()
+            .filter(
+                Condition::all()
+                    .add(page_lock::Column::LockType.eq(PageLockType::Edit))
+                    .add(page_lock::Column::ExpiresAt.lte(now)),
+            )
+            .exec(txn)
+            .await?;
+
+        let protections = PageLock::update_many()
+            .col_expr(page_lock::Column::DeletedAt, Expr::value(now))
+            .filter(
+                Condition::all()
+                    .add(page_lock::Column::LockType.eq(PageLockType::Protect))
+                    .add(page_lock::Column::DeletedAt.is_null())
+                    .add(page_lock::Column::ExpiresAt.lte(now)),
+            )
+            .exec(txn)
+            .await?
+            .rows_affected;
+
+        debug!("Pruned {edit_locks} expired edit locks and {protections} expired protections");
+        Ok(())
+    }
+}
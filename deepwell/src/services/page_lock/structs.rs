@@ -0,0 +1,80 @@
+/*
+ * services/page_lock/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page_lock::Model as PageLockModel;
+use time::OffsetDateTime;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageLocks<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GetPageLocksOutput {
+    pub protection: Option<PageLockModel>,
+    pub edit_locks: Vec<PageLockModel>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AcquireEditLock<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub user_id: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AcquireEditLockOutput {
+    pub lock: PageLockModel,
+
+    /// Edit locks held by other users on this page.
+    ///
+    /// If this is not empty, then the user should be warned that
+    /// someone else is editing the page at the same time.
+    pub other_locks: Vec<PageLockModel>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReleaseEditLock<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub user_id: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProtectPage<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub user_id: i64,
+    pub reason: String,
+
+    /// When this protection is lifted.
+    ///
+    /// If `None`, then the page is protected until it is manually unprotected.
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UnprotectPage<'a> {
+    pub site_id: i64,
+    pub page: Reference<'a>,
+    pub user_id: i64,
+}
@@ -26,6 +26,7 @@ mod prelude {
 
 mod service;
 mod structs;
+mod variables;
 
 pub use self::service::PageQueryService;
 pub use self::structs::*;
@@ -18,17 +18,24 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-#![allow(dead_code, unused_variables)] // TEMP
-
 use super::prelude::*;
+use super::variables::fill_variables;
 use crate::models::page::{self, Entity as Page};
 use crate::models::page_category::{self, Entity as PageCategory};
 use crate::models::page_connection::{self, Entity as PageConnection};
+use crate::models::page_data_field::{self, Entity as PageDataField};
 use crate::models::page_parent::{self, Entity as PageParent};
-use crate::models::{page_revision, text};
-use crate::services::{PageService, ParentService};
-use sea_query::{Expr, Query};
-use std::convert::Infallible;
+use crate::models::page_revision::{self, Entity as PageRevision};
+use crate::models::page_vote::{self, Entity as PageVote};
+use crate::models::text;
+use crate::models::user::{self, Entity as User};
+use crate::services::score::ScoreValue;
+use crate::services::{PageService, ParentService, SiteService};
+use crate::utils::get_regular_slug;
+use sea_orm::query::Order;
+use sea_query::extension::postgres::PgBinOper;
+use sea_query::{func::Func, Alias, Expr, Query, SimpleExpr};
+use time::{Date, Duration, Month, OffsetDateTime, Time};
 
 #[derive(Debug)]
 pub struct PageQueryService;
@@ -68,12 +75,15 @@ impl PageQueryService {
             pagination,
             variables,
         }: PageQuery<'_>,
-    ) -> Result<Infallible> {
+    ) -> Result<PageQueryOutput> {
         info!("Building ListPages query from specification");
 
         let txn = ctx.transaction();
         let mut condition = Condition::all();
 
+        // Deleted pages are never listed.
+        condition = condition.add(page::Column::DeletedAt.is_null());
+
         // Site ID
         //
         // The site to query from. If not specified, then this is the current site.
@@ -189,95 +199,109 @@ impl PageQueryService {
         }
 
         let page_parent_condition = match page_parent {
+            // No constraints on parent pages.
+            None => {
+                debug!("Selecting pages regardless of parents");
+                None
+            }
+
             // Pages with no parents.
             // This means that there should be no rows in `page_parent`
             // where they are the child page.
-            PageParentSelector::NoParent => {
+            Some(PageParentSelector::NoParent) => {
                 debug!("Selecting pages with no parents");
 
-                page::Column::PageId.not_in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .to_owned(),
+                Some(
+                    page::Column::PageId.not_in_subquery(
+                        Query::select()
+                            .column(page_parent::Column::ChildPageId)
+                            .from(PageParent)
+                            .to_owned(),
+                    ),
                 )
             }
 
             // Pages which are siblings of the current page,
             // i.e., they share parents in common with the current page.
-            PageParentSelector::SameParents => {
+            Some(PageParentSelector::SameParents) => {
                 debug!("Selecting pages are siblings under the given parents");
 
-                page::Column::PageId.in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .and_where(
-                            page_parent::Column::ParentPageId.is_in(get_parents!()),
-                        )
-                        .to_owned(),
+                Some(
+                    page::Column::PageId.in_subquery(
+                        Query::select()
+                            .column(page_parent::Column::ChildPageId)
+                            .from(PageParent)
+                            .and_where(
+                                page_parent::Column::ParentPageId.is_in(get_parents!()),
+                            )
+                            .to_owned(),
+                    ),
                 )
             }
 
             // Pages which are not siblings of the current page,
             // i.e., they do not share any parents with the current page.
-            PageParentSelector::DifferentParents => {
+            Some(PageParentSelector::DifferentParents) => {
                 debug!("Selecting pages which are not siblings under the given parents",);
 
-                let parents = ParentService::get_parents(
-                    ctx,
-                    current_site_id,
-                    Reference::Id(current_page_id),
-                )
-                .await?
-                .into_iter()
-                .map(|parent| parent.parent_page_id);
-
-                page::Column::PageId.in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .and_where(
-                            page_parent::Column::ParentPageId.is_not_in(get_parents!()),
-                        )
-                        .to_owned(),
+                Some(
+                    page::Column::PageId.in_subquery(
+                        Query::select()
+                            .column(page_parent::Column::ChildPageId)
+                            .from(PageParent)
+                            .and_where(
+                                page_parent::Column::ParentPageId
+                                    .is_not_in(get_parents!()),
+                            )
+                            .to_owned(),
+                    ),
                 )
             }
 
             // Pages which are children of the current page.
-            PageParentSelector::ChildOf => {
+            Some(PageParentSelector::ChildOf) => {
                 debug!("Selecting pages which are children of the current page",);
 
-                page::Column::PageId.in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .and_where(page_parent::Column::ParentPageId.eq(current_page_id))
-                        .to_owned(),
+                Some(
+                    page::Column::PageId.in_subquery(
+                        Query::select()
+                            .column(page_parent::Column::ChildPageId)
+                            .from(PageParent)
+                            .and_where(
+                                page_parent::Column::ParentPageId.eq(current_page_id),
+                            )
+                            .to_owned(),
+                    ),
                 )
             }
 
             // Pages with any of the specified parents.
             // TODO: Possibly allow either *any* or *all* of specified parents
             //       rather than only any, in the future.
-            PageParentSelector::HasParents(parents) => {
+            Some(PageParentSelector::HasParents(parents)) => {
                 debug!("Selecting on pages which have one of the given as parents",);
 
-                let parent_ids = PageService::get_pages(ctx, queried_site_id, parents)
+                let parent_ids = PageService::get_pages(ctx, queried_site_id, &parents)
                     .await?
                     .into_iter()
                     .map(|page| page.page_id);
 
-                page::Column::PageId.in_subquery(
-                    Query::select()
-                        .column(page_parent::Column::ChildPageId)
-                        .from(PageParent)
-                        .and_where(page_parent::Column::ParentPageId.is_in(parent_ids))
-                        .to_owned(),
+                Some(
+                    page::Column::PageId.in_subquery(
+                        Query::select()
+                            .column(page_parent::Column::ChildPageId)
+                            .from(PageParent)
+                            .and_where(
+                                page_parent::Column::ParentPageId.is_in(parent_ids),
+                            )
+                            .to_owned(),
+                    ),
                 )
             }
         };
-        condition = condition.add(page_parent_condition);
+        if let Some(page_parent_condition) = page_parent_condition {
+            condition = condition.add(page_parent_condition);
+        }
 
         // Slug
         if let Some(slug) = slug {
@@ -286,55 +310,196 @@ impl PageQueryService {
             condition = condition.add(page::Column::Slug.eq(slug));
         }
 
+        // Name
+        //
+        // The page's slug without its category, and may contain '%' wildcards.
+        if let Some(name) = name {
+            let name = name.as_ref();
+            debug!("Filtering based on page name {name}");
+            condition = condition.add(Expr::expr(page_name_expr()).like(name));
+        }
+
         // Contains-link
         //
         // Selects pages that have an outgoing link (`from_page_id`)
         // to a specified page (`to_page_id`).
-        condition = condition.add(
-            page::Column::PageId.in_subquery(
-                Query::select()
-                    .column(page_connection::Column::FromPageId)
-                    .from(PageConnection)
-                    .and_where({
-                        let incoming_ids = PageService::get_pages(
-                            ctx,
-                            queried_site_id,
-                            contains_outgoing_links,
+        if !contains_outgoing_links.is_empty() {
+            debug!("Filtering based on outgoing links");
+
+            let incoming_ids =
+                PageService::get_pages(ctx, queried_site_id, &contains_outgoing_links)
+                    .await?
+                    .into_iter()
+                    .map(|page| page.page_id);
+
+            condition = condition.add(
+                page::Column::PageId.in_subquery(
+                    Query::select()
+                        .column(page_connection::Column::FromPageId)
+                        .from(PageConnection)
+                        .and_where(page_connection::Column::ToPageId.is_in(incoming_ids))
+                        .to_owned(),
+                ),
+            );
+        }
+
+        // Range
+        //
+        // "Before" and "after" depend on the final ordering,
+        // so they are applied once the query has been executed.
+        match range {
+            Some(RangeSelector::Current) => {
+                debug!("Selecting only the current page");
+                condition = condition.add(page::Column::PageId.eq(current_page_id));
+            }
+            Some(RangeSelector::Others) => {
+                debug!("Selecting all pages except the current page");
+                condition = condition.add(page::Column::PageId.ne(current_page_id));
+            }
+            Some(RangeSelector::Before | RangeSelector::After) | None => (),
+        }
+
+        // Author
+        //
+        // Selects pages whose first revision was made by one of the given users.
+        if !author.is_empty() {
+            debug!("Filtering based on page author");
+
+            let author_slugs = author.iter().map(|name| get_regular_slug(name.as_ref()));
+            condition = condition.add(
+                page::Column::PageId.in_subquery(
+                    Query::select()
+                        .column((PageRevision, page_revision::Column::PageId))
+                        .from(PageRevision)
+                        .inner_join(
+                            User,
+                            Expr::col((PageRevision, page_revision::Column::UserId))
+                                .equals((User, user::Column::UserId)),
                         )
-                        .await?
-                        .into_iter()
-                        .map(|page| page.page_id);
+                        .and_where(page_revision::Column::RevisionNumber.eq(0))
+                        .and_where(user::Column::Slug.is_in(author_slugs))
+                        .to_owned(),
+                ),
+            );
+        }
 
-                        page_connection::Column::ToPageId.is_in(incoming_ids)
-                    })
-                    .to_owned(),
-            ),
-        );
+        // Creation and update dates
+        if let Some(selector) = creation_date {
+            debug!("Filtering based on creation date");
+            let column = Expr::col((Page, page::Column::CreatedAt)).into();
+            condition = condition.add(date_condition(column, selector));
+        }
 
-        // Tag filtering
-        // TODO requires joining with most recent revision
+        if let Some(selector) = update_date {
+            debug!("Filtering based on last update date");
+
+            // Pages which have never been updated count as updated at creation.
+            let column = SimpleExpr::FunctionCall(Func::coalesce([
+                Expr::col((Page, page::Column::UpdatedAt)).into(),
+                Expr::col((Page, page::Column::CreatedAt)).into(),
+            ]));
+            condition = condition.add(date_condition(column, selector));
+        }
+
+        // Votes and scores
+        //
+        // Up/down votes are compared against their sum,
+        // and five-star ratings against their mean.
+        for ScoreSelector { score, comparison } in votes {
+            debug!("Filtering based on vote sum {comparison:?} {score:?}");
+            let rating = vote_aggregate_expr(Func::sum(vote_value_expr()).into());
+            condition =
+                condition.add(compare(rating, comparison, score_value_expr(score)));
+        }
+
+        for ScoreSelector { score, comparison } in score {
+            debug!("Filtering based on rating {comparison:?} {score:?}");
+            let rating = vote_aggregate_expr(Func::avg(vote_value_expr()).into());
+            condition =
+                condition.add(compare(rating, comparison, score_value_expr(score)));
+        }
+
+        // Data form fields
+        for DataFormSelector { field, value } in data_form_fields {
+            debug!("Filtering based on data form field '{field}'");
+
+            condition = condition.add(
+                page::Column::PageId.in_subquery(
+                    Query::select()
+                        .column(page_data_field::Column::PageId)
+                        .from(PageDataField)
+                        .and_where(page_data_field::Column::FieldName.eq(field.as_ref()))
+                        .and_where(page_data_field::Column::FieldValue.eq(value.as_ref()))
+                        .to_owned(),
+                ),
+            );
+        }
 
         // Build the final query
         let mut query = Page::find().filter(condition);
 
-        // Add necessary joins
-        macro_rules! join_revision {
-            () => {
-                query = query.join(JoinType::Join, page::Relation::PageRevision.def());
-            };
+        // Tag filtering
+        //
+        // Tags are stored on each revision, so this requires
+        // joining with the most recent revision of the page.
+        let tag_filtering =
+            !any_tags.is_empty() || !all_tags.is_empty() || !no_tags.is_empty();
+
+        let revision_joined = tag_filtering
+            || matches!(
+                order,
+                Some(OrderBySelector {
+                    property: OrderProperty::Title
+                        | OrderProperty::AltTitle
+                        | OrderProperty::Size
+                        | OrderProperty::Revisions,
+                    ..
+                }),
+            );
+
+        if revision_joined {
+            // ON page_revision.revision_id = page.latest_revision_id
+            let latest_revision = Page::belongs_to(PageRevision)
+                .from(page::Column::LatestRevisionId)
+                .to(page_revision::Column::RevisionId)
+                .into();
+
+            query = query.join(JoinType::Join, latest_revision);
         }
-        macro_rules! join_text {
-            () => {
-                query = query.join(JoinType::Join, page_revision::Relation::Text1.def());
-            };
+
+        if tag_filtering {
+            macro_rules! tags {
+                ($list:expr) => {
+                    Expr::val($list.iter().map(|tag| str!(tag)).collect::<Vec<String>>())
+                };
+            }
+
+            let tags_column = || Expr::col((PageRevision, page_revision::Column::Tags));
+
+            if !any_tags.is_empty() {
+                debug!("Selecting pages with any of the tags {any_tags:?}");
+                query = query
+                    .filter(tags_column().binary(PgBinOper::Overlap, tags!(any_tags)));
+            }
+
+            if !all_tags.is_empty() {
+                debug!("Selecting pages with all of the tags {all_tags:?}");
+                query = query
+                    .filter(tags_column().binary(PgBinOper::Contains, tags!(all_tags)));
+            }
+
+            if !no_tags.is_empty() {
+                debug!("Selecting pages with none of the tags {no_tags:?}");
+                query = query.filter(
+                    tags_column()
+                        .binary(PgBinOper::Overlap, tags!(no_tags))
+                        .not(),
+                );
+            }
         }
-        // TODO other joins
 
-        // Add on at the query-level (ORDER BY, LIMIT)
+        // Add on at the query-level (ORDER BY)
         {
-            use sea_orm::query::Order;
-            use sea_query::{func::Func, SimpleExpr};
-
             let OrderBySelector {
                 property,
                 ascending,
@@ -347,31 +512,52 @@ impl PageQueryService {
 
             let order = if ascending { Order::Asc } else { Order::Desc };
 
-            // TODO: implement missing properties. these require subqueries or joins or something
             match property {
                 OrderProperty::PageSlug => {
-                    // idk how to do this, we need to strip off the category part somehow
-                    // PgExpr::matches?
-                    error!("Ordering by page slug (no category), not yet implemented",);
-                    todo!() // TODO
+                    debug!("Ordering by page slug (no category)");
+                    query = query.order_by(page_name_expr(), order);
                 }
                 OrderProperty::FullSlug => {
                     debug!("Ordering by page slug (with category");
                     query = query.order_by(page::Column::Slug, order);
                 }
                 OrderProperty::Title => {
-                    error!("Ordering by title, not yet implemented");
-                    join_revision!();
+                    debug!("Ordering by title");
                     query = query.order_by(page_revision::Column::Title, order);
                 }
                 OrderProperty::AltTitle => {
-                    error!("Ordering by alt title, not yet implemented");
-                    join_revision!();
+                    debug!("Ordering by alt title");
                     query = query.order_by(page_revision::Column::AltTitle, order);
                 }
                 OrderProperty::CreatedBy => {
-                    error!("Ordering by author, not yet implemented");
-                    todo!() // TODO
+                    debug!("Ordering by author");
+
+                    // SELECT name FROM "user"
+                    // WHERE user_id = (
+                    //     SELECT user_id FROM page_revision
+                    //     WHERE page_id = page.page_id
+                    //     AND revision_number = 0
+                    // )
+                    let creator_id = Query::select()
+                        .column((PageRevision, page_revision::Column::UserId))
+                        .from(PageRevision)
+                        .and_where(
+                            Expr::col((PageRevision, page_revision::Column::PageId))
+                                .equals((Page, page::Column::PageId)),
+                        )
+                        .and_where(page_revision::Column::RevisionNumber.eq(0))
+                        .to_owned();
+
+                    let creator_name = Query::select()
+                        .column((User, user::Column::Name))
+                        .from(User)
+                        .and_where(
+                            Expr::col((User, user::Column::UserId))
+                                .eq(subquery_expr(creator_id)),
+                        )
+                        .to_owned();
+
+                    query = query.order_by(subquery_expr(creator_name), order);
                 }
                 OrderProperty::CreatedAt => {
                     debug!("Ordering by page creation timestamp");
@@ -382,62 +568,539 @@ impl PageQueryService {
                     query = query.order_by(page::Column::UpdatedAt, order);
                 }
                 OrderProperty::Size => {
-                    error!("Ordering by page size, not yet implemented");
-                    join_revision!();
-                    join_text!();
+                    debug!("Ordering by page size");
+                    query =
+                        query.join(JoinType::Join, page_revision::Relation::Text1.def());
                     let col = Expr::col(text::Column::Contents);
                     let expr = SimpleExpr::FunctionCall(Func::char_length(col));
                     query = query.order_by(expr, order);
                 }
                 OrderProperty::Score => {
-                    error!("Ordering by score, not yet implemented");
-                    todo!() // TODO
+                    debug!("Ordering by score");
+                    let expr = vote_aggregate_expr(Func::sum(vote_value_expr()).into());
+                    query = query.order_by(expr, order);
                 }
                 OrderProperty::Votes => {
-                    error!("Ordering by vote count, not yet implemented");
-                    todo!() // TODO
+                    debug!("Ordering by vote count");
+                    let expr = vote_aggregate_expr(Func::count(vote_value_expr()).into());
+                    query = query.order_by(expr, order);
                 }
                 OrderProperty::Revisions => {
-                    error!("Ordering by revision count, not yet implemented");
-                    todo!() // TODO
+                    debug!("Ordering by revision count");
+                    query = query.order_by(page_revision::Column::RevisionNumber, order);
                 }
                 OrderProperty::Comments => {
-                    error!("Ordering by comment count, not yet implemented");
-                    todo!() // TODO
+                    // There is no comment system yet, so all pages have zero comments.
+                    warn!("Ordering by comment count, which is not yet supported");
                 }
                 OrderProperty::Random => {
                     debug!("Ordering by random value");
                     let expr = SimpleExpr::FunctionCall(Func::random());
                     query = query.order_by(expr, order);
                 }
-                OrderProperty::DataFormFieldName => {
-                    error!("Ordering by data form field, not yet implemented");
-                    todo!() // TODO
+                OrderProperty::DataFormFieldName(field) => {
+                    debug!("Ordering by data form field '{field}'");
+
+                    let field_value = Query::select()
+                        .column((PageDataField, page_data_field::Column::FieldValue))
+                        .from(PageDataField)
+                        .and_where(
+                            Expr::col((PageDataField, page_data_field::Column::PageId))
+                                .equals((Page, page::Column::PageId)),
+                        )
+                        .and_where(page_data_field::Column::FieldName.eq(field.as_ref()))
+                        .to_owned();
+
+                    query = query.order_by(subquery_expr(field_value), order);
                 }
             };
+
+            // Ensure the ordering is stable for otherwise-equal pages
+            query = query.order_by_asc(page::Column::PageId);
         }
 
-        if let Some(limit) = pagination.limit {
-            debug!("Limiting ListPages to a maximum of {limit} pages total");
-            query = query.limit(limit);
+        // Execute it!
+        let query = query.select_only().column(page::Column::PageId);
+        let PageSelection {
+            entries,
+            total,
+            page_number,
+            page_count,
+        } = match range {
+            // Ranges are relative to the current page's position in the results,
+            // so every matching page must be fetched to find it.
+            Some(_) => {
+                let page_ids: Vec<i64> = query.into_tuple().all(txn).await?;
+                debug!("ListPages query matched {} pages", page_ids.len());
+                select_pages(page_ids, current_page_id, range, offset, pagination)
+            }
+
+            // Otherwise, only the requested page of results is fetched.
+            None => {
+                let matched = query.clone().count(txn).await?;
+                debug!("ListPages query matched {matched} pages");
+
+                let window = page_window(matched, offset, pagination);
+                let page_ids: Vec<i64> = if window.take > 0 {
+                    query
+                        .offset(window.skip)
+                        .limit(window.take)
+                        .into_tuple()
+                        .all(txn)
+                        .await?
+                } else {
+                    vec![]
+                };
+
+                window.select(page_ids, pagination.reversed)
+            }
+        };
+
+        // Fill in the requested variables for each page
+        let site = SiteService::get(ctx, Reference::Id(queried_site_id)).await?;
+        let limit = pagination.limit;
+        let pages =
+            fill_variables(ctx, &site, &entries, &variables, total, limit).await?;
+
+        Ok(PageQueryOutput {
+            pages,
+            total,
+            page_number,
+            page_count,
+        })
+    }
+}
+
+/// The set of pages to be returned from a ListPages query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PageSelection {
+    /// The pages on this page of results, paired with their index (starting from 1).
+    entries: Vec<(u64, i64)>,
+    total: u64,
+    page_number: u64,
+    page_count: u64,
+}
+
+/// The portion of the matching pages which are on the requested page of results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PageWindow {
+    /// How many matching pages come before this page of results.
+    skip: u64,
+
+    /// How many pages are on this page of results.
+    take: u64,
+
+    /// The index of the first page on this page of results, relative to the offset.
+    first_index: u64,
+
+    total: u64,
+    page_number: u64,
+    page_count: u64,
+}
+
+impl PageWindow {
+    /// Builds the selection from the pages fetched for this window.
+    fn select(self, page_ids: Vec<i64>, reversed: bool) -> PageSelection {
+        let mut entries = page_ids
+            .into_iter()
+            .enumerate()
+            .map(|(index, page_id)| (self.first_index + index as u64, page_id))
+            .collect::<Vec<_>>();
+
+        if reversed {
+            entries.reverse();
         }
 
-        // TODO pagination
-        //      the "reverse" field means that, for each page, it is reversed.
-        //
-        //      this does not affect the overall ORDER BY
-        //      for instance, imagine we are selecting from the positive integers
-        //      if the pagination limit is 5 and the order is ascending, but reverse = true,
-        //      then this means we get pages like:
-        //
-        //      1. [ 4,  3,  2,  1,  0]
-        //      2. [ 9,  8,  7,  6,  5]
-        //      3. [14, 13, 12, 11, 10]
+        PageSelection {
+            entries,
+            total: self.total,
+            page_number: self.page_number,
+            page_count: self.page_count,
+        }
+    }
+}
 
-        // Execute it!
-        let result = query.all(txn).await?;
+/// Determines which of the matching pages to return.
+///
+/// This applies the offset and limit to the number of pages
+/// which matched, and then finds the requested page of results.
+fn page_window(matched: u64, offset: u32, pagination: PaginationSelector) -> PageWindow {
+    let offset = u64::from(offset);
+    let limit = pagination.limit.unwrap_or(u64::MAX);
+    let total = matched.saturating_sub(offset).min(limit);
+    let per_page = u64::from(pagination.per_page.max(1));
+    let page_count = total.div_ceil(per_page);
+    let page_number = pagination.page_number.max(1);
+    let start = (page_number - 1).saturating_mul(per_page);
+    let take = total.saturating_sub(start).min(per_page);
+
+    PageWindow {
+        skip: offset.saturating_add(start),
+        take,
+        first_index: start.saturating_add(1),
+        total,
+        page_number,
+        page_count,
+    }
+}
+
+/// Selects the pages to return from the ordered list of matching pages.
+///
+/// This applies the `before` and `after` ranges, then the offset and limit,
+/// and then returns the requested page of results.
+///
+/// If the results are `reversed`, then each page of results is reversed,
+/// but this does not affect the overall ordering. For instance, when
+/// selecting from the non-negative integers in ascending order with
+/// five per page, the pages are:
+///
+/// 1. `[ 4,  3,  2,  1,  0]`
+/// 2. `[ 9,  8,  7,  6,  5]`
+/// 3. `[14, 13, 12, 11, 10]`
+fn select_pages(
+    mut page_ids: Vec<i64>,
+    current_page_id: i64,
+    range: Option<RangeSelector>,
+    offset: u32,
+    pagination: PaginationSelector,
+) -> PageSelection {
+    let current_position = page_ids.iter().position(|&id| id == current_page_id);
+
+    match (range, current_position) {
+        (Some(RangeSelector::Before), Some(position)) => page_ids.truncate(position),
+        (Some(RangeSelector::After), Some(position)) => {
+            page_ids.drain(..=position);
+        }
+        (Some(RangeSelector::Before | RangeSelector::After), None) => page_ids.clear(),
+        _ => (),
+    }
+
+    let window = page_window(page_ids.len() as u64, offset, pagination);
+    let page_ids = page_ids
+        .into_iter()
+        .skip(usize::try_from(window.skip).unwrap_or(usize::MAX))
+        .take(usize::try_from(window.take).unwrap_or(usize::MAX))
+        .collect();
+
+    window.select(page_ids, pagination.reversed)
+}
+
+/// Gets the start (inclusive) and end (exclusive) of the span of time
+/// containing `timestamp` at the given resolution.
+///
+/// For instance, a timestamp on 2023-04-10 with a resolution of `Month`
+/// gives the span from 2023-04-01 to 2023-05-01.
+fn date_span(
+    timestamp: OffsetDateTime,
+    resolution: DateTimeResolution,
+) -> (OffsetDateTime, OffsetDateTime) {
+    let date = timestamp.date();
+    let time = timestamp.time();
+
+    macro_rules! at {
+        ($date:expr, $time:expr $(,)?) => {
+            timestamp.replace_date($date).replace_time($time)
+        };
+    }
+
+    match resolution {
+        DateTimeResolution::Second => {
+            let start = at!(
+                date,
+                Time::from_hms(time.hour(), time.minute(), time.second()).unwrap()
+            );
+            (start, start + Duration::SECOND)
+        }
+        DateTimeResolution::Minute => {
+            let start = at!(date, Time::from_hms(time.hour(), time.minute(), 0).unwrap());
+            (start, start + Duration::MINUTE)
+        }
+        DateTimeResolution::Hour => {
+            let start = at!(date, Time::from_hms(time.hour(), 0, 0).unwrap());
+            (start, start + Duration::HOUR)
+        }
+        DateTimeResolution::Day => {
+            let start = at!(date, Time::MIDNIGHT);
+            (start, start + Duration::DAY)
+        }
+        DateTimeResolution::Month => {
+            let month = date.month();
+            let start = Date::from_calendar_date(date.year(), month, 1).unwrap();
+            let end = match month {
+                Month::December => {
+                    Date::from_calendar_date(date.year() + 1, Month::January, 1)
+                }
+                _ => Date::from_calendar_date(date.year(), month.next(), 1),
+            }
+            .unwrap();
+
+            (at!(start, Time::MIDNIGHT), at!(end, Time::MIDNIGHT))
+        }
+        DateTimeResolution::Year => {
+            let start = Date::from_calendar_date(date.year(), Month::January, 1).unwrap();
+            let end =
+                Date::from_calendar_date(date.year() + 1, Month::January, 1).unwrap();
+            (at!(start, Time::MIDNIGHT), at!(end, Time::MIDNIGHT))
+        }
+    }
+}
+
+/// Builds a condition comparing a timestamp column against a date selector.
+fn date_condition(column: SimpleExpr, selector: DateSelector) -> SimpleExpr {
+    match selector {
+        DateSelector::FromPresent { start } => Expr::expr(column).gte(start),
+        DateSelector::Span {
+            timestamp,
+            resolution,
+            comparison,
+        } => {
+            let (start, end) = date_span(timestamp, resolution);
+            let column = || Expr::expr(column.clone());
+
+            match comparison {
+                ComparisonOperation::Equal => column().gte(start).and(column().lt(end)),
+                ComparisonOperation::NotEqual => column().lt(start).or(column().gte(end)),
+                ComparisonOperation::GreaterThan => column().gte(end),
+                ComparisonOperation::GreaterOrEqualThan => column().gte(start),
+                ComparisonOperation::LessThan => column().lt(start),
+                ComparisonOperation::LessOrEqualThan => column().lt(end),
+            }
+        }
+    }
+}
 
-        // TODO implement query construction
-        todo!()
+/// Builds a condition comparing two expressions.
+fn compare(
+    lhs: SimpleExpr,
+    comparison: ComparisonOperation,
+    rhs: SimpleExpr,
+) -> SimpleExpr {
+    let lhs = Expr::expr(lhs);
+
+    match comparison {
+        ComparisonOperation::GreaterThan => lhs.gt(rhs),
+        ComparisonOperation::LessThan => lhs.lt(rhs),
+        ComparisonOperation::GreaterOrEqualThan => lhs.gte(rhs),
+        ComparisonOperation::LessOrEqualThan => lhs.lte(rhs),
+        ComparisonOperation::Equal => lhs.eq(rhs),
+        ComparisonOperation::NotEqual => lhs.ne(rhs),
+    }
+}
+
+fn score_value_expr(score: ScoreValue) -> SimpleExpr {
+    match score {
+        ScoreValue::Integer(value) => Expr::val(value).into(),
+        ScoreValue::Float(value) => Expr::val(value).into(),
     }
 }
+
+/// The page's slug with its category removed.
+///
+/// In SQL: `regexp_replace(page.slug, '^.*:', '')`
+fn page_name_expr() -> SimpleExpr {
+    SimpleExpr::FunctionCall(
+        Func::cust(Alias::new("regexp_replace"))
+            .arg(Expr::col((Page, page::Column::Slug)))
+            .arg("^.*:")
+            .arg(""),
+    )
+}
+
+fn vote_value_expr() -> Expr {
+    Expr::col((PageVote, page_vote::Column::Value))
+}
+
+/// Aggregates the active votes of the page being selected.
+///
+/// In SQL:
+///
+/// ```sql
+/// SELECT COALESCE($aggregate, 0) FROM page_vote
+/// WHERE page_id = page.page_id
+/// AND deleted_at IS NULL
+/// AND disabled_at IS NULL
+/// ```
+fn vote_aggregate_expr(aggregate: SimpleExpr) -> SimpleExpr {
+    let query = Query::select()
+        .expr(Func::coalesce([aggregate, Expr::val(0).into()]))
+        .from(PageVote)
+        .and_where(
+            Expr::col((PageVote, page_vote::Column::PageId))
+                .equals((Page, page::Column::PageId)),
+        )
+        .and_where(page_vote::Column::DeletedAt.is_null())
+        .and_where(page_vote::Column::DisabledAt.is_null())
+        .to_owned();
+
+    subquery_expr(query)
+}
+
+#[inline]
+fn subquery_expr(query: sea_query::SelectStatement) -> SimpleExpr {
+    SimpleExpr::SubQuery(None, Box::new(query.into_sub_query_statement()))
+}
+
+#[test]
+fn date_spans() {
+    use time::macros::datetime;
+
+    macro_rules! check {
+        ($timestamp:expr, $resolution:ident, $start:expr, $end:expr $(,)?) => {
+            assert_eq!(
+                date_span($timestamp, DateTimeResolution::$resolution),
+                ($start, $end),
+                "Actual date span doesn't match expected",
+            );
+        };
+    }
+
+    let timestamp = datetime!(2023-12-10 14:35:12.5 UTC);
+
+    check!(
+        timestamp,
+        Second,
+        datetime!(2023-12-10 14:35:12 UTC),
+        datetime!(2023-12-10 14:35:13 UTC),
+    );
+    check!(
+        timestamp,
+        Minute,
+        datetime!(2023-12-10 14:35:00 UTC),
+        datetime!(2023-12-10 14:36:00 UTC),
+    );
+    check!(
+        timestamp,
+        Hour,
+        datetime!(2023-12-10 14:00:00 UTC),
+        datetime!(2023-12-10 15:00:00 UTC),
+    );
+    check!(
+        timestamp,
+        Day,
+        datetime!(2023-12-10 00:00:00 UTC),
+        datetime!(2023-12-11 00:00:00 UTC),
+    );
+    check!(
+        timestamp,
+        Month,
+        datetime!(2023-12-01 00:00:00 UTC),
+        datetime!(2024-01-01 00:00:00 UTC),
+    );
+    check!(
+        timestamp,
+        Year,
+        datetime!(2023-01-01 00:00:00 UTC),
+        datetime!(2024-01-01 00:00:00 UTC),
+    );
+    check!(
+        datetime!(2024-02-29 23:59:59 +02:00),
+        Month,
+        datetime!(2024-02-01 00:00:00 +02:00),
+        datetime!(2024-03-01 00:00:00 +02:00),
+    );
+}
+
+#[test]
+fn pagination() {
+    macro_rules! check {
+        ($range:expr, $offset:expr, $limit:expr, $per_page:expr, $page_number:expr, $reversed:expr, $expected_ids:expr, $total:expr, $page_count:expr $(,)?) => {{
+            let pagination = PaginationSelector {
+                limit: $limit,
+                per_page: $per_page,
+                reversed: $reversed,
+                page_number: $page_number,
+            };
+
+            let selection =
+                select_pages((1..=12).collect(), 5, $range, $offset, pagination);
+            let actual_ids = selection
+                .entries
+                .iter()
+                .map(|&(_, page_id)| page_id)
+                .collect::<Vec<i64>>();
+
+            let expected_ids: &[i64] = &$expected_ids;
+            assert_eq!(
+                actual_ids, expected_ids,
+                "Actual page IDs don't match expected"
+            );
+            assert_eq!(
+                selection.total, $total,
+                "Actual total doesn't match expected"
+            );
+            assert_eq!(
+                selection.page_count, $page_count,
+                "Actual page count doesn't match expected"
+            );
+        }};
+    }
+
+    check!(None, 0, None, 5, 1, false, [1, 2, 3, 4, 5], 12, 3);
+    check!(None, 0, None, 5, 3, false, [11, 12], 12, 3);
+    check!(None, 0, None, 5, 4, false, [], 12, 3);
+    check!(None, 0, None, 5, 2, true, [10, 9, 8, 7, 6], 12, 3);
+    check!(None, 2, Some(6), 4, 2, false, [7, 8], 6, 2);
+    check!(
+        Some(RangeSelector::Before),
+        0,
+        None,
+        20,
+        1,
+        false,
+        [1, 2, 3, 4],
+        4,
+        1
+    );
+    check!(
+        Some(RangeSelector::After),
+        0,
+        Some(3),
+        20,
+        1,
+        false,
+        [6, 7, 8],
+        3,
+        1
+    );
+    check!(None, 20, None, 20, 1, false, [], 0, 0);
+}
+
+#[test]
+fn page_windows() {
+    macro_rules! check {
+        ($matched:expr, $offset:expr, $limit:expr, $per_page:expr, $page_number:expr, $skip:expr, $take:expr, $first_index:expr, $total:expr $(,)?) => {{
+            let pagination = PaginationSelector {
+                limit: $limit,
+                per_page: $per_page,
+                reversed: false,
+                page_number: $page_number,
+            };
+
+            let window = page_window($matched, $offset, pagination);
+            assert_eq!(window.skip, $skip, "Actual skip doesn't match expected");
+            assert_eq!(window.take, $take, "Actual take doesn't match expected");
+            assert_eq!(
+                window.first_index, $first_index,
+                "Actual first index doesn't match expected",
+            );
+            assert_eq!(window.total, $total, "Actual total doesn't match expected");
+        }};
+    }
+
+    check!(12, 0, None, 5, 1, 0, 5, 1, 12);
+    check!(12, 0, None, 5, 3, 10, 2, 11, 12);
+    check!(12, 0, None, 5, 4, 15, 0, 16, 12);
+    check!(12, 2, Some(6), 4, 2, 6, 2, 5, 6);
+    check!(12, 20, None, 20, 1, 20, 0, 1, 0);
+    check!(
+        u64::MAX,
+        u32::MAX,
+        None,
+        20,
+        u64::MAX,
+        u64::MAX,
+        0,
+        u64::MAX,
+        u64::MAX - u64::from(u32::MAX)
+    );
+}
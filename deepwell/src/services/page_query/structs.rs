@@ -18,43 +18,44 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-#![allow(dead_code)] // TEMP
-
 use super::prelude::*;
-use crate::models::{
-    page::Model as PageModel, page_parent::Model as PageParentModel,
-    page_revision::Model as PageRevisionModel,
-};
 use crate::services::score::ScoreValue;
+use serde_json::Value as JsonValue;
 use std::borrow::Cow;
 use time::OffsetDateTime;
 
 /// What kinds of pages (hidden or not) to select from.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
 pub enum PageTypeSelector {
     All,
+    #[default]
     Normal,
     Hidden,
 }
 
-pub type CategoryList<'a> = &'a [Cow<'a, str>];
-pub type TagList<'a> = &'a [Cow<'a, str>];
+pub type CategoryList<'a> = Vec<Cow<'a, str>>;
+pub type TagList<'a> = Vec<Cow<'a, str>>;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
 pub enum IncludedCategories<'a> {
+    #[default]
     All,
     List(CategoryList<'a>),
 }
 
 /// Which categories to select from.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
 pub struct CategoriesSelector<'a> {
     pub included_categories: IncludedCategories<'a>,
     pub excluded_categories: CategoryList<'a>,
 }
 
 /// What tag conditions to maintain during the search.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
 pub struct TagCondition<'a> {
     /// Represents an OR operator for the tags; page may contain any of these tags.
     pub any_present: TagList<'a>,
@@ -67,7 +68,8 @@ pub struct TagCondition<'a> {
 }
 
 /// The relationship of the pages being queried to their parent/child pages.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
 pub enum PageParentSelector<'a> {
     /// Pages which have no parent page.
     NoParent,
@@ -82,10 +84,11 @@ pub enum PageParentSelector<'a> {
     ChildOf,
 
     /// Pages which have specified parent pages.
-    HasParents(&'a [Reference<'a>]),
+    HasParents(Vec<Reference<'a>>),
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
 pub enum ComparisonOperation {
     GreaterThan,
     LessThan,
@@ -95,7 +98,8 @@ pub enum ComparisonOperation {
     NotEqual,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
 pub enum DateTimeResolution {
     Second,
     Minute,
@@ -105,7 +109,8 @@ pub enum DateTimeResolution {
     Year,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
 pub enum DateSelector {
     /// A time span represented by a timestamp, the "resolution" of the time, and a comparison operator.
     Span {
@@ -118,14 +123,15 @@ pub enum DateSelector {
     FromPresent { start: OffsetDateTime },
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
 pub struct ScoreSelector {
     pub score: ScoreValue,
     pub comparison: ComparisonOperation,
 }
 
 /// Range of pages to display, relative to the current page.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
 pub enum RangeSelector {
     /// Display only the current page.
     Current,
@@ -141,14 +147,15 @@ pub enum RangeSelector {
 }
 
 /// Selects all pages that have a data form with matching field-value pairs.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct DataFormSelector<'a> {
     pub field: Cow<'a, str>,
     pub value: Cow<'a, str>,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum OrderProperty {
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderProperty<'a> {
     PageSlug,
     FullSlug,
     Title,
@@ -162,16 +169,16 @@ pub enum OrderProperty {
     Revisions,
     Comments,
     Random,
-    DataFormFieldName,
+    DataFormFieldName(Cow<'a, str>),
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub struct OrderBySelector {
-    pub property: OrderProperty,
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OrderBySelector<'a> {
+    pub property: OrderProperty<'a>,
     pub ascending: bool,
 }
 
-impl Default for OrderBySelector {
+impl Default for OrderBySelector<'_> {
     fn default() -> Self {
         OrderBySelector {
             property: OrderProperty::CreatedAt,
@@ -180,11 +187,15 @@ impl Default for OrderBySelector {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(default)]
 pub struct PaginationSelector {
     pub limit: Option<u64>,
     pub per_page: u8,
     pub reversed: bool,
+
+    /// Which page of results to return, starting from 1.
+    pub page_number: u64,
 }
 
 impl Default for PaginationSelector {
@@ -193,17 +204,20 @@ impl Default for PaginationSelector {
             limit: None,
             per_page: 20,
             reversed: false,
+            page_number: 1,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
 pub enum PageQueryVariables<'a> {
     CreatedAt,
     CreatedBy,
     CreatedBySlug,
     CreatedById,
     CreatedByLinked,
+    Attributions,
     UpdatedAt,
     UpdatedBy,
     UpdatedBySlug,
@@ -257,40 +271,90 @@ pub enum PageQueryVariables<'a> {
     SiteDomain,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct PageQuery<'a> {
     pub current_page_id: i64,
     pub current_site_id: i64,
+
+    #[serde(default)]
     pub queried_site_id: Option<i64>,
+
+    #[serde(default)]
     pub page_type: PageTypeSelector,
+
+    #[serde(default)]
     pub categories: CategoriesSelector<'a>,
+
+    #[serde(default)]
     pub tags: TagCondition<'a>,
-    pub page_parent: PageParentSelector<'a>,
-    pub contains_outgoing_links: &'a [Reference<'a>],
-    pub creation_date: DateSelector,
-    pub update_date: DateSelector,
-    pub author: &'a [Cow<'a, str>],
-    pub score: &'a [ScoreSelector], // 5-star rating selector
-    pub votes: &'a [ScoreSelector], // upvote/downvote rating selector
+
+    #[serde(default)]
+    pub page_parent: Option<PageParentSelector<'a>>,
+
+    #[serde(default)]
+    pub contains_outgoing_links: Vec<Reference<'a>>,
+
+    #[serde(default)]
+    pub creation_date: Option<DateSelector>,
+
+    #[serde(default)]
+    pub update_date: Option<DateSelector>,
+
+    #[serde(default)]
+    pub author: Vec<Cow<'a, str>>,
+
+    #[serde(default)]
+    pub score: Vec<ScoreSelector>, // 5-star rating selector
+
+    #[serde(default)]
+    pub votes: Vec<ScoreSelector>, // upvote/downvote rating selector
+
+    #[serde(default)]
     pub offset: u32,
-    pub range: RangeSelector,
+
+    #[serde(default)]
+    pub range: Option<RangeSelector>,
+
+    #[serde(default)]
     pub name: Option<Cow<'a, str>>,
+
+    #[serde(default)]
     pub slug: Option<Cow<'a, str>>,
-    pub data_form_fields: &'a [DataFormSelector<'a>],
-    pub order: Option<OrderBySelector>,
+
+    #[serde(default)]
+    pub data_form_fields: Vec<DataFormSelector<'a>>,
+
+    #[serde(default)]
+    pub order: Option<OrderBySelector<'a>>,
+
+    #[serde(default)]
     pub pagination: PaginationSelector,
-    pub variables: &'a [PageQueryVariables<'a>],
+
+    #[serde(default)]
+    pub variables: Vec<PageQueryVariables<'a>>,
 }
 
-#[derive(Serialize, Debug, PartialEq, Clone)]
-pub struct PageQueryOutput<'a>(&'a [PageResult]);
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PageQueryOutput {
+    /// The pages in this page of results, in display order.
+    pub pages: Vec<PageResult>,
+
+    /// The total number of pages matched by the query, after `offset` and `limit`.
+    pub total: u64,
+
+    /// Which page of results this is, starting from 1.
+    pub page_number: u64,
 
-#[derive(Serialize, Debug, PartialEq, Clone)]
+    /// How many pages of results there are in total.
+    pub page_count: u64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct PageResult {
-    metadata: PageModel,
-    last_revision: PageRevisionModel,
-    // last_comment: TODO,
-    page_parents: Vec<PageParentModel>,
-    wikitext: String,
-    score: f32,
+    pub page_id: i64,
+
+    /// The values of each of the requested `PageQueryVariables`.
+    ///
+    /// These are in the same order as the `variables` field in the query.
+    pub variables: Vec<JsonValue>,
 }
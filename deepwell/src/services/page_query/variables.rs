@@ -0,0 +1,559 @@
+/*
+ * services/page_query/variables.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Filling in the values of ListPages variables for each page in the results.
+//!
+//! Data which is expensive to retrieve (for instance the wikitext or votes)
+//! is only fetched if a variable needs it.
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::page_data_field::{self, Entity as PageDataField};
+use crate::models::page_parent::{self, Entity as PageParent};
+use crate::models::page_revision::Model as PageRevisionModel;
+use crate::models::site::Model as SiteModel;
+use crate::models::user::Model as UserModel;
+use crate::services::score::{ScoreValue, VoteMap};
+use crate::services::{
+    DomainService, PageAttributionService, PageRevisionService, PageService,
+    ParentService, ScoreService, TextService, UserService,
+};
+use crate::utils::split_category_name;
+use serde_json::{json, Value as JsonValue};
+
+/// The number of characters used in `%%preview%%` if no length is given.
+const DEFAULT_PREVIEW_LENGTH: usize = 200;
+
+pub async fn fill_variables(
+    ctx: &ServiceContext<'_>,
+    site: &SiteModel,
+    entries: &[(u64, i64)],
+    variables: &[PageQueryVariables<'_>],
+    total: u64,
+    limit: Option<u64>,
+) -> Result<Vec<PageResult>> {
+    let mut results = Vec::with_capacity(entries.len());
+
+    for &(index, page_id) in entries {
+        let page = PageService::get_direct(ctx, page_id, false).await?;
+        let revision =
+            PageRevisionService::get_latest(ctx, page.site_id, page.page_id).await?;
+
+        let mut data = PageData {
+            ctx,
+            page,
+            revision,
+            wikitext: None,
+            creator: None,
+            updater: None,
+            parent: None,
+            fields: None,
+            votes: None,
+        };
+
+        let mut values = Vec::with_capacity(variables.len());
+        for variable in variables {
+            let value = match variable {
+                // Page creation
+                PageQueryVariables::CreatedAt => json!(data.page.created_at),
+                PageQueryVariables::CreatedBy => json!(data.creator().await?.name),
+                PageQueryVariables::CreatedBySlug => json!(data.creator().await?.slug),
+                PageQueryVariables::CreatedById => json!(data.creator().await?.user_id),
+                PageQueryVariables::CreatedByLinked => {
+                    json!(user_link(data.creator().await?))
+                }
+
+                // Page attributions
+                PageQueryVariables::Attributions => {
+                    let attributions =
+                        PageAttributionService::get_all_for_page(ctx, data.page.page_id)
+                            .await?;
+
+                    let mut values = Vec::with_capacity(attributions.len());
+                    for attribution in attributions {
+                        let user =
+                            UserService::get(ctx, Reference::Id(attribution.user_id))
+                                .await?;
+
+                        values.push(json!({
+                            "type": attribution.attribution_type,
+                            "date": attribution.attribution_date,
+                            "user_id": user.user_id,
+                            "user_name": user.name,
+                            "user_slug": user.slug,
+                        }));
+                    }
+
+                    json!(values)
+                }
+
+                // Last page update
+                PageQueryVariables::UpdatedAt => {
+                    json!(data.page.updated_at.unwrap_or(data.page.created_at))
+                }
+                PageQueryVariables::UpdatedBy => json!(data.updater().await?.name),
+                PageQueryVariables::UpdatedBySlug => json!(data.updater().await?.slug),
+                PageQueryVariables::UpdatedById => json!(data.updater().await?.user_id),
+                PageQueryVariables::UpdatedByLinked => {
+                    json!(user_link(data.updater().await?))
+                }
+
+                // Comments are not yet implemented, so pages have none
+                PageQueryVariables::CommentedAt
+                | PageQueryVariables::CommentedBy
+                | PageQueryVariables::CommentedBySlug
+                | PageQueryVariables::CommentedById
+                | PageQueryVariables::CommentedByLinked => JsonValue::Null,
+                PageQueryVariables::Comments => json!(0),
+
+                // Page metadata
+                PageQueryVariables::PageSlug => {
+                    json!(split_category_name(&data.page.slug).1)
+                }
+                PageQueryVariables::Category => {
+                    json!(split_category_name(&data.page.slug).0)
+                }
+                PageQueryVariables::FullSlug => json!(data.page.slug),
+                PageQueryVariables::Title => json!(data.revision.title),
+                PageQueryVariables::TitleLinked => {
+                    json!(page_link(&data.page.slug, &data.revision.title))
+                }
+                PageQueryVariables::Link => json!(format!("/{}", data.page.slug)),
+
+                // Parent page
+                PageQueryVariables::ParentNamed => match data.parent().await? {
+                    Some((page, _)) => json!(split_category_name(&page.slug).1),
+                    None => JsonValue::Null,
+                },
+                PageQueryVariables::ParentCategory => match data.parent().await? {
+                    Some((page, _)) => json!(split_category_name(&page.slug).0),
+                    None => JsonValue::Null,
+                },
+                PageQueryVariables::ParentSlug => match data.parent().await? {
+                    Some((page, _)) => json!(page.slug),
+                    None => JsonValue::Null,
+                },
+                PageQueryVariables::ParentTitle => match data.parent().await? {
+                    Some((_, revision)) => json!(revision.title),
+                    None => JsonValue::Null,
+                },
+                PageQueryVariables::ParentTitleLinked => match data.parent().await? {
+                    Some((page, revision)) => {
+                        json!(page_link(&page.slug, &revision.title))
+                    }
+                    None => JsonValue::Null,
+                },
+
+                // Page contents
+                PageQueryVariables::Content => json!(data.wikitext().await?),
+                PageQueryVariables::ContentN(n) => {
+                    let sections = split_sections(data.wikitext().await?);
+                    let section = usize::try_from(*n)
+                        .ok()
+                        .and_then(|n| n.checked_sub(1))
+                        .and_then(|n| sections.get(n));
+
+                    match section {
+                        Some(section) => json!(section),
+                        None => JsonValue::Null,
+                    }
+                }
+                PageQueryVariables::Preview => {
+                    json!(preview(data.wikitext().await?, DEFAULT_PREVIEW_LENGTH))
+                }
+                PageQueryVariables::PreviewN(n) => {
+                    let length = usize::try_from(*n).unwrap_or(usize::MAX);
+                    json!(preview(data.wikitext().await?, length))
+                }
+                PageQueryVariables::Summary => {
+                    let paragraph = first_paragraph(data.wikitext().await?);
+                    json!(preview(paragraph, DEFAULT_PREVIEW_LENGTH))
+                }
+                PageQueryVariables::FirstParagraph => {
+                    json!(first_paragraph(data.wikitext().await?))
+                }
+                PageQueryVariables::Size => {
+                    json!(data.wikitext().await?.chars().count())
+                }
+
+                // Tags
+                PageQueryVariables::Tags => json!(tags(&data.revision, false).join(" ")),
+                PageQueryVariables::TagsLinked => {
+                    json!(tags_linked(&data.revision, false, "/system:page-tags/tag/"))
+                }
+                PageQueryVariables::TagsLinkedURL(url) => {
+                    json!(tags_linked(&data.revision, false, url))
+                }
+                PageQueryVariables::HiddenTags => {
+                    json!(tags(&data.revision, true).join(" "))
+                }
+                PageQueryVariables::HiddenTagsLinked => {
+                    json!(tags_linked(&data.revision, true, "/system:page-tags/tag/"))
+                }
+                PageQueryVariables::HiddenTagsLinkedURL(url) => {
+                    json!(tags_linked(&data.revision, true, url))
+                }
+
+                // Data form fields
+                PageQueryVariables::FormData(name)
+                | PageQueryVariables::FormRaw(name) => match data.field(name).await? {
+                    Some(value) => json!(value),
+                    None => JsonValue::Null,
+                },
+                PageQueryVariables::FormLabel(name) => json!(name),
+                PageQueryVariables::FormHint(_) => JsonValue::Null,
+
+                // Related pages
+                PageQueryVariables::Children => {
+                    let txn = ctx.transaction();
+                    let count = PageParent::find()
+                        .filter(page_parent::Column::ParentPageId.eq(data.page.page_id))
+                        .count(txn)
+                        .await?;
+
+                    json!(count)
+                }
+
+                // Votes
+                PageQueryVariables::Score => {
+                    let score = ScoreService::score(ctx, data.page.page_id).await?;
+                    json!(score)
+                }
+                PageQueryVariables::ScoreVotes => json!(data.votes().await?.count()),
+                PageQueryVariables::ScorePercent => {
+                    let votes = data.votes().await?;
+                    let upvotes = votes.get(1) as f64;
+                    let total = votes.count() as f64;
+
+                    if total > 0.0 {
+                        json!(ScoreValue::Float(upvotes / total * 100.0))
+                    } else {
+                        json!(ScoreValue::Integer(0))
+                    }
+                }
+
+                // Revisions, starting from 1
+                PageQueryVariables::Revisions => json!(data.revision.revision_number + 1),
+
+                // Query results
+                PageQueryVariables::Index => json!(index),
+                PageQueryVariables::Total => json!(total),
+                PageQueryVariables::Limit => json!(limit),
+                PageQueryVariables::TotalOrLimit => match limit {
+                    Some(limit) => json!(total.min(limit)),
+                    None => json!(total),
+                },
+
+                // Site
+                PageQueryVariables::SiteTitle => json!(site.name),
+                PageQueryVariables::SiteName => json!(site.slug),
+                PageQueryVariables::SiteDomain => {
+                    json!(DomainService::domain_for_site(ctx.config(), site))
+                }
+            };
+
+            values.push(value);
+        }
+
+        results.push(PageResult {
+            page_id: data.page.page_id,
+            variables: values,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Holds the data for a page used to fill in variables.
+///
+/// Fields which are `None` have not been fetched yet.
+struct PageData<'a, 'c> {
+    ctx: &'a ServiceContext<'c>,
+    page: PageModel,
+    revision: PageRevisionModel,
+    wikitext: Option<String>,
+    creator: Option<UserModel>,
+    updater: Option<UserModel>,
+    parent: Option<Option<(PageModel, PageRevisionModel)>>,
+    fields: Option<Vec<(String, String)>>,
+    votes: Option<VoteMap>,
+}
+
+impl PageData<'_, '_> {
+    async fn wikitext(&mut self) -> Result<&str> {
+        if self.wikitext.is_none() {
+            let wikitext =
+                TextService::get(self.ctx, &self.revision.wikitext_hash).await?;
+            self.wikitext = Some(wikitext);
+        }
+
+        Ok(self.wikitext.as_ref().unwrap())
+    }
+
+    async fn creator(&mut self) -> Result<&UserModel> {
+        if self.creator.is_none() {
+            let first_revision = PageRevisionService::get(
+                self.ctx,
+                self.page.site_id,
+                self.page.page_id,
+                0,
+            )
+            .await?;
+
+            let user =
+                UserService::get(self.ctx, Reference::Id(first_revision.user_id)).await?;
+            self.creator = Some(user);
+        }
+
+        Ok(self.creator.as_ref().unwrap())
+    }
+
+    async fn updater(&mut self) -> Result<&UserModel> {
+        if self.updater.is_none() {
+            let user =
+                UserService::get(self.ctx, Reference::Id(self.revision.user_id)).await?;
+            self.updater = Some(user);
+        }
+
+        Ok(self.updater.as_ref().unwrap())
+    }
+
+    /// Gets the first parent of this page, if any.
+    async fn parent(&mut self) -> Result<Option<&(PageModel, PageRevisionModel)>> {
+        if self.parent.is_none() {
+            let parents = ParentService::get_parents(
+                self.ctx,
+                self.page.site_id,
+                Reference::Id(self.page.page_id),
+            )
+            .await?;
+
+            let parent = match parents.first() {
+                None => None,
+                Some(parent) => {
+                    let page =
+                        PageService::get_direct(self.ctx, parent.parent_page_id, false)
+                            .await?;
+                    let revision = PageRevisionService::get_latest(
+                        self.ctx,
+                        page.site_id,
+                        page.page_id,
+                    )
+                    .await?;
+
+                    Some((page, revision))
+                }
+            };
+
+            self.parent = Some(parent);
+        }
+
+        Ok(self.parent.as_ref().unwrap().as_ref())
+    }
+
+    async fn field(&mut self, name: &str) -> Result<Option<&str>> {
+        if self.fields.is_none() {
+            let txn = self.ctx.transaction();
+            let fields = PageDataField::find()
+                .filter(page_data_field::Column::PageId.eq(self.page.page_id))
+                .all(txn)
+                .await?
+                .into_iter()
+                .map(|field| (field.field_name, field.field_value))
+                .collect();
+
+            self.fields = Some(fields);
+        }
+
+        let value = self
+            .fields
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, field_value)| field_value.as_str());
+
+        Ok(value)
+    }
+
+    async fn votes(&mut self) -> Result<&VoteMap> {
+        if self.votes.is_none() {
+            let txn = self.ctx.transaction();
+            let condition = ScoreService::build_condition(self.page.page_id);
+            let votes = ScoreService::collect_votes(txn, condition).await?;
+            self.votes = Some(votes);
+        }
+
+        Ok(self.votes.as_ref().unwrap())
+    }
+}
+
+fn user_link(user: &UserModel) -> String {
+    format!("[[*user {}]]", user.name)
+}
+
+fn page_link(slug: &str, title: &str) -> String {
+    format!("[[[{slug}|{title}]]]")
+}
+
+/// Gets the tags on this revision.
+///
+/// Hidden tags are those which begin with an underscore.
+fn tags(revision: &PageRevisionModel, hidden: bool) -> Vec<&str> {
+    revision
+        .tags
+        .iter()
+        .map(String::as_str)
+        .filter(|tag| tag.starts_with('_') == hidden)
+        .collect()
+}
+
+fn tags_linked(revision: &PageRevisionModel, hidden: bool, url: &str) -> String {
+    tags(revision, hidden)
+        .into_iter()
+        .map(|tag| format!("[{url}{tag} {tag}]"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits wikitext into the sections used by `%%content{n}%%`.
+///
+/// Sections are separated by lines consisting only of four or more `=`.
+fn split_sections(wikitext: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+
+    for line in wikitext.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed.len() >= 4 && trimmed.bytes().all(|b| b == b'=') {
+            sections.push(wikitext[start..offset].trim());
+            start = offset + line.len();
+        }
+
+        offset += line.len();
+    }
+
+    sections.push(wikitext[start..].trim());
+    sections
+}
+
+/// Gets the first paragraph of the wikitext, that is,
+/// everything up until the first blank line.
+fn first_paragraph(wikitext: &str) -> &str {
+    let wikitext = wikitext.trim_start();
+    let mut end = 0;
+
+    for line in wikitext.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            break;
+        }
+
+        end += line.len();
+    }
+
+    wikitext[..end].trim_end()
+}
+
+/// Produces a preview of the given text, with whitespace collapsed.
+///
+/// If the text is longer than `length` characters, it is cut off
+/// and an ellipsis is added.
+fn preview(text: &str, length: usize) -> String {
+    let mut preview = String::new();
+    let mut chars = 0;
+
+    for word in text.split_whitespace() {
+        if !preview.is_empty() {
+            preview.push(' ');
+            chars += 1;
+        }
+
+        for ch in word.chars() {
+            if chars >= length {
+                preview.push_str("...");
+                return preview;
+            }
+
+            preview.push(ch);
+            chars += 1;
+        }
+    }
+
+    preview
+}
+
+#[test]
+fn sections() {
+    macro_rules! check {
+        ($wikitext:expr, $expected:expr $(,)?) => {{
+            let expected: &[&str] = &$expected;
+            assert_eq!(
+                split_sections($wikitext),
+                expected,
+                "Actual sections don't match expected",
+            );
+        }};
+    }
+
+    check!("", [""]);
+    check!("apple\nbanana", ["apple\nbanana"]);
+    check!("apple\n====\nbanana", ["apple", "banana"]);
+    check!("apple\n=======\nbanana\n====\n", ["apple", "banana", ""]);
+    check!("apple\n===\nbanana", ["apple\n===\nbanana"]);
+    check!("apple ====\nbanana", ["apple ====\nbanana"]);
+}
+
+#[test]
+fn paragraphs() {
+    macro_rules! check {
+        ($wikitext:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                first_paragraph($wikitext),
+                $expected,
+                "Actual first paragraph doesn't match expected",
+            );
+        };
+    }
+
+    check!("", "");
+    check!("apple", "apple");
+    check!("apple\nbanana\n\ncherry", "apple\nbanana");
+    check!("\n\n  apple\n   \ncherry", "apple");
+}
+
+#[test]
+fn previews() {
+    macro_rules! check {
+        ($text:expr, $length:expr, $expected:expr $(,)?) => {
+            assert_eq!(
+                preview($text, $length),
+                $expected,
+                "Actual preview doesn't match expected",
+            );
+        };
+    }
+
+    check!("", 10, "");
+    check!("apple banana", 20, "apple banana");
+    check!("apple\n\n  banana\tcherry", 20, "apple banana cherry");
+    check!("apple banana cherry", 8, "apple ba...");
+    check!("apple banana", 12, "apple banana");
+}
@@ -0,0 +1,403 @@
+/*
+ * services/page_revision/diff.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Helpers to build a human-readable diff between two page revisions.
+
+use super::prelude::*;
+use crate::utils::{diff, DiffEdit};
+
+/// Compares two revisions of a page.
+///
+/// The revisions must already have their hidden fields stripped,
+/// and fields which are hidden in either revision are not compared.
+pub fn diff_revisions(
+    from: &PageRevisionModelFiltered,
+    to: &PageRevisionModelFiltered,
+) -> PageRevisionDiffOutput {
+    fn changed<T: PartialEq + Clone>(from: &T, to: &T) -> Option<FieldChange<T>> {
+        if from == to {
+            None
+        } else {
+            Some(FieldChange {
+                from: from.clone(),
+                to: to.clone(),
+            })
+        }
+    }
+
+    let wikitext = match (&from.wikitext, &to.wikitext) {
+        (Some(from), Some(to)) if from == to => Some(vec![]),
+        (Some(from), Some(to)) => Some(diff_wikitext(from, to)),
+        _ => None,
+    };
+
+    let title = match (&from.title, &to.title) {
+        (Some(from), Some(to)) => changed(from, to),
+        _ => None,
+    };
+
+    // A missing alt title could be hidden or just unset, so check explicitly
+    let alt_title_hidden = |revision: &PageRevisionModelFiltered| {
+        revision.hidden.iter().any(|field| field == "alt_title")
+    };
+
+    let alt_title = if alt_title_hidden(from) || alt_title_hidden(to) {
+        None
+    } else {
+        changed(&from.alt_title, &to.alt_title)
+    };
+
+    let slug = match (&from.slug, &to.slug) {
+        (Some(from), Some(to)) => changed(from, to),
+        _ => None,
+    };
+
+    let tags = match (&from.tags, &to.tags) {
+        (Some(from), Some(to)) => Some(diff_tags(from, to)),
+        _ => None,
+    }
+    .filter(|tags| !tags.added.is_empty() || !tags.removed.is_empty());
+
+    PageRevisionDiffOutput {
+        from_revision_id: from.revision_id,
+        to_revision_id: to.revision_id,
+        wikitext,
+        title,
+        alt_title,
+        slug,
+        tags,
+    }
+}
+
+/// Produces a line-level diff of two wikitext sources.
+///
+/// Where lines are removed and others added in their place, they are paired up
+/// and given a word-level diff instead, so small edits to a line are readable.
+pub fn diff_wikitext(from: &str, to: &str) -> Vec<LineDiff> {
+    let from_lines: Vec<&str> = from.lines().collect();
+    let to_lines: Vec<&str> = to.lines().collect();
+
+    let mut output = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    for edit in diff(&from_lines, &to_lines) {
+        match edit {
+            DiffEdit::Removed(i) => removed.push(i),
+            DiffEdit::Added(j) => added.push(j),
+            DiffEdit::Equal(i, j) => {
+                flush_changes(
+                    &from_lines,
+                    &to_lines,
+                    &mut removed,
+                    &mut added,
+                    &mut output,
+                );
+                output.push(LineDiff::Equal {
+                    from_line: i,
+                    to_line: j,
+                    text: str!(from_lines[i]),
+                });
+            }
+        }
+    }
+
+    flush_changes(
+        &from_lines,
+        &to_lines,
+        &mut removed,
+        &mut added,
+        &mut output,
+    );
+    output
+}
+
+/// Emits a changed region of lines, pairing removed and added lines as modifications.
+fn flush_changes(
+    from_lines: &[&str],
+    to_lines: &[&str],
+    removed: &mut Vec<usize>,
+    added: &mut Vec<usize>,
+    output: &mut Vec<LineDiff>,
+) {
+    let paired = removed.len().min(added.len());
+
+    for (&i, &j) in removed.iter().zip(added.iter()) {
+        output.push(LineDiff::Modified {
+            from_line: i,
+            to_line: j,
+            words: diff_words(from_lines[i], to_lines[j]),
+        });
+    }
+
+    for &i in &removed[paired..] {
+        output.push(LineDiff::Removed {
+            from_line: i,
+            text: str!(from_lines[i]),
+        });
+    }
+
+    for &j in &added[paired..] {
+        output.push(LineDiff::Added {
+            to_line: j,
+            text: str!(to_lines[j]),
+        });
+    }
+
+    removed.clear();
+    added.clear();
+}
+
+/// Produces a word-level diff of two lines.
+///
+/// Adjacent runs of the same kind are merged together.
+pub fn diff_words(from: &str, to: &str) -> Vec<WordDiff> {
+    let from_words = split_words(from);
+    let to_words = split_words(to);
+
+    let mut output: Vec<WordDiff> = Vec::new();
+    for edit in diff(&from_words, &to_words) {
+        let (kind, text) = match edit {
+            DiffEdit::Equal(i, _) => (DiffKind::Equal, from_words[i]),
+            DiffEdit::Removed(i) => (DiffKind::Removed, from_words[i]),
+            DiffEdit::Added(j) => (DiffKind::Added, to_words[j]),
+        };
+
+        match output.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(text),
+            _ => output.push(WordDiff {
+                kind,
+                text: str!(text),
+            }),
+        }
+    }
+
+    output
+}
+
+/// Splits a line into words, runs of whitespace, and individual punctuation.
+///
+/// Together the pieces make up the whole line, so no text is lost.
+fn split_words(line: &str) -> Vec<&str> {
+    #[derive(PartialEq, Eq, Copy, Clone)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+
+    fn classify(c: char) -> Class {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    }
+
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+
+    for (index, c) in line.char_indices() {
+        let class = classify(c);
+
+        // Punctuation is always split character-by-character
+        if index > start && (previous != Some(class) || class == Class::Other) {
+            words.push(&line[start..index]);
+            start = index;
+        }
+
+        previous = Some(class);
+    }
+
+    if start < line.len() {
+        words.push(&line[start..]);
+    }
+
+    words
+}
+
+/// Finds which tags were added and removed between two revisions.
+pub fn diff_tags(from: &[String], to: &[String]) -> TagChanges {
+    let added = to
+        .iter()
+        .filter(|tag| !from.contains(tag))
+        .cloned()
+        .collect();
+    let removed = from
+        .iter()
+        .filter(|tag| !to.contains(tag))
+        .cloned()
+        .collect();
+    TagChanges { added, removed }
+}
+
+#[test]
+fn words() {
+    macro_rules! check {
+        ($line:expr, $expected:expr $(,)?) => {{
+            let actual = split_words($line);
+            let expected: Vec<&str> = $expected.to_vec();
+            assert_eq!(actual, expected, "Actual split words don't match expected");
+        }};
+    }
+
+    check!("", []);
+    check!("apple", ["apple"]);
+    check!("apple banana", ["apple", " ", "banana"]);
+    check!("  **bold**!", ["  ", "*", "*", "bold", "*", "*", "!"]);
+    check!("[[div]]", ["[", "[", "div", "]", "]"]);
+    check!("it's 10x", ["it", "'", "s", " ", "10x"]);
+}
+
+#[test]
+fn wikitext() {
+    macro_rules! word {
+        ($kind:ident, $text:expr) => {
+            WordDiff {
+                kind: DiffKind::$kind,
+                text: str!($text),
+            }
+        };
+    }
+
+    let diff = diff_wikitext(
+        "First line\nThe quick fox\nRemoved line\nLast line",
+        "First line\nThe slow fox\nLast line\nNew line",
+    );
+
+    assert_eq!(
+        diff,
+        vec![
+            LineDiff::Equal {
+                from_line: 0,
+                to_line: 0,
+                text: str!("First line"),
+            },
+            LineDiff::Modified {
+                from_line: 1,
+                to_line: 1,
+                words: vec![
+                    word!(Equal, "The "),
+                    word!(Removed, "quick"),
+                    word!(Added, "slow"),
+                    word!(Equal, " fox"),
+                ],
+            },
+            LineDiff::Removed {
+                from_line: 2,
+                text: str!("Removed line"),
+            },
+            LineDiff::Equal {
+                from_line: 3,
+                to_line: 2,
+                text: str!("Last line"),
+            },
+            LineDiff::Added {
+                to_line: 3,
+                text: str!("New line"),
+            },
+        ],
+    );
+}
+
+#[test]
+fn tags() {
+    let tags = |tags: &[&str]| -> Vec<String> { tags.iter().map(|s| str!(s)).collect() };
+
+    assert_eq!(
+        diff_tags(&tags(&["a", "b", "c"]), &tags(&["b", "d", "c"])),
+        TagChanges {
+            added: tags(&["d"]),
+            removed: tags(&["a"]),
+        },
+    );
+}
+
+#[test]
+fn hidden_fields() {
+    use crate::models::sea_orm_active_enums::PageRevisionType;
+    use time::OffsetDateTime;
+
+    let revision =
+        |revision_id, title: &str, alt_title: Option<&str>, hidden: &[&str]| {
+            PageRevisionModelFiltered {
+                revision_id,
+                revision_type: PageRevisionType::Regular,
+                created_at: OffsetDateTime::UNIX_EPOCH,
+                updated_at: None,
+                from_wikidot: false,
+                revision_number: revision_id as i32,
+                page_id: 1,
+                site_id: 1,
+                user_id: 1,
+                changes: vec![],
+                wikitext: (!hidden.contains(&"wikitext")).then(|| str!("text")),
+                compiled_html: None,
+                compiled_at: OffsetDateTime::UNIX_EPOCH,
+                compiled_generator: str!(""),
+                comments: None,
+                hidden: hidden.iter().map(|field| str!(field)).collect(),
+                title: (!hidden.contains(&"title")).then(|| str!(title)),
+                alt_title: alt_title
+                    .filter(|_| !hidden.contains(&"alt_title"))
+                    .map(String::from),
+                slug: Some(str!("page")),
+                tags: Some(vec![]),
+            }
+        };
+
+    // Visible changes are reported
+    let output = diff_revisions(
+        &revision(1, "Apple", Some("Fruit"), &[]),
+        &revision(2, "Banana", None, &[]),
+    );
+    assert_eq!(output.wikitext, Some(vec![]));
+    assert_eq!(
+        output.title,
+        Some(FieldChange {
+            from: str!("Apple"),
+            to: str!("Banana"),
+        }),
+    );
+    assert_eq!(
+        output.alt_title,
+        Some(FieldChange {
+            from: Some(str!("Fruit")),
+            to: None,
+        }),
+    );
+
+    // Hidden fields in either revision are not
+    let output = diff_revisions(
+        &revision(
+            1,
+            "Apple",
+            Some("Fruit"),
+            &["wikitext", "title", "alt_title"],
+        ),
+        &revision(2, "Banana", None, &[]),
+    );
+    assert_eq!(output.wikitext, None);
+    assert_eq!(output.title, None);
+    assert_eq!(output.alt_title, None);
+}
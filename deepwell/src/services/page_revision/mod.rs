@@ -25,9 +25,11 @@ mod prelude {
     pub use super::tasks::PageRevisionTasks;
 }
 
+mod diff;
 mod service;
 mod structs;
 mod tasks;
 
+pub use self::diff::diff_revisions;
 pub use self::service::PageRevisionService;
 pub use self::structs::*;
@@ -91,6 +91,85 @@ pub struct GetPageRevision {
     pub revision_number: i32,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetPageRevisionDiff {
+    pub site_id: i64,
+    pub page_id: i64,
+    pub from_revision_number: i32,
+    pub to_revision_number: i32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PageRevisionDiffOutput {
+    pub from_revision_id: i64,
+    pub to_revision_id: i64,
+
+    /// Line-by-line changes to the wikitext.
+    ///
+    /// This is `None` if the wikitext of either revision is hidden.
+    pub wikitext: Option<Vec<LineDiff>>,
+
+    /// Changes to each field, which are `None` if it is unchanged or hidden.
+    pub title: Option<FieldChange<String>>,
+    pub alt_title: Option<FieldChange<Option<String>>>,
+    pub slug: Option<FieldChange<String>>,
+    pub tags: Option<TagChanges>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange<T> {
+    pub from: T,
+    pub to: T,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TagChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// A line in the wikitext diff.
+///
+/// Line numbers are zero-indexed, and refer to the
+/// older (`from`) and newer (`to`) revisions respectively.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum LineDiff {
+    Equal {
+        from_line: usize,
+        to_line: usize,
+        text: String,
+    },
+    Added {
+        to_line: usize,
+        text: String,
+    },
+    Removed {
+        from_line: usize,
+        text: String,
+    },
+    Modified {
+        from_line: usize,
+        to_line: usize,
+        words: Vec<WordDiff>,
+    },
+}
+
+/// A run of text within a modified line.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WordDiff {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffKind {
+    Equal,
+    Added,
+    Removed,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetPageRevisionDetails {
     #[serde(flatten)]
@@ -0,0 +1,45 @@
+/*
+ * services/permission/mod.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The permission service, determining what actions users can take on a site.
+//!
+//! Each user has a role in a site, which is derived from their relations to it:
+//! * Users who are not logged in are guests.
+//! * Logged in users who are not members of the site are registered.
+//! * Site members are members, unless they have also been given a staff role
+//!   (moderator, admin, or owner) by a `site` / `role` / `user` relation.
+//!
+//! Each action (the `PermissionType`) requires a minimum role to perform.
+//! Sites may override the default requirements, either for the whole site
+//! or for pages within a particular category, which takes precedence.
+//!
+//! Users who are banned from a site cannot perform any actions on it.
+
+#[allow(unused_imports)]
+mod prelude {
+    pub use super::super::prelude::*;
+    pub use super::structs::*;
+}
+
+mod service;
+mod structs;
+
+pub use self::service::PermissionService;
+pub use self::structs::*;
@@ -0,0 +1,406 @@
+/*
+ * services/permission/service.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::sea_orm_active_enums::UserType;
+use crate::models::site_permission::{
+    self, Entity as SitePermission, Model as SitePermissionModel,
+};
+use crate::services::relation::{
+    CreateSiteRole, GetSiteBan, GetSiteMember, GetSiteRole, RemoveSiteRole, SiteRoleData,
+};
+use crate::services::{CategoryService, PageService, RelationService, UserService};
+use crate::utils::get_category_name;
+use sea_orm::sea_query::OnConflict;
+use wikidot_normalize::normalize;
+
+#[derive(Debug)]
+pub struct PermissionService;
+
+impl PermissionService {
+    /// Determines the role and ban status of a user in a site.
+    ///
+    /// If `user_id` is `None`, then this is a guest who is not logged in.
+    pub async fn get_user_permissions(
+        ctx: &ServiceContext<'_>,
+        GetUserPermissions { site_id, user_id }: GetUserPermissions,
+    ) -> Result<UserPermissions> {
+        let user_id = match user_id {
+            Some(user_id) => user_id,
+            None => return Ok(UserPermissions::GUEST),
+        };
+
+        let banned =
+            RelationService::is_site_banned(ctx, GetSiteBan { site_id, user_id }).await?;
+
+        let role = match RelationService::get_optional_site_role(
+            ctx,
+            GetSiteRole { site_id, user_id },
+        )
+        .await?
+        {
+            Some(relation) => {
+                let SiteRoleData { role } = serde_json::from_value(relation.metadata)?;
+                role
+            }
+            None => {
+                let member = RelationService::site_member_exists(
+                    ctx,
+                    GetSiteMember { site_id, user_id },
+                )
+                .await?;
+
+                if member {
+                    SiteRole::Member
+                } else {
+                    SiteRole::Registered
+                }
+            }
+        };
+
+        Ok(UserPermissions { role, banned })
+    }
+
+    /// Gets the minimum role needed to perform an action.
+    ///
+    /// A rule for the category takes precedence over a rule for the whole site,
+    /// and if neither is set then the default for this permission is used.
+    pub async fn get_required_role(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category_id: Option<i64>,
+        permission: PermissionType,
+    ) -> Result<SiteRole> {
+        let txn = ctx.transaction();
+        let category_condition = match category_id {
+            Some(category_id) => Condition::any()
+                .add(site_permission::Column::CategoryId.eq(category_id))
+                .add(site_permission::Column::CategoryId.is_null()),
+            None => Condition::all().add(site_permission::Column::CategoryId.is_null()),
+        };
+
+        let rules = SitePermission::find()
+            .filter(
+                Condition::all()
+                    .add(site_permission::Column::SiteId.eq(site_id))
+                    .add(site_permission::Column::Permission.eq(permission))
+                    .add(category_condition),
+            )
+            .all(txn)
+            .await?;
+
+        let rule = rules
+            .iter()
+            .find(|rule| rule.category_id.is_some())
+            .or_else(|| rules.first());
+
+        Ok(match rule {
+            Some(rule) => rule.role,
+            None => default_role(permission),
+        })
+    }
+
+    /// Determines if the user is able to perform this action.
+    pub async fn has_permission(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category_id: Option<i64>,
+        user_permissions: UserPermissions,
+        permission: PermissionType,
+    ) -> Result<bool> {
+        let required =
+            Self::get_required_role(ctx, site_id, category_id, permission).await?;
+
+        Ok(user_permissions.satisfies(required))
+    }
+
+    /// Ensures the user is able to perform this action, returning an error if not.
+    pub async fn check(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        category_id: Option<i64>,
+        user_id: i64,
+        permission: PermissionType,
+    ) -> Result<()> {
+        let user_permissions = Self::get_user_permissions(
+            ctx,
+            GetUserPermissions {
+                site_id,
+                user_id: Some(user_id),
+            },
+        )
+        .await?;
+
+        if Self::has_permission(ctx, site_id, category_id, user_permissions, permission)
+            .await?
+        {
+            Ok(())
+        } else {
+            warn!(
+                "User ID {user_id} ({user_permissions:?}) lacks permission {permission:?} in site ID {site_id} (category {category_id:?})",
+            );
+
+            Err(Error::PermissionDenied)
+        }
+    }
+
+    /// Determines if the user is platform staff.
+    ///
+    /// Platform staff manage content across all sites, such as the
+    /// platform-wide filters. The system user is always considered staff.
+    pub async fn is_platform_staff(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<bool> {
+        let user = UserService::get(ctx, Reference::Id(user_id)).await?;
+        Ok(user.platform_staff || user.user_type == UserType::System)
+    }
+
+    /// Ensures the user is platform staff, returning an error if not.
+    pub async fn check_platform_staff(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+    ) -> Result<()> {
+        if Self::is_platform_staff(ctx, user_id).await? {
+            Ok(())
+        } else {
+            warn!("User ID {user_id} is not platform staff");
+            Err(Error::PermissionDenied)
+        }
+    }
+
+    /// Ensures the user is able to perform this action on an existing page.
+    pub async fn check_page(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        reference: Reference<'_>,
+        user_id: i64,
+        permission: PermissionType,
+    ) -> Result<()> {
+        let PageModel {
+            page_category_id, ..
+        } = PageService::get(ctx, site_id, reference).await?;
+
+        Self::check(ctx, site_id, Some(page_category_id), user_id, permission).await
+    }
+
+    /// Ensures the user is able to perform this action on a page, given its ID.
+    ///
+    /// Unlike `check_page()`, this also finds deleted pages, and takes
+    /// the site from the page, so it can be used when restoring.
+    pub async fn check_page_id(
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+        user_id: i64,
+        permission: PermissionType,
+    ) -> Result<()> {
+        let PageModel {
+            site_id,
+            page_category_id,
+            ..
+        } = PageService::get_direct(ctx, page_id, true).await?;
+
+        Self::check(ctx, site_id, Some(page_category_id), user_id, permission).await
+    }
+
+    /// Ensures the user is able to perform this action on a page at the given slug.
+    ///
+    /// The page need not exist, which is used when creating or moving pages.
+    /// If the category does not exist yet, then only site-wide rules apply.
+    pub async fn check_slug(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        slug: &str,
+        user_id: i64,
+        permission: PermissionType,
+    ) -> Result<()> {
+        let mut slug = str!(slug);
+        normalize(&mut slug);
+
+        let category = CategoryService::get_optional(
+            ctx,
+            site_id,
+            Reference::Slug(cow!(get_category_name(&slug))),
+        )
+        .await?;
+
+        let category_id = category.map(|category| category.category_id);
+        Self::check(ctx, site_id, category_id, user_id, permission).await
+    }
+
+    /// Gets all the permission rules configured for a site.
+    pub async fn get_rules(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<Vec<SitePermissionModel>> {
+        let txn = ctx.transaction();
+        let rules = SitePermission::find()
+            .filter(site_permission::Column::SiteId.eq(site_id))
+            .order_by_asc(site_permission::Column::CategoryId)
+            .order_by_asc(site_permission::Column::Permission)
+            .all(txn)
+            .await?;
+
+        Ok(rules)
+    }
+
+    /// Sets or clears the role required for an action in a site or category.
+    pub async fn set_rule(
+        ctx: &ServiceContext<'_>,
+        SetPermissionRule {
+            site_id,
+            category_id,
+            permission,
+            role,
+            acting_user_id,
+        }: SetPermissionRule,
+    ) -> Result<()> {
+        info!(
+            "Setting permission {permission:?} in site ID {site_id} (category {category_id:?}) to {role:?}",
+        );
+
+        Self::check(ctx, site_id, None, acting_user_id, PermissionType::Manage).await?;
+
+        let txn = ctx.transaction();
+        match role {
+            Some(role) => {
+                let model = site_permission::ActiveModel {
+                    site_id: Set(site_id),
+                    category_id: Set(category_id),
+                    permission: Set(permission),
+                    role: Set(role),
+                    ..Default::default()
+                };
+
+                SitePermission::insert(model)
+                    .on_conflict(
+                        OnConflict::columns([
+                            site_permission::Column::SiteId,
+                            site_permission::Column::CategoryId,
+                            site_permission::Column::Permission,
+                        ])
+                        .update_column(site_permission::Column::Role)
+                        .value(site_permission::Column::UpdatedAt, now())
+                        .to_owned(),
+                    )
+                    .exec(txn)
+                    .await?;
+            }
+            None => {
+                let category_condition = match category_id {
+                    Some(category_id) => {
+                        site_permission::Column::CategoryId.eq(category_id)
+                    }
+                    None => site_permission::Column::CategoryId.is_null(),
+                };
+
+                SitePermission::delete_many()
+                    .filter(
+                        Condition::all()
+                            .add(site_permission::Column::SiteId.eq(site_id))
+                            .add(site_permission::Column::Permission.eq(permission))
+                            .add(category_condition),
+                    )
+                    .exec(txn)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gives a user a staff role in a site, or removes it.
+    ///
+    /// The acting user must be able to manage the site, and cannot grant
+    /// or revoke a role higher than their own.
+    pub async fn set_role(
+        ctx: &ServiceContext<'_>,
+        SetSiteRole {
+            site_id,
+            user_id,
+            role,
+            acting_user_id,
+        }: SetSiteRole,
+    ) -> Result<()> {
+        info!("Setting role for user ID {user_id} in site ID {site_id} to {role:?}");
+
+        Self::check(ctx, site_id, None, acting_user_id, PermissionType::Manage).await?;
+
+        let acting = Self::get_user_permissions(
+            ctx,
+            GetUserPermissions {
+                site_id,
+                user_id: Some(acting_user_id),
+            },
+        )
+        .await?;
+
+        let current = Self::get_user_permissions(
+            ctx,
+            GetUserPermissions {
+                site_id,
+                user_id: Some(user_id),
+            },
+        )
+        .await?;
+
+        for affected in [Some(current.role), role].into_iter().flatten() {
+            if !acting.satisfies(affected) {
+                warn!(
+                    "User ID {acting_user_id} ({:?}) cannot change the role {affected:?}",
+                    acting.role,
+                );
+                return Err(Error::PermissionDenied);
+            }
+        }
+
+        let key = GetSiteRole { site_id, user_id };
+        match role {
+            Some(role) => {
+                RelationService::create_site_role(
+                    ctx,
+                    CreateSiteRole {
+                        site_id,
+                        user_id,
+                        metadata: SiteRoleData { role },
+                        created_by: acting_user_id,
+                    },
+                )
+                .await?;
+            }
+            None => {
+                if RelationService::site_role_exists(ctx, key).await? {
+                    RelationService::remove_site_role(
+                        ctx,
+                        RemoveSiteRole {
+                            site_id,
+                            user_id,
+                            removed_by: acting_user_id,
+                        },
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
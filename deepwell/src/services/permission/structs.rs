@@ -0,0 +1,170 @@
+/*
+ * services/permission/structs.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+pub use crate::models::sea_orm_active_enums::{PermissionType, SiteRole};
+
+/// The permission-relevant state of a user in a site.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UserPermissions {
+    pub role: SiteRole,
+    pub banned: bool,
+}
+
+impl UserPermissions {
+    /// The permissions for a user who is not logged in.
+    pub const GUEST: Self = UserPermissions {
+        role: SiteRole::Guest,
+        banned: false,
+    };
+
+    #[inline]
+    pub fn is_banned(self) -> bool {
+        self.banned
+    }
+
+    /// Determines if this user meets the role required for an action.
+    pub fn satisfies(self, required: SiteRole) -> bool {
+        !self.banned && role_rank(self.role) >= role_rank(required)
+    }
+}
+
+/// The role given to the user who creates a site.
+///
+/// This must be the highest role, since a role can only be assigned
+/// by users who have it, and otherwise no one could make an owner.
+/// It also keeps admins from demoting the site's creator.
+pub const SITE_CREATOR_ROLE: SiteRole = SiteRole::Owner;
+
+/// Gets the relative privilege of a role, where higher ranks can do more.
+pub fn role_rank(role: SiteRole) -> u8 {
+    match role {
+        SiteRole::Guest => 0,
+        SiteRole::Registered => 1,
+        SiteRole::Member => 2,
+        SiteRole::Moderator => 3,
+        SiteRole::Admin => 4,
+        SiteRole::Owner => 5,
+    }
+}
+
+/// Gets the role required for an action if the site has not configured one.
+pub fn default_role(permission: PermissionType) -> SiteRole {
+    match permission {
+        PermissionType::View => SiteRole::Guest,
+        PermissionType::Create
+        | PermissionType::Edit
+        | PermissionType::Move
+        | PermissionType::Vote
+        | PermissionType::Upload => SiteRole::Member,
+        PermissionType::Delete | PermissionType::Moderate => SiteRole::Moderator,
+        PermissionType::Manage => SiteRole::Admin,
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetUserPermissions {
+    pub site_id: i64,
+    pub user_id: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct SetSiteRole {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    /// The new staff role, or `None` to remove it.
+    pub role: Option<SiteRole>,
+    pub acting_user_id: i64,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct SetPermissionRule {
+    pub site_id: i64,
+    pub category_id: Option<i64>,
+    pub permission: PermissionType,
+
+    /// The role now required, or `None` to revert to the default.
+    pub role: Option<SiteRole>,
+    pub acting_user_id: i64,
+}
+
+#[test]
+fn satisfies() {
+    macro_rules! check {
+        ($role:ident, $banned:expr, $required:ident, $expected:expr $(,)?) => {{
+            let permissions = UserPermissions {
+                role: SiteRole::$role,
+                banned: $banned,
+            };
+
+            assert_eq!(
+                permissions.satisfies(SiteRole::$required),
+                $expected,
+                "Role {:?} (banned {}) against required {:?} doesn't match expected",
+                SiteRole::$role,
+                $banned,
+                SiteRole::$required,
+            );
+        }};
+    }
+
+    check!(Guest, false, Guest, true);
+    check!(Guest, false, Registered, false);
+    check!(Registered, false, Member, false);
+    check!(Member, false, Member, true);
+    check!(Member, false, Moderator, false);
+    check!(Moderator, false, Member, true);
+    check!(Admin, false, Moderator, true);
+    check!(Admin, false, Owner, false);
+    check!(Owner, false, Admin, true);
+    check!(Member, true, Guest, false);
+    check!(Owner, true, Member, false);
+}
+
+#[test]
+fn site_creator() {
+    // Mirrors the checks in PermissionService::set_role() and set_rule()
+    let creator = UserPermissions {
+        role: SITE_CREATOR_ROLE,
+        banned: false,
+    };
+
+    assert!(
+        creator.satisfies(default_role(PermissionType::Manage)),
+        "Site creator cannot manage the site",
+    );
+
+    for role in [SiteRole::Moderator, SiteRole::Admin, SiteRole::Owner] {
+        assert!(
+            creator.satisfies(role),
+            "Site creator cannot assign role {role:?}",
+        );
+    }
+
+    let admin = UserPermissions {
+        role: SiteRole::Admin,
+        banned: false,
+    };
+
+    assert!(
+        !admin.satisfies(SITE_CREATOR_ROLE),
+        "Admin can change the site creator's role",
+    );
+}
@@ -110,7 +110,26 @@ macro_rules! impl_relation {
                     ).await
                 }
 
-                // TODO paginate
+                #[allow(dead_code)] // TEMP
+                pub async fn [<remove_optional_ $relation_type:snake>](
+                    ctx: &ServiceContext<'_>,
+                    [<Remove $relation_type>] {
+                        $dest_name,
+                        $from_name,
+                        removed_by,
+                    }: [<Remove $relation_type>],
+                ) -> Result<Option<RelationModel>> {
+                    Self::remove_optional(
+                        ctx,
+                        RelationReference::Relationship {
+                            relation_type: RelationType::$relation_type,
+                            dest: RelationObject::$dest_type($dest_name),
+                            from: RelationObject::$from_type($from_name),
+                        },
+                        removed_by,
+                    ).await
+                }
+
                 #[allow(dead_code)] // TEMP
                 pub async fn [<get_ $relation_type:snake _history>](
                     ctx: &ServiceContext<'_>,
@@ -118,28 +137,48 @@ macro_rules! impl_relation {
                         $dest_name,
                         $from_name,
                     }: [<Get $relation_type>],
-                ) -> Result<Vec<RelationModel>> {
+                    pagination: RelationPagination,
+                ) -> Result<RelationPage> {
                     Self::get_history(
                         ctx,
                         RelationType::$relation_type,
                         RelationObject::$dest_type($dest_name),
                         RelationObject::$from_type($from_name),
+                        pagination,
                     )
                     .await
                 }
 
-                // TODO paginate
                 #[allow(dead_code)] // TEMP
                 pub async fn [<get_ $relation_type:snake _entries>](
                     ctx: &ServiceContext<'_>,
                     object: RelationObject,
                     direction: RelationDirection,
-                ) -> Result<Vec<RelationModel>> {
+                    pagination: RelationPagination,
+                ) -> Result<RelationPage> {
                     Self::get_entries(
                         ctx,
                         RelationType::$relation_type,
                         object,
                         direction,
+                        pagination,
+                    )
+                    .await
+                }
+
+                #[allow(dead_code)] // TEMP
+                pub async fn [<get_current_ $relation_type:snake _entries>](
+                    ctx: &ServiceContext<'_>,
+                    object: RelationObject,
+                    direction: RelationDirection,
+                    pagination: RelationPagination,
+                ) -> Result<RelationPage> {
+                    Self::get_current_entries(
+                        ctx,
+                        RelationType::$relation_type,
+                        object,
+                        direction,
+                        pagination,
                     )
                     .await
                 }
@@ -28,6 +28,7 @@
 //!
 //! For example:
 //! * `site` / `member` / `user` &mdash; User is a site member
+//! * `site` / `role` / `user` &mdash; User has a staff role on a site
 //! * `user` / `block` / `user` &mdash; User has blocked another user
 
 #[allow(unused_imports)]
@@ -46,6 +47,7 @@ mod page_star;
 mod page_watch;
 mod site_ban;
 mod site_member;
+mod site_role;
 mod site_user;
 mod structs;
 mod user_block;
@@ -56,6 +58,7 @@ pub use self::page_star::*;
 pub use self::page_watch::*;
 pub use self::site_ban::*;
 pub use self::site_member::*;
+pub use self::site_role::*;
 pub use self::site_user::*;
 pub use self::structs::*;
 pub use self::user_block::*;
@@ -149,6 +152,22 @@ impl RelationService {
         Ok(output)
     }
 
+    /// Removes the relation, if it currently exists.
+    pub async fn remove_optional(
+        ctx: &ServiceContext<'_>,
+        reference: RelationReference,
+        deleted_by: i64,
+    ) -> Result<Option<RelationModel>> {
+        match Self::get_optional(ctx, reference).await? {
+            None => Ok(None),
+            Some(relation) => {
+                let reference = RelationReference::Id(relation.relation_id);
+                let output = Self::remove(ctx, reference, deleted_by).await?;
+                Ok(Some(output))
+            }
+        }
+    }
+
     pub async fn get_optional(
         ctx: &ServiceContext<'_>,
         reference: RelationReference,
@@ -200,8 +219,7 @@ impl RelationService {
             .map(|relation| relation.is_some())
     }
 
-    // TODO paginate
-    /// Gets the history of this `dest` / `from` relation.
+    /// Gets the history of this `dest` / `from` relation, newest first.
     ///
     /// This includes all all edits of the relation (`overwritten_at`)
     /// and deleted / remade versions of the relation (`deleted_at`).
@@ -210,54 +228,112 @@ impl RelationService {
         relation_type: RelationType,
         dest: RelationObject,
         from: RelationObject,
-    ) -> Result<Vec<RelationModel>> {
+        pagination: RelationPagination,
+    ) -> Result<RelationPage> {
         info!("Getting history of relations for {dest:?} / {relation_type:?} / {from:?}");
 
-        let txn = ctx.transaction();
-        let relations = Relation::find()
-            .filter(relation_condition(relation_type, dest, from))
-            .order_by_asc(relation::Column::CreatedAt)
-            .all(txn)
-            .await?;
+        Self::get_page(
+            ctx,
+            relation_condition(relation_type, dest, from),
+            pagination,
+        )
+        .await
+    }
+
+    /// Gets all relations from the starting object in the given direction, newest first.
+    ///
+    /// This includes overwritten and deleted relations. For only extant relations,
+    /// use `get_current_entries()` instead.
+    pub async fn get_entries(
+        ctx: &ServiceContext<'_>,
+        relation_type: RelationType,
+        object: RelationObject,
+        direction: RelationDirection,
+        pagination: RelationPagination,
+    ) -> Result<RelationPage> {
+        info!("Getting {direction:?} relations for {object:?} / {relation_type:?}",);
 
-        Ok(relations)
+        let condition = entries_condition(relation_type, object, direction);
+        Self::get_page(ctx, condition, pagination).await
     }
 
-    // TODO paginate
-    /// Gets all relations from the starting object in the given direction.
+    /// Gets extant relations from the starting object in the given direction, newest first.
     ///
     /// For instance, this can be used to get all blocked users, or all users who are blocking
     /// someone depending on the `RelationDirection`.
-    pub async fn get_entries(
+    pub async fn get_current_entries(
         ctx: &ServiceContext<'_>,
         relation_type: RelationType,
         object: RelationObject,
         direction: RelationDirection,
-    ) -> Result<Vec<RelationModel>> {
-        info!("Getting {direction:?} relations for {object:?} / {relation_type:?}",);
+        pagination: RelationPagination,
+    ) -> Result<RelationPage> {
+        info!(
+            "Getting current {direction:?} relations for {object:?} / {relation_type:?}",
+        );
 
-        let (object_type, object_id) = object.into();
-        let (object_type_column, object_id_column) = match direction {
-            RelationDirection::Dest => {
-                (relation::Column::DestType, relation::Column::DestId)
-            }
-            RelationDirection::From => {
-                (relation::Column::FromType, relation::Column::FromId)
-            }
-        };
+        let condition = current_entries_condition(relation_type, object, direction);
+        Self::get_page(ctx, condition, pagination).await
+    }
+
+    /// Determines if there are any relations from the starting object in the given direction.
+    ///
+    /// Like `get_entries()`, this includes overwritten and deleted relations.
+    pub async fn entries_exist(
+        ctx: &ServiceContext<'_>,
+        relation_type: RelationType,
+        object: RelationObject,
+        direction: RelationDirection,
+    ) -> Result<bool> {
+        info!("Checking for {direction:?} relations for {object:?} / {relation_type:?}",);
+
+        let txn = ctx.transaction();
+        let relation = Relation::find()
+            .filter(entries_condition(relation_type, object, direction))
+            .one(txn)
+            .await?;
 
+        Ok(relation.is_some())
+    }
+
+    /// Gets one page of the relations matching the condition.
+    ///
+    /// Relations are ordered by ID, newest first, which is stable
+    /// even as relations are added or removed between requests.
+    async fn get_page(
+        ctx: &ServiceContext<'_>,
+        condition: Condition,
+        RelationPagination { before_id, limit }: RelationPagination,
+    ) -> Result<RelationPage> {
+        let limit = limit.clamp(1, MAXIMUM_RELATION_PAGE_SIZE);
         let txn = ctx.transaction();
-        let relations = Relation::find()
+        let total = Relation::find()
+            .filter(condition.clone())
+            .count(txn)
+            .await?;
+
+        // Fetch one extra item to see if there is a next page
+        let mut relations = Relation::find()
             .filter(
-                Condition::all()
-                    .add(relation::Column::RelationType.eq(relation_type.value()))
-                    .add(object_type_column.eq(object_type))
-                    .add(object_id_column.eq(object_id)),
+                condition
+                    .add_option(before_id.map(|id| relation::Column::RelationId.lt(id))),
             )
-            .order_by_asc(relation::Column::CreatedAt)
+            .order_by_desc(relation::Column::RelationId)
+            .limit(limit + 1)
             .all(txn)
             .await?;
 
-        Ok(relations)
+        let next_before_id = if relations.len() as u64 > limit {
+            relations.pop();
+            relations.last().map(|relation| relation.relation_id)
+        } else {
+            None
+        };
+
+        Ok(RelationPage {
+            relations,
+            total,
+            next_before_id,
+        })
     }
 }
@@ -21,3 +21,30 @@
 use super::prelude::*;
 
 impl_relation!(PageStar, Page, page_id, User, user_id, ());
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetPageStars {
+    pub page_id: i64,
+
+    #[serde(flatten)]
+    pub pagination: RelationPagination,
+}
+
+impl RelationService {
+    /// Gets the users who starred this page, newest first.
+    pub async fn get_page_stars(
+        ctx: &ServiceContext<'_>,
+        GetPageStars {
+            page_id,
+            pagination,
+        }: GetPageStars,
+    ) -> Result<RelationPage> {
+        Self::get_current_page_star_entries(
+            ctx,
+            RelationObject::Page(page_id),
+            RelationDirection::Dest,
+            pagination,
+        )
+        .await
+    }
+}
@@ -21,3 +21,30 @@
 use super::prelude::*;
 
 impl_relation!(PageWatch, Page, page_id, User, user_id, ());
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetPageWatches {
+    pub page_id: i64,
+
+    #[serde(flatten)]
+    pub pagination: RelationPagination,
+}
+
+impl RelationService {
+    /// Gets the users watching this page, newest first.
+    pub async fn get_page_watches(
+        ctx: &ServiceContext<'_>,
+        GetPageWatches {
+            page_id,
+            pagination,
+        }: GetPageWatches,
+    ) -> Result<RelationPage> {
+        Self::get_current_page_watch_entries(
+            ctx,
+            RelationObject::Page(page_id),
+            RelationDirection::Dest,
+            pagination,
+        )
+        .await
+    }
+}
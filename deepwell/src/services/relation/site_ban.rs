@@ -19,15 +19,29 @@
  */
 
 use super::prelude::*;
-use super::site_member::RemoveSiteMember;
-use time::Date;
+use super::site_member::{GetSiteMember, RemoveSiteMember};
+use super::site_role::{GetSiteRole, RemoveSiteRole};
+use crate::constants::SYSTEM_USER_ID;
+use crate::models::relation::{self, Entity as Relation};
+use time::OffsetDateTime;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct SiteBanData {
-    pub banned_until: Option<Date>,
+    /// When this ban expires, or `None` if it is permanent.
+    pub banned_until: Option<OffsetDateTime>,
     pub reason: String,
 }
 
+impl SiteBanData {
+    #[inline]
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        match self.banned_until {
+            Some(banned_until) => banned_until <= now,
+            None => false,
+        }
+    }
+}
+
 impl_relation!(
     SiteBan,
     Site,
@@ -38,8 +52,21 @@ impl_relation!(
     NO_CREATE_IMPL,
 );
 
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetSiteBans {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    #[serde(flatten)]
+    pub pagination: RelationPagination,
+}
+
 impl RelationService {
-    #[allow(dead_code)] // TEMP
+    /// Bans a user from a site.
+    ///
+    /// This also removes their membership, staff role, and any pending
+    /// membership application. Since these all occur in the request's
+    /// transaction, either all of them take effect or none do.
     pub async fn create_site_ban(
         ctx: &ServiceContext<'_>,
         CreateSiteBan {
@@ -49,30 +76,73 @@ impl RelationService {
             metadata,
         }: CreateSiteBan,
     ) -> Result<()> {
-        Self::remove_site_member(
-            ctx,
-            RemoveSiteMember {
-                site_id,
-                user_id,
-                removed_by: created_by,
-            },
-        )
-        .await?;
-        // TODO: remove site member applications
-        // TODO: remove site roles
+        if metadata.is_expired(now()) {
+            error!("Cannot create site ban which has already expired");
+            return Err(Error::BadRequest);
+        }
+
+        if Self::site_member_exists(ctx, GetSiteMember { site_id, user_id }).await? {
+            Self::remove_site_member(
+                ctx,
+                RemoveSiteMember {
+                    site_id,
+                    user_id,
+                    removed_by: created_by,
+                },
+            )
+            .await?;
+        }
+
+        if Self::site_role_exists(ctx, GetSiteRole { site_id, user_id }).await? {
+            Self::remove_site_role(
+                ctx,
+                RemoveSiteRole {
+                    site_id,
+                    user_id,
+                    removed_by: created_by,
+                },
+            )
+            .await?;
+        }
+
+        let application = RelationReference::Relationship {
+            relation_type: RelationType::SiteApplication,
+            dest: RelationObject::Site(site_id),
+            from: RelationObject::User(user_id),
+        };
+
+        if Self::exists(ctx, application).await? {
+            Self::remove(ctx, application, created_by).await?;
+        }
 
         create_operation!(
             ctx, SiteBan, Site, site_id, User, user_id, created_by, &metadata,
         )
     }
 
+    /// Determines if the user is currently banned from the site.
+    ///
+    /// Bans which have expired but have not been lifted yet are ignored.
+    pub async fn is_site_banned(
+        ctx: &ServiceContext<'_>,
+        body: GetSiteBan,
+    ) -> Result<bool> {
+        match Self::get_optional_site_ban(ctx, body).await? {
+            None => Ok(false),
+            Some(relation) => {
+                let data: SiteBanData = serde_json::from_value(relation.metadata)?;
+                Ok(!data.is_expired(now()))
+            }
+        }
+    }
+
     /// Helper method for rejecting an relation if the user is banned.
     pub async fn check_site_ban(
         ctx: &ServiceContext<'_>,
         body: GetSiteBan,
         action: &str,
     ) -> Result<()> {
-        if Self::site_ban_exists(ctx, body).await? {
+        if Self::is_site_banned(ctx, body).await? {
             error!(
                 "User ID {} cannot {} site ID {} because they are banned",
                 body.user_id, action, body.site_id,
@@ -83,4 +153,63 @@ impl RelationService {
 
         Ok(())
     }
+
+    /// Removes all site bans which have expired.
+    ///
+    /// The bans are marked as deleted by the system user,
+    /// so they remain in the relation's history.
+    pub async fn lift_expired_site_bans(ctx: &ServiceContext<'_>) -> Result<u64> {
+        info!("Lifting all expired site bans");
+
+        let txn = ctx.transaction();
+        let bans = Relation::find()
+            .filter(
+                Condition::all()
+                    .add(relation::Column::RelationType.eq(RelationType::SiteBan.value()))
+                    .add(relation::Column::OverwrittenAt.is_null())
+                    .add(relation::Column::DeletedAt.is_null()),
+            )
+            .all(txn)
+            .await?;
+
+        let now = now();
+        let mut lifted = 0;
+
+        for ban in bans {
+            let data: SiteBanData = serde_json::from_value(ban.metadata)?;
+            if data.is_expired(now) {
+                debug!(
+                    "Lifting ban of user ID {} from site ID {}",
+                    ban.from_id, ban.dest_id,
+                );
+
+                Self::remove(ctx, RelationReference::Id(ban.relation_id), SYSTEM_USER_ID)
+                    .await?;
+
+                lifted += 1;
+            }
+        }
+
+        Ok(lifted)
+    }
+
+    /// Gets the extant bans of a site, newest first.
+    ///
+    /// This may include bans which have expired but have not been lifted yet.
+    pub async fn get_site_bans(
+        ctx: &ServiceContext<'_>,
+        GetSiteBans {
+            site_id,
+            user_id: _,
+            pagination,
+        }: GetSiteBans,
+    ) -> Result<RelationPage> {
+        Self::get_current_site_ban_entries(
+            ctx,
+            RelationObject::Site(site_id),
+            RelationDirection::Dest,
+            pagination,
+        )
+        .await
+    }
 }
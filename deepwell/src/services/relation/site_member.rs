@@ -45,6 +45,14 @@ impl_relation!(
     NO_CREATE_IMPL,
 );
 
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetSiteMembers {
+    pub site_id: i64,
+
+    #[serde(flatten)]
+    pub pagination: RelationPagination,
+}
+
 impl RelationService {
     pub async fn create_site_member(
         ctx: &ServiceContext<'_>,
@@ -62,4 +70,21 @@ impl RelationService {
             ctx, SiteMember, Site, site_id, User, user_id, created_by, &metadata,
         )
     }
+
+    /// Gets the members of a site, newest first.
+    pub async fn get_site_members(
+        ctx: &ServiceContext<'_>,
+        GetSiteMembers {
+            site_id,
+            pagination,
+        }: GetSiteMembers,
+    ) -> Result<RelationPage> {
+        Self::get_current_site_member_entries(
+            ctx,
+            RelationObject::Site(site_id),
+            RelationDirection::Dest,
+            pagination,
+        )
+        .await
+    }
 }
@@ -0,0 +1,72 @@
+/*
+ * services/relation/site_role.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Governs the relation which tracks the staff roles of users on a site.
+//!
+//! Ordinary membership is tracked by the `member` relation, so only the
+//! roles above it (moderator, admin, and owner) are stored here.
+
+use super::prelude::*;
+use crate::models::sea_orm_active_enums::SiteRole;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SiteRoleData {
+    pub role: SiteRole,
+}
+
+impl_relation!(
+    SiteRole,
+    Site,
+    site_id,
+    User,
+    user_id,
+    SiteRoleData,
+    NO_CREATE_IMPL
+);
+
+impl RelationService {
+    pub async fn create_site_role(
+        ctx: &ServiceContext<'_>,
+        CreateSiteRole {
+            site_id,
+            user_id,
+            metadata,
+            created_by,
+        }: CreateSiteRole,
+    ) -> Result<()> {
+        match metadata.role {
+            SiteRole::Moderator | SiteRole::Admin | SiteRole::Owner => (),
+            SiteRole::Guest | SiteRole::Registered | SiteRole::Member => {
+                error!(
+                    "Cannot assign role {:?}, it is not a staff role",
+                    metadata.role,
+                );
+                return Err(Error::SiteRoleInvalid);
+            }
+        }
+
+        // Cannot be staff if banned
+        Self::check_site_ban(ctx, GetSiteBan { site_id, user_id }, "be staff in").await?;
+
+        create_operation!(
+            ctx, SiteRole, Site, site_id, User, user_id, created_by, &metadata,
+        )
+    }
+}
@@ -58,29 +58,27 @@ impl RelationService {
         // This means there should be no results for both
         // this site_id -> anything and this user_id -> anything.
 
-        let sites = RelationService::get_entries(
+        if RelationService::entries_exist(
             ctx,
             RelationType::SiteUser,
             RelationObject::Site(site_id),
             RelationDirection::Dest,
         )
-        .await?;
-
-        if !sites.is_empty() {
-            error!("Found a different relation with this site, cannot create relation: {sites:?}");
+        .await?
+        {
+            error!("Found a different relation with this site, cannot create relation");
             return Err(Error::BadRequest);
         }
 
-        let users = RelationService::get_entries(
+        if RelationService::entries_exist(
             ctx,
             RelationType::SiteUser,
             RelationObject::User(user_id),
             RelationDirection::From,
         )
-        .await?;
-
-        if !users.is_empty() {
-            error!("Found a different relation with this user, cannot create relation: {users:?}");
+        .await?
+        {
+            error!("Found a different relation with this user, cannot create relation");
             return Err(Error::BadRequest);
         }
 
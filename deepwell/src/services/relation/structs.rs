@@ -18,7 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use crate::models::relation;
+use crate::models::relation::{self, Model as RelationModel};
 use crate::models::sea_orm_active_enums::RelationObjectType;
 use sea_orm::{ColumnTrait, Condition};
 
@@ -91,6 +91,71 @@ pub fn relation_condition(
         .add(relation::Column::FromId.eq(from_id))
 }
 
+/// Builds the condition for all relations from the starting object in the given direction.
+///
+/// This includes overwritten and deleted relations.
+pub fn entries_condition(
+    relation_type: RelationType,
+    object: RelationObject,
+    direction: RelationDirection,
+) -> Condition {
+    let (object_type, object_id) = object.into();
+    let (object_type_column, object_id_column) = match direction {
+        RelationDirection::Dest => (relation::Column::DestType, relation::Column::DestId),
+        RelationDirection::From => (relation::Column::FromType, relation::Column::FromId),
+    };
+
+    Condition::all()
+        .add(relation::Column::RelationType.eq(relation_type.value()))
+        .add(object_type_column.eq(object_type))
+        .add(object_id_column.eq(object_id))
+}
+
+/// Builds the condition for extant relations from the starting object in the given direction.
+///
+/// Unlike `entries_condition()`, this excludes overwritten and deleted relations.
+pub fn current_entries_condition(
+    relation_type: RelationType,
+    object: RelationObject,
+    direction: RelationDirection,
+) -> Condition {
+    entries_condition(relation_type, object, direction)
+        .add(relation::Column::OverwrittenAt.is_null())
+        .add(relation::Column::DeletedAt.is_null())
+}
+
+/// The largest number of relations returned in one page.
+pub const MAXIMUM_RELATION_PAGE_SIZE: u64 = 100;
+
+/// Which page of relations to get.
+///
+/// This uses the relation ID as a cursor, so pages are
+/// not shifted by relations being added or removed.
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct RelationPagination {
+    /// Only return relations older than this relation ID.
+    ///
+    /// Use the `next_before_id` of the previous page to get the next page.
+    #[serde(default)]
+    pub before_id: Option<i64>,
+
+    /// How many relations to return.
+    ///
+    /// This is clamped to between 1 and `MAXIMUM_RELATION_PAGE_SIZE`.
+    pub limit: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RelationPage {
+    pub relations: Vec<RelationModel>,
+
+    /// The number of relations across all pages.
+    pub total: u64,
+
+    /// The cursor for the next page, or `None` if this is the last page.
+    pub next_before_id: Option<i64>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct RelationObjectTypes {
     pub dest: RelationObjectType,
@@ -117,8 +182,8 @@ impl RelationObjectTypes {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-#[allow(dead_code)] // TEMP
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
 pub enum RelationDirection {
     Dest,
     From,
@@ -131,12 +196,11 @@ pub enum RelationType {
     #[allow(dead_code)] // TEMP
     SiteApplication,
     SiteMember,
+    SiteRole,
     PageStar,
     PageWatch,
     UserFollow,
-    #[allow(dead_code)] // TEMP
     UserContact,
-    #[allow(dead_code)] // TEMP
     UserContactRequest,
     UserBlock,
 }
@@ -152,6 +216,7 @@ impl RelationType {
             RelationType::SiteBan => "ban",
             RelationType::SiteApplication => "application",
             RelationType::SiteMember => "member",
+            RelationType::SiteRole => "role",
             RelationType::PageStar => "star",
             RelationType::PageWatch => "watch",
             RelationType::UserFollow => "follow",
@@ -176,6 +241,7 @@ impl RelationType {
             RelationType::SiteBan => t!(Site, User),
             RelationType::SiteApplication => t!(Site, User),
             RelationType::SiteMember => t!(Site, User),
+            RelationType::SiteRole => t!(Site, User),
             RelationType::PageStar => t!(Page, User),
             RelationType::PageWatch => t!(Page, User),
             RelationType::UserFollow => t!(User, User),
@@ -185,3 +251,40 @@ impl RelationType {
         }
     }
 }
+
+#[test]
+fn entries_conditions() {
+    use crate::models::relation::Entity as Relation;
+    use sea_orm::{DatabaseBackend, EntityTrait, QueryFilter, QueryTrait};
+
+    macro_rules! check {
+        ($condition:expr, $extant_only:expr $(,)?) => {{
+            let sql = Relation::find()
+                .filter($condition)
+                .build(DatabaseBackend::Postgres)
+                .to_string();
+
+            for column in ["overwritten_at", "deleted_at"] {
+                assert_eq!(
+                    sql.contains(&format!(r#""{column}" IS NULL"#)),
+                    $extant_only,
+                    "Condition filtering on {column} doesn't match expected: {sql}",
+                );
+            }
+        }};
+    }
+
+    let object = RelationObject::User(1);
+    check!(
+        entries_condition(RelationType::UserBlock, object, RelationDirection::From),
+        false,
+    );
+    check!(
+        current_entries_condition(
+            RelationType::UserBlock,
+            object,
+            RelationDirection::From
+        ),
+        true,
+    );
+}
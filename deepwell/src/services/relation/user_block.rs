@@ -35,8 +35,15 @@ impl_relation!(
     NO_CREATE_IMPL,
 );
 
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetUserBlocks {
+    pub user_id: i64,
+
+    #[serde(flatten)]
+    pub pagination: RelationPagination,
+}
+
 impl RelationService {
-    #[allow(dead_code)] // TEMP
     pub async fn create_user_block(
         ctx: &ServiceContext<'_>,
         CreateUserBlock {
@@ -50,7 +57,7 @@ impl RelationService {
 
         // Unfollow, remove contacts, etc., both ways
         try_join!(
-            Self::remove_user_follow(
+            Self::remove_optional_user_follow(
                 ctx,
                 RemoveUserFollow {
                     followed_user: blocked_user,
@@ -58,7 +65,7 @@ impl RelationService {
                     removed_by: created_by,
                 },
             ),
-            Self::remove_user_follow(
+            Self::remove_optional_user_follow(
                 ctx,
                 RemoveUserFollow {
                     followed_user: blocking_user,
@@ -66,8 +73,30 @@ impl RelationService {
                     removed_by: created_by,
                 },
             ),
-            // TODO add user_contact
-            // TODO add user_contact_request
+            Self::remove_user_contact_pair(
+                ctx,
+                RemoveUserContact {
+                    contact_user: blocked_user,
+                    owner_user: blocking_user,
+                    removed_by: created_by,
+                },
+            ),
+            Self::remove_optional_user_contact_request(
+                ctx,
+                RemoveUserContactRequest {
+                    recipient_user: blocked_user,
+                    sender_user: blocking_user,
+                    removed_by: created_by,
+                },
+            ),
+            Self::remove_optional_user_contact_request(
+                ctx,
+                RemoveUserContactRequest {
+                    recipient_user: blocking_user,
+                    sender_user: blocked_user,
+                    removed_by: created_by,
+                },
+            ),
         )?;
 
         create_operation!(
@@ -107,4 +136,21 @@ impl RelationService {
 
         Ok(())
     }
+
+    /// Gets the users this user has blocked, newest first.
+    pub async fn get_user_blocks(
+        ctx: &ServiceContext<'_>,
+        GetUserBlocks {
+            user_id,
+            pagination,
+        }: GetUserBlocks,
+    ) -> Result<RelationPage> {
+        Self::get_current_user_block_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::From,
+            pagination,
+        )
+        .await
+    }
 }
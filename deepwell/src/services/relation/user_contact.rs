@@ -18,4 +18,301 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-// TODO implement contacts and contact requests
+//! Governs the relations for contacts between users, and requests to become contacts.
+//!
+//! Contacts are mutual, so an accepted request creates a `contact` relation
+//! in each direction, and removing a contact removes both. A pending request
+//! is a `contact-request` relation from the sender to the recipient.
+
+use super::prelude::*;
+
+impl_relation!(
+    UserContact,
+    User,
+    contact_user,
+    User,
+    owner_user,
+    (),
+    NO_CREATE_IMPL,
+);
+
+impl_relation!(
+    UserContactRequest,
+    User,
+    recipient_user,
+    User,
+    sender_user,
+    (),
+    NO_CREATE_IMPL,
+);
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetUserContacts {
+    pub user_id: i64,
+
+    #[serde(flatten)]
+    pub pagination: RelationPagination,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContactRequestDirection {
+    /// Requests which were sent to this user.
+    Incoming,
+
+    /// Requests which this user has sent.
+    Outgoing,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetUserContactRequests {
+    pub user_id: i64,
+    pub direction: ContactRequestDirection,
+
+    #[serde(flatten)]
+    pub pagination: RelationPagination,
+}
+
+impl RelationService {
+    /// Sends a contact request to another user.
+    ///
+    /// If the recipient had already sent a request to the sender,
+    /// then that request is accepted instead.
+    pub async fn create_user_contact_request(
+        ctx: &ServiceContext<'_>,
+        CreateUserContactRequest {
+            recipient_user,
+            sender_user,
+            created_by,
+            metadata: (),
+        }: CreateUserContactRequest,
+    ) -> Result<()> {
+        if recipient_user == sender_user {
+            error!("User ID {sender_user} cannot send a contact request to themselves");
+            return Err(Error::BadRequest);
+        }
+
+        // Cannot request if blocked
+        Self::check_user_block(
+            ctx,
+            recipient_user,
+            sender_user,
+            "send a contact request to",
+        )
+        .await?;
+
+        let contact = GetUserContact {
+            contact_user: recipient_user,
+            owner_user: sender_user,
+        };
+
+        if Self::user_contact_exists(ctx, contact).await? {
+            error!(
+                "User ID {sender_user} is already a contact of user ID {recipient_user}"
+            );
+            return Err(Error::UserContactExists);
+        }
+
+        let reverse = GetUserContactRequest {
+            recipient_user: sender_user,
+            sender_user: recipient_user,
+        };
+
+        if Self::user_contact_request_exists(ctx, reverse).await? {
+            debug!("Contact request already sent the other way, accepting it");
+            return Self::accept_user_contact_request(ctx, reverse).await;
+        }
+
+        create_operation!(
+            ctx,
+            UserContactRequest,
+            User,
+            recipient_user,
+            User,
+            sender_user,
+            created_by,
+        )
+    }
+
+    /// Accepts a pending contact request, making both users contacts.
+    pub async fn accept_user_contact_request(
+        ctx: &ServiceContext<'_>,
+        GetUserContactRequest {
+            recipient_user,
+            sender_user,
+        }: GetUserContactRequest,
+    ) -> Result<()> {
+        info!("Accepting contact request from user ID {sender_user} to user ID {recipient_user}");
+
+        Self::remove_user_contact_request(
+            ctx,
+            RemoveUserContactRequest {
+                recipient_user,
+                sender_user,
+                removed_by: recipient_user,
+            },
+        )
+        .await?;
+
+        for (contact_user, owner_user) in
+            [(recipient_user, sender_user), (sender_user, recipient_user)]
+        {
+            Self::create_user_contact(
+                ctx,
+                CreateUserContact {
+                    contact_user,
+                    owner_user,
+                    created_by: recipient_user,
+                    metadata: (),
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds one side of a contact.
+    ///
+    /// Contacts are only created in pairs, by accepting a contact request.
+    async fn create_user_contact(
+        ctx: &ServiceContext<'_>,
+        CreateUserContact {
+            contact_user,
+            owner_user,
+            created_by,
+            metadata: (),
+        }: CreateUserContact,
+    ) -> Result<()> {
+        create_operation!(
+            ctx,
+            UserContact,
+            User,
+            contact_user,
+            User,
+            owner_user,
+            created_by,
+        )
+    }
+
+    /// Declines a pending contact request, as the recipient.
+    pub async fn decline_user_contact_request(
+        ctx: &ServiceContext<'_>,
+        GetUserContactRequest {
+            recipient_user,
+            sender_user,
+        }: GetUserContactRequest,
+    ) -> Result<()> {
+        info!("Declining contact request from user ID {sender_user} to user ID {recipient_user}");
+
+        Self::remove_user_contact_request(
+            ctx,
+            RemoveUserContactRequest {
+                recipient_user,
+                sender_user,
+                removed_by: recipient_user,
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Cancels a pending contact request, as the sender.
+    pub async fn cancel_user_contact_request(
+        ctx: &ServiceContext<'_>,
+        GetUserContactRequest {
+            recipient_user,
+            sender_user,
+        }: GetUserContactRequest,
+    ) -> Result<()> {
+        info!("Cancelling contact request from user ID {sender_user} to user ID {recipient_user}");
+
+        Self::remove_user_contact_request(
+            ctx,
+            RemoveUserContactRequest {
+                recipient_user,
+                sender_user,
+                removed_by: sender_user,
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes a contact, for both users.
+    ///
+    /// Does nothing if the two users are not contacts.
+    pub async fn remove_user_contact_pair(
+        ctx: &ServiceContext<'_>,
+        RemoveUserContact {
+            contact_user,
+            owner_user,
+            removed_by,
+        }: RemoveUserContact,
+    ) -> Result<()> {
+        info!("Removing contact between user ID {owner_user} and user ID {contact_user}");
+
+        try_join!(
+            Self::remove_optional_user_contact(
+                ctx,
+                RemoveUserContact {
+                    contact_user,
+                    owner_user,
+                    removed_by,
+                },
+            ),
+            Self::remove_optional_user_contact(
+                ctx,
+                RemoveUserContact {
+                    contact_user: owner_user,
+                    owner_user: contact_user,
+                    removed_by,
+                },
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    /// Gets the contacts of a user, newest first.
+    pub async fn get_user_contacts(
+        ctx: &ServiceContext<'_>,
+        GetUserContacts {
+            user_id,
+            pagination,
+        }: GetUserContacts,
+    ) -> Result<RelationPage> {
+        Self::get_current_user_contact_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::From,
+            pagination,
+        )
+        .await
+    }
+
+    /// Gets the pending contact requests of a user, newest first.
+    pub async fn get_user_contact_requests(
+        ctx: &ServiceContext<'_>,
+        GetUserContactRequests {
+            user_id,
+            direction,
+            pagination,
+        }: GetUserContactRequests,
+    ) -> Result<RelationPage> {
+        let direction = match direction {
+            ContactRequestDirection::Incoming => RelationDirection::Dest,
+            ContactRequestDirection::Outgoing => RelationDirection::From,
+        };
+
+        Self::get_current_user_contact_request_entries(
+            ctx,
+            RelationObject::User(user_id),
+            direction,
+            pagination,
+        )
+        .await
+    }
+}
@@ -30,6 +30,14 @@ impl_relation!(
     NO_CREATE_IMPL,
 );
 
+#[derive(Deserialize, Debug, Copy, Clone)]
+pub struct GetUserFollows {
+    pub user_id: i64,
+
+    #[serde(flatten)]
+    pub pagination: RelationPagination,
+}
+
 impl RelationService {
     #[allow(dead_code)] // TEMP
     pub async fn create_user_follow(
@@ -54,4 +62,38 @@ impl RelationService {
             created_by,
         )
     }
+
+    /// Gets the users following this user, newest first.
+    pub async fn get_user_followers(
+        ctx: &ServiceContext<'_>,
+        GetUserFollows {
+            user_id,
+            pagination,
+        }: GetUserFollows,
+    ) -> Result<RelationPage> {
+        Self::get_current_user_follow_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::Dest,
+            pagination,
+        )
+        .await
+    }
+
+    /// Gets the users this user is following, newest first.
+    pub async fn get_user_following(
+        ctx: &ServiceContext<'_>,
+        GetUserFollows {
+            user_id,
+            pagination,
+        }: GetUserFollows,
+    ) -> Result<RelationPage> {
+        Self::get_current_user_follow_entries(
+            ctx,
+            RelationObject::User(user_id),
+            RelationDirection::From,
+            pagination,
+        )
+        .await
+    }
 }
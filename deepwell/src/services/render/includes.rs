@@ -0,0 +1,475 @@
+/*
+ * services/render/includes.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Resolution of page includes during rendering.
+//!
+//! The ftml `Includer` trait is synchronous, but fetching pages requires database
+//! access. So includes are resolved in two passes over the wikitext: the first
+//! collects which pages are included, which are then fetched (and have their own
+//! includes resolved, recursively), and the second substitutes in the fetched
+//! contents.
+
+use super::prelude::*;
+use crate::services::{PageRevisionService, PageService, SiteService, TextService};
+use crate::utils::get_regular_slug;
+use fluent::{FluentArgs, FluentValue};
+use ftml::data::PageRef;
+use ftml::includes::{FetchedPage, IncludeRef, Includer};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use unic_langid::LanguageIdentifier;
+
+static VARIABLE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\$(?P<name>[a-zA-Z0-9_\-]+)\}").unwrap());
+
+/// The locale used for include error messages if the site's locale lacks them.
+static FALLBACK_LOCALE: Lazy<LanguageIdentifier> =
+    Lazy::new(|| LanguageIdentifier::from_bytes(b"en").unwrap());
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Identifies a page by its site slug and page slug.
+type PageKey = (String, String);
+
+#[derive(Debug)]
+pub struct IncludeOutput {
+    /// The wikitext with all includes substituted.
+    pub wikitext: String,
+
+    /// All pages which were included, directly or through other included pages.
+    ///
+    /// Pages on sites which do not exist are not listed.
+    pub included_pages: Vec<PageRef<'static>>,
+}
+
+/// Replaces all include blocks in the given wikitext with the contents of the included pages.
+pub async fn resolve_includes(
+    ctx: &ServiceContext<'_>,
+    wikitext: String,
+    page_info: &PageInfo<'_>,
+    settings: &WikitextSettings,
+) -> Result<IncludeOutput> {
+    let locale = LanguageIdentifier::from_bytes(page_info.language.as_bytes())?;
+    let mut resolver = IncludeResolver {
+        ctx,
+        settings,
+        locale,
+        current_site: str!(page_info.site),
+        max_depth: ctx.config().include_max_depth,
+        site_ids: HashMap::new(),
+        pages: HashMap::new(),
+        included_pages: Vec::new(),
+    };
+
+    let mut ancestors = Vec::new();
+    let site_slug = resolver.current_site.clone();
+    let wikitext = resolver
+        .expand(wikitext, &site_slug, &mut ancestors, 0)
+        .await?;
+
+    Ok(IncludeOutput {
+        wikitext,
+        included_pages: resolver.included_pages,
+    })
+}
+
+#[derive(Debug)]
+struct IncludeResolver<'a, 'c> {
+    ctx: &'a ServiceContext<'c>,
+    settings: &'a WikitextSettings,
+    locale: LanguageIdentifier,
+    current_site: String,
+    max_depth: u32,
+
+    /// Cache of site slugs to IDs, `None` if the site does not exist.
+    site_ids: HashMap<String, Option<i64>>,
+
+    /// Cache of page wikitext, `None` if the page does not exist.
+    pages: HashMap<PageKey, Option<String>>,
+
+    included_pages: Vec<PageRef<'static>>,
+}
+
+impl<'a, 'c> IncludeResolver<'a, 'c> {
+    /// Resolves all the includes in this wikitext.
+    ///
+    /// The `site_slug` is the site of the page this wikitext is from,
+    /// which includes without a site are relative to. The `ancestors`
+    /// are the pages which are being included in order to get to this
+    /// wikitext, which is used to detect include loops.
+    fn expand<'s>(
+        &'s mut self,
+        wikitext: String,
+        site_slug: &'s str,
+        ancestors: &'s mut Vec<PageKey>,
+        depth: u32,
+    ) -> BoxFuture<'s, Result<String>>
+    where
+        'a: 's,
+        'c: 's,
+    {
+        Box::pin(async move {
+            let includes = collect_includes(&wikitext, self.settings);
+            if includes.is_empty() {
+                return Ok(wikitext);
+            }
+
+            debug!(
+                "Resolving {} includes (depth {depth}, loop check {})",
+                includes.len(),
+                ancestors.len(),
+            );
+
+            let mut contents = Vec::with_capacity(includes.len());
+            for (page_ref, variables) in includes {
+                let (key, full_slug) =
+                    include_key(&page_ref, site_slug, &self.current_site);
+
+                if ancestors.contains(&key) {
+                    warn!("Include loop found for page {full_slug}, not resolving");
+                    let message =
+                        self.error_message("wiki-page-include-loop", &full_slug)?;
+                    contents.push(message);
+                    continue;
+                }
+
+                if depth >= self.max_depth {
+                    warn!("Include depth exceeded for page {full_slug}, not resolving");
+                    let message =
+                        self.error_message("wiki-page-include-depth", &full_slug)?;
+                    contents.push(message);
+                    continue;
+                }
+
+                let content = match self.fetch_page(&key).await? {
+                    Some(content) => {
+                        let content = replace_variables(&content, &variables);
+                        let included_site = key.0.clone();
+                        ancestors.push(key);
+                        let content = self
+                            .expand(content, &included_site, ancestors, depth + 1)
+                            .await?;
+                        ancestors.pop();
+                        content
+                    }
+                    None => {
+                        self.error_message("wiki-page-include-missing", &full_slug)?
+                    }
+                };
+
+                contents.push(content);
+            }
+
+            let includer = ReplaceIncluder {
+                contents: contents.into_iter(),
+            };
+
+            let (wikitext, _) =
+                ftml::include(&wikitext, self.settings, includer, || Error::BadRequest)?;
+
+            Ok(wikitext)
+        })
+    }
+
+    /// Gets the wikitext of the given page, if it exists.
+    ///
+    /// Pages which were looked up (even if missing) are recorded
+    /// as being included, unless their site does not exist.
+    async fn fetch_page(&mut self, key: &PageKey) -> Result<Option<String>> {
+        if let Some(wikitext) = self.pages.get(key) {
+            return Ok(wikitext.clone());
+        }
+
+        let (site_slug, page_slug) = key;
+        let site_id = match self.site_ids.get(site_slug) {
+            Some(site_id) => *site_id,
+            None => {
+                let site =
+                    SiteService::get_optional(self.ctx, Reference::Slug(cow!(site_slug)))
+                        .await?;
+                let site_id = site.map(|site| site.site_id);
+                self.site_ids.insert(site_slug.clone(), site_id);
+                site_id
+            }
+        };
+
+        let wikitext = match site_id {
+            None => None,
+            Some(site_id) => {
+                self.included_pages
+                    .push(if *site_slug == self.current_site {
+                        PageRef::page_only(page_slug.clone())
+                    } else {
+                        PageRef::page_and_site(site_slug.clone(), page_slug.clone())
+                    });
+
+                let page = PageService::get_optional(
+                    self.ctx,
+                    site_id,
+                    Reference::Slug(cow!(page_slug)),
+                )
+                .await?;
+
+                match page {
+                    None => None,
+                    Some(page) => {
+                        let revision = PageRevisionService::get_latest(
+                            self.ctx,
+                            site_id,
+                            page.page_id,
+                        )
+                        .await?;
+                        let wikitext =
+                            TextService::get(self.ctx, &revision.wikitext_hash).await?;
+                        Some(wikitext)
+                    }
+                }
+            }
+        };
+
+        self.pages.insert(key.clone(), wikitext.clone());
+        Ok(wikitext)
+    }
+
+    /// Produces the wikitext shown in place of an include which cannot be resolved.
+    fn error_message(&self, key: &str, slug: &str) -> Result<String> {
+        let mut args = FluentArgs::new();
+        args.set("slug", FluentValue::String(cow!(slug)));
+
+        let locales = [&self.locale, &*FALLBACK_LOCALE];
+        let message = self.ctx.localization().translate(locales, key, &args)?;
+        Ok(format!(
+            "[[div class=\"error-block\"]]\n{message}\n[[/div]]"
+        ))
+    }
+}
+
+/// Gets the key of an included page, and the slug to show for it in messages.
+///
+/// Includes without a site are relative to the site of the page containing
+/// the include, which may differ from the site of the page being rendered.
+fn include_key(
+    page_ref: &PageRef,
+    including_site: &str,
+    current_site: &str,
+) -> (PageKey, String) {
+    let site_slug = match page_ref.site() {
+        Some(site) => get_regular_slug(site),
+        None => str!(including_site),
+    };
+    let page_slug = get_regular_slug(page_ref.page());
+    let full_slug = if page_ref.site().is_some() || site_slug != current_site {
+        format!(":{site_slug}:{page_slug}")
+    } else {
+        page_slug.clone()
+    };
+
+    ((site_slug, page_slug), full_slug)
+}
+
+/// Gets all the pages included in this wikitext, with the variables passed to them.
+fn collect_includes(
+    wikitext: &str,
+    settings: &WikitextSettings,
+) -> Vec<(PageRef<'static>, HashMap<String, String>)> {
+    let mut includer = CollectIncluder {
+        includes: Vec::new(),
+    };
+
+    match ftml::include(wikitext, settings, &mut includer, || unreachable!()) {
+        Ok(_) => (),
+        Err(never) => match never {},
+    }
+
+    includer
+        .includes
+        .into_iter()
+        .map(|include| {
+            let page_ref = include.page_ref().to_owned();
+            let variables = include
+                .variables()
+                .iter()
+                .map(|(name, value)| (str!(name), str!(value)))
+                .collect();
+
+            (page_ref, variables)
+        })
+        .collect()
+}
+
+/// Replaces all `{$variable}` instances in the content with their values.
+///
+/// Variables which were not passed in the include are left as-is.
+fn replace_variables(content: &str, variables: &HashMap<String, String>) -> String {
+    VARIABLE_REGEX
+        .replace_all(content, |captures: &regex::Captures| {
+            let name = &captures["name"];
+            match variables.get(name) {
+                Some(value) => value.clone(),
+                None => str!(&captures[0]),
+            }
+        })
+        .into_owned()
+}
+
+/// An `Includer` which only records which pages were included.
+#[derive(Debug)]
+struct CollectIncluder<'t> {
+    includes: Vec<IncludeRef<'t>>,
+}
+
+impl<'t> Includer<'t> for &mut CollectIncluder<'t> {
+    type Error = Infallible;
+
+    fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> StdResult<Vec<FetchedPage<'t>>, Infallible> {
+        self.includes.extend_from_slice(includes);
+
+        let pages = includes
+            .iter()
+            .map(|include| FetchedPage {
+                page_ref: include.page_ref().clone(),
+                content: Some(Cow::Borrowed("")),
+            })
+            .collect();
+
+        Ok(pages)
+    }
+
+    fn no_such_include(
+        &mut self,
+        _page_ref: &PageRef<'t>,
+    ) -> StdResult<Cow<'t, str>, Infallible> {
+        Ok(Cow::Borrowed(""))
+    }
+}
+
+/// An `Includer` which substitutes already-resolved contents, in order.
+#[derive(Debug)]
+struct ReplaceIncluder<I> {
+    contents: I,
+}
+
+impl<'t, I> Includer<'t> for ReplaceIncluder<I>
+where
+    I: Iterator<Item = String>,
+{
+    type Error = Error;
+
+    fn include_pages(
+        &mut self,
+        includes: &[IncludeRef<'t>],
+    ) -> Result<Vec<FetchedPage<'t>>> {
+        let mut pages = Vec::with_capacity(includes.len());
+
+        for include in includes {
+            let content = self.contents.next().ok_or(Error::BadRequest)?;
+            pages.push(FetchedPage {
+                page_ref: include.page_ref().clone(),
+                content: Some(Cow::Owned(content)),
+            });
+        }
+
+        Ok(pages)
+    }
+
+    fn no_such_include(&mut self, _page_ref: &PageRef<'t>) -> Result<Cow<'t, str>> {
+        // All includes have content, whether the page or an error message.
+        Err(Error::BadRequest)
+    }
+}
+
+#[test]
+fn variables() {
+    macro_rules! check {
+        ($content:expr, $variables:expr, $expected:expr $(,)?) => {{
+            let variables: HashMap<String, String> = $variables
+                .iter()
+                .map(|&(name, value): &(&str, &str)| (str!(name), str!(value)))
+                .collect();
+
+            assert_eq!(
+                replace_variables($content, &variables),
+                $expected,
+                "Actual substituted content doesn't match expected",
+            );
+        }};
+    }
+
+    check!("", [], "");
+    check!("apple {$fruit}", [], "apple {$fruit}");
+    check!("apple {$fruit}", [("fruit", "banana")], "apple banana");
+    check!(
+        "{$a}-{$b}-{$a} {$c}",
+        [("a", "1"), ("b", "2")],
+        "1-2-1 {$c}",
+    );
+    check!("{$fruit-name}", [("fruit-name", "{$other}")], "{$other}");
+}
+
+#[test]
+fn cross_site_includes() {
+    use ftml::settings::WikitextMode;
+
+    let settings = WikitextSettings::from_mode(WikitextMode::Page);
+    let get_keys = |wikitext: &str, including_site: &str| -> Vec<(PageKey, String)> {
+        collect_includes(wikitext, &settings)
+            .iter()
+            .map(|(page_ref, _)| include_key(page_ref, including_site, "home"))
+            .collect()
+    };
+
+    macro_rules! key {
+        ($site:expr, $page:expr, $full_slug:expr $(,)?) => {
+            ((str!($site), str!($page)), str!($full_slug))
+        };
+    }
+
+    // The page being rendered, on site "home"
+    assert_eq!(
+        get_keys(
+            "[[include-messy local-page]]\n[[include-messy :other:remote-page]]\n",
+            "home"
+        ),
+        vec![
+            key!("home", "local-page", "local-page"),
+            key!("other", "remote-page", ":other:remote-page"),
+        ],
+    );
+
+    // The included page from site "other", which includes a relative page
+    assert_eq!(
+        get_keys(
+            "[[include-messy nested-page]]\n[[include-messy :home:back-page]]\n",
+            "other"
+        ),
+        vec![
+            key!("other", "nested-page", ":other:nested-page"),
+            key!("home", "back-page", ":home:back-page"),
+        ],
+    );
+}
@@ -33,6 +33,7 @@ mod prelude {
     };
 }
 
+mod includes;
 mod service;
 mod structs;
 
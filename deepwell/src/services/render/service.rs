@@ -18,6 +18,7 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use super::includes::resolve_includes;
 use super::prelude::*;
 use crate::services::TextService;
 use tokio::time::timeout;
@@ -28,19 +29,23 @@ pub struct RenderService;
 impl RenderService {
     pub async fn render(
         ctx: &ServiceContext<'_>,
-        mut wikitext: String,
+        wikitext: String,
         page_info: &PageInfo<'_>,
         settings: &WikitextSettings,
     ) -> Result<RenderOutput> {
         let compiled_generator = FTML_VERSION.clone();
 
+        // Substitute in included pages.
+        // This is done outside of the timeout since it fetches from the database.
+        let include_output = resolve_includes(ctx, wikitext, page_info, settings).await?;
+        let mut wikitext = include_output.wikitext;
+
         // Isolate the actual render task.
         // This way we can cut it off if it times out.
 
         let config = ctx.config();
-        let (html_output, errors) = timeout(config.render_timeout, async {
+        let (mut html_output, errors) = timeout(config.render_timeout, async {
             // Run ftml to parse and render
-            // TODO include
             ftml::preprocess(&mut wikitext);
             let tokens = ftml::tokenize(&wikitext);
             let result = ftml::parse(&tokens, page_info, settings);
@@ -53,6 +58,14 @@ impl RenderService {
         // and this error variant is not specific to all timeouts.
         .map_err(|_| Error::RenderTimeout)?;
 
+        // Includes have already been substituted, so ftml does not see them.
+        // Add them as backlinks so the include connections are recorded.
+        for page_ref in include_output.included_pages {
+            if !html_output.backlinks.included_pages.contains(&page_ref) {
+                html_output.backlinks.included_pages.push(page_ref);
+            }
+        }
+
         // Insert compiled HTML into text table
         let compiled_hash = TextService::create(ctx, html_output.body.clone()).await?;
 
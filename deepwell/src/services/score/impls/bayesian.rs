@@ -0,0 +1,111 @@
+/*
+ * services/score/impls/bayesian.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+
+/// How many votes the prior is worth.
+const PRIOR_WEIGHT: f64 = 10.0;
+
+/// The assumed average vote before any votes are cast, in this case neutral.
+const PRIOR_MEAN: f64 = 0.0;
+
+#[derive(Debug)]
+pub struct BayesianScorer;
+
+#[async_trait]
+impl Scorer for BayesianScorer {
+    #[inline]
+    fn score_type(&self) -> ScoreType {
+        ScoreType::Bayesian
+    }
+
+    fn accepts_vote_type(&self, vote_type: VoteType) -> bool {
+        match vote_type {
+            VoteType::UpsDowns => true,
+            VoteType::FiveStar => false,
+        }
+    }
+
+    async fn score(
+        &self,
+        txn: &DatabaseTransaction,
+        condition: Condition,
+    ) -> Result<ScoreValue> {
+        #[derive(FromQueryResult, Debug)]
+        struct BayesianRow {
+            sum: Option<i64>,
+            count: i64,
+        }
+
+        // Query for sum and count of all votes.
+        // Same as in mean.rs
+        //
+        // As raw SQL:
+        //
+        // SELECT SUM(value), COUNT(value)
+        // FROM page_vote
+        // WHERE page_id = $1
+        // AND deleted_at IS NULL
+        // AND disabled_at IS NULL;
+
+        let BayesianRow { sum, count } = PageVote::find()
+            .select_only()
+            .column_as(page_vote::Column::Value.sum(), "sum")
+            .column_as(page_vote::Column::Value.count(), "count")
+            .filter(condition)
+            .into_model::<BayesianRow>()
+            .one(txn)
+            .await?
+            .expect("No results in aggregate query");
+
+        let score = bayesian_average(sum.unwrap_or(0), count);
+        Ok(ScoreValue::Float(score))
+    }
+}
+
+/// Calculates the Bayesian average of the votes.
+///
+/// This is the mean of the votes with a number of imaginary neutral votes
+/// added, so pages with few votes tend towards the middle rather than the
+/// extremes, until enough votes have been cast to outweigh the prior.
+fn bayesian_average(sum: i64, count: i64) -> f64 {
+    (PRIOR_WEIGHT * PRIOR_MEAN + sum as f64) / (PRIOR_WEIGHT + count as f64)
+}
+
+#[test]
+fn bayesian() {
+    macro_rules! check {
+        ($sum:expr, $count:expr, $expected:expr $(,)?) => {{
+            let actual = bayesian_average($sum, $count);
+            assert!(
+                (actual - $expected).abs() < 0.0001,
+                "Actual Bayesian average {actual} doesn't match expected {}",
+                $expected,
+            );
+        }};
+    }
+
+    check!(0, 0, 0.0);
+    check!(1, 1, 0.0909);
+    check!(-1, 1, -0.0909);
+    check!(10, 10, 0.5);
+    check!(0, 10, 0.0);
+    check!(90, 110, 0.75);
+}
@@ -43,8 +43,8 @@ impl Scorer for MeanScorer {
     ) -> Result<ScoreValue> {
         #[derive(FromQueryResult, Debug)]
         struct MeanRow {
-            sum: u64,
-            count: u64,
+            sum: Option<i64>,
+            count: i64,
         }
 
         // Query for sum of all votes.
@@ -56,10 +56,10 @@ impl Scorer for MeanScorer {
         // FROM page_vote
         // WHERE page_id = $1
         // AND deleted_at IS NULL
-        // AND disabled_at IS NULL
-        // GROUP BY value;
+        // AND disabled_at IS NULL;
 
         let MeanRow { sum, count } = PageVote::find()
+            .select_only()
             .column_as(page_vote::Column::Value.sum(), "sum")
             .column_as(page_vote::Column::Value.count(), "count")
             .filter(condition)
@@ -71,7 +71,7 @@ impl Scorer for MeanScorer {
         let score = if count == 0 {
             0.0
         } else {
-            (sum / count) as f64
+            sum.unwrap_or(0) as f64 / count as f64
         };
 
         Ok(ScoreValue::Float(score))
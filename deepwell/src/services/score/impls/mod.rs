@@ -20,12 +20,18 @@
 
 use super::prelude;
 
+mod bayesian;
 mod mean;
+mod median;
 mod null;
 mod percent;
 mod sum;
+mod wilson;
 
+pub use self::bayesian::BayesianScorer;
 pub use self::mean::MeanScorer;
+pub use self::median::MedianScorer;
 pub use self::null::NullScorer;
 pub use self::percent::PercentScorer;
 pub use self::sum::SumScorer;
+pub use self::wilson::WilsonScorer;
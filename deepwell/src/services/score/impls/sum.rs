@@ -42,7 +42,7 @@ impl Scorer for SumScorer {
     ) -> Result<ScoreValue> {
         #[derive(FromQueryResult, Debug)]
         struct SumRow {
-            sum: i64,
+            sum: Option<i64>,
         }
 
         // Query for sum of all votes.
@@ -53,10 +53,10 @@ impl Scorer for SumScorer {
         // FROM page_vote
         // WHERE page_id = $1
         // AND deleted_at IS NULL
-        // AND disabled_at IS NULL
-        // GROUP BY value;
+        // AND disabled_at IS NULL;
 
         let result = PageVote::find()
+            .select_only()
             .column_as(page_vote::Column::Value.sum(), "sum")
             .filter(condition)
             .into_model::<SumRow>()
@@ -64,6 +64,8 @@ impl Scorer for SumScorer {
             .await?
             .expect("No results in aggregate query");
 
-        Ok(ScoreValue::Integer(result.sum))
+        // SUM() is NULL if there are no votes
+        let sum = result.sum.unwrap_or(0);
+        Ok(ScoreValue::Integer(sum))
     }
 }
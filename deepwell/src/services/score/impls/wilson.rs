@@ -0,0 +1,95 @@
+/*
+ * services/score/impls/wilson.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::prelude::*;
+use crate::services::ScoreService;
+
+/// The z-score for a 95% confidence interval.
+const Z: f64 = 1.96;
+
+#[derive(Debug)]
+pub struct WilsonScorer;
+
+#[async_trait]
+impl Scorer for WilsonScorer {
+    #[inline]
+    fn score_type(&self) -> ScoreType {
+        ScoreType::Wilson
+    }
+
+    fn accepts_vote_type(&self, vote_type: VoteType) -> bool {
+        match vote_type {
+            VoteType::UpsDowns => true,
+            VoteType::FiveStar => false,
+        }
+    }
+
+    async fn score(
+        &self,
+        txn: &DatabaseTransaction,
+        condition: Condition,
+    ) -> Result<ScoreValue> {
+        let votes = ScoreService::collect_votes(txn, condition).await?;
+        let score = wilson_lower_bound(votes.get(1), votes.get(-1));
+        Ok(ScoreValue::Float(score))
+    }
+}
+
+/// Calculates the lower bound of the Wilson score interval.
+///
+/// This gives a conservative estimate of the proportion of positive votes,
+/// meaning that pages with few votes are not ranked above pages with many
+/// votes just because of a high ratio.
+///
+/// See <https://www.evanmiller.org/how-not-to-sort-by-average-rating.html>.
+fn wilson_lower_bound(upvotes: u64, downvotes: u64) -> f64 {
+    let total = (upvotes + downvotes) as f64;
+    if total == 0.0 {
+        return 0.0;
+    }
+
+    let z2 = Z * Z;
+    let p = upvotes as f64 / total;
+    let center = p + z2 / (2.0 * total);
+    let spread = Z * (p * (1.0 - p) / total + z2 / (4.0 * total * total)).sqrt();
+    (center - spread) / (1.0 + z2 / total)
+}
+
+#[test]
+fn wilson() {
+    macro_rules! check {
+        ($upvotes:expr, $downvotes:expr, $expected:expr $(,)?) => {{
+            let actual = wilson_lower_bound($upvotes, $downvotes);
+            assert!(
+                (actual - $expected).abs() < 0.0001,
+                "Actual Wilson score {actual} doesn't match expected {}",
+                $expected,
+            );
+        }};
+    }
+
+    check!(0, 0, 0.0);
+    check!(0, 10, 0.0);
+    check!(1, 0, 0.2065);
+    check!(10, 0, 0.7225);
+    check!(5, 5, 0.2366);
+    check!(100, 0, 0.9630);
+    check!(90, 10, 0.8256);
+}
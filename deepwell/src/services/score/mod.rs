@@ -37,4 +37,5 @@ mod structs;
 pub use self::impls::*;
 pub use self::scorer::Scorer;
 pub use self::service::ScoreService;
+pub use self::structs::*;
 pub use ftml::data::ScoreValue;
@@ -21,16 +21,14 @@
 use super::prelude::*;
 
 #[async_trait]
-pub trait Scorer {
+pub trait Scorer: Send + Sync {
     /// What kind of score this scorer evaluates.
     ///
     /// There should be a 1-to-1 mapping between `Scorer`
     /// implementations and values for the `ScoreType` enum.
-    #[allow(dead_code)] // TEMP
     fn score_type(&self) -> ScoreType;
 
     /// Whether this scorer accepts vote maps of this type.
-    #[allow(dead_code)] // TEMP
     fn accepts_vote_type(&self, vtype: VoteType) -> bool;
 
     /// Calculates the score associated with the given page ID.
@@ -20,6 +20,9 @@
 
 use super::impls::*;
 use super::prelude::*;
+use crate::models::page::Model as PageModel;
+use crate::models::site::Model as SiteModel;
+use crate::services::{PageService, SiteService};
 
 #[derive(Debug)]
 pub struct ScoreService;
@@ -29,19 +32,55 @@ impl ScoreService {
         let txn = ctx.transaction();
         let condition = Self::build_condition(page_id);
         let scorer = Self::get_scorer(ctx, page_id).await?;
+        debug!(
+            "Calculating score for page ID {page_id} using {:?}",
+            scorer.score_type(),
+        );
+
         let score = scorer.score(txn, condition).await?;
         Ok(score)
     }
 
     /// Gets the correct `Scorer` implementation for this page.
     ///
-    /// Currently stubbed, will be implemented when relevant settings are added.
+    /// This is determined by the score type configured for the page's site.
     pub async fn get_scorer(
-        _ctx: &ServiceContext<'_>,
-        _page_id: i64,
-    ) -> Result<&'static impl Scorer> {
-        // TODO
-        Ok(&NullScorer)
+        ctx: &ServiceContext<'_>,
+        page_id: i64,
+    ) -> Result<&'static dyn Scorer> {
+        let PageModel { site_id, .. } =
+            PageService::get_direct(ctx, page_id, true).await?;
+
+        let SiteModel { score_type, .. } =
+            SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        Ok(Self::get_scorer_for_type(score_type))
+    }
+
+    /// Gets the `Scorer` implementation for this score type.
+    pub fn get_scorer_for_type(score_type: ScoreType) -> &'static dyn Scorer {
+        match score_type {
+            ScoreType::Null => &NullScorer,
+            ScoreType::Sum => &SumScorer,
+            ScoreType::Mean => &MeanScorer,
+            ScoreType::Median => &MedianScorer,
+            ScoreType::Percent => &PercentScorer,
+            ScoreType::Wilson => &WilsonScorer,
+            ScoreType::Bayesian => &BayesianScorer,
+        }
+    }
+
+    /// Ensures that the given score type can be used with the given vote type.
+    pub fn check_policy(score_type: ScoreType, vote_type: VoteType) -> Result<()> {
+        let scorer = Self::get_scorer_for_type(score_type);
+        if scorer.accepts_vote_type(vote_type) {
+            Ok(())
+        } else {
+            warn!(
+                "Score type {score_type:?} cannot be used with vote type {vote_type:?}"
+            );
+            Err(Error::ScoreTypeIncompatible)
+        }
     }
 
     /// Helper method for retrieving a `VoteMap` for a page.
@@ -70,6 +109,7 @@ impl ScoreService {
         }
 
         let counts = PageVote::find()
+            .select_only()
             .column(page_vote::Column::Value)
             .column_as(page_vote::Column::Value.count(), "count")
             .filter(condition)
@@ -88,7 +128,7 @@ impl ScoreService {
         Ok(map)
     }
 
-    fn build_condition(page_id: i64) -> Condition {
+    pub(crate) fn build_condition(page_id: i64) -> Condition {
         Condition::all()
             .add(page_vote::Column::PageId.eq(page_id))
             .add(page_vote::Column::DeletedAt.is_null())
@@ -20,24 +20,9 @@
 
 use std::collections::BTreeMap;
 
+pub use crate::models::sea_orm_active_enums::{ScoreType, VoteType};
 pub use crate::services::vote::VoteValue;
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
-#[non_exhaustive]
-pub enum VoteType {
-    UpsDowns,
-    FiveStar,
-}
-
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
-pub enum ScoreType {
-    Null,
-    Sum,
-    Mean,
-    Median,
-    Percent,
-}
-
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct VoteMap {
     inner: BTreeMap<VoteValue, u64>,
@@ -65,7 +50,6 @@ impl VoteMap {
     }
 
     #[inline]
-    #[allow(dead_code)] // TEMP
     pub fn is_empty(&self) -> bool {
         self.count() == 0
     }
@@ -81,9 +65,69 @@ impl VoteMap {
         })
     }
 
+    /// Gets the median of all the votes in this map.
+    ///
+    /// If there are an even number of votes, then the mean of
+    /// the two middle votes is used. Returns `None` if there are no votes.
+    pub fn median(&self) -> Option<f64> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        // Zero-indexed positions of the middle vote(s).
+        // If the count is odd, these are the same.
+        let lower_index = (count - 1) / 2;
+        let upper_index = count / 2;
+
+        let mut lower = None;
+        let mut seen = 0;
+
+        for (value, value_count) in self.iter() {
+            seen += value_count;
+
+            if lower.is_none() && lower_index < seen {
+                lower = Some(value);
+            }
+
+            if upper_index < seen {
+                let lower = f64::from(lower.unwrap_or(value));
+                let upper = f64::from(value);
+                return Some((lower + upper) / 2.0);
+            }
+        }
+
+        unreachable!("Vote count doesn't match vote map contents")
+    }
+
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = (VoteValue, u64)> + '_ {
         // We can't quite use .copied() here because we need to copy the tuple too
         self.inner.iter().map(|(&value, &count)| (value, count))
     }
 }
+
+#[test]
+fn vote_map() {
+    macro_rules! check {
+        ($votes:expr, $count:expr, $sum:expr, $median:expr $(,)?) => {{
+            let mut map = VoteMap::new();
+            for &(value, count) in $votes.iter() {
+                map.insert(value, count);
+            }
+
+            assert_eq!(map.count(), $count, "Vote count doesn't match");
+            assert_eq!(map.sum(), $sum, "Vote sum doesn't match");
+            assert_eq!(map.median(), $median, "Vote median doesn't match");
+        }};
+    }
+
+    check!([], 0, 0, None);
+    check!([(1, 0)], 0, 0, None);
+    check!([(1, 3)], 3, 3, Some(1.0));
+    check!([(-1, 2), (1, 2)], 4, 0, Some(0.0));
+    check!([(-1, 1), (1, 2)], 3, 1, Some(1.0));
+    check!([(-1, 3), (1, 2)], 5, -1, Some(-1.0));
+    check!([(1, 1), (2, 1), (5, 2)], 4, 13, Some(3.5));
+    check!([(1, 4), (3, 0), (4, 1), (5, 4)], 9, 28, Some(4.0));
+}
@@ -25,9 +25,15 @@ use crate::constants::SYSTEM_USER_ID;
 use crate::models::sea_orm_active_enums::{AliasType, UserType};
 use crate::models::site::{self, Entity as Site, Model as SiteModel};
 use crate::services::alias::CreateAlias;
-use crate::services::relation::CreateSiteUser;
+use crate::services::permission::SITE_CREATOR_ROLE;
+use crate::services::relation::{
+    CreateSiteMember, CreateSiteRole, CreateSiteUser, SiteMemberAccepted, SiteMemberData,
+    SiteRoleData,
+};
 use crate::services::user::{CreateUser, UpdateUserBody};
-use crate::services::{AliasService, RelationService, UserService};
+use crate::services::{
+    AliasService, OutdateService, RelationService, ScoreService, UserService, VoteService,
+};
 use crate::utils::validate_locale;
 
 #[derive(Debug)]
@@ -42,6 +48,7 @@ impl SiteService {
             tagline,
             description,
             locale,
+            user_id: creator_id,
         }: CreateSite,
     ) -> Result<CreateSiteOutput> {
         let txn = ctx.transaction();
@@ -104,6 +111,33 @@ impl SiteService {
         )
         .await?;
 
+        // Make the creator a member and administrator, so the site can be managed
+        RelationService::create_site_member(
+            ctx,
+            CreateSiteMember {
+                site_id: site.site_id,
+                user_id: creator_id,
+                metadata: SiteMemberData {
+                    accepted: SiteMemberAccepted::CreatedSite,
+                },
+                created_by: creator_id,
+            },
+        )
+        .await?;
+
+        RelationService::create_site_role(
+            ctx,
+            CreateSiteRole {
+                site_id: site.site_id,
+                user_id: creator_id,
+                metadata: SiteRoleData {
+                    role: SITE_CREATOR_ROLE,
+                },
+                created_by: creator_id,
+            },
+        )
+        .await?;
+
         // Return
         Ok(CreateSiteOutput {
             site_id: site.site_id,
@@ -156,6 +190,39 @@ impl SiteService {
             site_user_body.locales = ProvidedValue::Set(vec![locale]);
         }
 
+        if let ProvidedValue::Set(licensing) = input.default_file_licensing {
+            licensing.validate().map_err(Error::FileLicensingInvalid)?;
+            model.default_file_licensing = Set(serde_json::to_value(licensing)?);
+        }
+
+        // Changing the scoring policy requires all page scores to be recalculated
+        let score_type = input
+            .score_type
+            .to_option()
+            .copied()
+            .unwrap_or(site.score_type);
+        let vote_type = input
+            .vote_type
+            .to_option()
+            .copied()
+            .unwrap_or(site.vote_type);
+        let vote_type_changed = vote_type != site.vote_type;
+        let policy_changed = score_type != site.score_type || vote_type_changed;
+
+        if vote_type_changed && !input.reset_votes {
+            error!(
+                "Cannot change vote type of site ID {} from {:?} to {vote_type:?} without resetting votes",
+                site.site_id, site.vote_type,
+            );
+            return Err(Error::VoteTypeChangeNeedsReset);
+        }
+
+        if policy_changed {
+            ScoreService::check_policy(score_type, vote_type)?;
+            model.score_type = Set(score_type);
+            model.vote_type = Set(vote_type);
+        }
+
         // Update site
         model.updated_at = Set(Some(now()));
         let new_site = model.update(txn).await?;
@@ -171,6 +238,20 @@ impl SiteService {
             )?;
         }
 
+        // Existing votes have a different meaning under another vote type
+        if vote_type_changed {
+            let removed = VoteService::remove_site_votes(ctx, site.site_id).await?;
+            warn!(
+                "User ID {updating_user_id} changed vote type of site ID {} from {:?} to {vote_type:?}, removing {removed} votes",
+                site.site_id, site.vote_type,
+            );
+        }
+
+        // Rerender pages with the new score
+        if policy_changed {
+            OutdateService::outdate_site(ctx, site.site_id).await?;
+        }
+
         // Return
         Ok(new_site)
     }
@@ -225,6 +306,37 @@ impl SiteService {
         Ok(())
     }
 
+    /// Overrides the storage quota for files on this site.
+    ///
+    /// This is not part of `update()`, since only platform staff may change it.
+    pub async fn set_file_quota(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+        quota_bytes: Option<u64>,
+    ) -> Result<SiteModel> {
+        let txn = ctx.transaction();
+
+        info!("Setting file quota for site ID {site_id} to {quota_bytes:?} bytes");
+
+        let quota_bytes = match quota_bytes {
+            None => None,
+            Some(0) => {
+                error!("Site file quota cannot be zero");
+                return Err(Error::BadRequest);
+            }
+            Some(quota_bytes) => Some(i64::try_from(quota_bytes).unwrap_or(i64::MAX)),
+        };
+
+        let model = site::ActiveModel {
+            site_id: Set(site_id),
+            file_quota_bytes: Set(quota_bytes),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+        let site = model.update(txn).await?;
+        Ok(site)
+    }
+
     #[inline]
     pub async fn exists(
         ctx: &ServiceContext<'_>,
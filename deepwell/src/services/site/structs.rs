@@ -21,6 +21,8 @@
 use crate::models::alias::Model as AliasModel;
 use crate::models::site::Model as SiteModel;
 use crate::models::site_domain::Model as SiteDomainModel;
+use crate::services::file::FileLicensing;
+use crate::services::score::{ScoreType, VoteType};
 use crate::web::{ProvidedValue, Reference};
 
 #[derive(Deserialize, Debug, Clone)]
@@ -30,6 +32,9 @@ pub struct CreateSite {
     pub tagline: String,
     pub description: String,
     pub locale: String,
+
+    /// The user creating the site, who becomes its first administrator.
+    pub user_id: i64,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -39,6 +44,17 @@ pub struct CreateSiteOutput {
     pub slug: String,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+pub struct SetSiteFileQuota {
+    pub site_id: i64,
+    pub user_id: i64,
+
+    /// The site's storage quota for files, replacing the configured default.
+    ///
+    /// If `None`, then the configured default is used again.
+    pub quota_bytes: Option<u64>,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetSite<'a> {
     pub site: Reference<'a>,
@@ -69,4 +85,15 @@ pub struct UpdateSiteBody {
     pub tagline: ProvidedValue<String>,
     pub description: ProvidedValue<String>,
     pub locale: ProvidedValue<String>,
+    pub score_type: ProvidedValue<ScoreType>,
+
+    /// Changing the vote type requires `reset_votes` to be set.
+    pub vote_type: ProvidedValue<VoteType>,
+    pub default_file_licensing: ProvidedValue<FileLicensing>,
+
+    /// Confirms that all current votes on the site's pages should be removed.
+    ///
+    /// Votes cast under one vote type are meaningless under another,
+    /// so this must be set to change the vote type.
+    pub reset_votes: bool,
 }
@@ -19,12 +19,12 @@
  */
 
 use super::prelude::*;
-use crate::models::sea_orm_active_enums::{AliasType, UserType};
+use crate::models::sea_orm_active_enums::{AliasType, FilterContentType, UserType};
 use crate::models::user::{self, Entity as User, Model as UserModel};
 use crate::services::alias::CreateAlias;
 use crate::services::blob::{BlobService, CreateBlobOutput};
 use crate::services::email::{EmailClassification, EmailService};
-use crate::services::filter::{FilterClass, FilterType};
+use crate::services::filter::{FilterCheck, FilterClass, FilterType};
 use crate::services::{AliasService, FilterService, PasswordService};
 use crate::utils::regex_replace_in_place;
 use once_cell::sync::Lazy;
@@ -79,8 +79,8 @@ impl UserService {
         // Perform filter validation
         if !bypass_filter {
             try_join!(
-                Self::run_name_filter(ctx, &name, &slug),
-                Self::run_email_filter(ctx, &email),
+                Self::run_name_filter(ctx, None, &name, &slug),
+                Self::run_email_filter(ctx, None, &email),
             )?;
         }
 
@@ -364,7 +364,7 @@ impl UserService {
 
         if let ProvidedValue::Set(email) = input.email {
             if !input.bypass_filter {
-                Self::run_email_filter(ctx, &email).await?;
+                Self::run_email_filter(ctx, Some(user.user_id), &email).await?;
             }
 
             // Validate email
@@ -421,6 +421,10 @@ impl UserService {
             model.user_page = Set(user_page);
         }
 
+        if let ProvidedValue::Set(message_privacy) = input.message_privacy {
+            model.message_privacy = Set(message_privacy);
+        }
+
         if let ProvidedValue::Set(avatar) = input.avatar {
             let s3_hash = match avatar {
                 None => None,
@@ -481,7 +485,7 @@ impl UserService {
 
         // Perform filter validation
         if !bypass_filter {
-            Self::run_name_filter(ctx, &new_name, &new_slug).await?;
+            Self::run_name_filter(ctx, Some(user.user_id), &new_name, &new_slug).await?;
         }
 
         if new_slug == user.slug {
@@ -616,6 +620,29 @@ impl UserService {
         Ok(name_changes)
     }
 
+    /// Grants or revokes platform staff status for a user.
+    ///
+    /// This is deliberately not part of `UpdateUserBody`, so that
+    /// it cannot be changed through the regular user edit methods.
+    pub async fn set_platform_staff(
+        ctx: &ServiceContext<'_>,
+        user_id: i64,
+        platform_staff: bool,
+    ) -> Result<()> {
+        info!("Setting platform staff status for user ID {user_id} to {platform_staff}");
+
+        let txn = ctx.transaction();
+        let model = user::ActiveModel {
+            user_id: Set(user_id),
+            platform_staff: Set(platform_staff),
+            updated_at: Set(Some(now())),
+            ..Default::default()
+        };
+        model.update(txn).await?;
+
+        Ok(())
+    }
+
     /// Set the MFA secret fields for a user.
     pub async fn set_mfa_secrets(
         ctx: &ServiceContext<'_>,
@@ -694,6 +721,7 @@ impl UserService {
 
     async fn run_name_filter(
         ctx: &ServiceContext<'_>,
+        user_id: Option<i64>,
         name: &str,
         slug: &str,
     ) -> Result<()> {
@@ -703,22 +731,38 @@ impl UserService {
             FilterService::get_matcher(ctx, FilterClass::Platform, FilterType::User)
                 .await?;
 
+        let check = FilterCheck {
+            site_id: None,
+            user_id,
+            content_type: FilterContentType::UserName,
+        };
+
         try_join!(
-            filter_matcher.verify(ctx, name),
-            filter_matcher.verify(ctx, slug),
+            filter_matcher.verify(ctx, check, name),
+            filter_matcher.verify(ctx, check, slug),
         )?;
 
         Ok(())
     }
 
-    async fn run_email_filter(ctx: &ServiceContext<'_>, email: &str) -> Result<()> {
+    async fn run_email_filter(
+        ctx: &ServiceContext<'_>,
+        user_id: Option<i64>,
+        email: &str,
+    ) -> Result<()> {
         info!("Checking user email data against filters...");
 
         let filter_matcher =
             FilterService::get_matcher(ctx, FilterClass::Platform, FilterType::Email)
                 .await?;
 
-        filter_matcher.verify(ctx, email).await?;
+        let check = FilterCheck {
+            site_id: None,
+            user_id,
+            content_type: FilterContentType::Email,
+        };
+
+        filter_matcher.verify(ctx, check, email).await?;
         Ok(())
     }
 
@@ -20,7 +20,7 @@
 
 use super::prelude::*;
 use crate::models::alias::Model as AliasModel;
-use crate::models::sea_orm_active_enums::UserType;
+use crate::models::sea_orm_active_enums::{MessagePrivacy, UserType};
 use crate::models::user::Model as UserModel;
 use crate::web::Bytes;
 use time::Date;
@@ -80,6 +80,7 @@ pub struct UpdateUserBody {
     pub location: ProvidedValue<Option<String>>,
     pub biography: ProvidedValue<Option<String>>,
     pub user_page: ProvidedValue<Option<String>>,
+    pub message_privacy: ProvidedValue<MessagePrivacy>,
 
     #[serde(default)]
     pub bypass_filter: bool,
@@ -34,11 +34,12 @@ use crate::models::page::Model as PageModel;
 use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::site::Model as SiteModel;
 use crate::services::domain::SiteDomainResult;
+use crate::services::permission::{GetUserPermissions, PermissionType, UserPermissions};
 use crate::services::render::RenderOutput;
 use crate::services::special_page::{GetSpecialPageOutput, SpecialPageType};
 use crate::services::{
-    DomainService, PageRevisionService, PageService, SessionService, SpecialPageService,
-    TextService, UserService,
+    DomainService, PageRevisionService, PageService, PermissionService, SessionService,
+    SpecialPageService, TextService, UserService,
 };
 use crate::utils::split_category;
 use fluent::{FluentArgs, FluentValue};
@@ -158,19 +159,17 @@ impl ViewService {
                 let user_permissions = match user_session {
                     Some(ref session) => session.user_permissions,
                     None => {
-                        debug!("No user for session, getting guest permission scheme",);
-
-                        // TODO get permissions from service
-                        UserPermissions
+                        debug!("No user for session, using guest permissions");
+                        UserPermissions::GUEST
                     }
                 };
 
                 // Determine whether to return the actual page contents,
                 // or the "private page" data (_public).
                 //
-                // This returns false if the user is banned *and* the site
-                // disallows banned viewing.
-                if Self::can_access_page(ctx, user_permissions).await? {
+                // This returns false if the user is banned, or if the
+                // page's category requires a higher role to view.
+                if Self::can_access_page(ctx, &page, user_permissions).await? {
                     debug!("User has page access, return text data");
 
                     let (wikitext, compiled_html) = try_join!(
@@ -361,7 +360,7 @@ impl ViewService {
         info!("Getting viewer data from domain '{domain}' and session token");
 
         // Get user data from session token (if present)
-        let user_session = match session_token {
+        let session_user = match session_token {
             None => None,
             Some("") => None,
             Some(token) => {
@@ -390,11 +389,7 @@ impl ViewService {
                     debug_assert!(user_locales.is_empty());
                 }
 
-                Some(UserSession {
-                    session,
-                    user,
-                    user_permissions: UserPermissions, // TODO add user permissions, get scheme for user and site
-                })
+                Some((session, user))
             }
         };
 
@@ -426,6 +421,27 @@ impl ViewService {
                 }
             };
 
+        // Get user permissions, now that we know the site
+        let user_session = match session_user {
+            None => None,
+            Some((session, user)) => {
+                let user_permissions = PermissionService::get_user_permissions(
+                    ctx,
+                    GetUserPermissions {
+                        site_id: site.site_id,
+                        user_id: Some(user.user_id),
+                    },
+                )
+                .await?;
+
+                Some(UserSession {
+                    session,
+                    user,
+                    user_permissions,
+                })
+            }
+        };
+
         Ok(ViewerResult::FoundSite(Viewer {
             site,
             redirect_site,
@@ -475,13 +491,19 @@ impl ViewService {
     }
 
     async fn can_access_page(
-        _ctx: &ServiceContext<'_>,
+        ctx: &ServiceContext<'_>,
+        page: &PageModel,
         permissions: UserPermissions,
     ) -> Result<bool> {
         info!("Checking page access: {permissions:?}");
-        debug!("TODO: stub");
-        // TODO perform permission checks
-        Ok(true)
+        PermissionService::has_permission(
+            ctx,
+            page.site_id,
+            Some(page.page_category_id),
+            permissions,
+            PermissionType::View,
+        )
+        .await
     }
 
     fn should_redirect_site(
@@ -25,17 +25,7 @@ use crate::models::page_revision::Model as PageRevisionModel;
 use crate::models::session::Model as SessionModel;
 use crate::models::site::Model as SiteModel;
 use crate::models::user::Model as UserModel;
-
-// TODO replace with actual user permissions type
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
-pub struct UserPermissions;
-
-impl UserPermissions {
-    pub fn is_banned(self) -> bool {
-        // TODO value from struct
-        false
-    }
-}
+use crate::services::permission::UserPermissions;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct GetPageView {
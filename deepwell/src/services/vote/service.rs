@@ -20,7 +20,12 @@
  */
 
 use super::prelude::*;
+use crate::models::page::{self, Entity as Page, Model as PageModel};
 use crate::models::page_vote::{self, Entity as PageVote, Model as PageVoteModel};
+use crate::models::site::Model as SiteModel;
+use crate::services::score::VoteType;
+use crate::services::{PageService, SiteService};
+use sea_orm::sea_query::{Expr, Query};
 use sea_orm::IntoActiveModel;
 
 #[derive(Debug)]
@@ -46,6 +51,18 @@ impl VoteService {
             user_id, page_id, value,
         );
 
+        // Ensure the vote is allowed by the site's policy
+        let PageModel { site_id, .. } =
+            PageService::get_direct(ctx, page_id, false).await?;
+
+        let SiteModel { vote_type, .. } =
+            SiteService::get(ctx, Reference::Id(site_id)).await?;
+
+        if !Self::value_allowed(vote_type, value) {
+            warn!("Vote value {value} is not allowed for vote type {vote_type:?}");
+            return Err(Error::VoteValueInvalid);
+        }
+
         // Get previous vote, if any
         let key = GetVote { page_id, user_id };
         if let Some(vote) = Self::get_optional(ctx, key).await? {
@@ -72,6 +89,49 @@ impl VoteService {
         Ok(Some(vote))
     }
 
+    /// Determines if a vote value is permitted for the given vote type.
+    pub fn value_allowed(vote_type: VoteType, value: VoteValue) -> bool {
+        match vote_type {
+            VoteType::UpsDowns => matches!(value, -1 | 1),
+            VoteType::FiveStar => matches!(value, 1..=5),
+        }
+    }
+
+    /// Removes all current votes on pages in the site.
+    ///
+    /// This is needed when the site's vote type changes, since the same
+    /// value can mean something else under the new type (e.g. `1` is an
+    /// upvote for `UpsDowns`, but the lowest rating for `FiveStar`).
+    ///
+    /// Votes are soft-deleted, so they remain in the page's vote history.
+    pub async fn remove_site_votes(
+        ctx: &ServiceContext<'_>,
+        site_id: i64,
+    ) -> Result<u64> {
+        info!("Removing all current votes on pages in site ID {site_id}");
+
+        let txn = ctx.transaction();
+        let result = PageVote::update_many()
+            .col_expr(page_vote::Column::DeletedAt, Expr::value(now()))
+            .filter(
+                Condition::all()
+                    .add(page_vote::Column::DeletedAt.is_null())
+                    .add(
+                        page_vote::Column::PageId.in_subquery(
+                            Query::select()
+                                .column(page::Column::PageId)
+                                .from(Page)
+                                .and_where(page::Column::SiteId.eq(site_id))
+                                .to_owned(),
+                        ),
+                    ),
+            )
+            .exec(txn)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
     #[inline]
     pub async fn get(ctx: &ServiceContext<'_>, key: GetVote) -> Result<PageVoteModel> {
         find_or_error!(Self::get_optional(ctx, key), Vote)
@@ -0,0 +1,291 @@
+/*
+ * utils/diff.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Generic sequence diffing, using the Myers algorithm.
+//!
+//! See "An O(ND) Difference Algorithm and Its Variations" (Myers, 1986).
+
+/// The largest edit distance that is searched for.
+///
+/// Both the time and memory used grow with the edit distance, so sequences
+/// which differ by more than this are treated as being entirely replaced.
+const MAXIMUM_EDIT_DISTANCE: usize = 1000;
+
+/// A single step in transforming the old sequence into the new one.
+///
+/// Indices refer to positions in the old and new sequences respectively.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiffEdit {
+    Equal(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Finds a minimal set of edits to transform `old` into `new`.
+///
+/// The edits are returned in order, covering every item in both sequences.
+/// Within a changed region, removals come before additions.
+///
+/// If the sequences differ by more than `MAXIMUM_EDIT_DISTANCE` edits,
+/// then everything between their common prefix and suffix is replaced.
+pub fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffEdit> {
+    diff_bounded(old, new, MAXIMUM_EDIT_DISTANCE)
+}
+
+fn diff_bounded<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    max_distance: usize,
+) -> Vec<DiffEdit> {
+    // Strip common prefix and suffix, which is cheap
+    // and usually most of the input for small edits.
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    edits.extend((0..prefix).map(|i| DiffEdit::Equal(i, i)));
+    myers(
+        &old[prefix..old_end],
+        &new[prefix..new_end],
+        prefix,
+        max_distance,
+        &mut edits,
+    );
+    edits.extend((0..suffix).map(|i| DiffEdit::Equal(old_end + i, new_end + i)));
+    edits
+}
+
+/// Replaces all of `old` with all of `new`.
+fn replace_all(old_len: usize, new_len: usize, offset: usize, edits: &mut Vec<DiffEdit>) {
+    edits.extend((0..old_len).map(|i| DiffEdit::Removed(offset + i)));
+    edits.extend((0..new_len).map(|i| DiffEdit::Added(offset + i)));
+}
+
+fn myers<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    offset: usize,
+    max_distance: usize,
+    edits: &mut Vec<DiffEdit>,
+) {
+    // Trivial cases
+    if old.is_empty() || new.is_empty() {
+        replace_all(old.len(), new.len(), offset, edits);
+        return;
+    }
+
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let max = n + m;
+    let max_distance = max.min(isize::try_from(max_distance).unwrap_or(isize::MAX));
+
+    // Index into the diagonal array for diagonal k.
+    let at = |k: isize| (k + max) as usize;
+
+    // Whether the path to diagonal k came down from k + 1 (an addition),
+    // rather than across from k - 1 (a removal).
+    let goes_down = |get: &dyn Fn(isize) -> isize, k: isize, d: isize| {
+        k == -d || (k != d && get(k - 1) < get(k + 1))
+    };
+
+    // Find the shortest edit script, saving each step to backtrack.
+    //
+    // Step d only reads diagonals -d - 1 to d + 1 of the previous step,
+    // so only those are saved, keeping memory proportional to the
+    // square of the edit distance rather than the length of the input.
+    let mut v = vec![0; 2 * max as usize + 2];
+    let mut trace = Vec::new();
+    let mut found = false;
+
+    'search: for d in 0..=max_distance {
+        let start = (max - d - 1).max(0) as usize;
+        let end = (at(d + 1) + 1).min(v.len());
+        trace.push((start, v[start..end].to_vec()));
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if goes_down(&|k| v[at(k)], k, d) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                found = true;
+                break 'search;
+            }
+        }
+    }
+
+    // Too many changes, so treat it as a replacement instead.
+    if !found {
+        replace_all(old.len(), new.len(), offset, edits);
+        return;
+    }
+
+    // Walk back through the trace to recover the edits.
+    let mut reversed = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for (d, (start, v)) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let get = |k: isize| v[at(k) - start];
+        let k = x - y;
+        let prev_k = if goes_down(&get, k, d) { k + 1 } else { k - 1 };
+        let prev_x = get(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            reversed.push(DiffEdit::Equal(offset + x as usize, offset + y as usize));
+        }
+
+        if d > 0 {
+            if x == prev_x {
+                reversed.push(DiffEdit::Added(offset + prev_y as usize));
+            } else {
+                reversed.push(DiffEdit::Removed(offset + prev_x as usize));
+            }
+        }
+
+        x = prev_x;
+        y = prev_y;
+    }
+
+    // Put removals before additions within each changed region
+    reversed.reverse();
+    for chunk in reversed.split_mut(|edit| matches!(edit, DiffEdit::Equal(_, _))) {
+        chunk.sort_by_key(|edit| match *edit {
+            DiffEdit::Removed(i) => (0, i),
+            DiffEdit::Added(i) => (1, i),
+            DiffEdit::Equal(_, _) => unreachable!(),
+        });
+    }
+
+    edits.append(&mut reversed);
+}
+
+#[test]
+fn diff_sequences() {
+    macro_rules! check {
+        ($old:expr, $new:expr, $changes:expr $(,)?) => {{
+            let old: Vec<char> = $old.chars().collect();
+            let new: Vec<char> = $new.chars().collect();
+            let edits = diff(&old, &new);
+
+            // Rebuild both sequences from the edits
+            let mut rebuilt_old = String::new();
+            let mut rebuilt_new = String::new();
+            let mut changes = 0;
+
+            for edit in &edits {
+                match *edit {
+                    DiffEdit::Equal(i, j) => {
+                        assert_eq!(old[i], new[j], "Equal edit for unequal items");
+                        rebuilt_old.push(old[i]);
+                        rebuilt_new.push(new[j]);
+                    }
+                    DiffEdit::Removed(i) => {
+                        rebuilt_old.push(old[i]);
+                        changes += 1;
+                    }
+                    DiffEdit::Added(j) => {
+                        rebuilt_new.push(new[j]);
+                        changes += 1;
+                    }
+                }
+            }
+
+            assert_eq!(rebuilt_old, $old, "Edits don't cover old sequence");
+            assert_eq!(rebuilt_new, $new, "Edits don't cover new sequence");
+            assert_eq!(changes, $changes, "Edit script is not minimal: {edits:?}");
+        }};
+    }
+
+    check!("", "", 0);
+    check!("abc", "abc", 0);
+    check!("", "abc", 3);
+    check!("abc", "", 3);
+    check!("abc", "abd", 2);
+    check!("abc", "xabc", 1);
+    check!("abc", "abcx", 1);
+    check!("abcabba", "cbabac", 5);
+    check!("kitten", "sitting", 5);
+    check!("the quick fox", "the slow fox", 9);
+    check!("aaaa", "aa", 2);
+    check!("xyz", "abc", 6);
+}
+
+#[test]
+fn diff_bounded_distance() {
+    let old: Vec<char> = "abcdef".chars().collect();
+    let new: Vec<char> = "axcyef".chars().collect();
+
+    // Within the limit, the minimal edits are found
+    assert_eq!(
+        diff_bounded(&old, &new, 4),
+        vec![
+            DiffEdit::Equal(0, 0),
+            DiffEdit::Removed(1),
+            DiffEdit::Added(1),
+            DiffEdit::Equal(2, 2),
+            DiffEdit::Removed(3),
+            DiffEdit::Added(3),
+            DiffEdit::Equal(4, 4),
+            DiffEdit::Equal(5, 5),
+        ],
+    );
+
+    // Beyond it, everything between the common prefix and suffix is replaced
+    assert_eq!(
+        diff_bounded(&old, &new, 3),
+        vec![
+            DiffEdit::Equal(0, 0),
+            DiffEdit::Removed(1),
+            DiffEdit::Removed(2),
+            DiffEdit::Removed(3),
+            DiffEdit::Added(1),
+            DiffEdit::Added(2),
+            DiffEdit::Added(3),
+            DiffEdit::Equal(4, 4),
+            DiffEdit::Equal(5, 5),
+        ],
+    );
+}
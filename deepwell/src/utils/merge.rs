@@ -0,0 +1,163 @@
+/*
+ * utils/merge.rs
+ *
+ * DEEPWELL - Wikijump API provider and database manager
+ * Copyright (C) 2019-2023 Wikijump Team
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Three-way merging of sequences, in the style of diff3.
+//!
+//! Both sides are diffed against the common base. Regions where the base
+//! is unchanged in both are kept, and in between, whichever side changed
+//! is taken. If both sides changed a region differently, it is a conflict.
+
+use super::diff::{diff, DiffEdit};
+
+/// A contiguous region of the merged output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeHunk<T> {
+    Resolved(Vec<T>),
+    Conflict {
+        base: Vec<T>,
+        ours: Vec<T>,
+        theirs: Vec<T>,
+    },
+}
+
+/// Merges the changes made from `base` to `ours` and from `base` to `theirs`.
+///
+/// Adjacent resolved regions are combined into a single hunk.
+pub fn merge3<T: PartialEq + Clone>(
+    base: &[T],
+    ours: &[T],
+    theirs: &[T],
+) -> Vec<MergeHunk<T>> {
+    let ours_matches = base_matches(base, ours);
+    let theirs_matches = base_matches(base, theirs);
+
+    let mut hunks = Vec::new();
+    let mut resolved = Vec::new();
+    let (mut i, mut o, mut t) = (0, 0, 0);
+
+    loop {
+        // Stable item, unchanged in both
+        if i < base.len() && ours_matches[i] == Some(o) && theirs_matches[i] == Some(t) {
+            resolved.push(base[i].clone());
+            i += 1;
+            o += 1;
+            t += 1;
+            continue;
+        }
+
+        // Find the next stable item, or the end
+        let next = (i..base.len())
+            .find(|&k| ours_matches[k].is_some() && theirs_matches[k].is_some());
+
+        let (k, o_end, t_end) = match next {
+            Some(k) => (k, ours_matches[k].unwrap(), theirs_matches[k].unwrap()),
+            None => (base.len(), ours.len(), theirs.len()),
+        };
+
+        let base_chunk = &base[i..k];
+        let ours_chunk = &ours[o..o_end];
+        let theirs_chunk = &theirs[t..t_end];
+
+        if ours_chunk == base_chunk || ours_chunk == theirs_chunk {
+            resolved.extend_from_slice(theirs_chunk);
+        } else if theirs_chunk == base_chunk {
+            resolved.extend_from_slice(ours_chunk);
+        } else {
+            if !resolved.is_empty() {
+                hunks.push(MergeHunk::Resolved(resolved));
+                resolved = Vec::new();
+            }
+
+            hunks.push(MergeHunk::Conflict {
+                base: base_chunk.to_vec(),
+                ours: ours_chunk.to_vec(),
+                theirs: theirs_chunk.to_vec(),
+            });
+        }
+
+        if next.is_none() {
+            break;
+        }
+
+        i = k;
+        o = o_end;
+        t = t_end;
+    }
+
+    if !resolved.is_empty() {
+        hunks.push(MergeHunk::Resolved(resolved));
+    }
+
+    hunks
+}
+
+/// For each item in `base`, find the index of the item it is matched with in `other`.
+fn base_matches<T: PartialEq>(base: &[T], other: &[T]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    for edit in diff(base, other) {
+        if let DiffEdit::Equal(i, j) = edit {
+            matches[i] = Some(j);
+        }
+    }
+
+    matches
+}
+
+#[test]
+fn merge_sequences() {
+    macro_rules! check {
+        ($base:expr, $ours:expr, $theirs:expr, $expected:expr $(,)?) => {{
+            let base: Vec<char> = $base.chars().collect();
+            let ours: Vec<char> = $ours.chars().collect();
+            let theirs: Vec<char> = $theirs.chars().collect();
+            let hunks = merge3(&base, &ours, &theirs);
+
+            let mut output = String::new();
+            for hunk in hunks {
+                match hunk {
+                    MergeHunk::Resolved(items) => output.extend(items),
+                    MergeHunk::Conflict { base, ours, theirs } => {
+                        output.push('<');
+                        output.extend(ours);
+                        output.push('|');
+                        output.extend(base);
+                        output.push('|');
+                        output.extend(theirs);
+                        output.push('>');
+                    }
+                }
+            }
+
+            assert_eq!(output, $expected, "Merged output doesn't match expected");
+        }};
+    }
+
+    check!("", "", "", "");
+    check!("abc", "abc", "abc", "abc");
+    check!("abc", "xabc", "abc", "xabc");
+    check!("abc", "abc", "abcx", "abcx");
+    check!("abc", "xabc", "abcx", "xabcx");
+    check!("abcdef", "aXcdef", "abcdYf", "aXcdYf");
+    check!("abcdef", "acdef", "abcde", "acde");
+    check!("abc", "aXc", "aXc", "aXc");
+    check!("abc", "aXc", "aYc", "a<X|b|Y>c");
+    check!("abc", "", "abc", "");
+    check!("abc", "", "aXc", "<|abc|aXc>");
+}
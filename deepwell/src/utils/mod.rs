@@ -22,16 +22,18 @@
 
 mod category;
 mod crypto;
-mod debug;
+mod diff;
 mod locale;
+mod merge;
 mod slug;
 mod string;
 mod time;
 
 pub use self::category::*;
 pub use self::crypto::*;
-pub use self::debug::*;
+pub use self::diff::*;
 pub use self::locale::*;
+pub use self::merge::*;
 pub use self::slug::*;
 pub use self::string::*;
 pub use self::time::*;
@@ -23,4 +23,15 @@
 pub struct FileDetails {
     /// Include the contents of the file.
     pub data: bool,
+
+    /// Include the list of resized copies available for this file.
+    pub derivatives: bool,
+
+    /// Return the contents of a resized copy, rather than the original.
+    ///
+    /// The smallest derivative at least this many pixels wide and tall is used,
+    /// or the largest one if none are big enough. If the file has no derivatives,
+    /// such as if it is not an image, then the original is returned.
+    /// Only has an effect if `data` is set.
+    pub size: Option<u32>,
 }
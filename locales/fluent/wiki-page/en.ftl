@@ -49,3 +49,11 @@ wiki-page-site-custom = <h1>No { -service-name } site exists with this address.<
     </p>
 
 wiki-page-no-render = Content not shown.
+
+### Page include errors
+
+wiki-page-include-missing = Page to be included //{ $slug }// cannot be found!
+
+wiki-page-include-loop = Page //{ $slug }// cannot be included, because it would end up including itself.
+
+wiki-page-include-depth = Page //{ $slug }// cannot be included, because includes are nested too deeply.